@@ -3,7 +3,7 @@ use time;
 
 use crate::gg::debug::*;
 use crate::gg::debug;
-use crate::gg::{rendering, input, window, Handler, games};
+use crate::gg::{rendering, input, Handler, games};
 use crate::gg::rendering::{DisplaySettings, StandardPrimitive};
 use crate::gg::collision::CollisionTestBuilder;
 use crate::gg::handler_basic::HandlerBasic;
@@ -23,8 +23,7 @@ fn main() {
     };
 
     let renderer: Box<dyn rendering::Renderer<Primitive=StandardPrimitive>> = Box::new(rendering::glium_renderer::GliumRenderer::new(display_settings));
-    let input_handler: Box<dyn input::InputHandler> = Box::new(input::multihandler::MultiInput::new());
-    let window_handler: Box<dyn window::WindowHandler> = Box::new(window::GlutinInput::new());
+    let (input_handler, window_handler) = input::new_platform_handlers();
     let game: Box<dyn games::Game<Primitive=StandardPrimitive>> = Box::new(
          CollisionTestBuilder::init()
             .add_line(Line::new(Point::new(-0.5, -0.2), Point::new(-0.5, -0.5)))
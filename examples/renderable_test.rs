@@ -4,7 +4,7 @@ use image;
 
 
 use crate::gg::debug::*;
-use crate::gg::{debug, rendering, input, window, games, Handler};
+use crate::gg::{debug, rendering, input, games, Handler};
 use crate::gg::handler_basic::HandlerBasic;
 use crate::gg::rendering::{DisplaySettings, StandardPrimitive};
 use std::env;
@@ -28,8 +28,7 @@ fn main() {
     let texture_array = vec![image1];
 
     let renderer = Box::new(rendering::GliumRenderer::new_with_textures(display_settings, texture_array));
-    let input_handler: Box<dyn input::InputHandler> = Box::new(input::multihandler::MultiInput::new());
-    let window_handler: Box<dyn window::WindowHandler> = Box::new(window::GlutinInput::new());
+    let (input_handler, window_handler) = input::new_platform_handlers();
     let game: Box<dyn games::Game<Primitive=StandardPrimitive>> = Box::new(renderable_test_game::RenderableTestGame::default());
     let mut handler: Box<dyn Handler> = Box::new(HandlerBasic::new(renderer, input_handler, window_handler, game));
 
@@ -66,7 +66,8 @@ impl Game for RenderableTestGame {
             transform: Rotation::new(0.0).get_matrix(),
             color: Color::new(1.0, 1.0, 1.0, 1.0),
             fixed: false,
-            align: TextAlign::Centered
+            align: TextAlign::Centered,
+            font: DEFAULT_FONT
         };
         
         let poly_corners = vec![
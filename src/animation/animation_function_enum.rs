@@ -1,11 +1,52 @@
 use crate::animation::animation_functions::*;
 
+/// The shapes of Robert Penner's easings, each eased in, out or both by AnimationFunctionEnum
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EasingCurve {
+    Quad,
+    Cubic,
+    Quart,
+    Quint,
+    Sine,
+    Expo,
+    Circ,
+    /// Overshoots, going below 0 when easing in and above 1 when easing out
+    Back,
+    /// Overshoots with wobbles
+    Elastic,
+    Bounce
+}
+
+impl EasingCurve {
+    pub fn ease_in(self, t: f64) -> f64 {
+        match self {
+            EasingCurve::Quad => t.powi(2),
+            EasingCurve::Cubic => t.powi(3),
+            EasingCurve::Quart => t.powi(4),
+            EasingCurve::Quint => t.powi(5),
+            EasingCurve::Sine => sine_in(t),
+            EasingCurve::Expo => expo_in(t),
+            EasingCurve::Circ => circ_in(t),
+            EasingCurve::Back => back_in(t),
+            EasingCurve::Elastic => elastic_in(t),
+            EasingCurve::Bounce => bounce_in(t)
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum AnimationFunctionEnum {
     SlowInSlowOut,
     SlowIn,
     SlowOut,
-    Linear
+    Linear,
+    EaseIn(EasingCurve),
+    EaseOut(EasingCurve),
+    EaseInOut(EasingCurve),
+    /// Control points (x1, y1, x2, y2) as in CSS's cubic-bezier
+    CubicBezier(f64, f64, f64, f64),
+    /// Holds the start until the very end, then jumps
+    Stepped
 }
 
 impl AnimationFunctionEnum {
@@ -14,7 +55,23 @@ impl AnimationFunctionEnum {
             AnimationFunctionEnum::SlowInSlowOut => Box::new(interpolation_cubic),
             AnimationFunctionEnum::SlowOut => Box::new(reversed_quadratic),
             AnimationFunctionEnum::SlowIn => Box::new(simple_quadratic),
-            AnimationFunctionEnum::Linear => Box::new(identity)
+            AnimationFunctionEnum::Linear => Box::new(identity),
+            _ => Box::new(move |t| self.apply(t))
+        }
+    }
+
+    /// Evaluates the function directly, for hot loops where boxing per call is too slow
+    pub fn apply(self, t: f64) -> f64 {
+        match self {
+            AnimationFunctionEnum::SlowInSlowOut => interpolation_cubic(t),
+            AnimationFunctionEnum::SlowOut => reversed_quadratic(t),
+            AnimationFunctionEnum::SlowIn => simple_quadratic(t),
+            AnimationFunctionEnum::Linear => identity(t),
+            AnimationFunctionEnum::EaseIn(curve) => curve.ease_in(t),
+            AnimationFunctionEnum::EaseOut(curve) => ease_out(|t| curve.ease_in(t), t),
+            AnimationFunctionEnum::EaseInOut(curve) => ease_in_out(|t| curve.ease_in(t), t),
+            AnimationFunctionEnum::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t),
+            AnimationFunctionEnum::Stepped => if t >= 1.0 { 1.0 } else { 0.0 }
         }
     }
-}
\ No newline at end of file
+}
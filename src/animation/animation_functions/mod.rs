@@ -1,3 +1,5 @@
+use std::f64::consts::PI;
+
 // Satisfies f(0) = f'(0) = f'(1) = 0 and f(1) = 1
 pub fn interpolation_cubic(t: f64) -> f64 {
     -2.0 * t * t * t + 3.0 * t * t 
@@ -13,4 +15,91 @@ pub fn reversed_quadratic(t: f64) -> f64 {
 
 pub fn identity(t: f64) -> f64 {
     t
-}
\ No newline at end of file
+}
+
+// Robert Penner's easing curves, as ease-in functions. Ease-out and ease-in-out are made from these.
+
+pub fn sine_in(t: f64) -> f64 {
+    1.0 - (t * PI / 2.0).cos()
+}
+
+pub fn expo_in(t: f64) -> f64 {
+    if t <= 0.0 { 0.0 } else { 2f64.powf(10.0 * t - 10.0) }
+}
+
+pub fn circ_in(t: f64) -> f64 {
+    1.0 - (1.0 - t * t).max(0.0).sqrt()
+}
+
+/// Pulls back a little before setting off
+pub fn back_in(t: f64) -> f64 {
+    const OVERSHOOT: f64 = 1.70158;
+    (OVERSHOOT + 1.0) * t * t * t - OVERSHOOT * t * t
+}
+
+/// Winds up with growing wobbles
+pub fn elastic_in(t: f64) -> f64 {
+    if t <= 0.0 || t >= 1.0 {
+        return t.clamp(0.0, 1.0);
+    }
+    -(2f64.powf(10.0 * t - 10.0)) * ((10.0 * t - 10.75) * 2.0 * PI / 3.0).sin()
+}
+
+/// Bounces along, growing until it lands at 1
+pub fn bounce_in(t: f64) -> f64 {
+    1.0 - bounce_out(1.0 - t)
+}
+
+pub fn bounce_out(t: f64) -> f64 {
+    const STRENGTH: f64 = 7.5625;
+    const WIDTH: f64 = 2.75;
+    if t < 1.0 / WIDTH {
+        STRENGTH * t * t
+    } else if t < 2.0 / WIDTH {
+        let t = t - 1.5 / WIDTH;
+        STRENGTH * t * t + 0.75
+    } else if t < 2.5 / WIDTH {
+        let t = t - 2.25 / WIDTH;
+        STRENGTH * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / WIDTH;
+        STRENGTH * t * t + 0.984375
+    }
+}
+
+/// An ease-in function played backwards, fast at the start and slow at the end
+pub fn ease_out(ease_in: impl Fn(f64) -> f64, t: f64) -> f64 {
+    1.0 - ease_in(1.0 - t)
+}
+
+/// An ease-in function for the first half, then the same eased out
+pub fn ease_in_out(ease_in: impl Fn(f64) -> f64, t: f64) -> f64 {
+    if t < 0.5 {
+        ease_in(2.0 * t) / 2.0
+    } else {
+        1.0 - ease_in(2.0 - 2.0 * t) / 2.0
+    }
+}
+
+/// The CSS-style curve from (0, 0) to (1, 1) with control points (x1, y1) and (x2, y2), which
+/// need x1 and x2 between 0 and 1 for every time to have one value
+pub fn cubic_bezier(x1: f64, y1: f64, x2: f64, y2: f64, t: f64) -> f64 {
+    let bezier = |a: f64, b: f64, s: f64| 3.0 * a * s * (1.0 - s) * (1.0 - s) + 3.0 * b * s * s * (1.0 - s) + s * s * s;
+    let t = t.clamp(0.0, 1.0);
+
+    // Find where along the curve x is t, by bisection as x only grows along it
+    let (mut low, mut high) = (0.0, 1.0);
+    let mut s = t;
+    for _ in 0..BEZIER_ITERATIONS {
+        let x = bezier(x1, x2, s);
+        if (x - t).abs() < BEZIER_PRECISION {
+            break;
+        }
+        if x < t { low = s } else { high = s }
+        s = (low + high) / 2.0;
+    }
+    bezier(y1, y2, s)
+}
+
+const BEZIER_ITERATIONS: usize = 64;
+const BEZIER_PRECISION: f64 = 1e-9;
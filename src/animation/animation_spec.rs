@@ -1,54 +1,271 @@
 use crate::animation::{AnimationWindow, AnimationType, AnimationTime};
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum AnimationEvent<Stage: Clone> {
+    /// Time moved into the window with this stage, in either direction
+    StageEntered(Stage),
+    /// Wrapped round to the start, or turned round for ping-pong
+    Looped,
+    /// Reached the end (or the start, playing in reverse) of an animation that doesn't repeat
+    Finished
+}
+
 pub struct AnimationSpec<Stage: Clone> {
     windows: Vec<AnimationWindow<Stage>>,
+    /// When each window ends, for finding the current one by binary search
+    cumulative_times: Vec<f64>,
     anim_type: AnimationType,
-    time: f64
+    /// From 0 to the total running time, whichever way the animation is playing
+    time: f64,
+    /// Whether ping-pong is on its way back
+    backwards: bool,
+    speed: f64,
+    paused: bool,
+    finished: bool,
+    /// From the last update
+    events: Vec<AnimationEvent<Stage>>
 }
 
 impl<Stage: Clone> AnimationSpec<Stage>{
     pub fn new(windows: Vec<AnimationWindow<Stage>>, anim_type: AnimationType) -> Self {
-        Self {
+        let cumulative_times = windows.iter()
+            .scan(0.0, |total, window| {
+                *total += window.length;
+                Some(*total)
+            })
+            .collect();
+        let mut spec = Self {
             windows,
+            cumulative_times,
             anim_type,
-            time: 0.0
-        }
+            time: 0.0,
+            backwards: false,
+            speed: 1.0,
+            paused: false,
+            finished: false,
+            events: Vec::new()
+        };
+        spec.restart();
+        spec
     }
 
+    /// Moves time along by t_step times the speed, giving events for the windows entered on the way
     pub fn update(&mut self, t_step: f64) {
-        self.time += t_step;
+        self.events.clear();
+        let total = self.get_total_running_time();
+        if self.paused || self.finished || total <= 0.0 {
+            return;
+        }
 
-        if self.anim_type == AnimationType::Repeat {
-            self.time = self.time % self.get_total_running_time();
+        let mut remaining = (t_step * self.speed).abs();
+        // Whole cycles change nothing, so only the last one is played through for its events
+        let cycle = if self.anim_type == AnimationType::PingPong { 2.0 * total } else { total };
+        if self.anim_type.repeats() && remaining > cycle {
+            remaining = remaining % cycle + cycle;
         }
+
+        while remaining > 0.0 {
+            let forwards = self.is_moving_forwards();
+            let to_end = if forwards { total - self.time } else { self.time };
+            if remaining < to_end {
+                let target = if forwards { self.time + remaining } else { self.time - remaining };
+                self.move_to(target);
+                break;
+            }
+
+            remaining -= to_end;
+            self.move_to(if forwards { total } else { 0.0 });
+            match self.anim_type {
+                AnimationType::Linear | AnimationType::Reverse => {
+                    self.finished = true;
+                    self.events.push(AnimationEvent::Finished);
+                    break;
+                },
+                AnimationType::Repeat => {
+                    self.time = if forwards { 0.0 } else { total };
+                    self.events.push(AnimationEvent::Looped);
+                    let stage = self.windows[self.get_window_index(self.time)].stage.clone();
+                    self.events.push(AnimationEvent::StageEntered(stage));
+                },
+                AnimationType::PingPong => {
+                    self.backwards = !self.backwards;
+                    self.events.push(AnimationEvent::Looped);
+                }
+            }
+        }
+    }
+
+    /// Moves time, giving an event for each window passed into on the way
+    fn move_to(&mut self, time: f64) {
+        let from = self.get_window_index(self.time);
+        let to = self.get_window_index(time);
+        let entered: Vec<usize> = if to > from { (from + 1..=to).collect() } else { (to..from).rev().collect() };
+        for index in entered {
+            self.events.push(AnimationEvent::StageEntered(self.windows[index].stage.clone()));
+        }
+        self.time = time;
+    }
+
+    fn is_moving_forwards(&self) -> bool {
+        let reversed = self.anim_type == AnimationType::Reverse;
+        (self.speed >= 0.0) != (self.backwards != reversed)
+    }
+
+    /// The events from the last update, e.g. for sounds on stage changes
+    pub fn get_events(&self) -> &[AnimationEvent<Stage>] {
+        &self.events
     }
 
     fn get_total_running_time(&self) -> f64 {
-        self.windows.iter().map(|window| {window.length}).sum()
+        self.cumulative_times.last().cloned().unwrap_or(0.0)
     }
 
-    pub fn get_current_time(&self) -> AnimationTime<Stage> {
-        let window_times: Vec<f64> = self.windows.iter().map(|window| {window.length}).collect(); 
-        let cumulative_times: Vec<f64> = (1..window_times.len() + 1)
-            .map(|index| {window_times.iter().take(index).sum()})
-            .collect(); 
-        let cumulative_times_prev: Vec<f64> = (0..window_times.len())
-            .map(|index| {window_times.iter().take(index).sum()})
-            .collect(); 
-
-        let current_window_opt = cumulative_times.into_iter().position(|time| {self.time <= time});
-        let current_window_index = match current_window_opt {
-            Some(index) => index,
-            None => {
-                let current_window = &self.windows[self.windows.len() - 1];
-                return AnimationTime::new(current_window.stage.clone(), 1.0, true);
-            }
-        };
+    /// The index of the window a time falls in, the earlier one on a boundary
+    fn get_window_index(&self, time: f64) -> usize {
+        let index = self.cumulative_times.partition_point(|&end| end < time);
+        index.min(self.windows.len().saturating_sub(1))
+    }
 
+    pub fn get_current_time(&self) -> AnimationTime<Stage> {
+        let current_window_index = self.get_window_index(self.time);
         let current_window = &self.windows[current_window_index];
-        let current_window_time_unprocessed = (self.time - cumulative_times_prev[current_window_index]) / current_window.length;
+        let window_start = self.cumulative_times[current_window_index] - current_window.length;
+        let current_window_time_unprocessed = if current_window.length > 0.0 {
+            (self.time - window_start) / current_window.length
+        } else {
+            1.0
+        };
         let current_window_time = (current_window.time_function)(current_window_time_unprocessed);
 
-        AnimationTime::new(current_window.stage.clone(), current_window_time, false)
+        AnimationTime::new(current_window.stage.clone(), current_window_time, self.finished)
     }
-}
\ No newline at end of file
+
+    /// Seconds from the start of the windows
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    /// Jumps to a time from the start of the windows, without events
+    pub fn seek(&mut self, time: f64) {
+        self.time = time.clamp(0.0, self.get_total_running_time());
+        self.finished = false;
+        self.events.clear();
+    }
+
+    /// Back to the start (the end, for reverse) and unpaused
+    pub fn restart(&mut self) {
+        self.time = if self.anim_type == AnimationType::Reverse { self.get_total_running_time() } else { 0.0 };
+        self.backwards = false;
+        self.paused = false;
+        self.finished = false;
+        self.events.clear();
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn get_speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// 2 plays twice as fast, negative speeds play backwards
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::AnimationFunctionEnum;
+
+    fn build_spec(anim_type: AnimationType) -> AnimationSpec<char> {
+        AnimationSpec::new(vec![
+            AnimationWindow::new(AnimationFunctionEnum::Linear, 1.0, 'a'),
+            AnimationWindow::new(AnimationFunctionEnum::Linear, 2.0, 'b'),
+            AnimationWindow::new(AnimationFunctionEnum::Linear, 1.0, 'c')
+        ], anim_type)
+    }
+
+    fn stage_at(spec: &AnimationSpec<char>) -> (char, f64) {
+        let time = spec.get_current_time();
+        (time.stage, time.stage_time)
+    }
+
+    #[test]
+    fn stages_are_entered_and_finished_with_events() {
+        let mut spec = build_spec(AnimationType::Linear);
+        spec.update(0.5);
+        assert_eq!(stage_at(&spec), ('a', 0.5));
+        assert!(spec.get_events().is_empty());
+
+        spec.update(3.0);
+        assert_eq!(stage_at(&spec), ('c', 0.5));
+        assert_eq!(spec.get_events(), &[AnimationEvent::StageEntered('b'), AnimationEvent::StageEntered('c')]);
+
+        spec.update(1.0);
+        assert!(spec.is_finished() && spec.get_current_time().finished);
+        assert_eq!(spec.get_events(), &[AnimationEvent::Finished]);
+        spec.update(1.0);
+        assert!(spec.get_events().is_empty());
+
+        spec.seek(2.0);
+        assert_eq!(stage_at(&spec), ('b', 0.5));
+        assert!(spec.get_events().is_empty());
+        spec.pause();
+        spec.update(1.0);
+        assert_eq!(spec.get_time(), 2.0);
+        spec.restart();
+        assert_eq!(stage_at(&spec), ('a', 0.0));
+    }
+
+    #[test]
+    fn animations_repeat_reverse_and_ping_pong() {
+        let mut spec = build_spec(AnimationType::Repeat);
+        spec.set_speed(2.0);
+        spec.update(1.5);
+        spec.update(0.75);
+        assert_eq!(stage_at(&spec), ('a', 0.5));
+        assert_eq!(spec.get_events(), &[AnimationEvent::StageEntered('c'), AnimationEvent::Looped,
+            AnimationEvent::StageEntered('a')]);
+
+        let mut spec = build_spec(AnimationType::Reverse);
+        spec.update(1.5);
+        assert_eq!(stage_at(&spec), ('b', 0.75));
+        spec.update(3.0);
+        assert!(spec.is_finished());
+        assert_eq!(spec.get_events(), &[AnimationEvent::StageEntered('a'), AnimationEvent::Finished]);
+
+        let mut spec = build_spec(AnimationType::PingPong);
+        spec.update(5.0);
+        assert_eq!(stage_at(&spec), ('b', 1.0));
+        assert_eq!(spec.get_events(), &[AnimationEvent::StageEntered('b'), AnimationEvent::StageEntered('c'),
+            AnimationEvent::Looped, AnimationEvent::StageEntered('b')]);
+        spec.set_speed(-1.0);
+        spec.update(0.5);
+        assert_eq!(stage_at(&spec), ('c', 0.5));
+        assert_eq!(spec.get_events(), &[AnimationEvent::StageEntered('c')]);
+    }
+
+    #[test]
+    fn long_steps_loop_once() {
+        let mut spec = build_spec(AnimationType::Repeat);
+        spec.update(9.5);
+        assert_eq!(stage_at(&spec), ('b', 0.25));
+        assert_eq!(spec.get_events(), &[AnimationEvent::StageEntered('b'), AnimationEvent::StageEntered('c'),
+            AnimationEvent::Looped, AnimationEvent::StageEntered('a'), AnimationEvent::StageEntered('b')]);
+    }
+}
@@ -1,5 +1,15 @@
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum AnimationType {
     Linear,
-    Repeat
-}
\ No newline at end of file
+    Repeat,
+    /// Forwards then backwards, over and over
+    PingPong,
+    /// Once, from the end to the start
+    Reverse
+}
+
+impl AnimationType {
+    pub fn repeats(self) -> bool {
+        self == AnimationType::Repeat || self == AnimationType::PingPong
+    }
+}
@@ -4,9 +4,15 @@ pub mod animation_functions;
 pub mod animation_type;
 pub mod animation_function_enum;
 pub mod animation_time;
+pub mod tween;
+pub mod skeleton;
+pub mod spine;
 
 pub use self::animation_window::AnimationWindow;
-pub use self::animation_spec::AnimationSpec;
+pub use self::animation_spec::{AnimationSpec, AnimationEvent};
 pub use self::animation_type::AnimationType;
-pub use self::animation_function_enum::AnimationFunctionEnum;
-pub use self::animation_time::AnimationTime;
\ No newline at end of file
+pub use self::animation_function_enum::{AnimationFunctionEnum, EasingCurve};
+pub use self::animation_time::AnimationTime;
+pub use self::tween::*;
+pub use self::skeleton::*;
+pub use self::spine::{from_spine_json, load_spine};
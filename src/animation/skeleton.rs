@@ -0,0 +1,424 @@
+use std::{error, fmt, io};
+use crate::animation::{Keyframe, KeyframeTrack, Tween, Tweenable};
+use crate::geometry::{Matrix3, Point, Point3, Rotation};
+use crate::rendering::{TextureRect, Polygon};
+use crate::utils::transforms_2d::{translation_mat, rotation_mat, scaling_mat};
+
+#[derive(Debug)]
+pub enum SkeletonError {
+    Io(io::Error),
+    InvalidFile(String),
+    UnknownBone(String),
+    /// Bones have to come after their parents
+    ParentAfterChild(String),
+    BoneOutOfRange(usize),
+    /// A pose needs a transform for every bone
+    WrongPoseLength { expected: usize, found: usize }
+}
+
+impl fmt::Display for SkeletonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkeletonError::Io(err) => write!(f, "Could not access skeleton file: {}", err),
+            SkeletonError::InvalidFile(mes) => write!(f, "Invalid skeleton file: {}", mes),
+            SkeletonError::UnknownBone(name) => write!(f, "No bone named {}", name),
+            SkeletonError::ParentAfterChild(name) => write!(f, "Bone {} comes before its parent", name),
+            SkeletonError::BoneOutOfRange(index) => write!(f, "No bone at index {}", index),
+            SkeletonError::WrongPoseLength { expected, found } =>
+                write!(f, "Pose has {} transforms for {} bones", found, expected)
+        }
+    }
+}
+
+impl error::Error for SkeletonError {}
+
+impl From<io::Error> for SkeletonError {
+    fn from(err: io::Error) -> Self {
+        SkeletonError::Io(err)
+    }
+}
+
+/// A bone's place relative to its parent, scaled then rotated then moved
+#[derive(Copy, Clone, Debug)]
+pub struct BoneTransform {
+    pub position: Point,
+    pub rotation: Rotation,
+    pub scale: Point
+}
+
+impl Default for BoneTransform {
+    fn default() -> Self {
+        BoneTransform {
+            position: Point::zero(),
+            rotation: Rotation::new(0.0),
+            scale: Point::one()
+        }
+    }
+}
+
+impl BoneTransform {
+    pub fn get_matrix(&self) -> Matrix3 {
+        translation_mat(self.position) * rotation_mat(self.rotation.angle) * scaling_mat(self.scale)
+    }
+}
+
+impl Tweenable for BoneTransform {
+    fn tween(self, other: Self, t: f64) -> Self {
+        BoneTransform {
+            position: self.position.tween(other.position, t),
+            rotation: self.rotation.tween(other.rotation, t),
+            scale: self.scale.tween(other.scale, t)
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Bone {
+    pub name: String,
+    /// The index of the parent bone, which comes before this one, None for roots
+    pub parent: Option<usize>,
+    /// Where the bone is when not animated
+    pub setup: BoneTransform,
+    pub length: f64
+}
+
+impl Bone {
+    pub fn new(name: &str, parent: Option<usize>, setup: BoneTransform) -> Self {
+        Self {
+            name: name.to_string(),
+            parent,
+            setup,
+            length: 0.0
+        }
+    }
+}
+
+/// Every bone's transform, in the skeleton's bone order
+pub type Pose = Vec<BoneTransform>;
+
+/// Mixes two poses of the same skeleton, weight 0 giving the first and 1 the second
+pub fn blend_poses(first: &[BoneTransform], second: &[BoneTransform], weight: f64) -> Pose {
+    debug_assert_eq!(first.len(), second.len(), "Blending poses of different skeletons");
+    first.iter().zip(second.iter()).map(|(first, second)| first.tween(*second, weight)).collect()
+}
+
+/// What's drawn for a bone, placed relative to the bone
+#[derive(Clone)]
+pub enum PartShape {
+    Texture(TextureRect),
+    Polygon(Polygon)
+}
+
+#[derive(Clone)]
+pub struct SkeletonPart {
+    pub bone: usize,
+    pub shape: PartShape
+}
+
+/// Bones posed by animations, moving the parts attached to them. Draw with its Renderable impl.
+pub struct Skeleton {
+    bones: Vec<Bone>,
+    pose: Pose,
+    /// Bone space to skeleton space, for each bone
+    world_matrices: Vec<Matrix3>,
+    /// Drawn in order, so later parts go on top
+    pub parts: Vec<SkeletonPart>,
+    /// Where the skeleton is in the world
+    pub transform: BoneTransform
+}
+
+impl Skeleton {
+    pub fn new(bones: Vec<Bone>) -> Result<Self, SkeletonError> {
+        for (index, bone) in bones.iter().enumerate() {
+            if bone.parent.is_some_and(|parent| parent >= index) {
+                return Err(SkeletonError::ParentAfterChild(bone.name.clone()));
+            }
+        }
+        let mut skeleton = Self {
+            pose: bones.iter().map(|bone| bone.setup).collect(),
+            world_matrices: Vec::new(),
+            bones,
+            parts: Vec::new(),
+            transform: BoneTransform::default()
+        };
+        skeleton.update_world_matrices();
+
+        Ok(skeleton)
+    }
+
+    pub fn get_bones(&self) -> &[Bone] {
+        &self.bones
+    }
+
+    pub fn find_bone(&self, name: &str) -> Option<usize> {
+        self.bones.iter().position(|bone| bone.name == name)
+    }
+
+    pub fn get_setup_pose(&self) -> Pose {
+        self.bones.iter().map(|bone| bone.setup).collect()
+    }
+
+    pub fn get_pose(&self) -> &[BoneTransform] {
+        &self.pose
+    }
+
+    /// Takes a pose with a transform for every bone, e.g. from SkeletalAnimation::sample
+    pub fn set_pose(&mut self, pose: Pose) -> Result<(), SkeletonError> {
+        if pose.len() != self.bones.len() {
+            return Err(SkeletonError::WrongPoseLength { expected: self.bones.len(), found: pose.len() });
+        }
+        self.pose = pose;
+        self.update_world_matrices();
+        Ok(())
+    }
+
+    pub fn attach(&mut self, bone: usize, shape: PartShape) -> Result<(), SkeletonError> {
+        if bone >= self.bones.len() {
+            return Err(SkeletonError::BoneOutOfRange(bone));
+        }
+        self.parts.push(SkeletonPart { bone, shape });
+        Ok(())
+    }
+
+    /// From the bone's space to the world's, None for a bone not in the skeleton
+    pub fn get_world_matrix(&self, bone: usize) -> Option<Matrix3> {
+        self.world_matrices.get(bone).map(|matrix| self.transform.get_matrix() * *matrix)
+    }
+
+    /// Where a point in the bone's space is in the world
+    pub fn get_world_position(&self, bone: usize, point: Point) -> Option<Point> {
+        let world = self.get_world_matrix(bone)? * Point3::new(point.x, point.y, 1.0);
+        Some(Point::new(world.x, world.y))
+    }
+
+    fn update_world_matrices(&mut self) {
+        let mut world_matrices: Vec<Matrix3> = Vec::with_capacity(self.bones.len());
+        for (bone, transform) in self.bones.iter().zip(self.pose.iter()) {
+            let local = transform.get_matrix();
+            let world = match bone.parent {
+                Some(parent) => world_matrices[parent] * local,
+                None => local
+            };
+            world_matrices.push(world);
+        }
+        self.world_matrices = world_matrices;
+    }
+
+    /// The parts placed by their bones. Textures can only be scaled along their own axes, so a
+    /// skewing scale up the hierarchy leaves them unskewed, and a mirroring one flips their height.
+    /// Parts on bones not in the skeleton are left out.
+    pub fn get_posed_parts(&self) -> Vec<PartShape> {
+        self.parts.iter().filter_map(|part| {
+            let matrix = self.get_world_matrix(part.bone)?;
+            Some(match &part.shape {
+                PartShape::Texture(rect) => {
+                    let mut rect = *rect;
+                    let x_axis = Point::new(matrix.xx, matrix.yx);
+                    let y_axis = Point::new(matrix.xy, matrix.yy);
+                    let centre = matrix * Point3::new(rect.pos.x, rect.pos.y, 1.0);
+                    rect.pos = Point3::new(centre.x, centre.y, rect.pos.z);
+                    rect.rot = Rotation::new(x_axis.get_angle() + rect.rot.angle);
+                    rect.length *= x_axis.norm();
+                    rect.height *= y_axis.norm();
+                    if matrix.xx * matrix.yy - matrix.xy * matrix.yx < 0.0 {
+                        rect.height = -rect.height;
+                    }
+                    PartShape::Texture(rect)
+                },
+                PartShape::Polygon(polygon) => {
+                    let mut polygon = polygon.clone();
+                    // Corners are drawn at pos + rot * corner, which is folded into the bone's matrix
+                    let local = translation_mat(Point::new(polygon.pos.x, polygon.pos.y)) * rotation_mat(polygon.rot.angle);
+                    let to_world = |corner: Point| {
+                        let world = matrix * local * Point3::new(corner.x, corner.y, 1.0);
+                        Point::new(world.x, world.y)
+                    };
+                    polygon.corners = polygon.corners.iter().map(|&corner| to_world(corner)).collect();
+                    polygon.center = to_world(polygon.center);
+                    polygon.pos = Point3::new(0.0, 0.0, polygon.pos.z);
+                    polygon.rot = Rotation::new(0.0);
+                    PartShape::Polygon(polygon)
+                }
+            })
+        }).collect()
+    }
+}
+
+/// Keyframes for one bone, as changes from its setup pose: moved by the translation, turned by the
+/// rotation and the setup scale multiplied by the scale
+pub struct BoneTimeline {
+    pub bone: usize,
+    pub translation: KeyframeTrack<Point, Point>,
+    pub rotation: KeyframeTrack<Rotation, Rotation>,
+    pub scale: KeyframeTrack<Point, Point>
+}
+
+impl BoneTimeline {
+    pub fn new(bone: usize) -> Self {
+        Self {
+            bone,
+            translation: KeyframeTrack::new(vec![]),
+            rotation: KeyframeTrack::new(vec![]),
+            scale: KeyframeTrack::new(vec![])
+        }
+    }
+
+    pub fn with_translation(mut self, keyframes: Vec<Keyframe<Point>>) -> Self {
+        self.translation = KeyframeTrack::new(keyframes);
+        self
+    }
+
+    pub fn with_rotation(mut self, keyframes: Vec<Keyframe<Rotation>>) -> Self {
+        self.rotation = KeyframeTrack::new(keyframes);
+        self
+    }
+
+    pub fn with_scale(mut self, keyframes: Vec<Keyframe<Point>>) -> Self {
+        self.scale = KeyframeTrack::new(keyframes);
+        self
+    }
+
+    fn apply(&self, time: f64, setup: BoneTransform) -> BoneTransform {
+        let translation = self.translation.get_value(time).unwrap_or_else(Point::zero);
+        let rotation = self.rotation.get_value(time).map_or(0.0, |rotation| rotation.angle);
+        let scale = self.scale.get_value(time).unwrap_or_else(Point::one);
+        BoneTransform {
+            position: setup.position + translation,
+            rotation: Rotation::new(setup.rotation.angle + rotation),
+            scale: setup.scale * scale
+        }
+    }
+}
+
+/// Bone keyframes, sampled into poses. Bones without a timeline stay in their setup pose.
+pub struct SkeletalAnimation {
+    pub name: String,
+    pub timelines: Vec<BoneTimeline>
+}
+
+impl SkeletalAnimation {
+    pub fn new(name: &str, timelines: Vec<BoneTimeline>) -> Self {
+        Self {
+            name: name.to_string(),
+            timelines
+        }
+    }
+
+    /// When the last keyframe is
+    pub fn get_duration(&self) -> f64 {
+        self.timelines.iter()
+            .map(|timeline| timeline.translation.get_duration().max(timeline.rotation.get_duration()).max(timeline.scale.get_duration()))
+            .fold(0.0, f64::max)
+    }
+
+    /// The pose at a time in seconds, held at the ends. Use time % get_duration() to loop.
+    pub fn sample(&self, skeleton: &Skeleton, time: f64) -> Pose {
+        let mut pose = skeleton.get_setup_pose();
+        for timeline in &self.timelines {
+            if let Some(transform) = pose.get_mut(timeline.bone) {
+                *transform = timeline.apply(time, *transform);
+            }
+        }
+        pose
+    }
+
+    /// Both animations' poses blended, weight 0 giving this one and 1 the other, e.g. to ease from
+    /// walking into running
+    pub fn sample_blended(&self, other: &SkeletalAnimation, skeleton: &Skeleton, time: f64, other_time: f64, weight: f64) -> Pose {
+        blend_poses(&self.sample(skeleton, time), &other.sample(skeleton, other_time), weight)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+    use crate::animation::AnimationFunctionEnum;
+    use crate::rendering::Color;
+
+    fn assert_near(point: Point, x: f64, y: f64) {
+        assert!((point.x - x).abs() < 1e-9 && (point.y - y).abs() < 1e-9, "{:?} is not ({}, {})", point, x, y);
+    }
+
+    fn build_arm() -> Skeleton {
+        let elbow = BoneTransform { position: Point::new(2.0, 0.0), ..Default::default() };
+        Skeleton::new(vec![
+            Bone::new("shoulder", None, BoneTransform::default()),
+            Bone::new("elbow", Some(0), elbow)
+        ]).unwrap()
+    }
+
+    #[test]
+    fn children_follow_their_parents() {
+        let mut skeleton = build_arm();
+        assert_near(skeleton.get_world_position(1, Point::new(1.0, 0.0)).unwrap(), 3.0, 0.0);
+
+        let mut pose = skeleton.get_setup_pose();
+        pose[0].rotation = Rotation::new(PI / 2.0);
+        pose[1].scale = Point::new(2.0, 1.0);
+        skeleton.set_pose(pose).unwrap();
+        skeleton.transform.position = Point::new(10.0, 0.0);
+        assert_near(skeleton.get_world_position(1, Point::new(1.0, 0.0)).unwrap(), 10.0, 4.0);
+
+        skeleton.attach(1, PartShape::Texture(TextureRect::new_regular(1.0, 1.0, Point3::new(1.0, 0.0, 0.5), Point3::zero(), Point::one(), false))).unwrap();
+        skeleton.attach(1, PartShape::Polygon(Polygon::new_regular(vec![Point::new(1.0, 0.0)], Point::zero(), Point3::zero(), Color::white(), false))).unwrap();
+        let parts = skeleton.get_posed_parts();
+        match &parts[0] {
+            PartShape::Texture(rect) => {
+                assert_near(Point::new(rect.pos.x, rect.pos.y), 10.0, 4.0);
+                assert!((rect.rot.angle - PI / 2.0).abs() < 1e-9);
+                assert_eq!((rect.length, rect.pos.z), (2.0, 0.5));
+            },
+            _ => panic!("Expected a texture")
+        }
+        match &parts[1] {
+            PartShape::Polygon(polygon) => assert_near(polygon.corners[0], 10.0, 4.0),
+            _ => panic!("Expected a polygon")
+        }
+
+        assert!(matches!(Skeleton::new(vec![Bone::new("loose", Some(0), BoneTransform::default())]),
+            Err(SkeletonError::ParentAfterChild(_))));
+        assert!(matches!(skeleton.attach(2, PartShape::Polygon(Polygon::new_regular(vec![], Point::zero(), Point3::zero(), Color::white(), false))),
+            Err(SkeletonError::BoneOutOfRange(2))));
+        assert!(skeleton.get_world_position(2, Point::zero()).is_none());
+        assert!(matches!(skeleton.set_pose(Vec::new()), Err(SkeletonError::WrongPoseLength { expected: 2, found: 0 })));
+    }
+
+    #[test]
+    fn mirrored_textures_are_flipped() {
+        let mut skeleton = build_arm();
+        skeleton.transform.scale = Point::new(-1.0, 1.0);
+        skeleton.attach(1, PartShape::Texture(TextureRect::new_regular(1.0, 2.0, Point3::new(1.0, 0.0, 0.5), Point3::zero(), Point::one(), false))).unwrap();
+        match &skeleton.get_posed_parts()[0] {
+            PartShape::Texture(rect) => {
+                assert_near(Point::new(rect.pos.x, rect.pos.y), -3.0, 0.0);
+                assert!((rect.rot.angle.abs() - PI).abs() < 1e-9);
+                assert_eq!((rect.length, rect.height), (1.0, -2.0));
+            },
+            _ => panic!("Expected a texture")
+        }
+    }
+
+    #[test]
+    fn animations_are_sampled_and_blended() {
+        let mut skeleton = build_arm();
+        let wave = SkeletalAnimation::new("wave", vec![
+            BoneTimeline::new(1).with_rotation(vec![
+                Keyframe::new(0.0, Rotation::new(0.0), AnimationFunctionEnum::Linear),
+                Keyframe::new(1.0, Rotation::new(PI / 2.0), AnimationFunctionEnum::Linear)
+            ])
+        ]);
+        let reach = SkeletalAnimation::new("reach", vec![
+            BoneTimeline::new(1).with_translation(vec![Keyframe::new(0.0, Point::new(2.0, 0.0), AnimationFunctionEnum::Linear)])
+        ]);
+        assert_eq!(wave.get_duration(), 1.0);
+
+        skeleton.set_pose(wave.sample(&skeleton, 1.0)).unwrap();
+        assert_near(skeleton.get_world_position(1, Point::new(1.0, 0.0)).unwrap(), 2.0, 1.0);
+
+        skeleton.set_pose(wave.sample_blended(&reach, &skeleton, 1.0, 0.0, 0.5)).unwrap();
+        let pose = skeleton.get_pose();
+        assert_near(pose[1].position, 3.0, 0.0);
+        assert!((pose[1].rotation.angle - PI / 4.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use serde_json::Value;
+use crate::animation::{AnimationFunctionEnum, Keyframe};
+use crate::animation::skeleton::*;
+use crate::geometry::{Point, Rotation};
+
+#[derive(Deserialize)]
+struct SpineFile {
+    skeleton: Option<SpineHeader>,
+    bones: Vec<SpineBone>,
+    #[serde(default)]
+    animations: HashMap<String, SpineAnimation>
+}
+
+#[derive(Deserialize)]
+struct SpineHeader {
+    /// The version of Spine that exported the file
+    spine: Option<String>
+}
+
+fn one() -> f64 {
+    1.0
+}
+
+#[derive(Deserialize)]
+struct SpineBone {
+    name: String,
+    parent: Option<String>,
+    #[serde(default)]
+    length: f64,
+    #[serde(default)]
+    x: f64,
+    #[serde(default)]
+    y: f64,
+    /// In degrees
+    #[serde(default)]
+    rotation: f64,
+    #[serde(default = "one", rename = "scaleX")]
+    scale_x: f64,
+    #[serde(default = "one", rename = "scaleY")]
+    scale_y: f64
+}
+
+#[derive(Deserialize)]
+struct SpineAnimation {
+    #[serde(default)]
+    bones: HashMap<String, SpineTimelines>
+}
+
+#[derive(Deserialize)]
+struct SpineTimelines {
+    #[serde(default)]
+    rotate: Vec<SpineKey>,
+    #[serde(default)]
+    translate: Vec<SpineKey>,
+    #[serde(default)]
+    scale: Vec<SpineKey>
+}
+
+#[derive(Deserialize)]
+struct SpineKey {
+    #[serde(default)]
+    time: f64,
+    /// Rotations, called value from Spine 4
+    angle: Option<f64>,
+    value: Option<f64>,
+    x: Option<f64>,
+    y: Option<f64>,
+    /// The easing to the next key: "stepped", [c1, c2, c3, c4] or c1 with c2 to c4 alongside
+    curve: Option<Value>,
+    c2: Option<f64>,
+    c3: Option<f64>,
+    c4: Option<f64>
+}
+
+impl SpineKey {
+    fn get_easing(&self) -> AnimationFunctionEnum {
+        match &self.curve {
+            Some(Value::String(curve)) if curve == "stepped" => AnimationFunctionEnum::Stepped,
+            Some(Value::Array(points)) if points.len() == 4 => {
+                let point = |index: usize| points[index].as_f64().unwrap_or(0.0);
+                AnimationFunctionEnum::CubicBezier(point(0), point(1), point(2), point(3))
+            },
+            Some(Value::Number(c1)) => AnimationFunctionEnum::CubicBezier(
+                c1.as_f64().unwrap_or(0.0),
+                self.c2.unwrap_or(0.0),
+                self.c3.unwrap_or(1.0),
+                self.c4.unwrap_or(1.0)),
+            _ => AnimationFunctionEnum::Linear
+        }
+    }
+}
+
+/// Spine keys ease out towards the next key, keyframes ease in from the one before
+fn to_keyframes<T, F: Fn(&SpineKey) -> T>(keys: &[SpineKey], value: F) -> Vec<Keyframe<T>> {
+    keys.iter().enumerate().map(|(index, key)| {
+        let easing = match index {
+            0 => AnimationFunctionEnum::Linear,
+            _ => keys[index - 1].get_easing()
+        };
+        Keyframe::new(key.time, value(key), easing)
+    }).collect()
+}
+
+/// Reads a skeleton and its animations from Spine's JSON export, with normalised (Spine 3) curves.
+/// Lengths are multiplied by scale, e.g. to turn pixels into world units.
+pub fn from_spine_json(json: &str, scale: f64) -> Result<(Skeleton, Vec<SkeletalAnimation>), SkeletonError> {
+    let file: SpineFile = serde_json::from_str(json).map_err(|err| SkeletonError::InvalidFile(err.to_string()))?;
+    // Spine 4 changed how curves are stored
+    if let Some(version) = file.skeleton.as_ref().and_then(|header| header.spine.as_ref()) {
+        let major: u32 = version.split('.').next().and_then(|major| major.parse().ok()).unwrap_or(0);
+        if major >= 4 {
+            return Err(SkeletonError::InvalidFile(format!("Spine {} exports aren't supported, export as Spine 3", version)));
+        }
+    }
+
+    let mut bone_indices: HashMap<&str, usize> = HashMap::new();
+    let mut bones = Vec::new();
+    for (index, bone) in file.bones.iter().enumerate() {
+        let parent = match &bone.parent {
+            Some(parent) => match bone_indices.get(parent.as_str()) {
+                Some(&parent) => Some(parent),
+                None if file.bones.iter().any(|other| &other.name == parent) =>
+                    return Err(SkeletonError::ParentAfterChild(bone.name.clone())),
+                None => return Err(SkeletonError::UnknownBone(parent.clone()))
+            },
+            None => None
+        };
+        let setup = BoneTransform {
+            position: scale * Point::new(bone.x, bone.y),
+            rotation: Rotation::new(bone.rotation.to_radians()),
+            scale: Point::new(bone.scale_x, bone.scale_y)
+        };
+        bones.push(Bone { length: bone.length * scale, ..Bone::new(&bone.name, parent, setup) });
+        bone_indices.insert(&bone.name, index);
+    }
+
+    let mut animations = Vec::new();
+    for (name, animation) in &file.animations {
+        let mut timelines = Vec::new();
+        for (bone_name, keys) in &animation.bones {
+            let bone = *bone_indices.get(bone_name.as_str()).ok_or_else(|| SkeletonError::UnknownBone(bone_name.clone()))?;
+            timelines.push(BoneTimeline::new(bone)
+                .with_rotation(to_keyframes(&keys.rotate, |key| {
+                    Rotation::new(key.angle.or(key.value).unwrap_or(0.0).to_radians())
+                }))
+                .with_translation(to_keyframes(&keys.translate, |key| {
+                    scale * Point::new(key.x.unwrap_or(0.0), key.y.unwrap_or(0.0))
+                }))
+                .with_scale(to_keyframes(&keys.scale, |key| Point::new(key.x.unwrap_or(1.0), key.y.unwrap_or(1.0)))));
+        }
+        animations.push(SkeletalAnimation::new(name, timelines));
+    }
+    animations.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok((Skeleton::new(bones)?, animations))
+}
+
+pub fn load_spine<P: AsRef<Path>>(path: P, scale: f64) -> Result<(Skeleton, Vec<SkeletalAnimation>), SkeletonError> {
+    from_spine_json(&fs::read_to_string(path)?, scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPINE_JSON: &str = r#"{
+        "skeleton": { "spine": "3.8.99" },
+        "bones": [
+            { "name": "root" },
+            { "name": "arm", "parent": "root", "length": 50, "x": 100, "rotation": 90 }
+        ],
+        "animations": {
+            "wave": {
+                "bones": {
+                    "arm": {
+                        "rotate": [
+                            { "curve": "stepped" },
+                            { "time": 0.5, "angle": -90, "curve": [0.25, 0, 0.75, 1] },
+                            { "time": 1, "angle": 0 }
+                        ]
+                    }
+                }
+            }
+        }
+    }"#;
+
+    #[test]
+    fn spine_skeletons_are_imported() {
+        let (mut skeleton, animations) = from_spine_json(SPINE_JSON, 0.01).unwrap();
+        let arm = skeleton.find_bone("arm").unwrap();
+        assert_eq!(skeleton.get_bones()[arm].length, 0.5);
+        let tip = skeleton.get_world_position(arm, Point::new(0.5, 0.0)).unwrap();
+        assert!((tip.x - 1.0).abs() < 1e-9 && (tip.y - 0.5).abs() < 1e-9);
+
+        let wave = &animations[0];
+        assert_eq!((wave.name.as_str(), wave.get_duration()), ("wave", 1.0));
+        assert!((wave.sample(&skeleton, 0.49)[arm].rotation.angle - 90f64.to_radians()).abs() < 1e-9);
+        skeleton.set_pose(wave.sample(&skeleton, 0.5)).unwrap();
+        assert!(skeleton.get_pose()[arm].rotation.angle.abs() < 1e-9);
+
+        let missing_parent = r#"{ "bones": [{ "name": "arm", "parent": "root" }] }"#;
+        assert!(matches!(from_spine_json(missing_parent, 1.0), Err(SkeletonError::UnknownBone(name)) if name == "root"));
+        let late_parent = r#"{ "bones": [{ "name": "arm", "parent": "root" }, { "name": "root" }] }"#;
+        assert!(matches!(from_spine_json(late_parent, 1.0), Err(SkeletonError::ParentAfterChild(name)) if name == "arm"));
+        let spine_4 = r#"{ "skeleton": { "spine": "4.1.17" }, "bones": [{ "name": "root" }] }"#;
+        assert!(matches!(from_spine_json(spine_4, 1.0), Err(SkeletonError::InvalidFile(_))));
+        assert!(matches!(from_spine_json("{", 1.0), Err(SkeletonError::InvalidFile(_))));
+    }
+}
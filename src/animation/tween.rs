@@ -0,0 +1,354 @@
+use std::f64::consts::PI;
+use crate::animation::AnimationFunctionEnum;
+use crate::geometry::{Point, Point3, Rotation};
+use crate::rendering::Color;
+
+/// Values that can be animated between keyframes
+pub trait Tweenable: Copy {
+    /// t from 0 (self) to 1 (other), though easings like Back and Elastic go beyond
+    fn tween(self, other: Self, t: f64) -> Self;
+}
+
+impl Tweenable for f64 {
+    fn tween(self, other: Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Tweenable for Point {
+    fn tween(self, other: Self, t: f64) -> Self {
+        self.interpolate(&other, t)
+    }
+}
+
+impl Tweenable for Point3 {
+    fn tween(self, other: Self, t: f64) -> Self {
+        self.interpolate(&other, t)
+    }
+}
+
+impl Tweenable for Color {
+    fn tween(self, other: Self, t: f64) -> Self {
+        self.interpolate(other, t)
+    }
+}
+
+/// Turns the short way round
+impl Tweenable for Rotation {
+    fn tween(self, other: Self, t: f64) -> Self {
+        let turn = (other.angle - self.angle + PI).rem_euclid(2.0 * PI) - PI;
+        Rotation::new(self.angle + turn * t)
+    }
+}
+
+/// A value at a time, eased into from the keyframe before
+#[derive(Copy, Clone, Debug)]
+pub struct Keyframe<T> {
+    pub time: f64,
+    pub value: T,
+    pub easing: AnimationFunctionEnum
+}
+
+impl<T> Keyframe<T> {
+    pub fn new(time: f64, value: T, easing: AnimationFunctionEnum) -> Self {
+        Self {
+            time,
+            value,
+            easing
+        }
+    }
+}
+
+/// Something animated over time, setting values on a target S (e.g. a sprite, or just the value)
+pub trait Tween<S> {
+    /// In seconds
+    fn get_duration(&self) -> f64;
+    /// Sets the target as it is at a time in seconds from the tween's start, clamped to the tween
+    fn apply(&self, time: f64, target: &mut S);
+}
+
+/// Keyframes for one value of the target
+pub struct KeyframeTrack<S, T> {
+    /// In time order
+    keyframes: Vec<Keyframe<T>>,
+    property: fn(&mut S) -> &mut T
+}
+
+impl<T: Tweenable> KeyframeTrack<T, T> {
+    /// Animates the target itself
+    pub fn new(keyframes: Vec<Keyframe<T>>) -> Self {
+        Self::on(|value| value, keyframes)
+    }
+}
+
+impl<S, T: Tweenable> KeyframeTrack<S, T> {
+    /// Animates one of the target's values, e.g. KeyframeTrack::on(|sprite: &mut Sprite| &mut sprite.position, ..)
+    pub fn on(property: fn(&mut S) -> &mut T, mut keyframes: Vec<Keyframe<T>>) -> Self {
+        keyframes.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(std::cmp::Ordering::Equal));
+        Self {
+            keyframes,
+            property
+        }
+    }
+
+    /// The first keyframe's value before it and the last's after it, None without keyframes
+    pub fn get_value(&self, time: f64) -> Option<T> {
+        let next_index = self.keyframes.partition_point(|keyframe| keyframe.time <= time);
+        if next_index == 0 {
+            return self.keyframes.first().map(|keyframe| keyframe.value);
+        }
+        if next_index == self.keyframes.len() {
+            return self.keyframes.last().map(|keyframe| keyframe.value);
+        }
+
+        let previous = &self.keyframes[next_index - 1];
+        let next = &self.keyframes[next_index];
+        let t = (time - previous.time) / (next.time - previous.time);
+        Some(previous.value.tween(next.value, next.easing.apply(t)))
+    }
+}
+
+impl<S, T: Tweenable> Tween<S> for KeyframeTrack<S, T> {
+    fn get_duration(&self) -> f64 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    fn apply(&self, time: f64, target: &mut S) {
+        if let Some(value) = self.get_value(time) {
+            *(self.property)(target) = value;
+        }
+    }
+}
+
+/// Tweens one after another
+pub struct Sequence<S> {
+    tweens: Vec<Box<dyn Tween<S>>>
+}
+
+impl<S> Sequence<S> {
+    pub fn new(tweens: Vec<Box<dyn Tween<S>>>) -> Self {
+        Self {
+            tweens
+        }
+    }
+}
+
+impl<S> Tween<S> for Sequence<S> {
+    fn get_duration(&self) -> f64 {
+        self.tweens.iter().map(|tween| tween.get_duration()).sum()
+    }
+
+    /// Tweens that have started are applied in order, so later ones win over earlier ones
+    fn apply(&self, time: f64, target: &mut S) {
+        let mut start = 0.0;
+        for (index, tween) in self.tweens.iter().enumerate() {
+            if index > 0 && time < start {
+                break;
+            }
+            tween.apply(time - start, target);
+            start += tween.get_duration();
+        }
+    }
+}
+
+/// Tweens all at once, lasting as long as the longest. Where they set the same value the last wins.
+pub struct Parallel<S> {
+    tweens: Vec<Box<dyn Tween<S>>>
+}
+
+impl<S> Parallel<S> {
+    pub fn new(tweens: Vec<Box<dyn Tween<S>>>) -> Self {
+        Self {
+            tweens
+        }
+    }
+}
+
+impl<S> Tween<S> for Parallel<S> {
+    fn get_duration(&self) -> f64 {
+        self.tweens.iter().map(|tween| tween.get_duration()).fold(0.0, f64::max)
+    }
+
+    fn apply(&self, time: f64, target: &mut S) {
+        for tween in &self.tweens {
+            tween.apply(time, target);
+        }
+    }
+}
+
+/// Holds a tween at its start for a while before playing it
+pub struct Delay<S> {
+    delay: f64,
+    tween: Box<dyn Tween<S>>
+}
+
+impl<S> Delay<S> {
+    pub fn new(delay: f64, tween: Box<dyn Tween<S>>) -> Self {
+        Self {
+            delay,
+            tween
+        }
+    }
+}
+
+impl<S> Tween<S> for Delay<S> {
+    fn get_duration(&self) -> f64 {
+        self.delay + self.tween.get_duration()
+    }
+
+    fn apply(&self, time: f64, target: &mut S) {
+        self.tween.apply((time - self.delay).max(0.0), target);
+    }
+}
+
+/// Plays a tween a number of times over
+pub struct Repeat<S> {
+    times: u32,
+    tween: Box<dyn Tween<S>>
+}
+
+impl<S> Repeat<S> {
+    pub fn new(times: u32, tween: Box<dyn Tween<S>>) -> Self {
+        Self {
+            times,
+            tween
+        }
+    }
+}
+
+impl<S> Tween<S> for Repeat<S> {
+    fn get_duration(&self) -> f64 {
+        self.tween.get_duration() * self.times as f64
+    }
+
+    fn apply(&self, time: f64, target: &mut S) {
+        let duration = self.tween.get_duration();
+        let time = if duration <= 0.0 || time >= self.get_duration() {
+            duration
+        } else {
+            time.max(0.0) % duration
+        };
+        self.tween.apply(time, target);
+    }
+}
+
+/// Plays a tween over time. Call update every frame, then apply to set the target's values.
+pub struct TweenPlayer<S> {
+    tween: Box<dyn Tween<S>>,
+    time: f64
+}
+
+impl<S> TweenPlayer<S> {
+    pub fn new(tween: Box<dyn Tween<S>>) -> Self {
+        Self {
+            tween,
+            time: 0.0
+        }
+    }
+
+    pub fn update(&mut self, t_step: f64) {
+        self.time = (self.time + t_step).min(self.tween.get_duration());
+    }
+
+    pub fn apply(&self, target: &mut S) {
+        self.tween.apply(self.time, target);
+    }
+
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.time >= self.tween.get_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::EasingCurve;
+
+    struct Sprite {
+        position: Point,
+        rotation: Rotation,
+        alpha: f64
+    }
+
+    fn assert_near(value: f64, expected: f64) {
+        assert!((value - expected).abs() < 1e-9, "{} is not {}", value, expected);
+    }
+
+    #[test]
+    fn keyframes_ease_between_values() {
+        let track = KeyframeTrack::new(vec![
+            Keyframe::new(1.0, 10.0, AnimationFunctionEnum::Linear),
+            Keyframe::new(0.0, 0.0, AnimationFunctionEnum::Linear),
+            Keyframe::new(2.0, 20.0, AnimationFunctionEnum::EaseIn(EasingCurve::Quad))
+        ]);
+        assert_eq!(track.get_value(-1.0), Some(0.0));
+        assert_eq!(track.get_value(0.5), Some(5.0));
+        assert_eq!(track.get_value(1.5), Some(12.5));
+        assert_eq!(track.get_value(3.0), Some(20.0));
+        assert_eq!(KeyframeTrack::<f64, f64>::new(vec![]).get_value(1.0), None);
+
+        let turn = Rotation::new(0.9 * PI).tween(Rotation::new(-0.9 * PI), 0.5);
+        assert_near(turn.angle, PI);
+    }
+
+    #[test]
+    fn easings_start_at_zero_and_end_at_one() {
+        let curves = [EasingCurve::Quad, EasingCurve::Cubic, EasingCurve::Quart, EasingCurve::Quint, EasingCurve::Sine,
+            EasingCurve::Expo, EasingCurve::Circ, EasingCurve::Back, EasingCurve::Elastic, EasingCurve::Bounce];
+        for &curve in curves.iter() {
+            for &easing in [AnimationFunctionEnum::EaseIn(curve), AnimationFunctionEnum::EaseOut(curve), AnimationFunctionEnum::EaseInOut(curve)].iter() {
+                assert!(easing.apply(0.0).abs() < 1e-3, "{:?}", easing);
+                assert!((easing.apply(1.0) - 1.0).abs() < 1e-3, "{:?}", easing);
+            }
+        }
+        assert!(AnimationFunctionEnum::EaseIn(EasingCurve::Back).apply(0.2) < 0.0);
+
+        let ease = AnimationFunctionEnum::CubicBezier(0.25, 0.1, 0.25, 1.0);
+        assert_near(ease.apply(0.0), 0.0);
+        assert_near(ease.apply(1.0), 1.0);
+        assert!((AnimationFunctionEnum::CubicBezier(0.0, 0.0, 1.0, 1.0).apply(0.3) - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn combinators_share_a_target() {
+        let slide = KeyframeTrack::on(|sprite: &mut Sprite| &mut sprite.position, vec![
+            Keyframe::new(0.0, Point::zero(), AnimationFunctionEnum::Linear),
+            Keyframe::new(1.0, Point::new(2.0, 0.0), AnimationFunctionEnum::Linear)
+        ]);
+        let spin = KeyframeTrack::on(|sprite: &mut Sprite| &mut sprite.rotation, vec![
+            Keyframe::new(0.0, Rotation::new(0.0), AnimationFunctionEnum::Linear),
+            Keyframe::new(0.5, Rotation::new(1.0), AnimationFunctionEnum::Linear)
+        ]);
+        let fade = KeyframeTrack::on(|sprite: &mut Sprite| &mut sprite.alpha, vec![
+            Keyframe::new(0.0, 1.0, AnimationFunctionEnum::Linear),
+            Keyframe::new(1.0, 0.0, AnimationFunctionEnum::Linear)
+        ]);
+        let tween = Sequence::new(vec![
+            Box::new(Parallel::new(vec![Box::new(slide), Box::new(Repeat::new(2, Box::new(spin)))])),
+            Box::new(Delay::new(0.5, Box::new(fade)))
+        ]);
+        assert_eq!(tween.get_duration(), 2.5);
+
+        let mut sprite = Sprite { position: Point::new(9.0, 9.0), rotation: Rotation::new(9.0), alpha: 9.0 };
+        let mut player = TweenPlayer::new(Box::new(tween));
+        player.apply(&mut sprite);
+        assert_eq!((sprite.position.x, sprite.rotation.angle, sprite.alpha), (0.0, 0.0, 9.0));
+
+        player.update(0.75);
+        player.apply(&mut sprite);
+        assert_eq!((sprite.position.x, sprite.rotation.angle, sprite.alpha), (1.5, 0.5, 9.0));
+
+        player.update(0.5);
+        player.apply(&mut sprite);
+        assert_eq!((sprite.position.x, sprite.rotation.angle, sprite.alpha), (2.0, 1.0, 1.0));
+
+        player.update(10.0);
+        player.apply(&mut sprite);
+        assert!(player.is_finished());
+        assert_eq!(sprite.alpha, 0.0);
+    }
+}
@@ -1,11 +1,17 @@
 use crate::collision::{CollisionObjectResults, Collidable, CollisionDetails, CollisionObjectState, 
-                CollisionDataType, collision_logic, CollisionResults};
+                CollisionDataType, collision_logic, CollisionResults, collision_debug};
 use crate::debug::*;
 
 pub struct Collider;
 
 impl Collider {
     pub fn process_all<T: Clone + CollisionDataType> (mut collidables: Vec<&mut dyn Collidable<Data=T>>) {
+        let debug_draw = collision_debug::collision_debug_enabled();
+        if debug_draw {
+            let states: Vec<_> = collidables.iter().flat_map(|collidable| collidable.get_collision_objects()).collect();
+            collision_debug::with_collision_debug(|debug_draw| debug_draw.record_states(states));
+        }
+
         loop {
             if let Some ((first_collidable, rest)) = collidables.split_last_mut() {
                 for second_collidable in rest.into_iter() {
@@ -15,12 +21,18 @@ impl Collider {
 
                         if T::can_collide(&data1, &data2) {
                             if let Some((details1, details2)) = Collider::process_pair_of_collidables(*first_collidable, *second_collidable) {
+                                if debug_draw {
+                                    Collider::record_contacts(*first_collidable, *second_collidable, &details1, &details2);
+                                }
                                 first_collidable.add_collision_results(CollisionResults::new(details1, data2));
                                 second_collidable.add_collision_results(CollisionResults::new(details2, data1));
                             }
                         }
                     } else {
                         if let Some((details1, details2)) = Collider::process_pair_of_collidables(*first_collidable, *second_collidable) {
+                            if debug_draw {
+                                Collider::record_contacts(*first_collidable, *second_collidable, &details1, &details2);
+                            }
                             let data1 = first_collidable.get_own_collision_data();
                             let data2 = second_collidable.get_own_collision_data();
 
@@ -37,6 +49,15 @@ impl Collider {
         }
     }
 
+    fn record_contacts<T: Clone>(first: &dyn Collidable<Data=T>, second: &dyn Collidable<Data=T>,
+                                 details1: &CollisionDetails, details2: &CollisionDetails) {
+        let (states1, states2) = (first.get_collision_objects(), second.get_collision_objects());
+        collision_debug::with_collision_debug(|debug_draw| {
+            debug_draw.record_contact(&states1, details1.clone());
+            debug_draw.record_contact(&states2, details2.clone());
+        });
+    }
+
     fn process_pair_of_collidables<T: Clone> (first: &dyn Collidable<Data=T>, second: &dyn Collidable<Data=T>) 
         -> Option<(CollisionDetails, CollisionDetails)> {
 
@@ -0,0 +1,205 @@
+use crate::collision::*;
+use crate::geometry::*;
+use crate::rendering::*;
+use crate::debug::*;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref COLLISION_DEBUG: Mutex<CollisionDebugDraw> = Mutex::new(CollisionDebugDraw::default());
+}
+
+/// Collider records what it sees while the collision log target is at Debug (e.g. DebugFlags::DEBUGCOLLISION)
+pub fn collision_debug_enabled() -> bool {
+    log_enabled(LogLevel::Debug, LogTarget::Collision)
+}
+
+pub fn with_collision_debug<F: FnOnce(&mut CollisionDebugDraw) -> R, R>(action: F) -> R {
+    action(&mut COLLISION_DEBUG.lock().unwrap())
+}
+
+/// Drops the shapes recorded in an earlier frame that weren't drawn, e.g. while paused
+pub fn begin_collision_debug_frame() {
+    with_collision_debug(|debug_draw| debug_draw.begin_frame());
+}
+
+/// Renderables for everything Collider recorded since the last call, see CollisionDebugDraw::take_renderables
+pub fn take_collision_debug_renderables() -> Vec<Box<StandardRenderable>> {
+    with_collision_debug(|debug_draw| debug_draw.take_renderables())
+}
+
+#[derive(Clone)]
+pub struct ContactRecord {
+    pub object: CollisionObject,
+    pub details: CollisionDetails,
+    /// Frames drawn since the contact happened
+    pub age: usize
+}
+
+/// Debug drawing of every collider Collider processes: shape now and before (with the swept path
+/// between their centers), bounds of the whole sweep and the latest contacts with their normal and time
+pub struct CollisionDebugDraw {
+    pub shape_color: Color,
+    pub previous_shape_color: Color,
+    pub bounds_color: Color,
+    pub contact_color: Color,
+    pub line_thickness: f64,
+    pub text_size: f64,
+    /// Drawn in front of the scene (in world coordinates)
+    pub depth: f64,
+    /// Frames a contact stays drawn for
+    pub contact_frames: usize,
+    /// The oldest contacts are dropped past this, as they only age when drawn
+    pub max_contacts: usize,
+    states: Vec<CollisionObjectState>,
+    contacts: Vec<ContactRecord>
+}
+
+impl CollisionDebugDraw {
+    pub fn begin_frame(&mut self) {
+        self.states.clear();
+    }
+
+    pub fn record_states(&mut self, states: Vec<CollisionObjectState>) {
+        self.states.extend(states);
+    }
+
+    /// Contact on the object at details.location of states
+    pub fn record_contact(&mut self, states: &[CollisionObjectState], details: CollisionDetails) {
+        if let Some(state) = states.get(details.location) {
+            let (object, _) = state.clone().split();
+            self.contacts.push(ContactRecord {
+                object,
+                details,
+                age: 0
+            });
+            if self.contacts.len() > self.max_contacts {
+                let excess = self.contacts.len() - self.max_contacts;
+                self.contacts.drain(..excess);
+            }
+        }
+    }
+
+    pub fn get_contacts(&self) -> &[ContactRecord] {
+        &self.contacts
+    }
+
+    /// Clears the recorded shapes and ages the contacts, dropping old ones
+    pub fn take_renderables(&mut self) -> Vec<Box<StandardRenderable>> {
+        let mut renderables = Vec::new();
+        for state in self.states.drain(..) {
+            let (next, prev) = state.split();
+            renderables.append(&mut prev.render(self.previous_shape_color, self.depth, false));
+            renderables.append(&mut next.render(self.shape_color, self.depth, false));
+
+            if let (Some(next_center), Some(prev_center)) = (next.get_center(), prev.get_center()) {
+                if (next_center - prev_center).norm() > 0.0 {
+                    renderables.push(Box::new(LineRenderable::new_square(prev_center, next_center, self.line_thickness, self.bounds_color, self.depth, false)));
+                }
+            }
+            if let Some((min, max)) = get_combined_bounds(&[&next, &prev]) {
+                let center = 0.5 * (min + max);
+                let dim = max - min;
+                renderables.push(Box::new(BoxBorder::new(self.line_thickness, Point3::new(center.x, center.y, self.depth), dim.y, dim.x, self.bounds_color, false)));
+            }
+        }
+
+        for contact in self.contacts.iter() {
+            renderables.append(&mut contact.object.render_collision_details(contact.details.object_details.clone(), self.contact_color, self.depth, false));
+            if let Some(center) = contact.object.get_center() {
+                renderables.push(Box::new(PlainText {
+                    content: format!("t = {:.3}", contact.details.time),
+                    position: Point3::new(center.x, center.y, self.depth),
+                    scale: Point::new(self.text_size, self.text_size),
+                    color: self.contact_color,
+                    fixed: false,
+                    ..Default::default()
+                }));
+            }
+        }
+        let contact_frames = self.contact_frames;
+        self.contacts.iter_mut().for_each(|contact| contact.age += 1);
+        self.contacts.retain(|contact| contact.age < contact_frames);
+
+        renderables
+    }
+}
+
+impl Default for CollisionDebugDraw {
+    fn default() -> Self {
+        Self {
+            shape_color: Color::new(0.2, 0.9, 0.9, 0.35),
+            previous_shape_color: Color::new(0.2, 0.9, 0.9, 0.15),
+            bounds_color: Color::new(0.9, 0.9, 0.2, 0.8),
+            contact_color: Color::new(1.0, 0.3, 0.3, 1.0),
+            line_thickness: 0.005,
+            text_size: 0.04,
+            depth: -0.2,
+            contact_frames: 60,
+            max_contacts: 256,
+            states: Vec::new(),
+            contacts: Vec::new()
+        }
+    }
+}
+
+/// Smallest axis aligned box (min, max) around all the objects
+pub fn get_combined_bounds(objects: &[&CollisionObject]) -> Option<(Point, Point)> {
+    objects.iter()
+        .filter_map(|object| object.get_bounds())
+        .fold(None, |acc, (min, max)| match acc {
+            None => Some((min, max)),
+            Some((acc_min, acc_max)) => Some((
+                Point::new(acc_min.x.min(min.x), acc_min.y.min(min.y)),
+                Point::new(acc_max.x.max(max.x), acc_max.y.max(max.y))
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_cover_the_whole_sweep() {
+        let next = CollisionObject::Circ(Circle::new(0.5, Point::new(2.0, 0.0)));
+        let prev = CollisionObject::Circ(Circle::new(0.5, Point::new(0.0, 1.0)));
+        let (min, max) = get_combined_bounds(&[&next, &prev]).unwrap();
+        assert_eq!((min.x, min.y, max.x, max.y), (-0.5, -0.5, 2.5, 1.5));
+        assert!(get_combined_bounds(&[&CollisionObject::None]).is_none());
+    }
+
+    #[test]
+    fn contacts_fade_after_their_frames() {
+        let mut debug_draw = CollisionDebugDraw {
+            contact_frames: 2,
+            ..Default::default()
+        };
+        let circle = Circle::new(1.0, Point::zero());
+        let states = vec![CollisionObjectState::Circ(circle.clone(), circle)];
+        debug_draw.record_states(states.clone());
+        debug_draw.record_contact(&states, CollisionDetails::new(0, CollisionObjectDetails::Circ(Point::new(1.0, 0.0)), 0.25));
+
+        debug_draw.take_renderables();
+        assert_eq!(debug_draw.get_contacts().len(), 1);
+        debug_draw.take_renderables();
+        assert!(debug_draw.get_contacts().is_empty());
+    }
+
+    #[test]
+    fn undrawn_frames_do_not_pile_up() {
+        let mut debug_draw = CollisionDebugDraw {
+            max_contacts: 3,
+            ..Default::default()
+        };
+        let circle = Circle::new(1.0, Point::zero());
+        let states = vec![CollisionObjectState::Circ(circle.clone(), circle)];
+        for frame in 0..5 {
+            debug_draw.begin_frame();
+            debug_draw.record_states(states.clone());
+            debug_draw.record_contact(&states, CollisionDetails::new(0, CollisionObjectDetails::Circ(Point::zero()), frame as f64));
+        }
+        assert_eq!(debug_draw.states.len(), 1);
+        let times: Vec<f64> = debug_draw.get_contacts().iter().map(|contact| contact.details.time).collect();
+        assert_eq!(times, vec![2.0, 3.0, 4.0]);
+    }
+}
@@ -39,6 +39,35 @@ impl CollisionObject {
         }
     }
 
+    pub fn get_center(&self) -> Option<Point> {
+        match *self {
+            CollisionObject::None => None,
+            CollisionObject::Circ(ref circle) => Some(circle.center),
+            CollisionObject::ConPoly(ref con_poly) => Some(con_poly.get_center()),
+            CollisionObject::Line(line) => Some(line.get_center()),
+            CollisionObject::Point(point) => Some(point)
+        }
+    }
+
+    /// Axis aligned bounding box as (min, max)
+    pub fn get_bounds(&self) -> Option<(Point, Point)> {
+        let points = match *self {
+            CollisionObject::None => return None,
+            CollisionObject::Circ(ref circle) => {
+                let rad = Point::new(circle.rad, circle.rad);
+                return Some((circle.center - rad, circle.center + rad));
+            },
+            CollisionObject::ConPoly(ref con_poly) => con_poly.corners.clone(),
+            CollisionObject::Line(line) => vec![line.beg, line.end],
+            CollisionObject::Point(point) => vec![point]
+        };
+        let first = *points.first()?;
+        Some(points.iter().fold((first, first), |(min, max), point| (
+            Point::new(min.x.min(point.x), min.y.min(point.y)),
+            Point::new(max.x.max(point.x), max.y.max(point.y))
+        )))
+    }
+
     pub fn render(&self, color: Color, depth: f64, fixed: bool) -> Vec<Box<StandardRenderable>> {
         match self {
             &CollisionObject::None => vec![],
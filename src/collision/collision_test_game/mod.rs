@@ -2,7 +2,7 @@ use crate::collision::{Collider, Collidable};
 use crate::games::Game;
 use crate::rendering::*;
 use crate::input::mouse::MouseInput;
-use crate::input::keyboard::KeyboardInput;
+use crate::input::keyboard::{KeyboardInput, KeyCode};
 use crate::input::bool_switch::BoolSwitch;
 use crate::games;
 use crate::geometry::{ConPoly, Line, Circle, Point};
@@ -125,14 +125,14 @@ impl CollisionTestGame {
     }
 
     fn update_switches(&mut self) {
-        self.game_input.right_switch.update_state(self.external_input.kbd.devices[0].right);
-        self.game_input.left_switch.update_state(self.external_input.kbd.devices[0].left);        
+        self.game_input.right_switch.update_state(self.external_input.kbd.devices[0].get_key(KeyCode::Right));
+        self.game_input.left_switch.update_state(self.external_input.kbd.devices[0].get_key(KeyCode::Left));        
     }
 
     fn update_object_controls(&mut self) {
-        self.game_input.mov_horizontal = self.external_input.kbd.devices[0].d as isize - (self.external_input.kbd.devices[0].a as isize);
-        self.game_input.mov_vertical = self.external_input.kbd.devices[0].w as isize - (self.external_input.kbd.devices[0].s as isize);
-        self.game_input.rot = self.external_input.kbd.devices[0].e as isize - (self.external_input.kbd.devices[0].q as isize);
+        self.game_input.mov_horizontal = self.external_input.kbd.devices[0].get_key(KeyCode::D) as isize - (self.external_input.kbd.devices[0].get_key(KeyCode::A) as isize);
+        self.game_input.mov_vertical = self.external_input.kbd.devices[0].get_key(KeyCode::W) as isize - (self.external_input.kbd.devices[0].get_key(KeyCode::S) as isize);
+        self.game_input.rot = self.external_input.kbd.devices[0].get_key(KeyCode::E) as isize - (self.external_input.kbd.devices[0].get_key(KeyCode::Q) as isize);
     }
 
     fn update_player_control(&mut self) {
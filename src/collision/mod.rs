@@ -13,6 +13,7 @@ pub mod collision_object_details;
 pub mod collision_details;
 pub mod collider;
 pub mod collision_data_type;
+pub mod collision_debug;
 
 pub use self::collision_object::{CollisionObject, ToCollisionObjects};
 pub use self::collision_test_game::CollisionTestGame;
@@ -23,6 +24,7 @@ pub use self::collision_object_details::{CollisionObjectDetails, ConPolyInfo, Li
 pub use self::collision_details::CollisionDetails;
 pub use self::collider::Collider;
 pub use self::collision_data_type::CollisionDataType;
+pub use self::collision_debug::{CollisionDebugDraw, ContactRecord, collision_debug_enabled, with_collision_debug, begin_collision_debug_frame, take_collision_debug_renderables};
 
 pub trait Collidable {
     type Data: Clone;
@@ -55,3 +57,16 @@ pub enum CollisionObjectState {
     Line(Line, Line),
     Point(Point, Point)
 }
+
+impl CollisionObjectState {
+    /// The (next, previous) objects the state was built from
+    pub fn split(self) -> (CollisionObject, CollisionObject) {
+        match self {
+            CollisionObjectState::None => (CollisionObject::None, CollisionObject::None),
+            CollisionObjectState::Circ(next, prev) => (CollisionObject::Circ(next), CollisionObject::Circ(prev)),
+            CollisionObjectState::ConPoly(next, prev) => (CollisionObject::ConPoly(next), CollisionObject::ConPoly(prev)),
+            CollisionObjectState::Line(next, prev) => (CollisionObject::Line(next), CollisionObject::Line(prev)),
+            CollisionObjectState::Point(next, prev) => (CollisionObject::Point(next), CollisionObject::Point(prev))
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+
+/// Text printed back to the console, Err is shown as an error
+pub type CommandResult = Result<String, String>;
+
+/// Who runs a command once the console has read it
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CommandTarget {
+    /// Handled by the console itself (help, clear)
+    Console,
+    /// Handled by the game loop (pause, step, set_debug_flags, log_level, profiler, export_trace,
+    /// record_input, write_recording)
+    Handler,
+    /// Passed on to Game::run_console_command
+    Game
+}
+
+#[derive(Clone, Debug)]
+pub struct ConsoleCommand {
+    pub name: String,
+    pub usage: String,
+    pub description: String,
+    pub target: CommandTarget
+}
+
+impl ConsoleCommand {
+    pub fn new(name: &str, usage: &str, description: &str, target: CommandTarget) -> Self {
+        Self {
+            name: name.to_string(),
+            usage: usage.to_string(),
+            description: description.to_string(),
+            target
+        }
+    }
+
+    pub fn get_help(&self) -> String {
+        format!("{} - {}", self.usage, self.description)
+    }
+}
+
+/// Every command the console knows about, kept in name order for help and tab completion
+#[derive(Clone, Debug)]
+pub struct CommandRegistry {
+    commands: BTreeMap<String, ConsoleCommand>
+}
+
+impl CommandRegistry {
+    /// Registry holding only the built in console and game loop commands
+    pub fn new() -> Self {
+        let mut registry = Self {
+            commands: BTreeMap::new()
+        };
+        registry.register(ConsoleCommand::new("help", "help [command]", "List the commands or describe one", CommandTarget::Console));
+        registry.register(ConsoleCommand::new("clear", "clear", "Clear the console history", CommandTarget::Console));
+        registry.register(ConsoleCommand::new("pause", "pause", "Pause or resume the game", CommandTarget::Handler));
+        registry.register(ConsoleCommand::new("step", "step [frames]", "Pause and advance the given number of frames", CommandTarget::Handler));
+        registry.register(ConsoleCommand::new("set_debug_flags", "set_debug_flags [flag...]", "Set the debug flags by name, or show them when none are given", CommandTarget::Handler));
+        registry.register(ConsoleCommand::new("log_level", "log_level [target level]", "Set a log target's level, or show them all", CommandTarget::Handler));
+        registry.register(ConsoleCommand::new("profiler", "profiler", "Show or hide the debug clock overlay (also F7)", CommandTarget::Handler));
+        registry.register(ConsoleCommand::new("export_trace", "export_trace [path]", "Write the recent debug clocks as Chrome trace JSON", CommandTarget::Handler));
+        registry.register(ConsoleCommand::new("record_input", "record_input", "Start recording the game's input, frame by frame", CommandTarget::Handler));
+        registry.register(ConsoleCommand::new("write_recording", "write_recording [path]", "Stop recording input and write the recording", CommandTarget::Handler));
+        registry
+    }
+
+    /// A command with the same name as an existing one replaces it
+    pub fn register(&mut self, command: ConsoleCommand) {
+        self.commands.insert(command.name.clone(), command);
+    }
+
+    pub fn register_game_command(&mut self, name: &str, usage: &str, description: &str) {
+        self.register(ConsoleCommand::new(name, usage, description, CommandTarget::Game));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ConsoleCommand> {
+        self.commands.get(name)
+    }
+
+    pub fn get_commands(&self) -> Vec<&ConsoleCommand> {
+        self.commands.values().collect()
+    }
+
+    /// Names starting with prefix, in name order
+    pub fn get_completions(&self, prefix: &str) -> Vec<&str> {
+        self.commands.keys()
+            .filter(|name| name.starts_with(prefix))
+            .map(|name| name.as_str())
+            .collect()
+    }
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Splits a line into words on whitespace, double quotes keep spaces inside one word
+pub fn split_command_line(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_word = true;
+            },
+            c if c.is_whitespace() && !quoted => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            },
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+
+    words
+}
+
+/// Longest start shared by all the words
+pub fn get_common_prefix(words: &[&str]) -> String {
+    let first = match words.first() {
+        Some(first) => first,
+        None => return String::new()
+    };
+    first.char_indices()
+        .map(|(index, c)| index + c.len_utf8())
+        .take_while(|&end| words.iter().all(|word| word.starts_with(&first[..end])))
+        .last()
+        .map(|end| first[..end].to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_keeps_quoted_words_together() {
+        let words = split_command_line("  spawn \"big rock\" 3 \"\"");
+        assert_eq!(words, vec!["spawn", "big rock", "3", ""]);
+    }
+
+    #[test]
+    fn completions_are_sorted_and_prefixed() {
+        let mut registry = CommandRegistry::new();
+        registry.register_game_command("spawn", "spawn <name>", "Spawn an entity");
+        registry.register_game_command("speed", "speed <scale>", "Set the game speed");
+
+        assert_eq!(registry.get_completions("s"), vec!["set_debug_flags", "spawn", "speed", "step"]);
+        assert_eq!(registry.get_completions("sp"), vec!["spawn", "speed"]);
+        assert_eq!(get_common_prefix(&registry.get_completions("sp")), "sp");
+        assert_eq!(get_common_prefix(&registry.get_completions("spa")), "spawn");
+    }
+}
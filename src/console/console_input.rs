@@ -0,0 +1,41 @@
+use crate::input::{KeyboardInput, TextInput};
+
+/// Keys the console reads in a frame (held state, presses are found by comparing frames)
+/// and the text typed during it
+#[derive(Clone, Debug, Default)]
+pub struct ConsoleInput {
+    pub submit: bool,
+    pub complete: bool,
+    pub backspace: bool,
+    pub delete: bool,
+    pub left: bool,
+    pub right: bool,
+    pub home: bool,
+    pub end: bool,
+    pub history_back: bool,
+    pub history_forward: bool,
+    pub scroll_up: bool,
+    pub scroll_down: bool,
+    pub typed: String
+}
+
+impl ConsoleInput {
+    /// Return submits, tab completes, up/down walk the history and page up/down scroll the log
+    pub fn from_devices(kbd: &KeyboardInput, text: &TextInput) -> Self {
+        Self {
+            submit: kbd.get_ret(),
+            complete: kbd.get_tab(),
+            backspace: kbd.get_backspace(),
+            delete: kbd.get_delete(),
+            left: kbd.get_left(),
+            right: kbd.get_right(),
+            home: kbd.get_home(),
+            end: kbd.get_end(),
+            history_back: kbd.get_up(),
+            history_forward: kbd.get_down(),
+            scroll_up: kbd.get_pageup(),
+            scroll_down: kbd.get_pagedown(),
+            typed: text.get_text()
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use crate::rendering::*;
+use crate::geometry::*;
+use crate::input::{KeyboardInput, TextInput};
+use crate::games::GameInput;
+use crate::ui::{UiTheme, UiRect};
+use std::collections::VecDeque;
+use std::mem;
+use super::*;
+
+/// Oldest lines are dropped past this
+pub const CONSOLE_MAX_LINES: usize = 500;
+pub const CONSOLE_PROMPT: &str = "> ";
+/// Line height as a multiple of the text size
+pub const CONSOLE_LINE_SPACING: f64 = 1.25;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ConsoleLineKind {
+    Log,
+    Command,
+    Output,
+    Error
+}
+
+#[derive(Clone, Debug)]
+pub struct ConsoleLine {
+    pub text: String,
+    pub kind: ConsoleLineKind
+}
+
+/// Drop down developer console: shows the log history and turns typed lines into commands
+/// It only edits text, update_input hands submitted lines back so the owner can run them
+pub struct Console {
+    pub theme: UiTheme,
+    /// Fraction of the screen the console covers, from the top
+    pub height: f64,
+    pub error_color: Color,
+    pub registry: CommandRegistry,
+    keyboard: KeyboardInput,
+    text: TextInput,
+    /// The key that opened the console also types its character, which is dropped
+    skip_typed: bool,
+    input: ConsoleInput,
+    previous_input: ConsoleInput,
+    open: bool,
+    lines: VecDeque<ConsoleLine>,
+    line: String,
+    caret: usize,
+    history: Vec<String>,
+    history_index: Option<usize>,
+    draft: String,
+    /// Number of lines scrolled back from the newest
+    scroll: usize
+}
+
+impl Console {
+    pub fn new(theme: UiTheme) -> Self {
+        Self {
+            theme,
+            height: 0.5,
+            error_color: Color::new(1.0, 0.35, 0.3, 1.0),
+            registry: CommandRegistry::new(),
+            keyboard: KeyboardInput::default(),
+            text: TextInput::default(),
+            skip_typed: false,
+            input: ConsoleInput::default(),
+            previous_input: ConsoleInput::default(),
+            open: false,
+            lines: VecDeque::new(),
+            line: String::new(),
+            caret: 0,
+            history: Vec::new(),
+            history_index: None,
+            draft: String::new(),
+            scroll: 0
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        // Key releases aren't passed on while closed so start again from nothing held
+        self.keyboard = KeyboardInput::default();
+        self.text = TextInput::default();
+        self.skip_typed = true;
+        self.input = ConsoleInput::default();
+        self.previous_input = ConsoleInput::default();
+    }
+
+    pub fn get_line(&self) -> &str {
+        &self.line
+    }
+
+    pub fn get_caret(&self) -> usize {
+        self.caret
+    }
+
+    pub fn get_lines(&self) -> &VecDeque<ConsoleLine> {
+        &self.lines
+    }
+
+    pub fn get_history(&self) -> &[String] {
+        &self.history
+    }
+
+    pub fn log(&mut self, text: &str) {
+        self.push_line(text, ConsoleLineKind::Log);
+    }
+
+    pub fn print(&mut self, text: &str) {
+        self.push_line(text, ConsoleLineKind::Output);
+    }
+
+    pub fn print_error(&mut self, text: &str) {
+        self.push_line(text, ConsoleLineKind::Error);
+    }
+
+    /// Prints a command's result, nothing is printed for an empty Ok
+    pub fn print_result(&mut self, result: CommandResult) {
+        match result {
+            Ok(output) => self.print(&output),
+            Err(error) => self.print_error(&error)
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.scroll = 0;
+    }
+
+    fn push_line(&mut self, text: &str, kind: ConsoleLineKind) {
+        for line in text.lines() {
+            self.lines.push_back(ConsoleLine {
+                text: line.to_string(),
+                kind
+            });
+        }
+        while self.lines.len() > CONSOLE_MAX_LINES {
+            self.lines.pop_front();
+        }
+    }
+
+    /// Reads the keyboard passed on to the console, returning a submitted line
+    pub fn update_input(&mut self) -> Option<String> {
+        let mut input = ConsoleInput::from_devices(&self.keyboard, &self.text);
+        if self.skip_typed {
+            input.typed.clear();
+            self.skip_typed = false;
+        }
+        self.handle_input(input)
+    }
+
+    pub fn handle_input(&mut self, input: ConsoleInput) -> Option<String> {
+        self.previous_input = mem::replace(&mut self.input, input);
+
+        let typed: Vec<char> = self.input.typed.chars().collect();
+        for c in typed {
+            self.line.insert(get_byte_index(&self.line, self.caret), c);
+            self.caret += 1;
+        }
+
+        let char_count = self.line.chars().count();
+        if self.pressed(|input| input.backspace) && self.caret > 0 {
+            self.caret -= 1;
+            self.line.remove(get_byte_index(&self.line, self.caret));
+        }
+        if self.pressed(|input| input.delete) && self.caret < char_count {
+            self.line.remove(get_byte_index(&self.line, self.caret));
+        }
+        if self.pressed(|input| input.left) && self.caret > 0 {
+            self.caret -= 1;
+        }
+        if self.pressed(|input| input.right) && self.caret < self.line.chars().count() {
+            self.caret += 1;
+        }
+        if self.pressed(|input| input.home) {
+            self.caret = 0;
+        }
+        if self.pressed(|input| input.end) {
+            self.caret = self.line.chars().count();
+        }
+
+        if self.pressed(|input| input.history_back) {
+            self.step_history(true);
+        }
+        if self.pressed(|input| input.history_forward) {
+            self.step_history(false);
+        }
+
+        let page = self.get_visible_line_count() / 2;
+        if self.pressed(|input| input.scroll_up) {
+            self.scroll = (self.scroll + page).min(self.lines.len().saturating_sub(1));
+        }
+        if self.pressed(|input| input.scroll_down) {
+            self.scroll = self.scroll.saturating_sub(page);
+        }
+
+        if self.pressed(|input| input.complete) {
+            self.complete();
+        }
+
+        if self.pressed(|input| input.submit) {
+            return self.submit();
+        }
+        None
+    }
+
+    fn pressed<F: Fn(&ConsoleInput) -> bool>(&self, key: F) -> bool {
+        key(&self.input) && !key(&self.previous_input)
+    }
+
+    /// Moves through the submitted lines, the line being typed is kept to come back to
+    fn step_history(&mut self, back: bool) {
+        if self.history.is_empty() || (self.history_index.is_none() && !back) {
+            return;
+        }
+        let last = self.history.len() - 1;
+        self.history_index = match (self.history_index, back) {
+            (None, true) => {
+                self.draft = self.line.clone();
+                Some(last)
+            },
+            (None, false) => None,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index < last => Some(index + 1),
+            (Some(_), false) => None
+        };
+        self.line = match self.history_index {
+            Some(index) => self.history[index].clone(),
+            None => mem::take(&mut self.draft)
+        };
+        self.caret = self.line.chars().count();
+    }
+
+    /// Completes the command name being typed, listing the options when there's more than one
+    fn complete(&mut self) {
+        let prefix = self.line.trim_start().to_string();
+        if prefix.contains(char::is_whitespace) {
+            return;
+        }
+        let completions: Vec<String> = self.registry.get_completions(&prefix).iter().map(|name| name.to_string()).collect();
+        let completed = match completions.len() {
+            0 => return,
+            1 => format!("{} ", completions[0]),
+            _ => {
+                self.print(&completions.join("  "));
+                let names: Vec<&str> = completions.iter().map(|name| name.as_str()).collect();
+                get_common_prefix(&names)
+            }
+        };
+        self.line = completed;
+        self.caret = self.line.chars().count();
+    }
+
+    fn submit(&mut self) -> Option<String> {
+        let line = mem::take(&mut self.line);
+        self.caret = 0;
+        self.history_index = None;
+        self.draft.clear();
+        self.scroll = 0;
+        self.push_line(&format!("{}{}", CONSOLE_PROMPT, line), ConsoleLineKind::Command);
+
+        if line.trim().is_empty() {
+            return None;
+        }
+        if self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+        }
+        Some(line)
+    }
+
+    /// Runs the commands the console handles itself
+    pub fn run_console_command(&mut self, command: &str, args: &[&str]) -> CommandResult {
+        match command {
+            "help" => match args.first() {
+                Some(name) => match self.registry.get(name) {
+                    Some(command) => Ok(command.get_help()),
+                    None => Err(format!("Unknown command: {}", name))
+                },
+                None => Ok(self.registry.get_commands().iter()
+                    .map(|command| command.get_help())
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+            },
+            "clear" => {
+                self.clear();
+                Ok(String::new())
+            },
+            _ => Err(format!("{} is not a console command", command))
+        }
+    }
+
+    fn get_line_height(&self) -> f64 {
+        self.theme.text_size * CONSOLE_LINE_SPACING
+    }
+
+    fn get_rect(&self, aspect_ratio: f64) -> UiRect {
+        UiRect::new(Point::new(-aspect_ratio, 1.0 - 2.0 * self.height), Point::new(aspect_ratio, 1.0))
+    }
+
+    /// Log lines that fit above the input line
+    fn get_visible_line_count(&self) -> usize {
+        let log_height = 2.0 * self.height - 2.0 * self.theme.padding - self.get_line_height();
+        (log_height / self.get_line_height()).max(0.0) as usize
+    }
+
+    fn get_line_color(&self, kind: ConsoleLineKind) -> Color {
+        match kind {
+            ConsoleLineKind::Log | ConsoleLineKind::Output => self.theme.text_color,
+            ConsoleLineKind::Command => self.theme.accent_color,
+            ConsoleLineKind::Error => self.error_color
+        }
+    }
+
+    /// Screen fixed renderables for the console, nothing while it's closed
+    pub fn get_renderables(&self, window_spec: WindowSpec) -> Vec<Box<StandardRenderable>> {
+        if !self.open {
+            return Vec::new();
+        }
+        let aspect_ratio = if window_spec.aspect_ratio > 0.0 { window_spec.aspect_ratio } else { 1.0 };
+        let rect = self.get_rect(aspect_ratio);
+        let center = rect.get_center();
+        let depth = self.theme.depth;
+        let left = rect.min.x + self.theme.padding;
+        let line_height = self.get_line_height();
+        let style = TextStyle::new(DEFAULT_FONT, self.theme.text_size, self.theme.text_color);
+
+        let mut renderables: Vec<Box<StandardRenderable>> = Vec::new();
+        renderables.push(Box::new(RectanglePrimitive::new_regular(rect.get_width(), rect.get_height(), Point3::new(center.x, center.y, depth), self.theme.panel_color, true)));
+
+        let input_baseline = rect.min.y + self.theme.padding + (line_height - self.theme.text_size) / 2.0;
+        let input_text = format!("{}{}", CONSOLE_PROMPT, self.line);
+        renderables.push(Box::new(self.get_text(input_text, left, input_baseline, self.theme.text_color)));
+
+        let caret_text: String = self.line.chars().take(self.caret).collect();
+        let caret_x = left + measure_text_width(&format!("{}{}", CONSOLE_PROMPT, caret_text), &style);
+        let caret_y = input_baseline + self.theme.text_size / 2.0;
+        renderables.push(Box::new(RectanglePrimitive::new_regular(self.theme.border_thickness, self.theme.text_size, Point3::new(caret_x, caret_y, depth), self.theme.text_color, true)));
+
+        let shown = self.lines.iter().rev().skip(self.scroll).take(self.get_visible_line_count());
+        for (index, line) in shown.enumerate() {
+            if line.text.trim().is_empty() {
+                continue;
+            }
+            let baseline = input_baseline + (index + 1) as f64 * line_height;
+            renderables.push(Box::new(self.get_text(line.text.clone(), left, baseline, self.get_line_color(line.kind))));
+        }
+
+        renderables
+    }
+
+    fn get_text(&self, content: String, left: f64, baseline: f64, color: Color) -> PlainText {
+        let size = self.theme.text_size;
+        PlainText {
+            content,
+            position: Point3::new(left, baseline, self.theme.depth),
+            scale: Point::new(size, size),
+            color,
+            align: TextAlign::Origin,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        // In front of any UI
+        Self::new(UiTheme { depth: -0.6, ..Default::default() })
+    }
+}
+
+/// The console takes the keyboard while it's open
+impl GameInput for Console {
+    fn get_kbd_inp<'a>(&'a mut self) -> Option<&'a mut KeyboardInput> {
+        Some(&mut self.keyboard)
+    }
+
+    fn get_text_inp<'a>(&'a mut self) -> Option<&'a mut TextInput> {
+        Some(&mut self.text)
+    }
+}
+
+fn get_byte_index(text: &str, char_index: usize) -> usize {
+    text.char_indices()
+        .nth(char_index)
+        .map(|(index, _)| index)
+        .unwrap_or_else(|| text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_line(console: &mut Console, line: &str) -> Option<String> {
+        for c in line.chars() {
+            console.handle_input(ConsoleInput { typed: c.to_string(), ..Default::default() });
+            console.handle_input(ConsoleInput::default());
+        }
+        console.handle_input(ConsoleInput { submit: true, ..Default::default() })
+    }
+
+    #[test]
+    fn submitted_lines_go_into_history() {
+        let mut console = Console::default();
+        assert_eq!(type_line(&mut console, "pause"), Some("pause".to_string()));
+        console.handle_input(ConsoleInput::default());
+        assert_eq!(type_line(&mut console, "step 2"), Some("step 2".to_string()));
+        console.handle_input(ConsoleInput::default());
+
+        console.handle_input(ConsoleInput { typed: "x".to_string(), ..Default::default() });
+        console.handle_input(ConsoleInput { history_back: true, ..Default::default() });
+        assert_eq!(console.get_line(), "step 2");
+        console.handle_input(ConsoleInput::default());
+        console.handle_input(ConsoleInput { history_back: true, ..Default::default() });
+        assert_eq!(console.get_line(), "pause");
+        console.handle_input(ConsoleInput::default());
+        console.handle_input(ConsoleInput { history_forward: true, ..Default::default() });
+        console.handle_input(ConsoleInput::default());
+        console.handle_input(ConsoleInput { history_forward: true, ..Default::default() });
+        assert_eq!(console.get_line(), "x");
+        console.handle_input(ConsoleInput::default());
+        console.handle_input(ConsoleInput { history_forward: true, ..Default::default() });
+        assert_eq!(console.get_line(), "x");
+        assert_eq!(console.get_history(), &["pause".to_string(), "step 2".to_string()]);
+    }
+
+    #[test]
+    fn tab_completes_command_names() {
+        let mut console = Console::default();
+        console.handle_input(ConsoleInput { typed: "c".to_string(), ..Default::default() });
+        console.handle_input(ConsoleInput { complete: true, ..Default::default() });
+        assert_eq!(console.get_line(), "clear ");
+        assert_eq!(console.get_caret(), 6);
+
+        console.handle_input(ConsoleInput { submit: true, ..Default::default() });
+        console.handle_input(ConsoleInput { typed: "s".to_string(), ..Default::default() });
+        console.handle_input(ConsoleInput { complete: true, ..Default::default() });
+        assert_eq!(console.get_line(), "s");
+        assert_eq!(console.get_lines().back().unwrap().text, "set_debug_flags  step");
+    }
+}
@@ -0,0 +1,7 @@
+pub mod console_input;
+pub mod console_command;
+pub mod console_overlay;
+
+pub use self::console_input::ConsoleInput;
+pub use self::console_command::{ConsoleCommand, CommandRegistry, CommandTarget, CommandResult, split_command_line, get_common_prefix};
+pub use self::console_overlay::{Console, ConsoleLine, ConsoleLineKind, CONSOLE_MAX_LINES};
@@ -0,0 +1,89 @@
+use serde_json;
+use super::clock_writer::ClockFrame;
+
+/// Complete ("X") event of the Chrome trace event format, times are in microseconds
+#[derive(Serialize, Debug)]
+struct TraceEvent<'a> {
+    name: &'a str,
+    cat: &'a str,
+    ph: &'a str,
+    ts: f64,
+    dur: f64,
+    pid: u32,
+    tid: u32
+}
+
+#[derive(Serialize, Debug)]
+struct Trace<'a> {
+    #[serde(rename = "traceEvents")]
+    trace_events: Vec<TraceEvent<'a>>,
+    #[serde(rename = "displayTimeUnit")]
+    display_time_unit: &'a str
+}
+
+/// Frames as Chrome trace event JSON (for chrome://tracing or Perfetto), time starts at the first frame
+/// Clocks nest under their frame and parents since they're on the same thread and inside their times
+pub fn to_chrome_trace(frames: &[ClockFrame]) -> serde_json::Result<String> {
+    let origin = frames.first().map(|frame| frame.start).unwrap_or(0.0);
+    let to_micros = |time: f64| time * 1.0e6;
+
+    let mut trace_events = Vec::new();
+    for frame in frames {
+        trace_events.push(TraceEvent {
+            name: "Frame",
+            cat: "frame",
+            ph: "X",
+            ts: to_micros(frame.start - origin),
+            dur: to_micros(frame.duration),
+            pid: 0,
+            tid: 0
+        });
+        for span in &frame.spans {
+            trace_events.push(TraceEvent {
+                name: &span.name,
+                cat: "clock",
+                ph: "X",
+                ts: to_micros(span.start - origin),
+                dur: to_micros(span.duration),
+                pid: 0,
+                tid: 0
+            });
+        }
+    }
+
+    serde_json::to_string(&Trace {
+        trace_events,
+        display_time_unit: "ms"
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::clock_writer::ClockSpan;
+
+    #[test]
+    fn trace_times_are_microseconds_from_first_frame() {
+        let frames = vec![
+            ClockFrame {
+                start: 10.0,
+                duration: 0.016,
+                spans: vec![ClockSpan { name: "Render::glium_load".to_string(), start: 10.004, duration: 0.002 }]
+            },
+            ClockFrame {
+                start: 10.016,
+                duration: 0.017,
+                spans: Vec::new()
+            }
+        ];
+
+        let trace: serde_json::Value = serde_json::from_str(&to_chrome_trace(&frames).unwrap()).unwrap();
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[1]["name"], "Render::glium_load");
+        assert_eq!(events[1]["ph"], "X");
+        assert!((events[1]["ts"].as_f64().unwrap() - 4000.0).abs() < 1.0e-6);
+        assert!((events[1]["dur"].as_f64().unwrap() - 2000.0).abs() < 1.0e-6);
+        assert!((events[2]["ts"].as_f64().unwrap() - 16000.0).abs() < 1.0e-6);
+    }
+}
@@ -1,11 +1,40 @@
 use crate::debug::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::mem;
 use time;
 
+/// Frames kept for the profiler overlay and trace export
+pub const CLOCK_HISTORY_FRAMES: usize = 600;
+
 #[derive(Default)]
 pub struct ClockWriter {
     clocks: HashMap<String, ClockData>,
-    global_clock: ClockData
+    global_clock: ClockData,
+    frame_spans: Vec<ClockSpan>,
+    frames: VecDeque<ClockFrame>
+}
+
+/// One run of a named clock, times are in seconds (from time::precise_time_s)
+#[derive(Clone, Debug)]
+pub struct ClockSpan {
+    pub name: String,
+    pub start: f64,
+    pub duration: f64
+}
+
+impl ClockSpan {
+    /// Number of parents in the clock's name, e.g. 1 for Render::glium_load
+    pub fn get_depth(&self) -> usize {
+        self.name.matches("::").count()
+    }
+}
+
+/// Everything timed between a debug_clock_start_main and debug_clock_stop_main
+#[derive(Clone, Debug, Default)]
+pub struct ClockFrame {
+    pub start: f64,
+    pub duration: f64,
+    pub spans: Vec<ClockSpan>
 }
 
 impl ClockWriter {
@@ -22,7 +51,15 @@ impl ClockWriter {
     }
 
     pub fn stop(&mut self) {
-        self.global_clock.stop();
+        let frame_time = self.global_clock.stop();
+        self.frames.push_back(ClockFrame {
+            start: self.global_clock.last_time,
+            duration: frame_time,
+            spans: mem::take(&mut self.frame_spans)
+        });
+        while self.frames.len() > CLOCK_HISTORY_FRAMES {
+            self.frames.pop_front();
+        }
         
         let total_time = time::precise_time_s() - self.global_clock.start_time;
         
@@ -49,23 +86,36 @@ impl ClockWriter {
 
             if parent_exists {
                 self.clocks.get_mut(&parent_key.clone()).unwrap().child_keys.push(clock_name.clone());
-                self.clocks.insert(clock_name, ClockData::new_parent(parent_key));
+                let mut clock = ClockData::new_parent(parent_key);
+                clock.start();
+                self.clocks.insert(clock_name, clock);
             }
             else {
                 return;
             }
         }
         else {
-            self.clocks.insert(clock_name, ClockData::default());
+            let mut clock = ClockData::default();
+            clock.start();
+            self.clocks.insert(clock_name, clock);
         }
     }
 
     pub fn stop_clock(&mut self, clock_name: String) {
         if let Some(clock) = self.clocks.get_mut(&clock_name) {
-            clock.stop();
+            let duration = clock.stop();
+            self.frame_spans.push(ClockSpan {
+                name: clock_name,
+                start: clock.last_time,
+                duration
+            });
         }
     }
 
+    pub fn get_frames(&self) -> Vec<ClockFrame> {
+        self.frames.iter().cloned().collect()
+    }
+
     fn log_clock_recursive(&self, key: String, prefix: String) {
         if let Some(current_clock) = self.clocks.get(&key) {
             current_clock.log(prefix.clone(), key);
@@ -119,7 +169,7 @@ impl ClockData {
         self.last_time = current_time;
     }
 
-    pub fn stop(&mut self) {
+    pub fn stop(&mut self) -> f64 {
         let cycle_time = time::precise_time_s() - self.last_time;
 
         self.ticks += 1;
@@ -128,6 +178,7 @@ impl ClockData {
         if cycle_time > self.longest_tick {
             self.longest_tick = cycle_time;
         }
+        cycle_time
     }
 
     pub fn log(&self, prefix: String, name: String) {
@@ -0,0 +1,523 @@
+use std::collections::{HashMap, VecDeque};
+use std::error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use super::DebugFlags;
+
+/// Sink names set_flags manages, other names are left alone by it
+pub const CONSOLE_SINK: &str = "console";
+pub const FILE_SINK: &str = "file";
+pub const MEMORY_SINK: &str = "memory";
+
+/// Most severe first, a target logs everything up to its level
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace
+}
+
+impl LogLevel {
+    pub fn get_name(self) -> &'static str {
+        match self {
+            LogLevel::Error => "ERROR",
+            LogLevel::Warn => "WARN",
+            LogLevel::Info => "INFO",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Trace => "TRACE"
+        }
+    }
+
+    /// Case insensitive, e.g. "warn"
+    pub fn parse(name: &str) -> Option<LogLevel> {
+        match name.to_uppercase().as_str() {
+            "ERROR" => Some(LogLevel::Error),
+            "WARN" => Some(LogLevel::Warn),
+            "INFO" => Some(LogLevel::Info),
+            "DEBUG" => Some(LogLevel::Debug),
+            "TRACE" => Some(LogLevel::Trace),
+            _ => None
+        }
+    }
+}
+
+/// Subsystem a message comes from, matching the DebugFlags categories
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum LogTarget {
+    General,
+    Collision,
+    Rendering,
+    Input,
+    Game,
+    Clocks,
+    Sound
+}
+
+impl LogTarget {
+    pub const ALL: [LogTarget; 7] = [
+        LogTarget::General, LogTarget::Collision, LogTarget::Rendering,
+        LogTarget::Input, LogTarget::Game, LogTarget::Clocks, LogTarget::Sound
+    ];
+
+    pub fn get_name(self) -> &'static str {
+        match self {
+            LogTarget::General => "general",
+            LogTarget::Collision => "collision",
+            LogTarget::Rendering => "rendering",
+            LogTarget::Input => "input",
+            LogTarget::Game => "game",
+            LogTarget::Clocks => "clocks",
+            LogTarget::Sound => "sound"
+        }
+    }
+
+    /// Case insensitive, e.g. "Collision"
+    pub fn parse(name: &str) -> Option<LogTarget> {
+        LogTarget::ALL.iter().cloned().find(|target| target.get_name().eq_ignore_ascii_case(name))
+    }
+
+    /// General has no flag of its own, it always logs at Info
+    pub fn get_flag(self) -> DebugFlags {
+        match self {
+            LogTarget::General => DebugFlags::NOLOGGING,
+            LogTarget::Collision => DebugFlags::DEBUGCOLLISION,
+            LogTarget::Rendering => DebugFlags::DEBUGRENDERING,
+            LogTarget::Input => DebugFlags::DEBUGINPUT,
+            LogTarget::Game => DebugFlags::DEBUGGAME,
+            LogTarget::Clocks => DebugFlags::DEBUGCLOCKS,
+            LogTarget::Sound => DebugFlags::DEBUGSOUND
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LogRecord {
+    pub level: LogLevel,
+    pub target: LogTarget,
+    pub message: String
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.target {
+            LogTarget::General => write!(f, "[{}] {}", self.level.get_name(), self.message),
+            target => write!(f, "[{} {}] {}", self.level.get_name(), target.get_name(), self.message)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LoggerError {
+    Io(io::Error),
+    InvalidConfig(String)
+}
+
+impl fmt::Display for LoggerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoggerError::Io(err) => write!(f, "Could not open log file: {}", err),
+            LoggerError::InvalidConfig(mes) => write!(f, "Invalid logger configuration: {}", mes)
+        }
+    }
+}
+
+impl error::Error for LoggerError {}
+
+impl From<io::Error> for LoggerError {
+    fn from(err: io::Error) -> Self {
+        LoggerError::Io(err)
+    }
+}
+
+/// Somewhere log records end up
+pub trait LogSink: Send {
+    fn write(&mut self, record: &LogRecord) -> io::Result<()>;
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+/// Prints records to stdout
+pub struct ConsoleSink;
+
+impl LogSink for ConsoleSink {
+    fn write(&mut self, record: &LogRecord) -> io::Result<()> {
+        writeln!(io::stdout(), "{}", record)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FileSinkConfig {
+    pub path: PathBuf,
+    /// The file is rotated once it grows past this
+    pub max_bytes: u64,
+    /// Rotated files kept as path.1, path.2... (0 just truncates the file)
+    pub max_backups: usize
+}
+
+impl Default for FileSinkConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("log.txt"),
+            max_bytes: 1024 * 1024,
+            max_backups: 3
+        }
+    }
+}
+
+/// Appends records to a file, rotating it when it gets too big
+pub struct FileSink {
+    config: FileSinkConfig,
+    file: File,
+    written: u64
+}
+
+impl FileSink {
+    pub fn new(config: FileSinkConfig) -> Result<Self, LoggerError> {
+        if config.max_bytes == 0 {
+            return Err(LoggerError::InvalidConfig("max_bytes of a log file must be above 0".to_string()));
+        }
+        if config.path.as_os_str().is_empty() {
+            return Err(LoggerError::InvalidConfig("log file path is empty".to_string()));
+        }
+        let file = OpenOptions::new().append(true).create(true).open(&config.path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            config,
+            file,
+            written
+        })
+    }
+
+    fn get_backup_path(&self, index: usize) -> PathBuf {
+        let mut path = self.config.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (1..self.config.max_backups).rev() {
+            let from = self.get_backup_path(index);
+            if from.exists() {
+                fs::rename(&from, self.get_backup_path(index + 1))?;
+            }
+        }
+        if self.config.max_backups > 0 {
+            fs::rename(&self.config.path, self.get_backup_path(1))?;
+        }
+        self.file = OpenOptions::new().write(true).truncate(true).create(true).open(&self.config.path)?;
+        self.written = 0;
+        Ok(())
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.config.path
+    }
+}
+
+impl LogSink for FileSink {
+    fn write(&mut self, record: &LogRecord) -> io::Result<()> {
+        let line = format!("{}\n", record);
+        if self.written > 0 && self.written + line.len() as u64 > self.config.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[derive(Debug, Default)]
+struct LogRing {
+    records: VecDeque<LogRecord>,
+    capacity: usize,
+    /// Records ever written, so readers can tell which ones they've seen
+    total: u64
+}
+
+/// Shared handle on a MemorySink's records, each handle remembers what it has read
+#[derive(Clone, Debug)]
+pub struct LogBuffer {
+    ring: Arc<Mutex<LogRing>>,
+    read: u64
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            ring: Arc::new(Mutex::new(LogRing {
+                capacity,
+                ..Default::default()
+            })),
+            read: 0
+        }
+    }
+
+    pub fn get_records(&self) -> Vec<LogRecord> {
+        self.ring.lock().unwrap().records.iter().cloned().collect()
+    }
+
+    /// Records written since the last call (records that fell out of the ring are missed)
+    pub fn take_new(&mut self) -> Vec<LogRecord> {
+        let ring = self.ring.lock().unwrap();
+        let first = ring.total - ring.records.len() as u64;
+        let skip = self.read.max(first) - first;
+        self.read = ring.total;
+        ring.records.iter().skip(skip as usize).cloned().collect()
+    }
+}
+
+/// Keeps the latest records in memory, e.g. for the developer console
+pub struct MemorySink {
+    buffer: LogBuffer
+}
+
+impl MemorySink {
+    pub fn new(buffer: LogBuffer) -> Self {
+        Self {
+            buffer
+        }
+    }
+}
+
+impl LogSink for MemorySink {
+    fn write(&mut self, record: &LogRecord) -> io::Result<()> {
+        let mut ring = self.buffer.ring.lock().unwrap();
+        if ring.capacity == 0 {
+            return Ok(());
+        }
+        while ring.records.len() >= ring.capacity {
+            ring.records.pop_front();
+        }
+        ring.records.push_back(record.clone());
+        ring.total += 1;
+        Ok(())
+    }
+}
+
+/// Filters records by level per target and hands them to named sinks
+pub struct Logger {
+    levels: HashMap<LogTarget, LogLevel>,
+    sinks: Vec<(String, Box<dyn LogSink>)>
+}
+
+impl Logger {
+    /// Every target at Info and no sinks
+    pub fn new() -> Self {
+        Self {
+            levels: LogTarget::ALL.iter().map(|&target| (target, LogLevel::Info)).collect(),
+            sinks: Vec::new()
+        }
+    }
+
+    pub fn get_level(&self, target: LogTarget) -> LogLevel {
+        self.levels.get(&target).cloned().unwrap_or(LogLevel::Info)
+    }
+
+    pub fn set_level(&mut self, target: LogTarget, level: LogLevel) {
+        self.levels.insert(target, level);
+    }
+
+    pub fn enabled(&self, level: LogLevel, target: LogTarget) -> bool {
+        level <= self.get_level(target)
+    }
+
+    /// A sink with the same name is replaced
+    pub fn set_sink(&mut self, name: &str, sink: Box<dyn LogSink>) {
+        self.remove_sink(name);
+        self.sinks.push((name.to_string(), sink));
+    }
+
+    pub fn remove_sink(&mut self, name: &str) {
+        self.sinks.retain(|(sink_name, _)| sink_name != name);
+    }
+
+    pub fn has_sink(&self, name: &str) -> bool {
+        self.sinks.iter().any(|(sink_name, _)| sink_name == name)
+    }
+
+    /// Sink failures are reported on stderr rather than stopping the game
+    pub fn log(&mut self, record: &LogRecord) {
+        if !self.enabled(record.level, record.target) {
+            return;
+        }
+        for (name, sink) in self.sinks.iter_mut() {
+            if let Err(err) = sink.write(record) {
+                eprintln!("Log sink {} failed: {}", name, err);
+            }
+        }
+    }
+
+    /// Writes to one sink only, ignoring levels
+    pub fn log_to_sink(&mut self, name: &str, record: &LogRecord) {
+        for (sink_name, sink) in self.sinks.iter_mut().filter(|(sink_name, _)| sink_name == name) {
+            if let Err(err) = sink.write(record) {
+                eprintln!("Log sink {} failed: {}", sink_name, err);
+            }
+        }
+    }
+
+    pub fn flush(&mut self) {
+        for (name, sink) in self.sinks.iter_mut() {
+            if let Err(err) = sink.flush() {
+                eprintln!("Log sink {} failed: {}", name, err);
+            }
+        }
+    }
+
+    /// Sets the targets and the console/file sinks the way the old debug flags did
+    /// (a target's flag raises it to Debug, the file sink uses the default FileSinkConfig)
+    pub fn apply_flags(&mut self, flags: DebugFlags) -> Result<(), LoggerError> {
+        let file_sink = if flags.intersects(DebugFlags::WRITETOFILE) && !self.has_sink(FILE_SINK) {
+            Some(FileSink::new(FileSinkConfig::default())?)
+        } else {
+            None
+        };
+
+        for &target in LogTarget::ALL.iter() {
+            let flag = target.get_flag();
+            let level = if !flag.is_empty() && flags.contains(flag) { LogLevel::Debug } else { LogLevel::Info };
+            self.set_level(target, level);
+        }
+        if flags.intersects(DebugFlags::WRITETOCONSOLE) {
+            if !self.has_sink(CONSOLE_SINK) {
+                self.set_sink(CONSOLE_SINK, Box::new(ConsoleSink));
+            }
+        } else {
+            self.remove_sink(CONSOLE_SINK);
+        }
+        match file_sink {
+            Some(file_sink) => self.set_sink(FILE_SINK, Box::new(file_sink)),
+            None if !flags.intersects(DebugFlags::WRITETOFILE) => self.remove_sink(FILE_SINK),
+            None => ()
+        }
+        Ok(())
+    }
+
+    /// The debug flags matching the current targets and sinks
+    pub fn get_flags(&self) -> DebugFlags {
+        let mut flags = DebugFlags::NOLOGGING;
+        if self.has_sink(CONSOLE_SINK) {
+            flags |= DebugFlags::WRITETOCONSOLE;
+        }
+        if self.has_sink(FILE_SINK) {
+            flags |= DebugFlags::WRITETOFILE;
+        }
+        for &target in LogTarget::ALL.iter() {
+            if self.enabled(LogLevel::Debug, target) {
+                flags |= target.get_flag();
+            }
+        }
+        flags
+    }
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(level: LogLevel, target: LogTarget, message: &str) -> LogRecord {
+        LogRecord {
+            level,
+            target,
+            message: message.to_string()
+        }
+    }
+
+    #[test]
+    fn levels_filter_per_target() {
+        let buffer = LogBuffer::new(10);
+        let mut reader = buffer.clone();
+        let mut logger = Logger::new();
+        logger.set_sink(MEMORY_SINK, Box::new(MemorySink::new(buffer)));
+        logger.set_level(LogTarget::Collision, LogLevel::Debug);
+
+        logger.log(&record(LogLevel::Debug, LogTarget::Collision, "contact"));
+        logger.log(&record(LogLevel::Debug, LogTarget::Rendering, "draw"));
+        logger.log(&record(LogLevel::Warn, LogTarget::Rendering, "slow"));
+
+        let messages: Vec<String> = reader.take_new().iter().map(|record| record.to_string()).collect();
+        assert_eq!(messages, vec!["[DEBUG collision] contact", "[WARN rendering] slow"]);
+        assert!(reader.take_new().is_empty());
+    }
+
+    #[test]
+    fn memory_sink_keeps_latest_records() {
+        let buffer = LogBuffer::new(2);
+        let mut reader = buffer.clone();
+        let mut sink = MemorySink::new(buffer);
+        for message in &["a", "b", "c"] {
+            sink.write(&record(LogLevel::Info, LogTarget::General, message)).unwrap();
+        }
+
+        let messages: Vec<String> = reader.take_new().into_iter().map(|record| record.message).collect();
+        assert_eq!(messages, vec!["b", "c"]);
+        sink.write(&record(LogLevel::Info, LogTarget::General, "d")).unwrap();
+        assert_eq!(reader.take_new()[0].message, "d");
+    }
+
+    #[test]
+    fn flags_round_trip() {
+        let mut logger = Logger::new();
+        let flags = DebugFlags::WRITETOCONSOLE | DebugFlags::DEBUGCLOCKS | DebugFlags::DEBUGINPUT;
+        logger.apply_flags(flags).unwrap();
+        assert_eq!(logger.get_flags(), flags);
+        assert!(logger.enabled(LogLevel::Debug, LogTarget::Clocks));
+        assert!(!logger.enabled(LogLevel::Debug, LogTarget::Game));
+    }
+
+    #[test]
+    fn file_sink_rotates_when_full() {
+        let directory = std::env::temp_dir().join("generic_game_logger_rotation");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let config = FileSinkConfig {
+            path: directory.join("log.txt"),
+            max_bytes: 20,
+            max_backups: 1
+        };
+        let mut sink = FileSink::new(config).unwrap();
+        for message in &["first", "second", "third"] {
+            sink.write(&record(LogLevel::Info, LogTarget::General, message)).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(directory.join("log.txt")).unwrap(), "[INFO] third\n");
+        assert_eq!(fs::read_to_string(directory.join("log.txt.1")).unwrap(), "[INFO] second\n");
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn bad_file_config_is_an_error() {
+        let config = FileSinkConfig {
+            max_bytes: 0,
+            ..Default::default()
+        };
+        assert!(FileSink::new(config).is_err());
+
+        let config = FileSinkConfig {
+            path: PathBuf::from("/no/such/directory/log.txt"),
+            ..Default::default()
+        };
+        match FileSink::new(config) {
+            Err(LoggerError::Io(_)) => (),
+            _ => panic!("expected an io error")
+        }
+    }
+}
@@ -1,27 +1,23 @@
 use bitflags;
-use std::env;
-use std::path::PathBuf;
-use std::fs::{File, OpenOptions};
+use std::fs::File;
 use std::io::{Write, Stderr, Result, stderr};
-use std::collections::HashMap;
 pub mod clock;
 mod clock_writer;
+pub mod clock_trace;
+pub mod profiler_overlay;
+pub mod logger;
 use std::sync::Mutex;
 
-pub static mut DEBUGGER: Debugger = Debugger {
-    flags: DebugFlags::DEFAULTDEBUG,
-};
+pub use self::clock_writer::{ClockFrame, ClockSpan, CLOCK_HISTORY_FRAMES};
+pub use self::profiler_overlay::ProfilerOverlay;
+pub use self::logger::{Logger, LoggerError, LogLevel, LogTarget, LogRecord, LogSink, LogBuffer,
+                       ConsoleSink, FileSink, FileSinkConfig, MemorySink, CONSOLE_SINK, FILE_SINK, MEMORY_SINK};
 
 lazy_static! {
     static ref CLOCKWRITER: Mutex<clock_writer::ClockWriter> = Mutex::new(clock_writer::ClockWriter::default());
+    static ref LOGGER: Mutex<Logger> = Mutex::new(get_default_logger());
 }
 
-pub struct Debugger {
-    pub flags: DebugFlags,
-}
-
-const LOGFILEPATH: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), r"\log.txt");
-
 bitflags! {
     pub struct DebugFlags: u32 {
         const NOLOGGING       = 0b00000000;
@@ -32,129 +28,155 @@ bitflags! {
         const DEBUGINPUT      = 0b00010000;
         const DEBUGGAME       = 0b00100000;
         const DEBUGCLOCKS     = 0b01000000;
+        const DEBUGSOUND      = 0b10000000;
         const DEBUGALL        = 0b11111111;
         const DEFAULTDEBUG    = DebugFlags::WRITETOCONSOLE.bits | DebugFlags::DEBUGCLOCKS.bits;
     }
 }
 
+/// Console output and the debug clocks, like DebugFlags::DEFAULTDEBUG
+fn get_default_logger() -> Logger {
+    let mut logger = Logger::new();
+    logger.set_sink(CONSOLE_SINK, Box::new(ConsoleSink));
+    logger.set_level(LogTarget::Clocks, LogLevel::Debug);
+    logger
+}
+
+/// Configure the global logger (levels and sinks), logging from inside configure would deadlock
+pub fn with_logger<F: FnOnce(&mut Logger) -> R, R>(configure: F) -> R {
+    configure(&mut LOGGER.lock().unwrap())
+}
+
+pub fn log(level: LogLevel, target: LogTarget, mes: &str) {
+    LOGGER.lock().unwrap().log(&LogRecord {
+        level,
+        target,
+        message: mes.to_string()
+    });
+}
+
+pub fn log_enabled(level: LogLevel, target: LogTarget) -> bool {
+    LOGGER.lock().unwrap().enabled(level, target)
+}
+
+pub fn log_error(target: LogTarget, mes: &str) {
+    log(LogLevel::Error, target, mes);
+}
+
+pub fn log_warn(target: LogTarget, mes: &str) {
+    log(LogLevel::Warn, target, mes);
+}
+
 pub fn debug(mes: &str) {
-    log_to_file(mes);
-    log_to_console(mes);
+    log(LogLevel::Info, LogTarget::General, mes);
 }
 
+/// Writes to the file sink only (when there is one)
 pub fn log_to_file(mes: &str) {
-    unsafe {
-        if DEBUGGER.flags.intersects(DebugFlags::WRITETOFILE) {
-            let mut file = match OpenOptions::new().write(true).append(true).create(true).open(LOGFILEPATH) {
-                Ok(f) => f,
-                Err(_) => panic!("Could not open log file at {}", LOGFILEPATH)
-            };
-            match writeln!(file, "{}", mes) {
-                Ok(_) => (),
-                Err(_) => panic!("Could not write to log file!")
-            }
-        }
-    }
+    LOGGER.lock().unwrap().log_to_sink(FILE_SINK, &LogRecord {
+        level: LogLevel::Info,
+        target: LogTarget::General,
+        message: mes.to_string()
+    });
 }
 
 pub fn log_to_file_bytes(mes: &[u8]) {
-    unsafe {
-        if DEBUGGER.flags.intersects(DebugFlags::WRITETOFILE) {
-            let mut file = match OpenOptions::new().write(true).append(true).create(true).open(LOGFILEPATH) {
-                Ok(f) => f,
-                Err(_) => panic!("Could not open log file at {}", LOGFILEPATH)
-            };
-            match file.write(mes) {
-                Ok(_) => (),
-                Err(_) => panic!("Could not write to log file!")
-            }
-        }
-    }
+    log_to_file(String::from_utf8_lossy(mes).trim_end());
 }
 
-fn log_to_console(mes: &str) {
-    unsafe {
-        if DEBUGGER.flags.intersects(DebugFlags::WRITETOCONSOLE) {
-            println!("{}", mes);
-        }
-    }
+pub fn debug_clock(mes: &str) {
+    log(LogLevel::Debug, LogTarget::Clocks, mes);
 }
 
-pub fn debug_clock(mes: &str) {
-    unsafe {
-        if DEBUGGER.flags.intersects(DebugFlags::DEBUGCLOCKS) {
-            debug(mes);
-        }
-    }
+fn clocks_enabled() -> bool {
+    log_enabled(LogLevel::Debug, LogTarget::Clocks)
 }
 
 pub fn debug_clock_start(clock_name: &str) {
-    unsafe {
-        if DEBUGGER.flags.intersects(DebugFlags::DEBUGCLOCKS) {
-            CLOCKWRITER.lock().unwrap().start_clock(clock_name.to_string());
-        }
+    if clocks_enabled() {
+        CLOCKWRITER.lock().unwrap().start_clock(clock_name.to_string());
     }
 }
 
 pub fn debug_clock_start_main() {
-    unsafe {
-        if DEBUGGER.flags.intersects(DebugFlags::DEBUGCLOCKS) {
-            CLOCKWRITER.lock().unwrap().start();
-        }
+    if clocks_enabled() {
+        CLOCKWRITER.lock().unwrap().start();
     }
 }
 
 pub fn debug_clock_stop(clock_name: &str) {
-    unsafe {
-        if DEBUGGER.flags.intersects(DebugFlags::DEBUGCLOCKS) {
-            CLOCKWRITER.lock().unwrap().stop_clock(clock_name.to_string());
-        }
+    if clocks_enabled() {
+        CLOCKWRITER.lock().unwrap().stop_clock(clock_name.to_string());
     }
 }
 
 pub fn debug_clock_stop_main() {
-    unsafe {
-        if DEBUGGER.flags.intersects(DebugFlags::DEBUGCLOCKS) {
-            CLOCKWRITER.lock().unwrap().stop();
-        }
+    if clocks_enabled() {
+        CLOCKWRITER.lock().unwrap().stop();
     }
 }
 
+/// Recent frames timed by the debug clocks, oldest first
+pub fn get_clock_frames() -> Vec<ClockFrame> {
+    CLOCKWRITER.lock().unwrap().get_frames()
+}
+
+/// Writes the recent clock frames to path as Chrome trace event JSON
+pub fn write_clock_trace(path: &str) -> Result<()> {
+    let trace = clock_trace::to_chrome_trace(&get_clock_frames())?;
+    let mut file = File::create(path)?;
+    file.write_all(trace.as_bytes())
+}
+
 pub fn debug_inp(mes: &str) {
-    unsafe {
-        if DEBUGGER.flags.intersects(DebugFlags::DEBUGINPUT) {
-            debug(mes);
-        }
-    }
+    log(LogLevel::Debug, LogTarget::Input, mes);
 }
 
 pub fn debug_game(mes: &str) {
-    unsafe {
-        if DEBUGGER.flags.intersects(DebugFlags::DEBUGGAME) {
-            debug(mes);
-        }
-    }
+    log(LogLevel::Debug, LogTarget::Game, mes);
 }
 
 pub fn debug_rend(mes: &str) {
-    unsafe {
-        if DEBUGGER.flags.intersects(DebugFlags::DEBUGRENDERING) {
-            debug(mes);
-        }
-    }
+    log(LogLevel::Debug, LogTarget::Rendering, mes);
 }
 
 pub fn debug_coll(mes: &str) {
-    unsafe {
-        if DEBUGGER.flags.intersects(DebugFlags::DEBUGCOLLISION) {
-            debug(mes);
-        }
-    }
+    log(LogLevel::Debug, LogTarget::Collision, mes);
 }
 
+/// Sets the target levels and console/file sinks from the flags.
+/// When the log file can't be opened the error is logged and the other flags still apply.
 pub fn set_flags(flags: DebugFlags) {
-    unsafe{
-        DEBUGGER.flags = flags;
+    if let Err(err) = try_set_flags(flags) {
+        let _ = try_set_flags(flags - DebugFlags::WRITETOFILE);
+        log_error(LogTarget::General, &format!("Could not set up the log file: {}", err));
+    }
+}
+
+/// As set_flags, failing without changing anything when the log file can't be opened, see Logger::apply_flags
+pub fn try_set_flags(flags: DebugFlags) -> std::result::Result<(), LoggerError> {
+    LOGGER.lock().unwrap().apply_flags(flags)
+}
+
+pub fn get_flags() -> DebugFlags {
+    LOGGER.lock().unwrap().get_flags()
+}
+
+/// Flag named as in DebugFlags (case insensitive), e.g. "debugclocks"
+pub fn parse_debug_flag(name: &str) -> Option<DebugFlags> {
+    match name.to_uppercase().as_str() {
+        "NOLOGGING" => Some(DebugFlags::NOLOGGING),
+        "WRITETOCONSOLE" => Some(DebugFlags::WRITETOCONSOLE),
+        "WRITETOFILE" => Some(DebugFlags::WRITETOFILE),
+        "DEBUGCOLLISION" => Some(DebugFlags::DEBUGCOLLISION),
+        "DEBUGRENDERING" => Some(DebugFlags::DEBUGRENDERING),
+        "DEBUGINPUT" => Some(DebugFlags::DEBUGINPUT),
+        "DEBUGGAME" => Some(DebugFlags::DEBUGGAME),
+        "DEBUGCLOCKS" => Some(DebugFlags::DEBUGCLOCKS),
+        "DEBUGSOUND" => Some(DebugFlags::DEBUGSOUND),
+        "DEBUGALL" => Some(DebugFlags::DEBUGALL),
+        "DEFAULTDEBUG" => Some(DebugFlags::DEFAULTDEBUG),
+        _ => None
     }
 }
 
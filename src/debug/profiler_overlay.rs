@@ -0,0 +1,197 @@
+use crate::rendering::*;
+use crate::geometry::*;
+use crate::ui::{UiTheme, UiRect, Anchor};
+use std::collections::BTreeMap;
+use super::clock_writer::ClockFrame;
+
+/// Frames shown in the frame time graph
+pub const PROFILER_GRAPH_FRAMES: usize = 120;
+/// Frames averaged for the clock bars
+pub const PROFILER_AVERAGE_FRAMES: usize = 60;
+pub const TARGET_FRAME_TIME: f64 = 1.0 / 60.0;
+
+/// Debug clock overlay: average time per frame of each clock as bars, over a rolling frame time graph
+pub struct ProfilerOverlay {
+    pub theme: UiTheme,
+    pub visible: bool,
+    /// Size in fixed screen units, the overlay sits in the top right corner
+    pub dim: Point,
+    pub graph_height: f64,
+    /// Frame time at the top of the graph, longer frames are cut off
+    pub graph_max_time: f64,
+    pub fast_color: Color,
+    pub slow_color: Color,
+    pub hitch_color: Color
+}
+
+impl ProfilerOverlay {
+    pub fn new(theme: UiTheme) -> Self {
+        Self {
+            theme,
+            visible: false,
+            dim: Point::new(1.1, 1.2),
+            graph_height: 0.3,
+            graph_max_time: 2.0 * TARGET_FRAME_TIME,
+            fast_color: Color::new(0.3, 0.85, 0.4, 1.0),
+            slow_color: Color::new(0.95, 0.75, 0.2, 1.0),
+            hitch_color: Color::new(1.0, 0.35, 0.3, 1.0)
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    fn get_time_color(&self, time: f64) -> Color {
+        if time <= TARGET_FRAME_TIME {
+            self.fast_color
+        } else if time <= 2.0 * TARGET_FRAME_TIME {
+            self.slow_color
+        } else {
+            self.hitch_color
+        }
+    }
+
+    /// Screen fixed renderables for the newest frames, nothing while hidden
+    pub fn get_renderables(&self, frames: &[ClockFrame], window_spec: WindowSpec) -> Vec<Box<StandardRenderable>> {
+        if !self.visible {
+            return Vec::new();
+        }
+        let aspect_ratio = if window_spec.aspect_ratio > 0.0 { window_spec.aspect_ratio } else { 1.0 };
+        let rect = Anchor::TopRight.place(aspect_ratio, Point::zero(), self.dim);
+        let padding = self.theme.padding;
+        let text_size = self.theme.text_size;
+        let line_height = 1.25 * text_size;
+        let left = rect.min.x + padding;
+        let width = rect.get_width() - 2.0 * padding;
+
+        let mut renderables: Vec<Box<StandardRenderable>> = Vec::new();
+        renderables.push(self.get_rect(rect, self.theme.panel_color));
+
+        let averaged = &frames[frames.len().saturating_sub(PROFILER_AVERAGE_FRAMES)..];
+        let mut baseline = rect.max.y - padding - text_size;
+        let title = match get_average_frame_time(averaged) {
+            Some(frame_time) => format!("Frame: {:.2} ms ({:.0} fps)", 1000.0 * frame_time, 1.0 / frame_time),
+            None => "No clocks (enable DEBUGCLOCKS)".to_string()
+        };
+        renderables.push(Box::new(self.get_text(title, left, baseline, self.theme.text_color)));
+
+        // Bars are scaled so a full width bar is a whole frame at the target frame rate
+        let graph_top = rect.min.y + padding + self.graph_height;
+        let indent = 2.0 * padding;
+        for (name, time) in get_average_clock_times(averaged) {
+            baseline -= line_height;
+            if baseline - line_height < graph_top {
+                break;
+            }
+            let depth = name.matches("::").count() as f64;
+            let label = name.rsplit("::").next().unwrap_or(&name).to_string();
+            let bar_left = left + depth * indent;
+            let bar_width = (width - depth * indent) * (time / TARGET_FRAME_TIME).min(1.0);
+            let bar = UiRect::new(Point::new(bar_left, baseline - 0.2 * text_size), Point::new(bar_left + bar_width, baseline + 0.8 * text_size));
+            renderables.push(self.get_rect(bar, self.theme.widget_color));
+            renderables.push(Box::new(self.get_text(format!("{}: {:.2} ms", label, 1000.0 * time), bar_left, baseline, self.theme.text_color)));
+        }
+
+        let graph = UiRect::new(Point::new(left, rect.min.y + padding), Point::new(left + width, graph_top));
+        renderables.push(self.get_rect(graph, self.theme.pressed_color));
+        let bar_width = graph.get_width() / PROFILER_GRAPH_FRAMES as f64;
+        let graphed = &frames[frames.len().saturating_sub(PROFILER_GRAPH_FRAMES)..];
+        // Newest frame on the right
+        let first_left = graph.max.x - graphed.len() as f64 * bar_width;
+        for (index, frame) in graphed.iter().enumerate() {
+            let height = graph.get_height() * (frame.duration / self.graph_max_time).min(1.0);
+            let bar_left = first_left + index as f64 * bar_width;
+            let bar = UiRect::new(Point::new(bar_left, graph.min.y), Point::new(bar_left + bar_width, graph.min.y + height));
+            renderables.push(self.get_rect(bar, self.get_time_color(frame.duration)));
+        }
+
+        let target_y = graph.min.y + graph.get_height() * TARGET_FRAME_TIME / self.graph_max_time;
+        let thickness = self.theme.border_thickness;
+        let target_line = UiRect::new(Point::new(graph.min.x, target_y - thickness / 2.0), Point::new(graph.max.x, target_y + thickness / 2.0));
+        renderables.push(self.get_rect(target_line, self.theme.text_color));
+
+        renderables
+    }
+
+    fn get_rect(&self, rect: UiRect, color: Color) -> Box<StandardRenderable> {
+        let center = rect.get_center();
+        Box::new(RectanglePrimitive::new_regular(rect.get_width(), rect.get_height(), Point3::new(center.x, center.y, self.theme.depth), color, true))
+    }
+
+    fn get_text(&self, content: String, left: f64, baseline: f64, color: Color) -> PlainText {
+        let size = self.theme.text_size;
+        PlainText {
+            content,
+            position: Point3::new(left, baseline, self.theme.depth),
+            scale: Point::new(size, size),
+            color,
+            align: TextAlign::Origin,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for ProfilerOverlay {
+    fn default() -> Self {
+        Self::new(UiTheme { text_size: 0.04, ..Default::default() })
+    }
+}
+
+pub fn get_average_frame_time(frames: &[ClockFrame]) -> Option<f64> {
+    if frames.is_empty() {
+        return None;
+    }
+    Some(frames.iter().map(|frame| frame.duration).sum::<f64>() / frames.len() as f64)
+}
+
+/// Average time per frame spent in each clock (summing repeated runs in a frame)
+/// Ordered by name components so children follow their parents
+pub fn get_average_clock_times(frames: &[ClockFrame]) -> Vec<(String, f64)> {
+    let mut totals: BTreeMap<Vec<&str>, f64> = BTreeMap::new();
+    for span in frames.iter().flat_map(|frame| frame.spans.iter()) {
+        *totals.entry(span.name.split("::").collect()).or_insert(0.0) += span.duration;
+    }
+
+    let frame_count = frames.len().max(1) as f64;
+    totals.into_iter()
+        .map(|(components, total)| (components.join("::"), total / frame_count))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::clock_writer::ClockSpan;
+
+    fn span(name: &str, duration: f64) -> ClockSpan {
+        ClockSpan {
+            name: name.to_string(),
+            start: 0.0,
+            duration
+        }
+    }
+
+    #[test]
+    fn clock_times_are_averaged_per_frame_in_tree_order() {
+        let frames = vec![
+            ClockFrame {
+                start: 0.0,
+                duration: 0.02,
+                spans: vec![span("Render2", 0.001), span("Render::glium_load", 0.004), span("Render", 0.006), span("Render", 0.002)]
+            },
+            ClockFrame {
+                start: 0.02,
+                duration: 0.01,
+                spans: vec![span("Render", 0.004)]
+            }
+        ];
+
+        let times = get_average_clock_times(&frames);
+        let names: Vec<&str> = times.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["Render", "Render::glium_load", "Render2"]);
+        assert!((times[0].1 - 0.006).abs() < 1.0e-12);
+        assert!((times[1].1 - 0.002).abs() < 1.0e-12);
+        assert!((get_average_frame_time(&frames).unwrap() - 0.015).abs() < 1.0e-12);
+    }
+}
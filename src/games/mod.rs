@@ -4,7 +4,11 @@ use crate::rendering::Renderable;
 use crate::input::keyboard::KeyboardInput;
 use crate::input::mouse::MouseInput;
 use crate::input::joystick::JoystickInput;
-use crate::rendering::{DisplaySettings, WindowSpec};
+use crate::input::gamepad::GamepadInput;
+use crate::input::text_input::TextInput;
+use crate::input::button_states::ButtonStates;
+use crate::rendering::{DisplaySettings, WindowSpec, CursorSettings};
+use crate::console::{CommandRegistry, CommandResult};
 
 pub use self::view_details::{ViewDetails, ViewDetails2D, ViewDetails3D};
 
@@ -22,9 +26,17 @@ pub trait Game {
     }
     fn should_exit(&self) -> bool {false}
     fn on_exit(&mut self) {}
+    /// Lines logged since the last call, shown in the developer console
     fn get_console_logs(&mut self) -> Vec<String> { vec![] }
     fn write_to_log(&mut self, _: &str) {}
+    /// Commands registered with CommandRegistry::register_game_command are run by run_console_command
+    fn register_console_commands(&mut self, _registry: &mut CommandRegistry) {}
+    fn run_console_command(&mut self, command: &str, _args: &[&str]) -> CommandResult {
+        Err(format!("{} is not handled by the game", command))
+    }
     fn change_display_settings(&mut self) -> Option<DisplaySettings> {None}
+    /// The system cursor is hidden unless the game asks for it
+    fn get_cursor_settings(&self) -> CursorSettings { CursorSettings::default() }
 }
 
 /// GameInput
@@ -32,6 +44,12 @@ pub trait GameInput {
     fn get_kbd_inp<'a>(&'a mut self) -> Option<&'a mut KeyboardInput> { None }
     fn get_mouse_inp<'a>(&'a mut self) -> Option<&'a mut MouseInput> { None }
     fn get_joystick_inp<'a>(&'a mut self) -> Option<&'a mut JoystickInput> { None }
+    /// Joysticks known to be gamepads, in the standard layout
+    fn get_gamepad_inp<'a>(&'a mut self) -> Option<&'a mut GamepadInput> { None }
+    /// Typed text, separate from the keys held in get_kbd_inp
+    fn get_text_inp<'a>(&'a mut self) -> Option<&'a mut TextInput> { None }
+    /// Kept up to date by the input handler from the keyboard, mouse and joystick input above
+    fn get_button_states<'a>(&'a mut self) -> Option<&'a mut ButtonStates> { None }
 }
 
 pub struct GameInputStub;
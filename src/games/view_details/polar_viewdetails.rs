@@ -1,3 +1,6 @@
+use std::f64::consts::PI;
+use crate::geometry::Point;
+
 #[derive(Copy, Clone, Debug)]
 pub struct PolarViewDetails {
     pub rotation_angle: f64,
@@ -18,3 +21,13 @@ impl Default for PolarViewDetails {
         }
     }
 }
+
+impl PolarViewDetails {
+    /// Where a point of the polar world sits around the centre: x goes clockwise from the top,
+    /// length_circle per turn, turned by rotation_angle, and y is the distance out, moved by radial_shift
+    pub fn to_cartesian(&self, point: Point) -> Point {
+        let angle = 2.0 * PI * point.x / self.length_circle + self.rotation_angle;
+        let radius = (point.y + self.radial_shift).max(0.0);
+        Point::new(radius * angle.sin(), radius * angle.cos())
+    }
+}
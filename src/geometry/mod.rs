@@ -20,6 +20,7 @@ pub mod interval_collection;
 pub mod legendre;
 pub mod polynomial;
 pub mod polynomial2d;
+pub mod visibility;
 
 pub use self::interval::{Interval, IntervalEnd, IntervalCollisionObject};
 pub use self::interval_collection::IntervalCollection;
@@ -43,6 +44,7 @@ pub use self::vect::{get_normal_2d, get_rot90_2d};
 pub use self::polynomial::Polynomial;
 pub use self::polynomial2d::Polynomial2d;
 pub use self::legendre::{build_interpolating_poly, build_interpolating_poly2d};
+pub use self::visibility::{visibility_polygon, collision_objects_to_occluders};
 
 const EPSILON: f64 = 0.0000001;
 
@@ -0,0 +1,130 @@
+use std::f64::consts;
+use std::cmp::Ordering::Equal;
+use crate::geometry::*;
+use crate::collision::CollisionObject;
+
+const ANGLE_OFFSET: f64 = 0.00001;
+const CIRCLE_OCCLUDER_SIDES: usize = 16;
+
+/// Region visible from origin, bounded by a square of half-width radius
+/// Rays are cast at every occluder end (and just either side of it) so the
+/// resulting polygon is exact; corners are returned anticlockwise
+pub fn visibility_polygon(origin: Point, occluders: &[Line], radius: f64) -> Vec<Point> {
+    let bounds = vec![
+        origin + Point::new(-radius, -radius),
+        origin + Point::new(radius, -radius),
+        origin + Point::new(radius, radius),
+        origin + Point::new(-radius, radius)
+    ];
+    let mut segments: Vec<Line> = ConPoly::new(bounds).sides();
+    segments.extend(occluders.iter().cloned());
+
+    let mut angles: Vec<f64> = segments.iter()
+        .flat_map(|segment| vec![segment.beg, segment.end])
+        .map(|end| (end - origin).get_angle())
+        .flat_map(|angle| vec![angle - ANGLE_OFFSET, angle, angle + ANGLE_OFFSET])
+        .collect();
+    angles.sort_by(|angle1, angle2| angle1.partial_cmp(angle2).unwrap_or(Equal));
+
+    let mut polygon: Vec<Point> = Vec::new();
+    for angle in angles {
+        if let Some(distance) = ray_cast(origin, Point::from_angle(angle), &segments) {
+            let hit = origin + distance * Point::from_angle(angle);
+            let is_duplicate = match polygon.last() {
+                Some(last) => (*last - hit).norm_squared() < EPSILON,
+                None => false
+            };
+            if !is_duplicate {
+                polygon.push(hit);
+            }
+        }
+    }
+
+    polygon
+}
+
+/// Distance along the ray to the closest segment (dir should be normalised)
+pub fn ray_cast(origin: Point, dir: Point, segments: &[Line]) -> Option<f64> {
+    let mut closest: Option<f64> = None;
+
+    for segment in segments {
+        let seg_diff = segment.get_diff();
+        let denominator = dir.x * seg_diff.y - dir.y * seg_diff.x;
+        if denominator.abs() < EPSILON {
+            continue;
+        }
+        let to_segment = segment.beg - origin;
+        let ray_dist = (to_segment.x * seg_diff.y - to_segment.y * seg_diff.x) / denominator;
+        let seg_pos = (to_segment.x * dir.y - to_segment.y * dir.x) / denominator;
+
+        if ray_dist >= 0.0 && (0.0..=1.0).contains(&seg_pos) {
+            closest = match closest {
+                Some(dist) if dist <= ray_dist => Some(dist),
+                _ => Some(ray_dist)
+            };
+        }
+    }
+
+    closest
+}
+
+/// Builds shadow casting lines from the objects handed to the collider
+/// (circles are approximated by polygons, points don't cast shadows)
+pub fn collision_objects_to_occluders(objects: &[CollisionObject]) -> Vec<Line> {
+    let mut occluders = Vec::new();
+
+    for object in objects {
+        match object {
+            CollisionObject::ConPoly(con_poly) => occluders.extend(con_poly.sides()),
+            CollisionObject::Line(line) => occluders.push(*line),
+            CollisionObject::Circ(circle) => {
+                let corners: Vec<Point> = (0..CIRCLE_OCCLUDER_SIDES)
+                    .map(|index| 2.0 * consts::PI * index as f64 / CIRCLE_OCCLUDER_SIDES as f64)
+                    .map(|angle| circle.center + circle.rad * Point::from_angle(angle))
+                    .collect();
+                occluders.extend(ConPoly::new(corners).sides());
+            },
+            CollisionObject::Point(_) | CollisionObject::None => ()
+        }
+    }
+
+    occluders
+}
+
+/// Signed area (positive when anticlockwise)
+pub fn polygon_area(corners: &[Point]) -> f64 {
+    let shifted = corners.iter().cycle().skip(1);
+    corners.iter().zip(shifted)
+        .map(|(beg, end)| beg.x * end.y - end.x * beg.y)
+        .sum::<f64>() / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unobstructed_view_fills_bounds() {
+        let polygon = visibility_polygon(Point::new(1.0, 2.0), &[], 3.0);
+        assert!((polygon_area(&polygon) - 36.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn wall_casts_shadow() {
+        let wall = Line::new(Point::new(1.0, -1.0), Point::new(1.0, 1.0));
+        let polygon = visibility_polygon(Point::zero(), &[wall], 10.0);
+
+        // Shadow is the 90 degree wedge behind the wall, minus the part in front of it
+        assert!((polygon_area(&polygon) - 301.0).abs() < 0.01);
+        assert!(polygon.iter().all(|corner| corner.x <= 1.0 + EPSILON || corner.y.abs() >= corner.x - EPSILON));
+    }
+
+    #[test]
+    fn ray_cast_finds_nearest_segment() {
+        let near = Line::new(Point::new(2.0, -1.0), Point::new(2.0, 1.0));
+        let far = Line::new(Point::new(5.0, -1.0), Point::new(5.0, 1.0));
+        let distance = ray_cast(Point::zero(), Point::x(), &[far, near]).unwrap();
+        assert!((distance - 2.0).abs() < EPSILON);
+        assert!(ray_cast(Point::zero(), Point::y(), &[far, near]).is_none());
+    }
+}
@@ -1,17 +1,63 @@
 use crate::Handler;
-use crate::rendering::{GliumRenderer, Renderer, StandardPrimitive};
-use crate::input::InputHandler;
+use crate::rendering::{GliumRenderer, Renderer, Renderable, StandardPrimitive, WindowSpec, CursorSprite};
+use crate::input::{InputHandler, InputRecording, FrameInput, DEFAULT_RECORDING_PATH};
 use crate::window::WindowHandler;
 use crate::games::Game;
 use time;
 use crate::debug::*;
+#[cfg(windows)]
 use winapi;
-use crate::input::multihandler::MultiInput;
+use crate::collision::{collision_debug_enabled, begin_collision_debug_frame, take_collision_debug_renderables};
+use crate::console::{Console, CommandTarget, CommandResult, split_command_line, CONSOLE_MAX_LINES};
+use crate::geometry::Point;
 
+#[cfg(windows)]
 use libloading::{Library, Symbol};
 
+/// Where the export_trace console command writes when no path is given
+pub const DEFAULT_TRACE_PATH: &str = "clock_trace.json";
+
+#[cfg(windows)]
 type SetProcessDpiAwareness<'a> = Symbol<'a, unsafe extern "system" fn(awareness: winapi::um::shellscalingapi::PROCESS_DPI_AWARENESS) -> winapi::um::winnt::HRESULT>;
 
+/// What HandlerBasic draws over the game
+pub struct Overlays<'a> {
+    pub console: &'a Console,
+    pub profiler: &'a ProfilerOverlay,
+    /// The sprite drawn in place of the system cursor, and the cursor's screen position
+    pub cursor: Option<(CursorSprite, Point)>,
+    pub window_spec: WindowSpec
+}
+
+/// Primitives HandlerBasic's overlays can be drawn with. Taking the default leaves them out.
+///
+/// HandlerBasic is only a Handler for primitives with this trait, so games drawing their own
+/// primitive type need an impl for it, which can be empty: `impl OverlayPrimitive for MyPrimitive {}`
+pub trait OverlayPrimitive: Sized {
+    fn get_overlay_renderables(_overlays: &Overlays<'_>) -> Vec<Box<dyn Renderable<Self>>> {
+        Vec::new()
+    }
+}
+
+impl OverlayPrimitive for StandardPrimitive {
+    fn get_overlay_renderables(overlays: &Overlays<'_>) -> Vec<Box<dyn Renderable<Self>>> {
+        let mut renderables = Vec::new();
+        if collision_debug_enabled() {
+            renderables.extend(take_collision_debug_renderables());
+        }
+        if overlays.profiler.visible {
+            renderables.extend(overlays.profiler.get_renderables(&get_clock_frames(), overlays.window_spec));
+        }
+        renderables.extend(overlays.console.get_renderables(overlays.window_spec));
+        if let Some((sprite, position)) = overlays.cursor {
+            renderables.push(Box::new(sprite.get_texture_rect(position)));
+        }
+        renderables
+    }
+}
+
+/// Runs a game, with a console, profiler and debug drawing over it for primitives implementing
+/// OverlayPrimitive (which it needs to be a Handler)
 pub struct HandlerBasic<Prim> {
     renderer: Box<dyn Renderer<Primitive=Prim>>,
     input_handler: Box<dyn InputHandler>,
@@ -19,7 +65,15 @@ pub struct HandlerBasic<Prim> {
     game: Box<dyn Game<Primitive=Prim>>,
     last_time: f64,
     pause_active_flag: bool,
-    increment_frame: bool
+    increment_frame: bool,
+    step_frames: u32,
+    console: Console,
+    log_buffer: LogBuffer,
+    profiler: ProfilerOverlay,
+    recording: Option<InputRecording>,
+    recording_start: f64,
+    /// The input the game was given this frame, while recording
+    frame_input: Option<FrameInput>
 }
 
 impl<Prim> HandlerBasic<Prim> {
@@ -35,27 +89,166 @@ impl<Prim> HandlerBasic<Prim> {
             game: game,
             last_time: 0.0,
             pause_active_flag: false,
-            increment_frame: false
+            increment_frame: false,
+            step_frames: 0,
+            console: Console::default(),
+            log_buffer: LogBuffer::new(CONSOLE_MAX_LINES),
+            profiler: ProfilerOverlay::default(),
+            recording: None,
+            recording_start: 0.0,
+            frame_input: None
         }
     }
 
     pub fn pause_active(&self) -> bool {
         self.pause_active_flag && !self.increment_frame
     }
+
+    /// Records the input the game holds each logic update from now on, replacing any recording
+    pub fn start_recording(&mut self) {
+        self.recording = Some(InputRecording::new());
+        self.recording_start = time::precise_time_s();
+    }
+
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.recording.take()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    fn record_frame(&mut self, t_step: f64) {
+        if let Some(recording) = &mut self.recording {
+            recording.push(t_step, self.frame_input.take());
+        }
+    }
+
+    fn update_console(&mut self) {
+        for record in self.log_buffer.take_new() {
+            self.console.log(&record.to_string());
+        }
+        for log in self.game.get_console_logs() {
+            self.console.log(&log);
+        }
+
+        if self.console.is_open() {
+            self.input_handler.pass_on_input(Some(&mut self.console));
+            if let Some(line) = self.console.update_input() {
+                let result = self.run_console_line(&line);
+                self.console.print_result(result);
+            }
+        }
+    }
+
+    fn run_console_line(&mut self, line: &str) -> CommandResult {
+        let words = split_command_line(line);
+        let (command, args) = match words.split_first() {
+            Some((command, args)) => (command.as_str(), args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>()),
+            None => return Ok(String::new())
+        };
+
+        match self.console.registry.get(command).map(|registered| registered.target) {
+            Some(CommandTarget::Console) => self.console.run_console_command(command, &args),
+            Some(CommandTarget::Handler) => self.run_handler_command(command, &args),
+            Some(CommandTarget::Game) => self.game.run_console_command(command, &args),
+            None => Err(format!("Unknown command: {} (try help)", command))
+        }
+    }
+
+    fn run_handler_command(&mut self, command: &str, args: &[&str]) -> CommandResult {
+        match command {
+            "pause" => {
+                self.pause_active_flag = !self.pause_active_flag;
+                Ok(if self.pause_active_flag { "Paused" } else { "Resumed" }.to_string())
+            },
+            "step" => {
+                let frames = match args.first() {
+                    Some(frames) => frames.parse::<u32>().map_err(|_| format!("Not a number of frames: {}", frames))?,
+                    None => 1
+                };
+                self.pause_active_flag = true;
+                self.step_frames += frames;
+                Ok(format!("Stepping {} frame(s)", frames))
+            },
+            "set_debug_flags" => {
+                if !args.is_empty() {
+                    let flags = args.iter().try_fold(DebugFlags::NOLOGGING, |flags, name| {
+                        parse_debug_flag(name)
+                            .map(|flag| flags | flag)
+                            .ok_or_else(|| format!("Unknown debug flag: {}", name))
+                    })?;
+                    try_set_flags(flags).map_err(|error| error.to_string())?;
+                }
+                Ok(format!("Debug flags: {:?}", get_flags()))
+            },
+            "log_level" => {
+                if let [target, level] = args {
+                    let target = LogTarget::parse(target).ok_or_else(|| format!("Unknown log target: {}", target))?;
+                    let level = LogLevel::parse(level).ok_or_else(|| format!("Unknown log level: {}", level))?;
+                    with_logger(|logger| logger.set_level(target, level));
+                } else if !args.is_empty() {
+                    return Err("Usage: log_level [target level]".to_string());
+                }
+                Ok(LogTarget::ALL.iter()
+                    .map(|&target| format!("{}: {}", target.get_name(), with_logger(|logger| logger.get_level(target)).get_name()))
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+            },
+            "profiler" => {
+                self.profiler.toggle();
+                Ok(String::new())
+            },
+            "export_trace" => {
+                let path = args.first().cloned().unwrap_or(DEFAULT_TRACE_PATH);
+                write_clock_trace(path)
+                    .map(|_| format!("Wrote clock trace to {}", path))
+                    .map_err(|error| format!("Could not write clock trace to {}: {}", path, error))
+            },
+            "record_input" => {
+                self.start_recording();
+                Ok("Recording input".to_string())
+            },
+            "write_recording" => {
+                let path = args.first().cloned().unwrap_or(DEFAULT_RECORDING_PATH);
+                let recording = self.stop_recording().ok_or_else(|| "Not recording input".to_string())?;
+                recording.save(path)
+                    .map(|_| format!("Wrote {} frames of input to {}", recording.len(), path))
+                    .map_err(|error| format!("Could not write input recording to {}: {}", path, error))
+            },
+            _ => Err(format!("{} is not a game loop command", command))
+        }
+    }
+
+    /// Frames asked for by the step command are advanced one per update
+    fn take_step_frame(&mut self) -> bool {
+        if self.step_frames > 0 {
+            self.step_frames -= 1;
+            return true;
+        }
+        false
+    }
 }
 
-impl<Prim> Handler for HandlerBasic<Prim> {
+impl<Prim: OverlayPrimitive> Handler for HandlerBasic<Prim> {
     fn init(&mut self) {
         self.renderer.init();
         self.input_handler.init();
         self.game.init();
+        self.game.register_console_commands(&mut self.console.registry);
+        let log_buffer = self.log_buffer.clone();
+        with_logger(|logger| logger.set_sink(MEMORY_SINK, Box::new(MemorySink::new(log_buffer))));
         self.last_time = time::precise_time_s();
         set_process_dpi_aware();
     }
 
     fn update_input(&mut self) {
         debug_clock_start("Input");
-        self.window_handler.receive_input(self.renderer.get_events_loop().unwrap());
+        // Headless renderers have no window events
+        if let Some(events_loop) = self.renderer.get_events_loop() {
+            self.window_handler.receive_input(events_loop);
+        }
+        self.input_handler.set_window_spec(self.renderer.get_window_spec());
         
         if self.window_handler.is_focused() {
             self.input_handler.receive_input();
@@ -63,10 +256,23 @@ impl<Prim> Handler for HandlerBasic<Prim> {
             if self.input_handler.f8_key_pressed() {
                 self.pause_active_flag = !self.pause_active_flag;
             }
-            self.increment_frame = self.input_handler.f9_key_pressed();
+            if self.input_handler.f7_key_pressed() {
+                self.profiler.toggle();
+            }
+            if self.input_handler.backtick_key_pressed() {
+                self.console.toggle();
+            }
+            self.update_console();
+            self.increment_frame = self.input_handler.f9_key_pressed() || self.take_step_frame();
 
-            if !self.pause_active() {
+            // The game doesn't see the keyboard while the console is open
+            if !self.pause_active() && !self.console.is_open() {
                 self.input_handler.pass_on_input(self.game.get_input());
+                // Taken before the game's update_input, which may change its input
+                if self.recording.is_some() {
+                    let time = time::precise_time_s() - self.recording_start;
+                    self.frame_input = self.game.get_input().map(|input| FrameInput::capture(time, input));
+                }
                 self.game.update_input();
             }
             self.input_handler.flush_input();
@@ -75,24 +281,41 @@ impl<Prim> Handler for HandlerBasic<Prim> {
     }
 
     fn update_logic(&mut self) {
-        let t_step = time::precise_time_s() - self.last_time;
+        let now = time::precise_time_s();
+        let t_step = self.input_handler.get_time_step().unwrap_or(now - self.last_time);
         if !self.pause_active() {
             debug_clock_start("Logic");
+            if collision_debug_enabled() {
+                begin_collision_debug_frame();
+            }
             self.game.update_logic(t_step);
             debug_clock_stop("Logic");
+            self.record_frame(t_step);
         }
-        self.last_time = self.last_time + t_step;
+        self.frame_input = None;
+        self.last_time = now;
     }
 
     fn update_rendering(&mut self) {
-        if !self.pause_active() {
+        // Keep drawing while paused with the console open so it can be typed in
+        if !self.pause_active() || self.console.is_open() {
             debug_clock_start("Render");
             if let Some(display_settings) = self.game.change_display_settings() {
                 self.renderer.reset(display_settings);
                 self.input_handler.reset();
             }
             let window_spec = self.renderer.get_window_spec();
-            self.renderer.load_renderables(self.game.get_renderables(window_spec));
+            let mut renderables = self.game.get_renderables(window_spec);
+            let cursor_settings = self.game.get_cursor_settings();
+            let cursor = self.input_handler.get_cursor().map(|cursor| cursor.get_screen_position());
+            renderables.extend(Prim::get_overlay_renderables(&Overlays {
+                console: &self.console,
+                profiler: &self.profiler,
+                cursor: cursor_settings.sprite.zip(cursor),
+                window_spec
+            }));
+            self.renderer.set_cursor_settings(cursor_settings);
+            self.renderer.load_renderables(renderables);
             self.renderer.set_worldview(self.game.get_view());
             self.renderer.render();
             debug_clock_stop("Render");
@@ -108,6 +331,7 @@ impl<Prim> Handler for HandlerBasic<Prim> {
     }
 }
 
+#[cfg(windows)]
 fn set_process_dpi_aware() {
     match Library::new("Shcore.dll") {
         Ok(shcore_lib) => {
@@ -122,4 +346,8 @@ fn set_process_dpi_aware() {
         },
         Err(_) => ()
     };
-}
\ No newline at end of file
+}
+
+/// Only Windows needs telling to render at the monitor's real resolution
+#[cfg(not(windows))]
+fn set_process_dpi_aware() {}
@@ -0,0 +1,435 @@
+use crate::input::{KeyboardInput, MouseInput, JoystickInput, HatSwitch};
+use crate::input::keyboard::KeyCode;
+use crate::input::gamepad::GamepadButton;
+use crate::input::button_states::ButtonStates;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::{error, fmt, fs, io};
+
+/// How far a joystick axis has to move to count as held when bound to an action
+pub const DEFAULT_BUTTON_THRESHOLD: f64 = 0.5;
+/// Joystick axis values closer to zero than this read as zero
+pub const DEFAULT_AXIS_DEADZONE: f64 = 0.15;
+
+#[derive(Debug)]
+pub enum ActionMapError {
+    Io(io::Error),
+    InvalidBindings(String)
+}
+
+impl fmt::Display for ActionMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionMapError::Io(err) => write!(f, "Could not access bindings file: {}", err),
+            ActionMapError::InvalidBindings(mes) => write!(f, "Could not parse bindings: {}", mes)
+        }
+    }
+}
+
+impl error::Error for ActionMapError {}
+
+impl From<io::Error> for ActionMapError {
+    fn from(err: io::Error) -> Self {
+        ActionMapError::Io(err)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum MouseButtonCode {
+    Left,
+    Right,
+    Middle,
+    Button4,
+    Button5
+}
+
+impl MouseButtonCode {
+    pub const ALL: [MouseButtonCode; 5] = [
+        MouseButtonCode::Left, MouseButtonCode::Right, MouseButtonCode::Middle, MouseButtonCode::Button4, MouseButtonCode::Button5
+    ];
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum JoystickAxisCode {
+    X,
+    Y,
+    Z,
+    RX,
+    RY,
+    RZ
+}
+
+impl JoystickAxisCode {
+    pub const ALL: [JoystickAxisCode; 6] = [
+        JoystickAxisCode::X, JoystickAxisCode::Y, JoystickAxisCode::Z, JoystickAxisCode::RX, JoystickAxisCode::RY, JoystickAxisCode::RZ
+    ];
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum AxisDirection {
+    Negative,
+    Positive
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum HatDirection {
+    Up,
+    Down,
+    Left,
+    Right
+}
+
+impl HatDirection {
+    pub const ALL: [HatDirection; 4] = [HatDirection::Up, HatDirection::Down, HatDirection::Left, HatDirection::Right];
+
+    /// Diagonals count for both their directions
+    pub fn matches(self, hat: HatSwitch) -> bool {
+        match self {
+            HatDirection::Up => hat == HatSwitch::Up || hat == HatSwitch::UpLeft || hat == HatSwitch::UpRight,
+            HatDirection::Down => hat == HatSwitch::Down || hat == HatSwitch::DownLeft || hat == HatSwitch::DownRight,
+            HatDirection::Left => hat == HatSwitch::Left || hat == HatSwitch::UpLeft || hat == HatSwitch::DownLeft,
+            HatDirection::Right => hat == HatSwitch::Right || hat == HatSwitch::UpRight || hat == HatSwitch::DownRight
+        }
+    }
+}
+
+/// An input that holds an action down
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum InputBinding {
+    Key(KeyCode),
+    MouseButton(MouseButtonCode),
+    /// Numbered from 1, as in JoystickInput::get_button_1
+    JoystickButton(usize),
+    /// Held while the axis is past ActionMap::button_threshold in the direction
+    JoystickAxis(JoystickAxisCode, AxisDirection),
+    Hat(HatDirection),
+    /// A button of the standard gamepad layout, seen through ButtonStates
+    Gamepad(GamepadButton)
+}
+
+impl InputBinding {
+    /// Every binding: keys, then mouse buttons, then the joystick, then gamepad buttons
+    pub fn all() -> Vec<InputBinding> {
+        let keys = KeyCode::ALL.iter().map(|&key| InputBinding::Key(key));
+        let mouse_buttons = MouseButtonCode::ALL.iter().map(|&button| InputBinding::MouseButton(button));
+        let joystick_buttons = (1..=10).map(InputBinding::JoystickButton);
+        let joystick_axes = JoystickAxisCode::ALL.iter()
+            .flat_map(|&axis| vec![
+                InputBinding::JoystickAxis(axis, AxisDirection::Negative),
+                InputBinding::JoystickAxis(axis, AxisDirection::Positive)
+            ]);
+        let hat = HatDirection::ALL.iter().map(|&direction| InputBinding::Hat(direction));
+        let gamepad_buttons = GamepadButton::ALL.iter().map(|&button| InputBinding::Gamepad(button));
+
+        keys.chain(mouse_buttons)
+            .chain(joystick_buttons)
+            .chain(joystick_axes)
+            .chain(hat)
+            .chain(gamepad_buttons)
+            .collect()
+    }
+}
+
+/// An input that gives an axis a value
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AxisBinding {
+    /// -1 while the first binding is held, 1 while the second is (0 with both)
+    Buttons(InputBinding, InputBinding),
+    /// The axis value, 0 inside ActionMap::axis_deadzone
+    JoystickAxis(JoystickAxisCode),
+    /// Raw mouse movement this frame times the scale (not limited to [-1, 1])
+    MouseX(f64),
+    MouseY(f64)
+}
+
+/// Named actions (held or not) and axes (a value, mostly in [-1, 1]) that games read instead
+/// of particular keys or buttons, so the controls can be rebound. Any device's input counts.
+/// Saves to and loads from JSON.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ActionMap {
+    pub button_threshold: f64,
+    pub axis_deadzone: f64,
+    actions: BTreeMap<String, Vec<InputBinding>>,
+    axes: BTreeMap<String, Vec<AxisBinding>>
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self {
+            button_threshold: DEFAULT_BUTTON_THRESHOLD,
+            axis_deadzone: DEFAULT_AXIS_DEADZONE,
+            actions: BTreeMap::new(),
+            axes: BTreeMap::new()
+        }
+    }
+
+    /// Adds binding to the action (creating it), bindings already there are kept
+    pub fn bind(&mut self, action: &str, binding: InputBinding) {
+        let bindings = self.actions.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind(&mut self, action: &str, binding: InputBinding) {
+        if let Some(bindings) = self.actions.get_mut(action) {
+            bindings.retain(|&bound| bound != binding);
+        }
+    }
+
+    /// Replaces all the action's bindings
+    pub fn rebind(&mut self, action: &str, bindings: Vec<InputBinding>) {
+        self.actions.insert(action.to_string(), bindings);
+    }
+
+    pub fn get_bindings(&self, action: &str) -> &[InputBinding] {
+        self.actions.get(action).map(|bindings| bindings.as_slice()).unwrap_or(&[])
+    }
+
+    /// Action names in name order
+    pub fn get_actions(&self) -> Vec<&str> {
+        self.actions.keys().map(|action| action.as_str()).collect()
+    }
+
+    /// Actions the binding already holds down, e.g. to warn about clashes while rebinding
+    pub fn get_actions_bound_to(&self, binding: InputBinding) -> Vec<&str> {
+        self.actions.iter()
+            .filter(|(_, bindings)| bindings.contains(&binding))
+            .map(|(action, _)| action.as_str())
+            .collect()
+    }
+
+    /// Adds binding to the axis (creating it), bindings already there are kept
+    pub fn bind_axis(&mut self, axis: &str, binding: AxisBinding) {
+        let bindings = self.axes.entry(axis.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind_axis(&mut self, axis: &str, binding: AxisBinding) {
+        if let Some(bindings) = self.axes.get_mut(axis) {
+            bindings.retain(|&bound| bound != binding);
+        }
+    }
+
+    /// Replaces all the axis' bindings
+    pub fn rebind_axis(&mut self, axis: &str, bindings: Vec<AxisBinding>) {
+        self.axes.insert(axis.to_string(), bindings);
+    }
+
+    pub fn get_axis_bindings(&self, axis: &str) -> &[AxisBinding] {
+        self.axes.get(axis).map(|bindings| bindings.as_slice()).unwrap_or(&[])
+    }
+
+    /// Axis names in name order
+    pub fn get_axes(&self) -> Vec<&str> {
+        self.axes.keys().map(|axis| axis.as_str()).collect()
+    }
+
+    /// Whether any of the action's bindings is held, unknown actions are never held
+    pub fn get_action(&self, action: &str, kbd: &KeyboardInput, mouse: &MouseInput, joystick: &JoystickInput) -> bool {
+        self.get_bindings(action).iter()
+            .any(|&binding| self.get_binding_held(binding, kbd, mouse, joystick))
+    }
+
+    /// The largest value of the axis' bindings (by size), unknown axes are 0
+    pub fn get_axis(&self, axis: &str, kbd: &KeyboardInput, mouse: &MouseInput, joystick: &JoystickInput) -> f64 {
+        self.get_axis_bindings(axis).iter()
+            .map(|&binding| self.get_axis_binding_value(binding, kbd, mouse, joystick))
+            .fold(0.0, |acc, value| if value.abs() > acc.abs() { value } else { acc })
+    }
+
+    /// Gamepad bindings are never held here, read them with get_action_held
+    pub fn get_binding_held(&self, binding: InputBinding, kbd: &KeyboardInput, mouse: &MouseInput, joystick: &JoystickInput) -> bool {
+        match binding {
+            InputBinding::Key(key) => kbd.get_key(key),
+            InputBinding::MouseButton(button) => get_mouse_button(mouse, button),
+            InputBinding::JoystickButton(button) => get_joystick_button(joystick, button),
+            InputBinding::JoystickAxis(axis, AxisDirection::Negative) => get_joystick_axis(joystick, axis) < -self.button_threshold,
+            InputBinding::JoystickAxis(axis, AxisDirection::Positive) => get_joystick_axis(joystick, axis) > self.button_threshold,
+            InputBinding::Hat(direction) => direction.matches(joystick.get_hat_switch()),
+            InputBinding::Gamepad(_) => false
+        }
+    }
+
+    pub fn get_axis_binding_value(&self, binding: AxisBinding, kbd: &KeyboardInput, mouse: &MouseInput, joystick: &JoystickInput) -> f64 {
+        match binding {
+            AxisBinding::Buttons(negative, positive) => {
+                let negative = if self.get_binding_held(negative, kbd, mouse, joystick) { 1.0 } else { 0.0 };
+                let positive = if self.get_binding_held(positive, kbd, mouse, joystick) { 1.0 } else { 0.0 };
+                positive - negative
+            },
+            AxisBinding::JoystickAxis(axis) => {
+                let value = get_joystick_axis(joystick, axis);
+                if value.abs() < self.axis_deadzone { 0.0 } else { value }
+            },
+            AxisBinding::MouseX(scale) => scale * mouse.devices.iter().map(|device| device.movement.0 as f64).sum::<f64>(),
+            AxisBinding::MouseY(scale) => scale * mouse.devices.iter().map(|device| device.movement.1 as f64).sum::<f64>()
+        }
+    }
+
+    /// First input currently held (keys, then mouse buttons, then the joystick), for asking the
+    /// player to press what they want bound
+    pub fn get_held_binding(&self, kbd: &KeyboardInput, mouse: &MouseInput, joystick: &JoystickInput) -> Option<InputBinding> {
+        InputBinding::all().into_iter()
+            .find(|&binding| self.get_binding_held(binding, kbd, mouse, joystick))
+    }
+
+    /// Whether any of the action's bindings is held, gamepad buttons included
+    pub fn get_action_held(&self, action: &str, states: &ButtonStates) -> bool {
+        self.get_bindings(action).iter().any(|&binding| states.get_held(binding))
+    }
+
+    /// Whether any of the action's bindings went down this frame
+    pub fn get_action_pressed(&self, action: &str, states: &ButtonStates) -> bool {
+        self.get_bindings(action).iter().any(|&binding| states.get_pressed(binding))
+    }
+
+    /// Whether any of the action's bindings came up this frame
+    pub fn get_action_released(&self, action: &str, states: &ButtonStates) -> bool {
+        self.get_bindings(action).iter().any(|&binding| states.get_released(binding))
+    }
+
+    /// Whether any of the action's bindings was tapped twice in quick succession this frame
+    pub fn get_action_double_tapped(&self, action: &str, states: &ButtonStates) -> bool {
+        self.get_bindings(action).iter().any(|&binding| states.get_double_tapped(binding))
+    }
+
+    pub fn to_json(&self) -> Result<String, ActionMapError> {
+        serde_json::to_string_pretty(self).map_err(|err| ActionMapError::InvalidBindings(err.to_string()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ActionMapError> {
+        serde_json::from_str(json).map_err(|err| ActionMapError::InvalidBindings(err.to_string()))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ActionMapError> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ActionMapError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn get_mouse_button(mouse: &MouseInput, button: MouseButtonCode) -> bool {
+    match button {
+        MouseButtonCode::Left => mouse.get_left_button(),
+        MouseButtonCode::Right => mouse.get_right_button(),
+        MouseButtonCode::Middle => mouse.get_middle_button(),
+        MouseButtonCode::Button4 => mouse.get_button4(),
+        MouseButtonCode::Button5 => mouse.get_button5()
+    }
+}
+
+fn get_joystick_button(joystick: &JoystickInput, button: usize) -> bool {
+    match button {
+        1 => joystick.get_button_1(),
+        2 => joystick.get_button_2(),
+        3 => joystick.get_button_3(),
+        4 => joystick.get_button_4(),
+        5 => joystick.get_button_5(),
+        6 => joystick.get_button_6(),
+        7 => joystick.get_button_7(),
+        8 => joystick.get_button_8(),
+        9 => joystick.get_button_9(),
+        10 => joystick.get_button_10(),
+        _ => false
+    }
+}
+
+fn get_joystick_axis(joystick: &JoystickInput, axis: JoystickAxisCode) -> f64 {
+    match axis {
+        JoystickAxisCode::X => joystick.get_x_axis(),
+        JoystickAxisCode::Y => joystick.get_y_axis(),
+        JoystickAxisCode::Z => joystick.get_z_axis(),
+        JoystickAxisCode::RX => joystick.get_rx_axis(),
+        JoystickAxisCode::RY => joystick.get_ry_axis(),
+        JoystickAxisCode::RZ => joystick.get_rz_axis()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_default_map() -> ActionMap {
+        let mut map = ActionMap::new();
+        map.bind("jump", InputBinding::Key(KeyCode::Space));
+        map.bind("jump", InputBinding::JoystickButton(1));
+        map.bind_axis("move_x", AxisBinding::Buttons(InputBinding::Key(KeyCode::A), InputBinding::Key(KeyCode::D)));
+        map.bind_axis("move_x", AxisBinding::JoystickAxis(JoystickAxisCode::X));
+        map
+    }
+
+    #[test]
+    fn actions_follow_their_bindings() {
+        let mut map = get_default_map();
+        let mut kbd = KeyboardInput::default();
+        let mouse = MouseInput::default();
+        let mut joystick = JoystickInput::default();
+
+        assert!(!map.get_action("jump", &kbd, &mouse, &joystick));
+        joystick.devices[1].button_1 = true;
+        assert!(map.get_action("jump", &kbd, &mouse, &joystick));
+
+        map.rebind("jump", vec![InputBinding::Key(KeyCode::W)]);
+        assert!(!map.get_action("jump", &kbd, &mouse, &joystick));
+        kbd.devices[0].set_key(KeyCode::W, true);
+        assert!(map.get_action("jump", &kbd, &mouse, &joystick));
+        assert_eq!(map.get_actions_bound_to(InputBinding::Key(KeyCode::W)), vec!["jump"]);
+        assert!(!map.get_action("unknown", &kbd, &mouse, &joystick));
+    }
+
+    #[test]
+    fn axes_take_the_largest_value() {
+        let map = get_default_map();
+        let mut kbd = KeyboardInput::default();
+        let mouse = MouseInput::default();
+        let mut joystick = JoystickInput::default();
+
+        kbd.devices[0].set_key(KeyCode::A, true);
+        assert_eq!(map.get_axis("move_x", &kbd, &mouse, &joystick), -1.0);
+        kbd.devices[0].set_key(KeyCode::A, false);
+        joystick.devices[0].x_axis = 0.1;
+        assert_eq!(map.get_axis("move_x", &kbd, &mouse, &joystick), 0.0);
+        joystick.devices[0].x_axis = 0.6;
+        assert_eq!(map.get_axis("move_x", &kbd, &mouse, &joystick), 0.6);
+    }
+
+    #[test]
+    fn held_binding_is_found_for_rebinding() {
+        let map = ActionMap::new();
+        let kbd = KeyboardInput::default();
+        let mouse = MouseInput::default();
+        let mut joystick = JoystickInput::default();
+
+        assert_eq!(map.get_held_binding(&kbd, &mouse, &joystick), None);
+        joystick.devices[0].hat_switch = HatSwitch::UpLeft;
+        assert_eq!(map.get_held_binding(&kbd, &mouse, &joystick), Some(InputBinding::Hat(HatDirection::Up)));
+    }
+
+    #[test]
+    fn bindings_round_trip_through_json() {
+        let map = get_default_map();
+        let loaded = ActionMap::from_json(&map.to_json().unwrap()).unwrap();
+        assert_eq!(loaded, map);
+        assert!(ActionMap::from_json("{\"actions\": 3}").is_err());
+
+        let old_shift: InputBinding = serde_json::from_str("{\"Key\": \"Shift\"}").unwrap();
+        assert_eq!(old_shift, InputBinding::Key(KeyCode::LeftShift));
+
+        let mut map = ActionMap::new();
+        map.bind("jump", InputBinding::Gamepad(GamepadButton::South));
+        assert!(map.to_json().unwrap().contains("\"Gamepad\": \"South\""));
+        assert_eq!(ActionMap::from_json(&map.to_json().unwrap()).unwrap(), map);
+    }
+}
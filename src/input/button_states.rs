@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use crate::games::GameInput;
+use super::{KeyboardInput, MouseInput, JoystickInput};
+use super::gamepad::{GamepadInput, GamepadButton};
+use super::action_map::{InputBinding, MouseButtonCode, JoystickAxisCode, AxisDirection, HatDirection, DEFAULT_BUTTON_THRESHOLD};
+use super::joystick::JoystickInputKeys;
+
+/// The most seconds between two presses for them to count as a double tap
+pub const DEFAULT_DOUBLE_TAP_TIME: f64 = 0.3;
+
+/// A button over time, as seen at the latest frame
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ButtonState {
+    pub held: bool,
+    /// Went down this frame
+    pub pressed: bool,
+    /// Came up this frame
+    pub released: bool,
+    /// Pressed this frame soon enough after the previous press (a third tap starts over)
+    pub double_tapped: bool,
+    /// Seconds since the press while held, and how long it was held on the frame it's released
+    pub held_time: f64,
+    press_time: f64,
+    tap_time: Option<f64>
+}
+
+impl ButtonState {
+    pub fn update(&mut self, held: bool, time: f64, double_tap_time: f64) {
+        self.pressed = held && !self.held;
+        self.released = !held && self.held;
+        self.double_tapped = false;
+        if self.pressed {
+            self.double_tapped = self.tap_time.is_some_and(|tap_time| time - tap_time <= double_tap_time);
+            self.tap_time = if self.double_tapped { None } else { Some(time) };
+            self.press_time = time;
+        }
+        self.held = held;
+        self.held_time = if held || self.released { time - self.press_time } else { 0.0 };
+    }
+}
+
+/// Held, pressed and released states of every InputBinding, for any device and for each one.
+/// Input handlers update these after passing on the frame's input, see GameInput::get_button_states.
+#[derive(Clone, Debug)]
+pub struct ButtonStates {
+    pub double_tap_time: f64,
+    /// How far a joystick axis has to move to count as held
+    pub axis_threshold: f64,
+    buttons: HashMap<InputBinding, ButtonState>,
+    device_buttons: HashMap<(usize, InputBinding), ButtonState>
+}
+
+impl Default for ButtonStates {
+    fn default() -> Self {
+        ButtonStates {
+            double_tap_time: DEFAULT_DOUBLE_TAP_TIME,
+            axis_threshold: DEFAULT_BUTTON_THRESHOLD,
+            buttons: HashMap::new(),
+            device_buttons: HashMap::new()
+        }
+    }
+}
+
+impl ButtonStates {
+    /// time is in seconds, inputs the game doesn't take are left released
+    pub fn update(&mut self, time: f64, kbd: Option<&KeyboardInput>, mouse: Option<&MouseInput>, joystick: Option<&JoystickInput>, gamepads: Option<&GamepadInput>) {
+        let mut held = Vec::new();
+        if let Some(kbd) = kbd {
+            push_held_keys(&mut held, kbd);
+        }
+        if let Some(mouse) = mouse {
+            push_held_mouse_buttons(&mut held, mouse);
+        }
+        if let Some(joystick) = joystick {
+            push_held_joystick_bindings(&mut held, joystick, self.axis_threshold);
+        }
+        if let Some(gamepads) = gamepads {
+            push_held_gamepad_buttons(&mut held, gamepads);
+        }
+        self.update_held(time, &held);
+    }
+
+    /// Updates from the (device, binding) pairs held this frame. Only those and the bindings held
+    /// before are looked at, the rest are left released.
+    pub fn update_held(&mut self, time: f64, held: &[(usize, InputBinding)]) {
+        for &(device, binding) in held {
+            self.device_buttons.entry((device, binding)).or_default();
+            self.buttons.entry(binding).or_default();
+        }
+
+        let double_tap_time = self.double_tap_time;
+        for (key, state) in self.device_buttons.iter_mut() {
+            state.update(held.contains(key), time, double_tap_time);
+        }
+        for (&binding, state) in self.buttons.iter_mut() {
+            state.update(held.iter().any(|&(_, held_binding)| held_binding == binding), time, double_tap_time);
+        }
+
+        // Released buttons are kept while they could still be double tapped
+        let active = |state: &ButtonState| state.held || state.released
+            || state.tap_time.is_some_and(|tap_time| time - tap_time <= double_tap_time);
+        self.device_buttons.retain(|_, state| active(state));
+        self.buttons.retain(|_, state| active(state));
+    }
+
+    /// The binding on any device, e.g. held while any keyboard holds the key
+    pub fn get_state(&self, binding: InputBinding) -> ButtonState {
+        self.buttons.get(&binding).cloned().unwrap_or_default()
+    }
+
+    /// The binding on one keyboard, mouse or joystick (whichever the binding is for)
+    pub fn get_device_state(&self, device: usize, binding: InputBinding) -> ButtonState {
+        self.device_buttons.get(&(device, binding)).cloned().unwrap_or_default()
+    }
+
+    pub fn get_held(&self, binding: InputBinding) -> bool {
+        self.get_state(binding).held
+    }
+    pub fn get_pressed(&self, binding: InputBinding) -> bool {
+        self.get_state(binding).pressed
+    }
+    pub fn get_released(&self, binding: InputBinding) -> bool {
+        self.get_state(binding).released
+    }
+    pub fn get_double_tapped(&self, binding: InputBinding) -> bool {
+        self.get_state(binding).double_tapped
+    }
+    pub fn get_held_time(&self, binding: InputBinding) -> f64 {
+        self.get_state(binding).held_time
+    }
+
+    /// Bindings that went down this frame, in InputBinding::all order
+    pub fn get_pressed_bindings(&self) -> Vec<InputBinding> {
+        InputBinding::all().into_iter()
+            .filter(|&binding| self.get_pressed(binding))
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.buttons.clear();
+        self.device_buttons.clear();
+    }
+}
+
+/// Brings the game's button states up to date with the input just passed on, a no-op for games
+/// without them
+pub fn update_button_states(input: &mut dyn GameInput, time: f64) {
+    let axis_threshold = match input.get_button_states() {
+        Some(states) => states.axis_threshold,
+        None => return
+    };
+    let mut held = Vec::new();
+    if let Some(kbd) = input.get_kbd_inp() {
+        push_held_keys(&mut held, kbd);
+    }
+    if let Some(mouse) = input.get_mouse_inp() {
+        push_held_mouse_buttons(&mut held, mouse);
+    }
+    if let Some(joystick) = input.get_joystick_inp() {
+        push_held_joystick_bindings(&mut held, joystick, axis_threshold);
+    }
+    if let Some(gamepads) = input.get_gamepad_inp() {
+        push_held_gamepad_buttons(&mut held, gamepads);
+    }
+    if let Some(states) = input.get_button_states() {
+        states.update_held(time, &held);
+    }
+}
+
+fn push_held_keys(held: &mut Vec<(usize, InputBinding)>, kbd: &KeyboardInput) {
+    for (device, keys) in kbd.devices.iter().enumerate() {
+        held.extend(keys.get_held_keys().map(|key| (device, InputBinding::Key(key))));
+    }
+}
+
+fn push_held_mouse_buttons(held: &mut Vec<(usize, InputBinding)>, mouse: &MouseInput) {
+    for (device, keys) in mouse.devices.iter().enumerate() {
+        let buttons = [
+            (MouseButtonCode::Left, keys.left),
+            (MouseButtonCode::Right, keys.right),
+            (MouseButtonCode::Middle, keys.middle),
+            (MouseButtonCode::Button4, keys.button4),
+            (MouseButtonCode::Button5, keys.button5)
+        ];
+        held.extend(buttons.iter()
+            .filter(|&&(_, button_held)| button_held)
+            .map(|&(button, _)| (device, InputBinding::MouseButton(button))));
+    }
+}
+
+fn push_held_joystick_bindings(held: &mut Vec<(usize, InputBinding)>, joystick: &JoystickInput, axis_threshold: f64) {
+    for (device, keys) in joystick.devices.iter().enumerate() {
+        held.extend((1..=10)
+            .filter(|&button| get_joystick_button(keys, button))
+            .map(|button| (device, InputBinding::JoystickButton(button))));
+        for &axis in JoystickAxisCode::ALL.iter() {
+            let value = get_joystick_axis(keys, axis);
+            if value < -axis_threshold {
+                held.push((device, InputBinding::JoystickAxis(axis, AxisDirection::Negative)));
+            } else if value > axis_threshold {
+                held.push((device, InputBinding::JoystickAxis(axis, AxisDirection::Positive)));
+            }
+        }
+        held.extend(HatDirection::ALL.iter()
+            .filter(|direction| direction.matches(keys.hat_switch))
+            .map(|&direction| (device, InputBinding::Hat(direction))));
+    }
+}
+
+/// Devices are gamepad slots, disconnected gamepads hold nothing
+fn push_held_gamepad_buttons(held: &mut Vec<(usize, InputBinding)>, gamepads: &GamepadInput) {
+    for (index, gamepad) in gamepads.gamepads.iter().enumerate().filter(|(_, gamepad)| gamepad.connected) {
+        held.extend(GamepadButton::ALL.iter()
+            .filter(|&&button| gamepad.state.get_button(button))
+            .map(|&button| (index, InputBinding::Gamepad(button))));
+    }
+}
+
+pub(crate) fn get_joystick_button(keys: &JoystickInputKeys, button: usize) -> bool {
+    match button {
+        1 => keys.button_1,
+        2 => keys.button_2,
+        3 => keys.button_3,
+        4 => keys.button_4,
+        5 => keys.button_5,
+        6 => keys.button_6,
+        7 => keys.button_7,
+        8 => keys.button_8,
+        9 => keys.button_9,
+        10 => keys.button_10,
+        _ => false
+    }
+}
+
+fn get_joystick_axis(keys: &JoystickInputKeys, axis: JoystickAxisCode) -> f64 {
+    match axis {
+        JoystickAxisCode::X => keys.x_axis,
+        JoystickAxisCode::Y => keys.y_axis,
+        JoystickAxisCode::Z => keys.z_axis,
+        JoystickAxisCode::RX => keys.rx_axis,
+        JoystickAxisCode::RY => keys.ry_axis,
+        JoystickAxisCode::RZ => keys.rz_axis
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::KeyCode;
+    use crate::input::gamepad::{GamepadState, GamepadReport};
+
+    #[test]
+    fn presses_and_releases_last_one_frame() {
+        let mut states = ButtonStates::default();
+        let mut kbd = KeyboardInput::default();
+        let space = InputBinding::Key(KeyCode::Space);
+
+        kbd.devices[2].set_key(KeyCode::Space, true);
+        states.update(0.0, Some(&kbd), None, None, None);
+        assert!(states.get_pressed(space) && states.get_held(space));
+        assert!(states.get_device_state(2, space).pressed);
+        assert!(!states.get_device_state(0, space).held);
+
+        states.update(0.5, Some(&kbd), None, None, None);
+        assert!(!states.get_pressed(space) && states.get_held(space));
+        assert_eq!(states.get_held_time(space), 0.5);
+
+        kbd.devices[2].set_key(KeyCode::Space, false);
+        states.update(0.75, Some(&kbd), None, None, None);
+        assert!(states.get_released(space) && !states.get_held(space));
+        assert_eq!(states.get_held_time(space), 0.75);
+
+        states.update(1.0, Some(&kbd), None, None, None);
+        assert!(!states.get_released(space));
+        assert_eq!(states.get_held_time(space), 0.0);
+    }
+
+    #[test]
+    fn quick_second_presses_are_double_taps() {
+        let mut states = ButtonStates::default();
+        let mut joystick = JoystickInput::default();
+        let button = InputBinding::JoystickButton(1);
+
+        let mut tap = |time: f64, held: bool| {
+            joystick.devices[0].button_1 = held;
+            states.update(time, None, None, Some(&joystick), None);
+            states.get_double_tapped(button)
+        };
+        assert!(!tap(0.0, true));
+        assert!(!tap(0.1, false));
+        assert!(tap(0.2, true));
+        assert!(!tap(0.3, false));
+        // The third tap starts a new pair
+        assert!(!tap(0.4, true));
+        assert!(!tap(0.5, false));
+        assert!(!tap(1.0, true));
+    }
+
+    #[test]
+    fn gamepad_buttons_are_tracked_per_slot() {
+        let mut states = ButtonStates::default();
+        let mut gamepads = GamepadInput::default();
+        let south = InputBinding::Gamepad(GamepadButton::South);
+        let mut state = GamepadState::default();
+        state.set_button(GamepadButton::South, true);
+        gamepads.update(vec![
+            GamepadReport { id: "a".to_string(), name: "a".to_string(), state: GamepadState::default(), supports_rumble: false },
+            GamepadReport { id: "b".to_string(), name: "b".to_string(), state, supports_rumble: false }
+        ]);
+
+        states.update(0.0, None, None, None, Some(&gamepads));
+        assert!(states.get_pressed(south) && states.get_device_state(1, south).pressed);
+        assert!(!states.get_device_state(0, south).held);
+
+        gamepads.update(Vec::new());
+        states.update(0.1, None, None, None, Some(&gamepads));
+        assert!(states.get_released(south));
+    }
+}
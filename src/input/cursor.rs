@@ -0,0 +1,144 @@
+use glium::glutin::{Event, WindowEvent};
+use crate::geometry::*;
+use crate::games::ViewDetails;
+use crate::utils::transforms_2d;
+
+/// Where the system cursor is over the window
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CursorPosition {
+    /// Logical pixels from the window's top left corner
+    pub window_position: (f64, f64),
+    /// The window's logical inner size
+    pub window_size: (f64, f64)
+}
+
+impl CursorPosition {
+    pub fn get_aspect_ratio(&self) -> f64 {
+        self.window_size.0 / self.window_size.1
+    }
+
+    /// From -1 to 1 on both axes, with y up as in view space
+    pub fn get_normalised_position(&self) -> Point {
+        Point::new(
+            2.0 * self.window_position.0 / self.window_size.0 - 1.0,
+            1.0 - 2.0 * self.window_position.1 / self.window_size.1
+        )
+    }
+
+    /// In the coordinates of fixed renderables: y from -1 to 1 and x from -aspect ratio to aspect ratio
+    pub fn get_screen_position(&self) -> Point {
+        let normalised = self.get_normalised_position();
+        Point::new(normalised.x * self.get_aspect_ratio(), normalised.y)
+    }
+
+    /// The point of the world under the cursor, None for views other than 2D
+    pub fn get_world_position(&self, view: ViewDetails) -> Option<Point> {
+        match view {
+            ViewDetails::TwoDim(view) => Some(transforms_2d::view_to_world(
+                self.get_normalised_position(),
+                view.camera_pos,
+                view.viewport_height,
+                view.viewport_length,
+                self.get_aspect_ratio(),
+                view.up_vector,
+                view.use_aspect_ratio)),
+            _ => None
+        }
+    }
+}
+
+/// Follows the cursor through the window's events, for input handlers to pass on
+#[derive(Clone, Debug, Default)]
+pub struct CursorTracker {
+    window_position: Option<(f64, f64)>,
+    window_size: (f64, f64)
+}
+
+impl CursorTracker {
+    pub fn handle_event(&mut self, event: &Event) {
+        match event {
+            Event::WindowEvent { event: WindowEvent::CursorMoved { position, .. }, .. }
+                => self.window_position = Some((position.x, position.y)),
+            Event::WindowEvent { event: WindowEvent::CursorLeft { .. }, .. }
+                => self.window_position = None,
+            Event::WindowEvent { event: WindowEvent::Resized(size), .. }
+                => self.window_size = (size.width, size.height),
+            _ => ()
+        }
+    }
+
+    /// Resize events aren't sent for the window's first size, so the handler sets it each frame
+    pub fn set_window_size(&mut self, window_size: (f64, f64)) {
+        self.window_size = window_size;
+    }
+
+    /// None while the cursor is outside the window or the window is minimised
+    pub fn get_cursor(&self) -> Option<CursorPosition> {
+        let window_size = self.window_size;
+        if window_size.0 <= 0.0 || window_size.1 <= 0.0 {
+            return None;
+        }
+        self.window_position.map(|window_position| CursorPosition { window_position, window_size })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+    use crate::games::ViewDetails2D;
+
+    fn assert_near(point: Point, x: f64, y: f64) {
+        assert!((point.x - x).abs() < 1e-9 && (point.y - y).abs() < 1e-9, "{:?} is not ({}, {})", point, x, y);
+    }
+
+    #[test]
+    fn screen_positions_follow_the_fixed_coordinates() {
+        let cursor = CursorPosition { window_position: (0.0, 0.0), window_size: (800.0, 400.0) };
+        assert_near(cursor.get_normalised_position(), -1.0, 1.0);
+        assert_near(cursor.get_screen_position(), -2.0, 1.0);
+
+        let cursor = CursorPosition { window_position: (600.0, 300.0), ..cursor };
+        assert_near(cursor.get_screen_position(), 1.0, -0.5);
+    }
+
+    #[test]
+    fn world_positions_invert_the_worldview() {
+        let mut view = ViewDetails2D {
+            camera_pos: Point::new(3.0, -1.0),
+            viewport_height: 2.0,
+            viewport_length: 2.0,
+            ..Default::default()
+        };
+        let centre = CursorPosition { window_position: (400.0, 200.0), window_size: (800.0, 400.0) };
+        assert_near(centre.get_world_position(ViewDetails::TwoDim(view)).unwrap(), 3.0, -1.0);
+
+        let top_right = CursorPosition { window_position: (800.0, 0.0), ..centre };
+        assert_near(top_right.get_world_position(ViewDetails::TwoDim(view)).unwrap(), 7.0, 1.0);
+
+        // The worldview turns the world a quarter anticlockwise, so the top of the screen is to the right
+        view.set_rotation_angle(PI / 2.0);
+        let top = CursorPosition { window_position: (400.0, 0.0), ..centre };
+        assert_near(top.get_world_position(ViewDetails::TwoDim(view)).unwrap(), 5.0, -1.0);
+    }
+
+    #[test]
+    fn tracker_forgets_the_cursor_once_it_leaves() {
+        let mut tracker = CursorTracker::default();
+        let window_event = |event| Event::WindowEvent { window_id: unsafe { glium::glutin::WindowId::dummy() }, event };
+        let device_id = unsafe { glium::glutin::DeviceId::dummy() };
+
+        tracker.handle_event(&window_event(WindowEvent::CursorMoved {
+            device_id,
+            position: (10.0, 20.0).into(),
+            modifiers: Default::default()
+        }));
+        assert_eq!(tracker.get_cursor(), None);
+
+        tracker.set_window_size((100.0, 50.0));
+        assert_eq!(tracker.get_cursor().map(|cursor| cursor.window_position), Some((10.0, 20.0)));
+
+        tracker.handle_event(&window_event(WindowEvent::CursorLeft { device_id }));
+        assert_eq!(tracker.get_cursor(), None);
+    }
+}
@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+use crate::geometry::Point;
+use super::gamepad_mapping::GamepadMappingDatabase;
+
+/// XInput's recommended stick deadzone (as a fraction of full tilt)
+pub const DEFAULT_STICK_DEADZONE: f64 = 0.24;
+/// XInput's recommended trigger threshold
+pub const DEFAULT_TRIGGER_DEADZONE: f64 = 0.12;
+
+/// Buttons in the standard layout, named by position (south is A on Xbox pads and cross on PlayStation ones)
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftShoulder,
+    RightShoulder,
+    Back,
+    Start,
+    Guide,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight
+}
+
+impl GamepadButton {
+    pub const ALL: [GamepadButton; 15] = [
+        GamepadButton::South, GamepadButton::East, GamepadButton::West, GamepadButton::North,
+        GamepadButton::LeftShoulder, GamepadButton::RightShoulder, GamepadButton::Back, GamepadButton::Start,
+        GamepadButton::Guide, GamepadButton::LeftStick, GamepadButton::RightStick,
+        GamepadButton::DPadUp, GamepadButton::DPadDown, GamepadButton::DPadLeft, GamepadButton::DPadRight
+    ];
+}
+
+/// Sticks go from -1 to 1 with y up, triggers from 0 (released) to 1
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger
+}
+
+impl GamepadAxis {
+    pub const ALL: [GamepadAxis; 6] = [
+        GamepadAxis::LeftX, GamepadAxis::LeftY, GamepadAxis::RightX, GamepadAxis::RightY,
+        GamepadAxis::LeftTrigger, GamepadAxis::RightTrigger
+    ];
+}
+
+/// A gamepad's buttons and axes in the standard layout, before deadzones
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct GamepadState {
+    buttons: u16,
+    axes: [f64; 6]
+}
+
+impl GamepadState {
+    pub fn get_button(&self, button: GamepadButton) -> bool {
+        self.buttons & (1 << button as u16) != 0
+    }
+
+    pub fn set_button(&mut self, button: GamepadButton, held: bool) {
+        if held {
+            self.buttons |= 1 << button as u16;
+        } else {
+            self.buttons &= !(1 << button as u16);
+        }
+    }
+
+    pub fn get_axis(&self, axis: GamepadAxis) -> f64 {
+        self.axes[axis as usize]
+    }
+
+    pub fn set_axis(&mut self, axis: GamepadAxis, value: f64) {
+        self.axes[axis as usize] = value;
+    }
+}
+
+/// How a stick or trigger's travel past the deadzone turns into its value
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ResponseCurve {
+    Linear,
+    /// Finer control near the centre
+    Quadratic,
+    Cubic,
+    Power(f64)
+}
+
+impl ResponseCurve {
+    pub fn apply(self, value: f64) -> f64 {
+        match self {
+            ResponseCurve::Linear => value,
+            ResponseCurve::Quadratic => value * value,
+            ResponseCurve::Cubic => value * value * value,
+            ResponseCurve::Power(power) => value.powf(power)
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AxisResponse {
+    /// Travel below this reads as zero
+    pub deadzone: f64,
+    /// Travel within this of the edge reads as full, for pads that can't quite reach it
+    pub outer_deadzone: f64,
+    pub curve: ResponseCurve
+}
+
+impl AxisResponse {
+    pub fn new(deadzone: f64) -> Self {
+        AxisResponse {
+            deadzone,
+            outer_deadzone: 0.0,
+            curve: ResponseCurve::Linear
+        }
+    }
+
+    /// Rescales a magnitude from 0 to 1 so the deadzones are cut out
+    pub fn apply(&self, magnitude: f64) -> f64 {
+        if magnitude <= self.deadzone {
+            return 0.0;
+        }
+        let live_range = 1.0 - self.deadzone - self.outer_deadzone;
+        let scaled = if live_range > 0.0 { (magnitude - self.deadzone) / live_range } else { 1.0 };
+        self.curve.apply(scaled.min(1.0))
+    }
+
+    /// Radial deadzone: the stick's distance from the centre goes through the response and its
+    /// direction is kept, so diagonals don't snap to the axes
+    pub fn apply_to_stick(&self, stick: Point) -> Point {
+        let magnitude = stick.x.hypot(stick.y);
+        if magnitude <= self.deadzone {
+            return Point::zero();
+        }
+        let scale = self.apply(magnitude.min(1.0)) / magnitude;
+        Point::new(stick.x * scale, stick.y * scale)
+    }
+}
+
+/// What a backend reads from one connected gamepad each frame
+#[derive(Clone, Debug, PartialEq)]
+pub struct GamepadReport {
+    /// Stays the same while the gamepad stays connected, e.g. "xinput:0"
+    pub id: String,
+    pub name: String,
+    pub state: GamepadState,
+    pub supports_rumble: bool
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GamepadEvent {
+    Connected(usize),
+    Disconnected(usize)
+}
+
+/// Motor speeds from 0 to 1, the strong motor is the low frequency one
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rumble {
+    pub strong: f64,
+    pub weak: f64,
+    /// Seconds until the motors stop
+    pub duration: f64
+}
+
+#[derive(Clone, Debug)]
+pub struct Gamepad {
+    pub id: String,
+    pub name: String,
+    /// Disconnected gamepads keep their slot (reading as released) until something else connects
+    pub connected: bool,
+    pub supports_rumble: bool,
+    pub state: GamepadState
+}
+
+/// Gamepads in the standard layout, each keeping its slot while connected.
+/// Filled in by input handlers with gamepad support (MultiInput), others leave it empty.
+#[derive(Clone, Debug)]
+pub struct GamepadInput {
+    pub gamepads: Vec<Gamepad>,
+    pub stick_response: AxisResponse,
+    pub trigger_response: AxisResponse,
+    /// Layouts for joysticks that aren't XInput controllers, add to it with GamepadMappingDatabase::load
+    pub mappings: GamepadMappingDatabase,
+    events: Vec<GamepadEvent>,
+    rumble_requests: Vec<(usize, Rumble)>,
+    /// Slots rumbling, with when to stop
+    rumbling: HashMap<usize, f64>
+}
+
+impl Default for GamepadInput {
+    fn default() -> Self {
+        GamepadInput {
+            gamepads: Vec::new(),
+            stick_response: AxisResponse::new(DEFAULT_STICK_DEADZONE),
+            trigger_response: AxisResponse::new(DEFAULT_TRIGGER_DEADZONE),
+            mappings: GamepadMappingDatabase::default(),
+            events: Vec::new(),
+            rumble_requests: Vec::new(),
+            rumbling: HashMap::new()
+        }
+    }
+}
+
+impl GamepadInput {
+    /// Takes the connected gamepads from a backend. New ones go in the first free slot and
+    /// connections and disconnections since the last update become events.
+    pub fn update(&mut self, reports: Vec<GamepadReport>) {
+        self.events.clear();
+
+        for (index, gamepad) in self.gamepads.iter_mut().enumerate() {
+            if gamepad.connected && !reports.iter().any(|report| report.id == gamepad.id) {
+                gamepad.connected = false;
+                gamepad.state = GamepadState::default();
+                self.rumbling.remove(&index);
+                self.events.push(GamepadEvent::Disconnected(index));
+            }
+        }
+
+        for report in reports {
+            if let Some(gamepad) = self.gamepads.iter_mut().find(|gamepad| gamepad.connected && gamepad.id == report.id) {
+                gamepad.state = report.state;
+                continue;
+            }
+            // A gamepad coming back gets its old slot if it's still free
+            let index = self.gamepads.iter().position(|gamepad| !gamepad.connected && gamepad.id == report.id)
+                .or_else(|| self.gamepads.iter().position(|gamepad| !gamepad.connected))
+                .unwrap_or(self.gamepads.len());
+            let gamepad = Gamepad {
+                id: report.id,
+                name: report.name,
+                connected: true,
+                supports_rumble: report.supports_rumble,
+                state: report.state
+            };
+            if index == self.gamepads.len() {
+                self.gamepads.push(gamepad);
+            } else {
+                self.gamepads[index] = gamepad;
+            }
+            self.events.push(GamepadEvent::Connected(index));
+        }
+    }
+
+    /// Connections and disconnections seen by the last update
+    pub fn get_events(&self) -> &[GamepadEvent] {
+        &self.events
+    }
+
+    pub fn get_gamepad(&self, index: usize) -> Option<&Gamepad> {
+        self.gamepads.get(index).filter(|gamepad| gamepad.connected)
+    }
+
+    pub fn get_connected(&self) -> Vec<usize> {
+        (0..self.gamepads.len()).filter(|&index| self.gamepads[index].connected).collect()
+    }
+
+    pub fn get_button(&self, index: usize, button: GamepadButton) -> bool {
+        self.get_gamepad(index).is_some_and(|gamepad| gamepad.state.get_button(button))
+    }
+
+    /// Whether any gamepad holds the button
+    pub fn get_any_button(&self, button: GamepadButton) -> bool {
+        self.gamepads.iter().any(|gamepad| gamepad.connected && gamepad.state.get_button(button))
+    }
+
+    pub fn get_left_stick(&self, index: usize) -> Point {
+        self.get_stick(index, GamepadAxis::LeftX, GamepadAxis::LeftY)
+    }
+
+    pub fn get_right_stick(&self, index: usize) -> Point {
+        self.get_stick(index, GamepadAxis::RightX, GamepadAxis::RightY)
+    }
+
+    pub fn get_left_trigger(&self, index: usize) -> f64 {
+        self.get_trigger(index, GamepadAxis::LeftTrigger)
+    }
+
+    pub fn get_right_trigger(&self, index: usize) -> f64 {
+        self.get_trigger(index, GamepadAxis::RightTrigger)
+    }
+
+    /// The axis after its stick's or trigger's response
+    pub fn get_axis(&self, index: usize, axis: GamepadAxis) -> f64 {
+        match axis {
+            GamepadAxis::LeftX => self.get_left_stick(index).x,
+            GamepadAxis::LeftY => self.get_left_stick(index).y,
+            GamepadAxis::RightX => self.get_right_stick(index).x,
+            GamepadAxis::RightY => self.get_right_stick(index).y,
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => self.get_trigger(index, axis)
+        }
+    }
+
+    /// Asks the gamepad to rumble, replacing any rumble it's doing. Ignored by gamepads without motors.
+    pub fn rumble(&mut self, index: usize, rumble: Rumble) {
+        if self.get_gamepad(index).is_some_and(|gamepad| gamepad.supports_rumble) {
+            self.rumble_requests.push((index, rumble));
+        }
+    }
+
+    pub fn stop_rumble(&mut self, index: usize) {
+        self.rumble(index, Rumble { strong: 0.0, weak: 0.0, duration: 0.0 });
+    }
+
+    /// Motor speeds for the backend to set, by gamepad id: new requests and rumbles that have run
+    /// out (as zero). time is in seconds.
+    pub fn take_rumble(&mut self, time: f64) -> Vec<(String, f64, f64)> {
+        let mut outputs = Vec::new();
+        let expired: Vec<usize> = self.rumbling.iter()
+            .filter(|&(_, &end)| end <= time)
+            .map(|(&index, _)| index)
+            .collect();
+        for index in expired {
+            self.rumbling.remove(&index);
+            outputs.push((self.gamepads[index].id.clone(), 0.0, 0.0));
+        }
+        for (index, rumble) in self.rumble_requests.drain(..) {
+            let gamepad = &self.gamepads[index];
+            if !gamepad.connected {
+                continue;
+            }
+            outputs.retain(|(id, _, _)| *id != gamepad.id);
+            if rumble.duration > 0.0 {
+                self.rumbling.insert(index, time + rumble.duration);
+                outputs.push((gamepad.id.clone(), rumble.strong.clamp(0.0, 1.0), rumble.weak.clamp(0.0, 1.0)));
+            } else {
+                self.rumbling.remove(&index);
+                outputs.push((gamepad.id.clone(), 0.0, 0.0));
+            }
+        }
+        outputs
+    }
+
+    fn get_stick(&self, index: usize, x_axis: GamepadAxis, y_axis: GamepadAxis) -> Point {
+        self.get_gamepad(index).map_or(Point::zero(), |gamepad| {
+            let stick = Point::new(gamepad.state.get_axis(x_axis), gamepad.state.get_axis(y_axis));
+            self.stick_response.apply_to_stick(stick)
+        })
+    }
+
+    fn get_trigger(&self, index: usize, axis: GamepadAxis) -> f64 {
+        self.get_gamepad(index).map_or(0.0, |gamepad| self.trigger_response.apply(gamepad.state.get_axis(axis).max(0.0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(id: &str, south: bool) -> GamepadReport {
+        let mut state = GamepadState::default();
+        state.set_button(GamepadButton::South, south);
+        GamepadReport { id: id.to_string(), name: id.to_string(), state, supports_rumble: true }
+    }
+
+    #[test]
+    fn gamepads_keep_their_slots() {
+        let mut input = GamepadInput::default();
+        input.update(vec![report("a", false), report("b", true)]);
+        assert_eq!(input.get_events(), &[GamepadEvent::Connected(0), GamepadEvent::Connected(1)]);
+        assert!(input.get_button(1, GamepadButton::South));
+
+        input.update(vec![report("b", true)]);
+        assert_eq!(input.get_events(), &[GamepadEvent::Disconnected(0)]);
+        assert!(input.get_button(1, GamepadButton::South));
+
+        input.update(vec![report("c", false), report("b", false)]);
+        assert_eq!(input.get_events(), &[GamepadEvent::Connected(0)]);
+        assert_eq!(input.gamepads[0].id, "c");
+        assert_eq!(input.get_connected(), vec![0, 1]);
+    }
+
+    #[test]
+    fn sticks_use_a_radial_deadzone() {
+        let response = AxisResponse::new(0.2);
+        let stick = response.apply_to_stick(Point::new(0.1, 0.1));
+        assert_eq!((stick.x, stick.y), (0.0, 0.0));
+
+        // Past the deadzone the direction is kept and the edge still reads as full
+        let stick = response.apply_to_stick(Point::new(0.6, 0.8));
+        assert!((stick.x - 0.6).abs() < 1e-9 && (stick.y - 0.8).abs() < 1e-9);
+        let stick = response.apply_to_stick(Point::new(0.3, 0.4));
+        assert!((stick.x.hypot(stick.y) - 0.375).abs() < 1e-9);
+        assert!((stick.y / stick.x - 4.0 / 3.0).abs() < 1e-9);
+
+        let curved = AxisResponse { curve: ResponseCurve::Quadratic, ..response };
+        assert!((curved.apply(0.6) - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rumble_stops_after_its_duration() {
+        let mut input = GamepadInput::default();
+        input.update(vec![report("a", false)]);
+        input.rumble(0, Rumble { strong: 1.5, weak: 0.25, duration: 0.5 });
+        assert_eq!(input.take_rumble(1.0), vec![("a".to_string(), 1.0, 0.25)]);
+        assert!(input.take_rumble(1.25).is_empty());
+        assert_eq!(input.take_rumble(1.5), vec![("a".to_string(), 0.0, 0.0)]);
+        assert!(input.take_rumble(2.0).is_empty());
+    }
+}
@@ -0,0 +1,369 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::{error, fmt, fs, io};
+use super::joystick::{JoystickInputKeys, HatSwitch};
+use super::gamepad::{GamepadButton, GamepadAxis, GamepadState};
+
+/// The platform name mapping database lines are marked with, lines for other platforms are skipped
+#[cfg(windows)]
+pub const MAPPING_PLATFORM: &str = "Windows";
+#[cfg(target_os = "macos")]
+pub const MAPPING_PLATFORM: &str = "Mac OS X";
+#[cfg(not(any(windows, target_os = "macos")))]
+pub const MAPPING_PLATFORM: &str = "Linux";
+
+/// Mappings for common controllers that aren't XInput ones (those have a fixed layout).
+/// More can be added from the SDL community database (gamecontrollerdb.txt) with GamepadMappingDatabase::load.
+pub const DEFAULT_GAMEPAD_MAPPINGS: &str = "\
+030000004c050000c405000000000000,PS4 Controller,a:b1,b:b2,back:b8,dpdown:h0.4,dpleft:h0.8,dpright:h0.2,dpup:h0.1,guide:b12,leftshoulder:b4,leftstick:b10,lefttrigger:a3,leftx:a0,lefty:a1,rightshoulder:b5,rightstick:b11,righttrigger:a4,rightx:a2,righty:a5,start:b9,x:b0,y:b3,platform:Windows,
+030000004c050000cc09000000000000,PS4 Controller,a:b1,b:b2,back:b8,dpdown:h0.4,dpleft:h0.8,dpright:h0.2,dpup:h0.1,guide:b12,leftshoulder:b4,leftstick:b10,lefttrigger:a3,leftx:a0,lefty:a1,rightshoulder:b5,rightstick:b11,righttrigger:a4,rightx:a2,righty:a5,start:b9,x:b0,y:b3,platform:Windows,
+";
+
+#[derive(Debug)]
+pub enum GamepadMappingError {
+    Io(io::Error),
+    InvalidMapping(String)
+}
+
+impl fmt::Display for GamepadMappingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GamepadMappingError::Io(err) => write!(f, "Could not access mapping file: {}", err),
+            GamepadMappingError::InvalidMapping(mes) => write!(f, "Could not parse mapping: {}", mes)
+        }
+    }
+}
+
+impl error::Error for GamepadMappingError {}
+
+impl From<io::Error> for GamepadMappingError {
+    fn from(err: io::Error) -> Self {
+        GamepadMappingError::Io(err)
+    }
+}
+
+/// A joystick as HID reports it: buttons from 0, axes from -1 to 1 in usage order
+/// (X, Y, Z, RX, RY, RZ) with y down, and the first hat switch
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RawJoystickState {
+    pub buttons: Vec<bool>,
+    pub axes: [f64; 6],
+    pub hat: HatSwitch
+}
+
+impl<'a> From<&'a JoystickInputKeys> for RawJoystickState {
+    /// JoystickInput has y (and z) up, as MultiInput reports them
+    fn from(keys: &'a JoystickInputKeys) -> Self {
+        RawJoystickState {
+            buttons: vec![
+                keys.button_1, keys.button_2, keys.button_3, keys.button_4, keys.button_5,
+                keys.button_6, keys.button_7, keys.button_8, keys.button_9, keys.button_10
+            ],
+            axes: [keys.x_axis, -keys.y_axis, -keys.z_axis, keys.rx_axis, keys.ry_axis, keys.rz_axis],
+            hat: keys.hat_switch
+        }
+    }
+}
+
+/// The part of an input a mapping reads: all of it or one half (+ or -)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AxisRange {
+    Full,
+    Positive,
+    Negative
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MappingSource {
+    Button(usize),
+    /// Hat number and direction bits (1 up, 2 right, 4 down, 8 left)
+    Hat(usize, u8),
+    Axis { index: usize, range: AxisRange, inverted: bool }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MappingTarget {
+    Button(GamepadButton),
+    Axis(GamepadAxis, AxisRange)
+}
+
+/// One controller's layout, in SDL's game controller mapping format
+#[derive(Clone, Debug, PartialEq)]
+pub struct GamepadMapping {
+    pub guid: String,
+    pub name: String,
+    pub bindings: Vec<(MappingTarget, MappingSource)>
+}
+
+impl GamepadMapping {
+    /// Parses "guid,name,a:b0,leftx:a0,dpup:h0.1,...", ignoring fields it doesn't know (platform, misc1...)
+    pub fn parse(line: &str) -> Result<Self, GamepadMappingError> {
+        let mut fields = line.trim().split(',');
+        let guid = fields.next().unwrap_or("").to_lowercase();
+        if guid.len() != 32 || !guid.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(GamepadMappingError::InvalidMapping(format!("Bad GUID: {}", guid)));
+        }
+        let name = fields.next()
+            .ok_or_else(|| GamepadMappingError::InvalidMapping(format!("No name for {}", guid)))?
+            .to_string();
+
+        let mut bindings = Vec::new();
+        for field in fields.filter(|field| !field.is_empty()) {
+            let mut parts = field.splitn(2, ':');
+            let (target, source) = match (parts.next(), parts.next()) {
+                (Some(target), Some(source)) => (target, source),
+                _ => return Err(GamepadMappingError::InvalidMapping(format!("Bad field in {}: {}", name, field)))
+            };
+            if let Some(target) = parse_target(target) {
+                let source = parse_source(source)
+                    .ok_or_else(|| GamepadMappingError::InvalidMapping(format!("Bad input in {}: {}", name, field)))?;
+                bindings.push((target, source));
+            }
+        }
+        Ok(GamepadMapping { guid, name, bindings })
+    }
+
+    /// The joystick in the standard layout, with sticks turned y up
+    pub fn apply(&self, joystick: &RawJoystickState) -> GamepadState {
+        let mut state = GamepadState::default();
+        for &(target, source) in self.bindings.iter() {
+            match target {
+                MappingTarget::Button(button) => {
+                    let held = state.get_button(button) || read_source(source, joystick) > 0.5;
+                    state.set_button(button, held);
+                },
+                MappingTarget::Axis(axis, range) => {
+                    let value = read_source(source, joystick);
+                    let value = match (range, source) {
+                        (AxisRange::Negative, _) => -value,
+                        // Full range inputs go from released at -1 to pressed at 1 on triggers
+                        (AxisRange::Full, MappingSource::Axis { range: AxisRange::Full, .. })
+                            if is_trigger(axis) => (value + 1.0) / 2.0,
+                        _ => value
+                    };
+                    if value.abs() > state.get_axis(axis).abs() {
+                        state.set_axis(axis, value);
+                    }
+                }
+            }
+        }
+        state.set_axis(GamepadAxis::LeftY, -state.get_axis(GamepadAxis::LeftY));
+        state.set_axis(GamepadAxis::RightY, -state.get_axis(GamepadAxis::RightY));
+        state
+    }
+}
+
+/// Mappings by GUID
+#[derive(Clone, Debug)]
+pub struct GamepadMappingDatabase {
+    mappings: HashMap<String, GamepadMapping>
+}
+
+impl Default for GamepadMappingDatabase {
+    /// Holds DEFAULT_GAMEPAD_MAPPINGS
+    fn default() -> Self {
+        let mut database = GamepadMappingDatabase { mappings: HashMap::new() };
+        database.add_mappings(DEFAULT_GAMEPAD_MAPPINGS).unwrap();
+        database
+    }
+}
+
+impl GamepadMappingDatabase {
+    pub fn new() -> Self {
+        GamepadMappingDatabase { mappings: HashMap::new() }
+    }
+
+    /// Adds a mapping per line (replacing any for the same GUID), skipping comments and other
+    /// platforms' lines. Returns how many were added.
+    pub fn add_mappings(&mut self, text: &str) -> Result<usize, GamepadMappingError> {
+        let mut added = 0;
+        for line in text.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let platform = line.split(',')
+                .find(|field| field.starts_with("platform:"))
+                .map(|field| &field["platform:".len()..]);
+            if platform.is_some_and(|platform| platform != MAPPING_PLATFORM) {
+                continue;
+            }
+            let mapping = GamepadMapping::parse(line)?;
+            self.mappings.insert(mapping.guid.clone(), mapping);
+            added += 1;
+        }
+        Ok(added)
+    }
+
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<usize, GamepadMappingError> {
+        self.add_mappings(&fs::read_to_string(path)?)
+    }
+
+    pub fn get_mapping(&self, guid: &str) -> Option<&GamepadMapping> {
+        self.mappings.get(&guid.to_lowercase())
+    }
+}
+
+/// The GUID SDL gives a USB or Bluetooth HID device
+pub fn get_guid(vendor_id: u16, product_id: u16) -> String {
+    format!("03000000{:02x}{:02x}0000{:02x}{:02x}000000000000",
+        vendor_id & 0xff, vendor_id >> 8, product_id & 0xff, product_id >> 8)
+}
+
+/// Vendor and product ids from a device path such as \\?\HID#VID_054C&PID_05C4#...
+pub fn parse_device_ids(device_path: &str) -> Option<(u16, u16)> {
+    let upper = device_path.to_uppercase();
+    let read_id = |prefix: &str| {
+        let start = upper.find(prefix)? + prefix.len();
+        upper.get(start..start + 4).and_then(|id| u16::from_str_radix(id, 16).ok())
+    };
+    Some((read_id("VID_")?, read_id("PID_")?))
+}
+
+fn parse_target(target: &str) -> Option<MappingTarget> {
+    let (range, name) = split_range(target);
+    let button = match name {
+        "a" => GamepadButton::South,
+        "b" => GamepadButton::East,
+        "x" => GamepadButton::West,
+        "y" => GamepadButton::North,
+        "leftshoulder" => GamepadButton::LeftShoulder,
+        "rightshoulder" => GamepadButton::RightShoulder,
+        "back" => GamepadButton::Back,
+        "start" => GamepadButton::Start,
+        "guide" => GamepadButton::Guide,
+        "leftstick" => GamepadButton::LeftStick,
+        "rightstick" => GamepadButton::RightStick,
+        "dpup" => GamepadButton::DPadUp,
+        "dpdown" => GamepadButton::DPadDown,
+        "dpleft" => GamepadButton::DPadLeft,
+        "dpright" => GamepadButton::DPadRight,
+        _ => {
+            let axis = match name {
+                "leftx" => GamepadAxis::LeftX,
+                "lefty" => GamepadAxis::LeftY,
+                "rightx" => GamepadAxis::RightX,
+                "righty" => GamepadAxis::RightY,
+                "lefttrigger" => GamepadAxis::LeftTrigger,
+                "righttrigger" => GamepadAxis::RightTrigger,
+                _ => return None
+            };
+            return Some(MappingTarget::Axis(axis, range));
+        }
+    };
+    Some(MappingTarget::Button(button))
+}
+
+fn parse_source(source: &str) -> Option<MappingSource> {
+    let (range, source) = split_range(source);
+    let (inverted, source) = match source.strip_suffix('~') {
+        Some(source) => (true, source),
+        None => (false, source)
+    };
+    let mut chars = source.chars();
+    match (chars.next()?, chars.as_str()) {
+        ('b', button) => button.parse().ok().map(MappingSource::Button),
+        ('a', axis) => axis.parse().ok().map(|index| MappingSource::Axis { index, range, inverted }),
+        ('h', hat) => {
+            let mut parts = hat.splitn(2, '.');
+            let hat = parts.next()?.parse().ok()?;
+            let mask = parts.next()?.parse().ok()?;
+            Some(MappingSource::Hat(hat, mask))
+        },
+        _ => None
+    }
+}
+
+fn split_range(text: &str) -> (AxisRange, &str) {
+    if let Some(rest) = text.strip_prefix('+') {
+        (AxisRange::Positive, rest)
+    } else if let Some(rest) = text.strip_prefix('-') {
+        (AxisRange::Negative, rest)
+    } else {
+        (AxisRange::Full, text)
+    }
+}
+
+/// Buttons and hats read 0 or 1, half axes from 0 to 1 and full axes from -1 to 1
+fn read_source(source: MappingSource, joystick: &RawJoystickState) -> f64 {
+    match source {
+        MappingSource::Button(button) => {
+            if joystick.buttons.get(button).cloned().unwrap_or(false) { 1.0 } else { 0.0 }
+        },
+        MappingSource::Hat(0, mask) => {
+            if get_hat_bits(joystick.hat) & mask != 0 { 1.0 } else { 0.0 }
+        },
+        MappingSource::Hat(_, _) => 0.0,
+        MappingSource::Axis { index, range, inverted } => {
+            let value = joystick.axes.get(index).cloned().unwrap_or(0.0);
+            let value = if inverted { -value } else { value };
+            match range {
+                AxisRange::Full => value,
+                AxisRange::Positive => value.max(0.0),
+                AxisRange::Negative => (-value).max(0.0)
+            }
+        }
+    }
+}
+
+fn get_hat_bits(hat: HatSwitch) -> u8 {
+    match hat {
+        HatSwitch::Up => 1,
+        HatSwitch::UpRight => 1 | 2,
+        HatSwitch::Right => 2,
+        HatSwitch::DownRight => 4 | 2,
+        HatSwitch::Down => 4,
+        HatSwitch::DownLeft => 4 | 8,
+        HatSwitch::Left => 8,
+        HatSwitch::UpLeft => 1 | 8,
+        HatSwitch::Center => 0
+    }
+}
+
+fn is_trigger(axis: GamepadAxis) -> bool {
+    axis == GamepadAxis::LeftTrigger || axis == GamepadAxis::RightTrigger
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mappings_read_buttons_axes_and_hats() {
+        let mapping = GamepadMapping::parse(
+            "030000004c050000c405000000000000,PS4 Controller,a:b1,dpleft:h0.8,lefttrigger:a3,leftx:a0,lefty:a1,-rightx:b6,+rightx:b7,righty:a5~,misc1:b13,").unwrap();
+        let mut joystick = RawJoystickState { buttons: vec![false; 14], ..Default::default() };
+        joystick.buttons[1] = true;
+        joystick.buttons[6] = true;
+        joystick.hat = HatSwitch::DownLeft;
+        joystick.axes = [0.5, -1.0, 0.0, -1.0, 0.0, 0.25];
+
+        let state = mapping.apply(&joystick);
+        assert!(state.get_button(GamepadButton::South));
+        assert!(state.get_button(GamepadButton::DPadLeft));
+        assert!(!state.get_button(GamepadButton::DPadDown));
+        assert_eq!(state.get_axis(GamepadAxis::LeftX), 0.5);
+        // Pushed up, y down in HID becomes y up
+        assert_eq!(state.get_axis(GamepadAxis::LeftY), 1.0);
+        assert_eq!(state.get_axis(GamepadAxis::LeftTrigger), 0.0);
+        assert_eq!(state.get_axis(GamepadAxis::RightX), -1.0);
+        assert_eq!(state.get_axis(GamepadAxis::RightY), 0.25);
+    }
+
+    #[test]
+    fn database_skips_other_platforms_and_finds_devices() {
+        let mut database = GamepadMappingDatabase::new();
+        let text = format!("# comment\n{}\n03000000000000000000000000000000,Elsewhere,a:b0,platform:Nowhere,\n",
+            "030000004c050000c405000000000000,Pad,a:b0,");
+        assert_eq!(database.add_mappings(&text).unwrap(), 1);
+        assert!(database.add_mappings("xyz,Broken,a:b0,").is_err());
+        for field in ["a:", "+leftx:+", "a:~", "a:éb0"] {
+            let line = format!("030000004c050000c405000000000000,Broken,{},", field);
+            assert!(matches!(GamepadMapping::parse(&line), Err(GamepadMappingError::InvalidMapping(_))), "{}", field);
+        }
+
+        let ids = parse_device_ids(r"\\?\HID#VID_054C&PID_05C4&MI_00#7&1").unwrap();
+        assert_eq!(ids, (0x054c, 0x05c4));
+        assert_eq!(database.get_mapping(&get_guid(ids.0, ids.1)).unwrap().name, "Pad");
+        assert_eq!(GamepadMappingDatabase::default().get_mapping(&get_guid(0x054c, 0x09cc)).is_some(), cfg!(windows));
+    }
+}
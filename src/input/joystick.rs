@@ -1,4 +1,4 @@
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct JoystickInput {
     pub devices: Vec<JoystickInputKeys>
 }
@@ -123,7 +123,7 @@ impl JoystickInput {
     }
 }
 
-#[derive(Copy, Clone, Default)]
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
 pub struct JoystickInputKeys {
     pub button_1: bool,
     pub button_2: bool,
@@ -144,7 +144,7 @@ pub struct JoystickInputKeys {
     pub hat_switch: HatSwitch
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum HatSwitch {
     Up,
     UpRight,
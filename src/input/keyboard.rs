@@ -1,4 +1,5 @@
-#[derive(Clone)]
+/// Keys held on each keyboard
+#[derive(Clone, Debug, PartialEq)]
 pub struct KeyboardInput {
     pub devices: Vec<KeyboardInputKeys>
 }
@@ -12,316 +13,549 @@ impl Default for KeyboardInput {
 }
 
 impl KeyboardInput {
+    /// Whether any keyboard holds the key
+    pub fn get_key(&self, key: KeyCode) -> bool {
+        self.devices.iter().any(|device| device.get_key(key))
+    }
+
+    /// Keys any keyboard holds, in KeyCode order
+    pub fn get_held_keys(&self) -> Vec<KeyCode> {
+        KeyCode::ALL.iter()
+            .cloned()
+            .filter(|&key| self.get_key(key))
+            .collect()
+    }
+
     pub fn get_escape(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.escape})
-    } 
+        self.get_key(KeyCode::Escape)
+    }
     pub fn get_ret(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.ret})
+        self.get_key(KeyCode::Return) || self.get_key(KeyCode::NumpadEnter)
     }
     pub fn get_backspace(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.backspace})
+        self.get_key(KeyCode::Backspace)
     }
     pub fn get_left(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.left})
+        self.get_key(KeyCode::Left)
     }
     pub fn get_right(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.right})
+        self.get_key(KeyCode::Right)
     }
     pub fn get_up(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.up})
+        self.get_key(KeyCode::Up)
     }
     pub fn get_down(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.down})
+        self.get_key(KeyCode::Down)
     }
     pub fn get_space(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.space})
+        self.get_key(KeyCode::Space)
     }
     pub fn get_a(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.a})
+        self.get_key(KeyCode::A)
     }
     pub fn get_b(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.b})
+        self.get_key(KeyCode::B)
     }
     pub fn get_c(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.c})
+        self.get_key(KeyCode::C)
     }
     pub fn get_d(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.d})
+        self.get_key(KeyCode::D)
     }
     pub fn get_e(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.e})
+        self.get_key(KeyCode::E)
     }
     pub fn get_f(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.f})
+        self.get_key(KeyCode::F)
     }
     pub fn get_g(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.g})
+        self.get_key(KeyCode::G)
     }
     pub fn get_h(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.h})
+        self.get_key(KeyCode::H)
     }
     pub fn get_i(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.i})
+        self.get_key(KeyCode::I)
     }
     pub fn get_j(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.j})
+        self.get_key(KeyCode::J)
     }
     pub fn get_k(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.k})
+        self.get_key(KeyCode::K)
     }
     pub fn get_l(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.l})
+        self.get_key(KeyCode::L)
     }
     pub fn get_m(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.m})
+        self.get_key(KeyCode::M)
     }
     pub fn get_n(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.n})
+        self.get_key(KeyCode::N)
     }
     pub fn get_o(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.o})
+        self.get_key(KeyCode::O)
     }
     pub fn get_p(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.p})
+        self.get_key(KeyCode::P)
     }
     pub fn get_q(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.q})
+        self.get_key(KeyCode::Q)
     }
     pub fn get_r(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.r})
+        self.get_key(KeyCode::R)
     }
     pub fn get_s(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.s})
+        self.get_key(KeyCode::S)
     }
     pub fn get_t(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.t})
+        self.get_key(KeyCode::T)
     }
     pub fn get_u(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.u})
+        self.get_key(KeyCode::U)
     }
     pub fn get_v(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.v})
+        self.get_key(KeyCode::V)
     }
     pub fn get_w(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.w})
+        self.get_key(KeyCode::W)
     }
     pub fn get_x(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.x})
+        self.get_key(KeyCode::X)
     }
     pub fn get_y(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.y})
+        self.get_key(KeyCode::Y)
     }
     pub fn get_z(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.z})
+        self.get_key(KeyCode::Z)
     }
     pub fn get_zero(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.zero})
+        self.get_key(KeyCode::Zero)
     }
     pub fn get_one(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.one})
+        self.get_key(KeyCode::One)
     }
     pub fn get_two(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.two})
+        self.get_key(KeyCode::Two)
     }
     pub fn get_three(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.three})
+        self.get_key(KeyCode::Three)
     }
     pub fn get_four(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.four})
+        self.get_key(KeyCode::Four)
     }
     pub fn get_five(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.five})
+        self.get_key(KeyCode::Five)
     }
     pub fn get_six(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.six})
+        self.get_key(KeyCode::Six)
     }
     pub fn get_seven(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.seven})
+        self.get_key(KeyCode::Seven)
     }
     pub fn get_eight(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.eight})
+        self.get_key(KeyCode::Eight)
     }
     pub fn get_nine(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.nine})
+        self.get_key(KeyCode::Nine)
     }
     pub fn get_shift(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.shift})
+        self.get_key(KeyCode::LeftShift) || self.get_key(KeyCode::RightShift)
     }
     pub fn get_leftctrl(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.leftctrl})
+        self.get_key(KeyCode::LeftCtrl)
     }
     pub fn get_rightctrl(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.rightctrl})
+        self.get_key(KeyCode::RightCtrl)
     }
     pub fn get_leftalt(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.leftalt})
+        self.get_key(KeyCode::LeftAlt)
     }
     pub fn get_rightalt(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.rightalt})
+        self.get_key(KeyCode::RightAlt)
     }
     pub fn get_capslock(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.capslock})
+        self.get_key(KeyCode::CapsLock)
     }
     pub fn get_pause(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.pause})
+        self.get_key(KeyCode::Pause)
     }
     pub fn get_pageup(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.pageup})
+        self.get_key(KeyCode::PageUp)
     }
     pub fn get_pagedown(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.pagedown})
+        self.get_key(KeyCode::PageDown)
     }
     pub fn get_printscreen(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.printscreen})
+        self.get_key(KeyCode::PrintScreen)
     }
     pub fn get_insert(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.insert})
+        self.get_key(KeyCode::Insert)
     }
     pub fn get_end(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.end})
+        self.get_key(KeyCode::End)
     }
     pub fn get_home(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.home})
+        self.get_key(KeyCode::Home)
     }
     pub fn get_delete(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.delete})
+        self.get_key(KeyCode::Delete)
     }
     pub fn get_add(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.add})
+        self.get_key(KeyCode::Add)
     }
     pub fn get_subtract(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.subtract})
+        self.get_key(KeyCode::Subtract)
     }
     pub fn get_multiply(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.multiply})
+        self.get_key(KeyCode::Multiply)
     }
     pub fn get_separator(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.separator})
+        self.get_key(KeyCode::Separator)
     }
     pub fn get_decimal(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.decimal})
+        self.get_key(KeyCode::Decimal)
     }
     pub fn get_divide(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.divide})
+        self.get_key(KeyCode::Divide)
     }
     pub fn get_backslash(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.backslash})
+        self.get_key(KeyCode::Backslash)
     }
     pub fn get_forwardslash(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.forwardslash})
+        self.get_key(KeyCode::ForwardSlash)
     }
     pub fn get_plus(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.plus})
+        self.get_key(KeyCode::Plus)
     }
     pub fn get_minus(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.minus})
+        self.get_key(KeyCode::Minus)
     }
     pub fn get_fullstop(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.fullstop})
+        self.get_key(KeyCode::FullStop)
     }
     pub fn get_comma(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.comma})
+        self.get_key(KeyCode::Comma)
     }
     pub fn get_tab(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.tab})
+        self.get_key(KeyCode::Tab)
     }
     pub fn get_numlock(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.numlock})
+        self.get_key(KeyCode::NumLock)
     }
     pub fn get_leftsquarebracket(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.leftsquarebracket})
+        self.get_key(KeyCode::LeftSquareBracket)
     }
     pub fn get_rightsquarebracket(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.rightsquarebracket})
+        self.get_key(KeyCode::RightSquareBracket)
     }
     pub fn get_semicolon(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.semicolon})
+        self.get_key(KeyCode::Semicolon)
     }
     pub fn get_apostrophe(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.apostrophe})
+        self.get_key(KeyCode::Apostrophe)
     }
     pub fn get_hash(&self) -> bool {
-        self.devices.iter().fold(false, |acc, device| {acc || device.hash})
+        self.get_key(KeyCode::Hash)
+    }
+
+    /// Characters of the keys currently held (US layout for shifted symbols)
+    /// Text entry should use the TextInput events instead, which follow the keyboard layout and repeat
+    pub fn get_held_chars(&self) -> Vec<char> {
+        let shift = self.get_shift();
+        let upper_case = shift ^ self.get_capslock();
+        self.get_held_keys().into_iter()
+            .filter_map(|key| get_key_char(key, shift, upper_case))
+            .collect()
     }
 }
 
-#[derive(Copy, Clone, Default)]
+/// The keys one keyboard holds
+#[derive(Copy, Clone, Default, Debug, Eq, PartialEq)]
 pub struct KeyboardInputKeys {
-    pub escape: bool,
-    pub ret: bool,
-    pub backspace: bool,
-    pub left: bool,
-    pub right: bool,
-    pub up: bool,
-    pub down: bool,
-    pub space: bool,
-    pub a: bool,
-    pub b: bool,
-    pub c: bool,
-    pub d: bool,
-    pub e: bool,
-    pub f: bool,
-    pub g: bool,
-    pub h: bool,
-    pub i: bool,
-    pub j: bool,
-    pub k: bool,
-    pub l: bool,
-    pub m: bool,
-    pub n: bool,
-    pub o: bool,
-    pub p: bool,
-    pub q: bool,
-    pub r: bool,
-    pub s: bool,
-    pub t: bool,
-    pub u: bool,
-    pub v: bool,
-    pub w: bool,
-    pub x: bool,
-    pub y: bool,
-    pub z: bool,
-    pub zero: bool,
-    pub one: bool,
-    pub two: bool,
-    pub three: bool,
-    pub four: bool,
-    pub five: bool,
-    pub six: bool,
-    pub seven: bool,
-    pub eight: bool,
-    pub nine: bool,
-    pub shift: bool,
-    pub leftctrl: bool,
-    pub rightctrl: bool,
-    pub leftalt: bool,
-    pub rightalt: bool,
-    pub capslock: bool,
-    pub pause: bool,
-    pub pageup: bool,
-    pub pagedown: bool,
-    pub printscreen: bool,
-    pub insert: bool,
-    pub end: bool,
-    pub home: bool,
-    pub delete: bool,
-    pub add: bool,
-    pub subtract: bool,
-    pub multiply: bool,
-    pub separator: bool,
-    pub decimal: bool,
-    pub divide: bool,
-    pub backslash: bool,
-    pub forwardslash: bool,
-    pub plus: bool,
-    pub minus: bool,
-    pub fullstop: bool,
-    pub comma: bool,
-    pub tab: bool,
-    pub numlock: bool,
-    pub leftsquarebracket: bool,
-    pub rightsquarebracket: bool,
-    pub semicolon: bool,
-    pub apostrophe: bool,
-    pub hash: bool,
+    held: [u64; 2]
+}
+
+impl KeyboardInputKeys {
+    pub fn get_key(&self, key: KeyCode) -> bool {
+        let index = key as usize;
+        self.held[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    pub fn set_key(&mut self, key: KeyCode, held: bool) {
+        let index = key as usize;
+        if held {
+            self.held[index / 64] |= 1 << (index % 64);
+        } else {
+            self.held[index / 64] &= !(1 << (index % 64));
+        }
+    }
+
+    pub fn release_all(&mut self) {
+        self.held = [0; 2];
+    }
+
+    /// The held keys in KeyCode order, found from the set bits without checking every key
+    pub fn get_held_keys(&self) -> impl Iterator<Item=KeyCode> + '_ {
+        self.held.iter().enumerate().flat_map(|(word_index, &word)| {
+            let mut bits = word;
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let bit = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                Some(KeyCode::ALL[word_index * 64 + bit])
+            })
+        })
+    }
+}
+
+/// Every key a keyboard reports, named for a US/UK layout
+/// (Plus is the =/+ key and Hash the #/~ key next to return on UK keyboards)
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub enum KeyCode {
+    Escape,
+    Return,
+    Backspace,
+    Left,
+    Right,
+    Up,
+    Down,
+    Space,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    Zero,
+    One,
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    /// Action maps saved before the shift keys were told apart bind "Shift"
+    #[serde(alias = "Shift")]
+    LeftShift,
+    RightShift,
+    LeftCtrl,
+    RightCtrl,
+    LeftAlt,
+    RightAlt,
+    LeftSuper,
+    RightSuper,
+    Menu,
+    CapsLock,
+    Pause,
+    PageUp,
+    PageDown,
+    PrintScreen,
+    ScrollLock,
+    Insert,
+    End,
+    Home,
+    Delete,
+    Add,
+    Subtract,
+    Multiply,
+    Separator,
+    Decimal,
+    Divide,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadEnter,
+    NumpadEquals,
+    Backtick,
+    Backslash,
+    ForwardSlash,
+    Plus,
+    Minus,
+    FullStop,
+    Comma,
+    Tab,
+    NumLock,
+    LeftSquareBracket,
+    RightSquareBracket,
+    Semicolon,
+    Apostrophe,
+    Hash,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24
+}
+
+impl KeyCode {
+    pub const ALL: [KeyCode; 119] = [
+        KeyCode::Escape, KeyCode::Return, KeyCode::Backspace, KeyCode::Left, KeyCode::Right, KeyCode::Up,
+        KeyCode::Down, KeyCode::Space, KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F,
+        KeyCode::G, KeyCode::H, KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N,
+        KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R, KeyCode::S, KeyCode::T, KeyCode::U, KeyCode::V,
+        KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z, KeyCode::Zero, KeyCode::One, KeyCode::Two,
+        KeyCode::Three, KeyCode::Four, KeyCode::Five, KeyCode::Six, KeyCode::Seven, KeyCode::Eight,
+        KeyCode::Nine, KeyCode::LeftShift, KeyCode::RightShift, KeyCode::LeftCtrl, KeyCode::RightCtrl,
+        KeyCode::LeftAlt, KeyCode::RightAlt, KeyCode::LeftSuper, KeyCode::RightSuper, KeyCode::Menu,
+        KeyCode::CapsLock, KeyCode::Pause, KeyCode::PageUp, KeyCode::PageDown, KeyCode::PrintScreen,
+        KeyCode::ScrollLock, KeyCode::Insert, KeyCode::End, KeyCode::Home, KeyCode::Delete, KeyCode::Add,
+        KeyCode::Subtract, KeyCode::Multiply, KeyCode::Separator, KeyCode::Decimal, KeyCode::Divide,
+        KeyCode::Numpad0, KeyCode::Numpad1, KeyCode::Numpad2, KeyCode::Numpad3, KeyCode::Numpad4,
+        KeyCode::Numpad5, KeyCode::Numpad6, KeyCode::Numpad7, KeyCode::Numpad8, KeyCode::Numpad9,
+        KeyCode::NumpadEnter, KeyCode::NumpadEquals, KeyCode::Backtick, KeyCode::Backslash,
+        KeyCode::ForwardSlash, KeyCode::Plus, KeyCode::Minus, KeyCode::FullStop, KeyCode::Comma, KeyCode::Tab,
+        KeyCode::NumLock, KeyCode::LeftSquareBracket, KeyCode::RightSquareBracket, KeyCode::Semicolon,
+        KeyCode::Apostrophe, KeyCode::Hash, KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5,
+        KeyCode::F6, KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+        KeyCode::F13, KeyCode::F14, KeyCode::F15, KeyCode::F16, KeyCode::F17, KeyCode::F18, KeyCode::F19,
+        KeyCode::F20, KeyCode::F21, KeyCode::F22, KeyCode::F23, KeyCode::F24
+    ];
+}
+
+/// The character a key types on a US layout, None for keys that don't type
+pub fn get_key_char(key: KeyCode, shift: bool, upper_case: bool) -> Option<char> {
+    let (c, shifted) = match key {
+        KeyCode::A => ('a', 'A'),
+        KeyCode::B => ('b', 'B'),
+        KeyCode::C => ('c', 'C'),
+        KeyCode::D => ('d', 'D'),
+        KeyCode::E => ('e', 'E'),
+        KeyCode::F => ('f', 'F'),
+        KeyCode::G => ('g', 'G'),
+        KeyCode::H => ('h', 'H'),
+        KeyCode::I => ('i', 'I'),
+        KeyCode::J => ('j', 'J'),
+        KeyCode::K => ('k', 'K'),
+        KeyCode::L => ('l', 'L'),
+        KeyCode::M => ('m', 'M'),
+        KeyCode::N => ('n', 'N'),
+        KeyCode::O => ('o', 'O'),
+        KeyCode::P => ('p', 'P'),
+        KeyCode::Q => ('q', 'Q'),
+        KeyCode::R => ('r', 'R'),
+        KeyCode::S => ('s', 'S'),
+        KeyCode::T => ('t', 'T'),
+        KeyCode::U => ('u', 'U'),
+        KeyCode::V => ('v', 'V'),
+        KeyCode::W => ('w', 'W'),
+        KeyCode::X => ('x', 'X'),
+        KeyCode::Y => ('y', 'Y'),
+        KeyCode::Z => ('z', 'Z'),
+        KeyCode::Zero => ('0', ')'),
+        KeyCode::One => ('1', '!'),
+        KeyCode::Two => ('2', '@'),
+        KeyCode::Three => ('3', '#'),
+        KeyCode::Four => ('4', '$'),
+        KeyCode::Five => ('5', '%'),
+        KeyCode::Six => ('6', '^'),
+        KeyCode::Seven => ('7', '&'),
+        KeyCode::Eight => ('8', '*'),
+        KeyCode::Nine => ('9', '('),
+        KeyCode::Space => (' ', ' '),
+        KeyCode::Backtick => ('`', '~'),
+        KeyCode::Minus => ('-', '_'),
+        KeyCode::Plus => ('=', '+'),
+        KeyCode::FullStop => ('.', '>'),
+        KeyCode::Comma => (',', '<'),
+        KeyCode::ForwardSlash => ('/', '?'),
+        KeyCode::Backslash => ('\\', '|'),
+        KeyCode::Semicolon => (';', ':'),
+        KeyCode::Apostrophe => ('\'', '"'),
+        KeyCode::Hash => ('#', '~'),
+        KeyCode::LeftSquareBracket => ('[', '{'),
+        KeyCode::RightSquareBracket => (']', '}'),
+        KeyCode::Add => ('+', '+'),
+        KeyCode::Subtract => ('-', '-'),
+        KeyCode::Multiply => ('*', '*'),
+        KeyCode::Divide => ('/', '/'),
+        KeyCode::Decimal => ('.', '.'),
+        KeyCode::Numpad0 => ('0', '0'),
+        KeyCode::Numpad1 => ('1', '1'),
+        KeyCode::Numpad2 => ('2', '2'),
+        KeyCode::Numpad3 => ('3', '3'),
+        KeyCode::Numpad4 => ('4', '4'),
+        KeyCode::Numpad5 => ('5', '5'),
+        KeyCode::Numpad6 => ('6', '6'),
+        KeyCode::Numpad7 => ('7', '7'),
+        KeyCode::Numpad8 => ('8', '8'),
+        KeyCode::Numpad9 => ('9', '9'),
+        _ => return None
+    };
+    let letter = c.is_ascii_alphabetic();
+    Some(if (letter && upper_case) || (!letter && shift) { shifted } else { c })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_set_and_released() {
+        let mut keys = KeyboardInputKeys::default();
+        keys.set_key(KeyCode::F24, true);
+        keys.set_key(KeyCode::A, true);
+        assert!(keys.get_key(KeyCode::F24) && keys.get_key(KeyCode::A));
+        keys.set_key(KeyCode::A, false);
+        assert!(!keys.get_key(KeyCode::A) && keys.get_key(KeyCode::F24));
+        assert!(KeyCode::ALL.iter().enumerate().all(|(index, &key)| key as usize == index));
+        keys.set_key(KeyCode::Escape, true);
+        assert_eq!(keys.get_held_keys().collect::<Vec<_>>(), vec![KeyCode::Escape, KeyCode::F24]);
+
+        let mut kbd = KeyboardInput::default();
+        kbd.devices[3].set_key(KeyCode::RightShift, true);
+        kbd.devices[0].set_key(KeyCode::Two, true);
+        assert!(kbd.get_shift());
+        assert_eq!(kbd.get_held_keys(), vec![KeyCode::Two, KeyCode::RightShift]);
+        assert_eq!(kbd.get_held_chars(), vec!['@']);
+    }
 }
@@ -1,14 +1,39 @@
+#[cfg(windows)]
 pub mod multihandler;
+pub mod window_input;
+pub mod action_map;
 pub mod keyboard;
+pub mod text_input;
 pub mod mouse;
+pub mod cursor;
 pub mod joystick;
+pub mod gamepad;
+pub mod gamepad_mapping;
+#[cfg(windows)]
+pub mod xinput;
 pub mod bool_switch;
+pub mod button_states;
+pub mod players;
+pub mod recording;
 use crate::games::GameInput;
+use crate::window::{WindowHandler, GlutinInput, WindowEventQueue};
+use crate::rendering::WindowSpec;
 
-pub use self::keyboard::KeyboardInput;
+pub use self::keyboard::{KeyboardInput, KeyCode};
+pub use self::text_input::{TextInput, TextInputEvent, KeyTextTranslator};
 pub use self::mouse::MouseInput;
+pub use self::cursor::{CursorPosition, CursorTracker};
 pub use self::joystick::JoystickInput;
 pub use self::joystick::HatSwitch;
+pub use self::gamepad::{GamepadInput, Gamepad, GamepadButton, GamepadAxis, GamepadState, GamepadEvent, GamepadReport,
+                        Rumble, AxisResponse, ResponseCurve, DEFAULT_STICK_DEADZONE, DEFAULT_TRIGGER_DEADZONE};
+pub use self::gamepad_mapping::{GamepadMapping, GamepadMappingDatabase, GamepadMappingError, RawJoystickState};
+pub use self::window_input::WindowInput;
+pub use self::button_states::{ButtonStates, ButtonState, update_button_states, DEFAULT_DOUBLE_TAP_TIME};
+pub use self::players::{PlayerSlots, PlayerInput, PlayerEvent, InputDevice, DEFAULT_MAX_PLAYERS};
+pub use self::recording::{InputRecording, RecordedFrame, FrameInput, ReplayInputHandler, RecordingError, DEFAULT_RECORDING_PATH};
+pub use self::action_map::{ActionMap, ActionMapError, InputBinding, AxisBinding, MouseButtonCode, JoystickAxisCode,
+                           AxisDirection, HatDirection, DEFAULT_BUTTON_THRESHOLD, DEFAULT_AXIS_DEADZONE};
 
 pub trait InputHandler {
     fn init(&mut self) {}
@@ -17,9 +42,29 @@ pub trait InputHandler {
     fn pass_on_input<'a>(&self, _: Option<&'a mut dyn GameInput>) {}
     fn escape_key_pressed(&self) -> bool { false }
     fn backtick_key_pressed(&self) -> bool { false }
+    fn f7_key_pressed(&self) -> bool { false }
     fn f8_key_pressed(&self) -> bool { false }
     fn f9_key_pressed(&self) -> bool { false }    
     fn flush_input(&mut self) {}
+    /// The window the cursor position is measured in, set every frame
+    fn set_window_spec(&mut self, _: WindowSpec) {}
+    fn get_cursor(&self) -> Option<CursorPosition> { None }
+    /// Seconds to step the game's logic by in place of the measured frame time (replays give the recorded ones)
+    fn get_time_step(&self) -> Option<f64> { None }
+}
+
+/// The input and window handlers for this platform: raw input through MultiInput on Windows,
+/// the window's own events through WindowInput elsewhere (MultiInput still reads the cursor from them)
+#[cfg(windows)]
+pub fn new_platform_handlers() -> (Box<dyn InputHandler>, Box<dyn WindowHandler>) {
+    let event_queue = WindowEventQueue::new();
+    (Box::new(multihandler::MultiInput::with_window_events(event_queue.clone())), Box::new(GlutinInput::with_event_queue(event_queue)))
+}
+
+#[cfg(not(windows))]
+pub fn new_platform_handlers() -> (Box<dyn InputHandler>, Box<dyn WindowHandler>) {
+    let event_queue = WindowEventQueue::new();
+    (Box::new(WindowInput::new(event_queue.clone())), Box::new(GlutinInput::with_event_queue(event_queue)))
 }
 
 #[allow(dead_code)]
@@ -1,12 +1,17 @@
-#[derive(Clone)]
+use super::cursor::CursorPosition;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct MouseInput {
-    pub devices: Vec<MouseInputKeys>
+    pub devices: Vec<MouseInputKeys>,
+    /// The system cursor, shared by every mouse (None while it's outside the window)
+    pub cursor: Option<CursorPosition>
 }
 
 impl Default for MouseInput {
     fn default() -> Self {
         Self {
-            devices: vec![Default::default(); 8]
+            devices: vec![Default::default(); 8],
+            cursor: None
         }
     }
 }
@@ -37,11 +42,20 @@ impl MouseInput {
     pub fn get_button5(&self) -> bool {
         self.devices.iter().fold(false, |acc, device| {acc || device.button5})
     }
+    /// Wheel turns this frame summed over every mouse, in notches
+    pub fn get_wheel(&self) -> (f64, f64) {
+        self.devices.iter().fold((0.0, 0.0), |acc, device| {(acc.0 + device.wheel.0, acc.1 + device.wheel.1)})
+    }
+    pub fn get_cursor(&self) -> Option<CursorPosition> {
+        self.cursor
+    }
 }
 
-#[derive(Copy, Clone, Default)]
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
 pub struct MouseInputKeys {
     pub movement: (i32, i32),
+    /// Notches turned this frame, horizontally and vertically (positive is right and away from the user)
+    pub wheel: (f64, f64),
     pub left: bool,
     pub right: bool,
     pub middle: bool,
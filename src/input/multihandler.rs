@@ -4,13 +4,26 @@ use multiinput::devices::HatSwitch;
 use std::collections::HashMap;
 use crate::games::GameInput;
 use crate::input;
-use super::{InputHandler, bool_switch};
+use crate::window::WindowEventQueue;
+use crate::rendering::WindowSpec;
+use super::{InputHandler, bool_switch, update_button_states};
+use super::cursor::{CursorPosition, CursorTracker};
+use super::gamepad::GamepadReport;
+use super::gamepad_mapping::{GamepadMappingDatabase, RawJoystickState, get_guid, parse_device_ids};
+use super::xinput::{XInput, is_xinput_device};
+use super::keyboard::KeyCode;
+use super::text_input::{TextInputEvent, KeyTextTranslator};
 
 pub struct MultiInput {
     pub raw_states: RawStates,
     raw_manager: RawInputManager,
+    window_events: Option<WindowEventQueue>,
+    cursor_tracker: CursorTracker,
+    xinput: Option<XInput>,
+    xinput_reports: Vec<GamepadReport>,
     escape_key_switch: bool_switch::BoolSwitch,
     backtick_key_switch: bool_switch::BoolSwitch,
+    f7_key_switch: bool_switch::BoolSwitch,
     f8_key_switch: bool_switch::BoolSwitch,
     f9_key_switch: bool_switch::BoolSwitch
 }
@@ -20,10 +33,15 @@ pub struct RawStates {
     pub key_states: HashMap<Key, bool>,
     pub mouse_button_states: HashMap<MouseAndButton, bool>,
     pub mouse_move_states: HashMap<usize, (i32, i32)>,
+    pub mouse_wheel_states: HashMap<usize, f64>,
     pub joystick_button_states: HashMap<JoystickButton, bool>,
     pub joystick_axis_states: HashMap<JoystickAxis, f64>,
     pub joystick_hatswitch_states: HashMap<JoystickHatSwitch, HatSwitch>,
+    pub text_events: Vec<TextInputEvent>,
+    text_translator: KeyTextTranslator,
     device_stats: DeviceStats,
+    /// Device paths of the joysticks, by number
+    joystick_paths: Vec<String>,
 }
 
 impl RawStates {
@@ -31,6 +49,8 @@ impl RawStates {
         self.key_states.clear();
         self.mouse_button_states.clear();
         self.mouse_move_states.clear();
+        self.mouse_wheel_states.clear();
+        self.text_events.clear();
     }
 }
 
@@ -53,18 +73,88 @@ impl MultiInput {
         raw_manager.register_devices(DeviceType::Joysticks(XInputInclude::True));
         let raw_states = RawStates{
             device_stats: raw_manager.get_device_stats(),
+            joystick_paths: Self::get_joystick_paths(&raw_manager),
             ..Default::default()
             };
         MultiInput {
             raw_states: raw_states,
             raw_manager: raw_manager,
+            window_events: None,
+            cursor_tracker: CursorTracker::default(),
+            xinput: XInput::load(),
+            xinput_reports: Vec::new(),
             escape_key_switch: bool_switch::BoolSwitch::new(),
             backtick_key_switch: bool_switch::BoolSwitch::new(),
+            f7_key_switch: bool_switch::BoolSwitch::new(),
             f8_key_switch: bool_switch::BoolSwitch::new(),    
             f9_key_switch: bool_switch::BoolSwitch::new(),    
         }
     }
 
+    /// Raw input has no cursor, so it's followed through the window's events from
+    /// GlutinInput::with_event_queue
+    pub fn with_window_events(window_events: WindowEventQueue) -> Self {
+        MultiInput {
+            window_events: Some(window_events),
+            ..Self::new()
+        }
+    }
+
+    fn get_joystick_paths(raw_manager: &RawInputManager) -> Vec<String> {
+        raw_manager.get_device_list().joysticks.into_iter().map(|joystick| joystick.name).collect()
+    }
+
+    /// A joystick as HID reports it, undoing MultiInput turning y and z up
+    fn get_raw_joystick(&self, index: usize) -> RawJoystickState {
+        let raw_states = &self.raw_states;
+        let button_count = raw_states.joystick_button_states.keys()
+            .filter(|button| button.0 == index)
+            .map(|button| button.1 + 1)
+            .max()
+            .unwrap_or(0);
+        let buttons = (0..button_count)
+            .map(|button| raw_states.joystick_button_states.get(&JoystickButton(index, button)).cloned().unwrap_or(false))
+            .collect();
+        let get_axis = |axis| raw_states.joystick_axis_states.get(&JoystickAxis(index, axis)).cloned().unwrap_or(0.0);
+        let hat = match raw_states.joystick_hatswitch_states.get(&JoystickHatSwitch(index)) {
+            Some(HatSwitch::Up) => input::HatSwitch::Up,
+            Some(HatSwitch::UpRight) => input::HatSwitch::UpRight,
+            Some(HatSwitch::Right) => input::HatSwitch::Right,
+            Some(HatSwitch::DownRight) => input::HatSwitch::DownRight,
+            Some(HatSwitch::Down) => input::HatSwitch::Down,
+            Some(HatSwitch::DownLeft) => input::HatSwitch::DownLeft,
+            Some(HatSwitch::Left) => input::HatSwitch::Left,
+            Some(HatSwitch::UpLeft) => input::HatSwitch::UpLeft,
+            Some(HatSwitch::Center) | None => input::HatSwitch::Center
+        };
+        RawJoystickState {
+            buttons,
+            axes: [get_axis(Axis::X), -get_axis(Axis::Y), -get_axis(Axis::Z), get_axis(Axis::RX), get_axis(Axis::RY), get_axis(Axis::RZ)],
+            hat
+        }
+    }
+
+    /// XInput controllers, then joysticks with a mapping
+    fn get_gamepad_reports(&self, mappings: &GamepadMappingDatabase) -> Vec<GamepadReport> {
+        let mut reports = self.xinput_reports.clone();
+        for (index, path) in self.raw_states.joystick_paths.iter().enumerate() {
+            if is_xinput_device(path) {
+                continue;
+            }
+            let mapping = parse_device_ids(path)
+                .and_then(|(vendor_id, product_id)| mappings.get_mapping(&get_guid(vendor_id, product_id)));
+            if let Some(mapping) = mapping {
+                reports.push(GamepadReport {
+                    id: path.clone(),
+                    name: mapping.name.clone(),
+                    state: mapping.apply(&self.get_raw_joystick(index)),
+                    supports_rumble: false
+                });
+            }
+        }
+        reports
+    }
+
     fn get_current_mouse_move(move_states: &HashMap<usize, (i32, i32)>, num: usize) -> (i32, i32) {
         move_states.get(&num).unwrap_or(&(0, 0)).clone()
     }
@@ -78,6 +168,7 @@ impl InputHandler for MultiInput {
         self.raw_manager.register_devices(DeviceType::Joysticks(XInputInclude::True));
         self.raw_states = RawStates{
             device_stats: self.raw_manager.get_device_stats(),
+            joystick_paths: Self::get_joystick_paths(&self.raw_manager),
             ..Default::default()
         };
     }
@@ -87,7 +178,14 @@ impl InputHandler for MultiInput {
         while let Some(event) = self.raw_manager.get_event() {
             match event {
                 RawEvent::KeyboardEvent(num, key_id, state)
-                    => {raw_states.key_states.insert(Key(num, key_id), state == State::Pressed);},
+                    => {
+                        let pressed = state == State::Pressed;
+                        // Raw input has no typed text, so it's made from the key presses
+                        if let Some(c) = raw_states.text_translator.translate(get_key_code(&key_id), pressed) {
+                            raw_states.text_events.push(TextInputEvent::Char(c));
+                        }
+                        raw_states.key_states.insert(Key(num, key_id), pressed);
+                        },
                 RawEvent::MouseButtonEvent(num, mouse_button, state)
                     => {raw_states.mouse_button_states.insert(MouseAndButton(num, mouse_button), state == State::Pressed);},
                 RawEvent::MouseMoveEvent(num, x, y)
@@ -96,6 +194,8 @@ impl InputHandler for MultiInput {
                         let new_mouse_move = (current_mouse_movement.0 + x, current_mouse_movement.1 + y);
                         raw_states.mouse_move_states.insert(num, (new_mouse_move.0, new_mouse_move.1));
                         },
+                RawEvent::MouseWheelEvent(num, notches)
+                    => {*raw_states.mouse_wheel_states.entry(num).or_insert(0.0) += notches as f64;},
                 RawEvent::JoystickButtonEvent(num, button_num, state)
                     => {raw_states.joystick_button_states.insert(JoystickButton(num, button_num), state == State::Pressed);},
                 RawEvent::JoystickAxisEvent(num, axis, value)
@@ -106,6 +206,16 @@ impl InputHandler for MultiInput {
             }
         }
 
+        if let Some(xinput) = &mut self.xinput {
+            self.xinput_reports = xinput.poll(time::precise_time_s());
+        }
+
+        if let Some(window_events) = &self.window_events {
+            for event in window_events.take() {
+                self.cursor_tracker.handle_event(&event);
+            }
+        }
+
         for index in 0..raw_states.device_stats.number_of_keyboards {
             if let Some(&state) = raw_states.key_states.get(&Key(index, KeyId::Escape)) {
                 self.escape_key_switch.update_state(state);
@@ -118,6 +228,12 @@ impl InputHandler for MultiInput {
             } else {
                 self.backtick_key_switch.clear_switch();
             }
+
+            if let Some(&state) = raw_states.key_states.get(&Key(index, KeyId::F7)) {
+                self.f7_key_switch.update_state(state);
+            } else {
+                self.f7_key_switch.clear_switch();
+            }
             
             if let Some(&state) = raw_states.key_states.get(&Key(index, KeyId::F8)) {
                 self.f8_key_switch.update_state(state);
@@ -140,211 +256,24 @@ impl InputHandler for MultiInput {
                     if kbds.devices.iter().nth(index).is_none() {
                         kbds.devices.push(Default::default());
                     }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Escape)) {
-                        kbds.devices[index].escape = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Return)) {
-                        kbds.devices[index].ret = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Backspace)) {
-                        kbds.devices[index].backspace = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Space)) {
-                        kbds.devices[index].space = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::LeftCtrl)) {
-                        kbds.devices[index].leftctrl = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::RightCtrl)) {
-                        kbds.devices[index].rightctrl = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::LeftAlt)) {
-                        kbds.devices[index].leftalt = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::RightAlt)) {
-                        kbds.devices[index].rightalt = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Left)) {
-                        kbds.devices[index].left = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Right)) {
-                        kbds.devices[index].right = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Up)) {
-                        kbds.devices[index].up = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Down)) {
-                        kbds.devices[index].down = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::A)) {
-                        kbds.devices[index].a = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::B)) {
-                        kbds.devices[index].b = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::C)) {
-                        kbds.devices[index].c = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::D)) {
-                        kbds.devices[index].d = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::E)) {
-                        kbds.devices[index].e = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::F)) {
-                        kbds.devices[index].f = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::G)) {
-                        kbds.devices[index].g = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::H)) {
-                        kbds.devices[index].h = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::I)) {
-                        kbds.devices[index].i = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::J)) {
-                        kbds.devices[index].j = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::K)) {
-                        kbds.devices[index].k = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::L)) {
-                        kbds.devices[index].l = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::M)) {
-                        kbds.devices[index].m = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::N)) {
-                        kbds.devices[index].n = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::O)) {
-                        kbds.devices[index].o = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::P)) {
-                        kbds.devices[index].p = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Q)) {
-                        kbds.devices[index].q = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::R)) {
-                        kbds.devices[index].r = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::S)) {
-                        kbds.devices[index].s = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::T)) {
-                        kbds.devices[index].t = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::U)) {
-                        kbds.devices[index].u = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::V)) {
-                        kbds.devices[index].v = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::W)) {
-                        kbds.devices[index].w = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::X)) {
-                        kbds.devices[index].x = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Y)) {
-                        kbds.devices[index].y = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Z)) {
-                        kbds.devices[index].z = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Pause)) {
-                        kbds.devices[index].pause = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::CapsLock)) {
-                        kbds.devices[index].capslock = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::PageUp)) {
-                        kbds.devices[index].pageup = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::PageDown)) {
-                        kbds.devices[index].pagedown = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::PrintScreen)) {
-                        kbds.devices[index].printscreen = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Insert)) {
-                        kbds.devices[index].insert = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::End)) {
-                        kbds.devices[index].end = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Home)) {
-                        kbds.devices[index].home = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Delete)) {
-                        kbds.devices[index].delete = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Add)) {
-                        kbds.devices[index].add = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Subtract)) {
-                        kbds.devices[index].subtract = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Multiply)) {
-                        kbds.devices[index].multiply = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Separator)) {
-                        kbds.devices[index].separator = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Decimal)) {
-                        kbds.devices[index].decimal = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Divide)) {
-                        kbds.devices[index].divide = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::BackSlash)) {
-                        kbds.devices[index].backslash = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::ForwardSlash)) {
-                        kbds.devices[index].forwardslash = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Plus)) {
-                        kbds.devices[index].plus = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Minus)) {
-                        kbds.devices[index].minus = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::FullStop)) {
-                        kbds.devices[index].fullstop = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Comma)) {
-                        kbds.devices[index].comma = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Tab)) {
-                        kbds.devices[index].tab = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Numlock)) {
-                        kbds.devices[index].numlock = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::LeftSquareBracket)) {
-                        kbds.devices[index].leftsquarebracket = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::RightSquareBracket)) {
-                        kbds.devices[index].rightsquarebracket = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::SemiColon)) {
-                        kbds.devices[index].semicolon = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Apostrophe)) {
-                        kbds.devices[index].apostrophe = val;
-                    }
-                    if let Some(&val) = self.raw_states.key_states.get(&Key(index, KeyId::Hash)) {
-                        kbds.devices[index].hash = val;
+                }
+                for (&Key(index, ref key_id), &val) in self.raw_states.key_states.iter() {
+                    if let Some(keys) = kbds.devices.get_mut(index) {
+                        keys.set_key(get_key_code(key_id), val);
                     }
                 }
             }
 
+            if let Some(text) = input.get_text_inp() {
+                text.set_events(self.raw_states.text_events.clone());
+            }
+
             if let Some(mice) = input.get_mouse_inp() {
                 for mouse in &mut mice.devices {
                     mouse.movement = (0, 0);
-                } 
+                    mouse.wheel = (0.0, 0.0);
+                }
+                mice.cursor = self.cursor_tracker.get_cursor();
                 for index in 0..self.raw_states.device_stats.number_of_mice {
                     if mice.devices.iter().nth(index).is_none() {
                         mice.devices.push(Default::default());
@@ -367,6 +296,9 @@ impl InputHandler for MultiInput {
                     if let Some(&val) = self.raw_states.mouse_move_states.get(&index) {
                         mice.devices[index].movement = val;
                     }
+                    if let Some(&val) = self.raw_states.mouse_wheel_states.get(&index) {
+                        mice.devices[index].wheel = (0.0, val);
+                    }
                 }
             }
 
@@ -440,6 +372,18 @@ impl InputHandler for MultiInput {
                     }
                 }
             }
+
+            if let Some(gamepads) = input.get_gamepad_inp() {
+                let reports = self.get_gamepad_reports(&gamepads.mappings);
+                gamepads.update(reports);
+                if let Some(xinput) = &self.xinput {
+                    for (id, strong, weak) in gamepads.take_rumble(time::precise_time_s()) {
+                        xinput.set_rumble(&id, strong, weak);
+                    }
+                }
+            }
+
+            update_button_states(input, time::precise_time_s());
         }
     }
 
@@ -447,10 +391,22 @@ impl InputHandler for MultiInput {
         self.raw_states.flush();
     }
 
+    fn set_window_spec(&mut self, window_spec: WindowSpec) {
+        self.cursor_tracker.set_window_size(window_spec.size);
+    }
+
+    fn get_cursor(&self) -> Option<CursorPosition> {
+        self.cursor_tracker.get_cursor()
+    }
+
     fn escape_key_pressed(&self) -> bool {
         self.escape_key_switch.pressed()
     }
 
+    fn f7_key_pressed(&self) -> bool {
+        self.f7_key_switch.pressed()
+    }
+
     fn f8_key_pressed(&self) -> bool {
         self.f8_key_switch.pressed()
     }
@@ -463,3 +419,99 @@ impl InputHandler for MultiInput {
         self.backtick_key_switch.pressed()
     }
 }
+
+fn get_key_code(key_id: &KeyId) -> KeyCode {
+    match key_id {
+        KeyId::Escape => KeyCode::Escape,
+        KeyId::Return => KeyCode::Return,
+        KeyId::Backspace => KeyCode::Backspace,
+        KeyId::Space => KeyCode::Space,
+        KeyId::LeftCtrl => KeyCode::LeftCtrl,
+        KeyId::RightCtrl => KeyCode::RightCtrl,
+        KeyId::LeftAlt => KeyCode::LeftAlt,
+        KeyId::RightAlt => KeyCode::RightAlt,
+        KeyId::Left => KeyCode::Left,
+        KeyId::Right => KeyCode::Right,
+        KeyId::Up => KeyCode::Up,
+        KeyId::Down => KeyCode::Down,
+        KeyId::A => KeyCode::A,
+        KeyId::B => KeyCode::B,
+        KeyId::C => KeyCode::C,
+        KeyId::D => KeyCode::D,
+        KeyId::E => KeyCode::E,
+        KeyId::F => KeyCode::F,
+        KeyId::G => KeyCode::G,
+        KeyId::H => KeyCode::H,
+        KeyId::I => KeyCode::I,
+        KeyId::J => KeyCode::J,
+        KeyId::K => KeyCode::K,
+        KeyId::L => KeyCode::L,
+        KeyId::M => KeyCode::M,
+        KeyId::N => KeyCode::N,
+        KeyId::O => KeyCode::O,
+        KeyId::P => KeyCode::P,
+        KeyId::Q => KeyCode::Q,
+        KeyId::R => KeyCode::R,
+        KeyId::S => KeyCode::S,
+        KeyId::T => KeyCode::T,
+        KeyId::U => KeyCode::U,
+        KeyId::V => KeyCode::V,
+        KeyId::W => KeyCode::W,
+        KeyId::X => KeyCode::X,
+        KeyId::Y => KeyCode::Y,
+        KeyId::Z => KeyCode::Z,
+        KeyId::Pause => KeyCode::Pause,
+        KeyId::CapsLock => KeyCode::CapsLock,
+        KeyId::PageUp => KeyCode::PageUp,
+        KeyId::PageDown => KeyCode::PageDown,
+        KeyId::PrintScreen => KeyCode::PrintScreen,
+        KeyId::Insert => KeyCode::Insert,
+        KeyId::End => KeyCode::End,
+        KeyId::Home => KeyCode::Home,
+        KeyId::Delete => KeyCode::Delete,
+        KeyId::Add => KeyCode::Add,
+        KeyId::Subtract => KeyCode::Subtract,
+        KeyId::Multiply => KeyCode::Multiply,
+        KeyId::Separator => KeyCode::Separator,
+        KeyId::Decimal => KeyCode::Decimal,
+        KeyId::Divide => KeyCode::Divide,
+        KeyId::BackSlash => KeyCode::Backslash,
+        KeyId::ForwardSlash => KeyCode::ForwardSlash,
+        KeyId::Plus => KeyCode::Plus,
+        KeyId::Minus => KeyCode::Minus,
+        KeyId::FullStop => KeyCode::FullStop,
+        KeyId::Comma => KeyCode::Comma,
+        KeyId::Tab => KeyCode::Tab,
+        KeyId::Numlock => KeyCode::NumLock,
+        KeyId::LeftSquareBracket => KeyCode::LeftSquareBracket,
+        KeyId::RightSquareBracket => KeyCode::RightSquareBracket,
+        KeyId::SemiColon => KeyCode::Semicolon,
+        KeyId::Apostrophe => KeyCode::Apostrophe,
+        KeyId::Hash => KeyCode::Hash,
+        KeyId::Zero => KeyCode::Zero,
+        KeyId::One => KeyCode::One,
+        KeyId::Two => KeyCode::Two,
+        KeyId::Three => KeyCode::Three,
+        KeyId::Four => KeyCode::Four,
+        KeyId::Five => KeyCode::Five,
+        KeyId::Six => KeyCode::Six,
+        KeyId::Seven => KeyCode::Seven,
+        KeyId::Eight => KeyCode::Eight,
+        KeyId::Nine => KeyCode::Nine,
+        // Raw input doesn't tell the shift keys apart
+        KeyId::Shift => KeyCode::LeftShift,
+        KeyId::BackTick => KeyCode::Backtick,
+        KeyId::F1 => KeyCode::F1,
+        KeyId::F2 => KeyCode::F2,
+        KeyId::F3 => KeyCode::F3,
+        KeyId::F4 => KeyCode::F4,
+        KeyId::F5 => KeyCode::F5,
+        KeyId::F6 => KeyCode::F6,
+        KeyId::F7 => KeyCode::F7,
+        KeyId::F8 => KeyCode::F8,
+        KeyId::F9 => KeyCode::F9,
+        KeyId::F10 => KeyCode::F10,
+        KeyId::F11 => KeyCode::F11,
+        KeyId::F12 => KeyCode::F12
+    }
+}
@@ -0,0 +1,381 @@
+use std::collections::HashSet;
+use crate::geometry::Point;
+use super::{KeyboardInput, MouseInput, JoystickInput, KeyCode};
+use super::action_map::MouseButtonCode;
+use super::gamepad::{GamepadInput, GamepadButton, GamepadEvent};
+use super::button_states::{ButtonStates, get_joystick_button};
+use super::action_map::InputBinding;
+
+pub const DEFAULT_MAX_PLAYERS: usize = 4;
+
+/// A physical device, numbered as in the devices of KeyboardInput, MouseInput and JoystickInput
+/// and the slots of GamepadInput
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum InputDevice {
+    Keyboard(usize),
+    Mouse(usize),
+    Joystick(usize),
+    Gamepad(usize)
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PlayerEvent {
+    Joined(usize),
+    Left(usize),
+    DeviceClaimed(usize, InputDevice),
+    /// The player's gamepad disconnected, the player keeps their slot
+    DeviceLost(usize, InputDevice)
+}
+
+/// One player's share of the input: devices other players have are left released, at the same
+/// device numbers, so the usual getters (get_left...) only see this player
+#[derive(Clone, Default)]
+pub struct PlayerInput {
+    pub kbd: KeyboardInput,
+    pub mouse: MouseInput,
+    pub joystick: JoystickInput,
+    /// Slots in GamepadInput
+    pub gamepads: Vec<usize>
+}
+
+impl PlayerInput {
+    pub fn get_gamepad_button(&self, gamepads: &GamepadInput, button: GamepadButton) -> bool {
+        self.gamepads.iter().any(|&index| gamepads.get_button(index, button))
+    }
+
+    /// The player's gamepad stick pushed furthest
+    pub fn get_left_stick(&self, gamepads: &GamepadInput) -> Point {
+        self.get_furthest(self.gamepads.iter().map(|&index| gamepads.get_left_stick(index)))
+    }
+
+    pub fn get_right_stick(&self, gamepads: &GamepadInput) -> Point {
+        self.get_furthest(self.gamepads.iter().map(|&index| gamepads.get_right_stick(index)))
+    }
+
+    fn get_furthest<I: Iterator<Item=Point>>(&self, sticks: I) -> Point {
+        sticks.fold(Point::zero(), |acc, stick| if stick.x.hypot(stick.y) > acc.x.hypot(acc.y) { stick } else { acc })
+    }
+}
+
+/// Player slots for local multiplayer. Devices are claimed by players, by pressing a join
+/// input while joining is open ("press start to join") or directly, and can be moved or dropped
+/// at any time. Each device belongs to at most one player.
+#[derive(Clone, Debug)]
+pub struct PlayerSlots {
+    pub max_players: usize,
+    /// Only unclaimed devices pressing a join input while this is set start a player
+    pub joining_open: bool,
+    pub join_keys: Vec<KeyCode>,
+    pub join_mouse_buttons: Vec<MouseButtonCode>,
+    /// Numbered from 1, as in InputBinding::JoystickButton
+    pub join_joystick_buttons: Vec<usize>,
+    pub join_gamepad_buttons: Vec<GamepadButton>,
+    players: Vec<Option<Vec<InputDevice>>>,
+    held_joins: HashSet<InputDevice>,
+    events: Vec<PlayerEvent>,
+    /// How many events there were at the end of the last update, dropped by the next one
+    seen_events: usize
+}
+
+impl Default for PlayerSlots {
+    fn default() -> Self {
+        PlayerSlots {
+            max_players: DEFAULT_MAX_PLAYERS,
+            joining_open: true,
+            join_keys: vec![KeyCode::Return],
+            join_mouse_buttons: Vec::new(),
+            join_joystick_buttons: Vec::new(),
+            join_gamepad_buttons: vec![GamepadButton::Start],
+            players: Vec::new(),
+            held_joins: HashSet::new(),
+            events: Vec::new(),
+            seen_events: 0
+        }
+    }
+}
+
+impl PlayerSlots {
+    /// Call once a frame after the input is passed on: drops disconnected gamepads and lets
+    /// devices join
+    pub fn update(&mut self, kbd: Option<&KeyboardInput>, mouse: Option<&MouseInput>, joystick: Option<&JoystickInput>, gamepads: Option<&GamepadInput>) {
+        // Events from calls made since the last update are kept for this one
+        self.events.drain(..self.seen_events);
+
+        if let Some(gamepads) = gamepads {
+            for event in gamepads.get_events() {
+                if let GamepadEvent::Disconnected(index) = *event {
+                    let device = InputDevice::Gamepad(index);
+                    if let Some(player) = self.get_owner(device) {
+                        self.remove_device(device);
+                        self.events.push(PlayerEvent::DeviceLost(player, device));
+                    }
+                }
+            }
+        }
+
+        let joining: HashSet<InputDevice> = self.get_join_inputs(kbd, mouse, joystick, gamepads).into_iter().collect();
+        if self.joining_open {
+            let mut pressed: Vec<InputDevice> = joining.difference(&self.held_joins).cloned().collect();
+            pressed.sort_by_key(|&device| get_device_order(device));
+            for device in pressed {
+                if self.get_owner(device).is_none() {
+                    self.add_player(vec![device]);
+                }
+            }
+        }
+        self.held_joins = joining;
+        self.seen_events = self.events.len();
+    }
+
+    /// Players joined and left, devices claimed and lost in the last update, or by calls made
+    /// since the update before it
+    pub fn get_events(&self) -> &[PlayerEvent] {
+        &self.events
+    }
+
+    /// Takes the first free slot, None with every slot taken. The devices are taken from other players.
+    pub fn add_player(&mut self, devices: Vec<InputDevice>) -> Option<usize> {
+        let player = match self.players.iter().position(|slot| slot.is_none()) {
+            Some(player) => player,
+            None if self.players.len() < self.max_players => {
+                self.players.push(None);
+                self.players.len() - 1
+            },
+            None => return None
+        };
+        self.players[player] = Some(Vec::new());
+        self.events.push(PlayerEvent::Joined(player));
+        for device in devices {
+            self.claim(player, device);
+        }
+        Some(player)
+    }
+
+    /// Frees the slot and the player's devices
+    pub fn remove_player(&mut self, player: usize) {
+        if let Some(slot) = self.players.get_mut(player) {
+            if slot.take().is_some() {
+                self.events.push(PlayerEvent::Left(player));
+            }
+        }
+    }
+
+    /// Gives the device to the player, taking it from whoever had it. False if there's no such player.
+    pub fn claim(&mut self, player: usize, device: InputDevice) -> bool {
+        if !self.has_player(player) {
+            return false;
+        }
+        if self.get_owner(device) == Some(player) {
+            return true;
+        }
+        self.remove_device(device);
+        if let Some(Some(devices)) = self.players.get_mut(player) {
+            devices.push(device);
+        }
+        self.events.push(PlayerEvent::DeviceClaimed(player, device));
+        true
+    }
+
+    /// The device goes back to being unclaimed (and can join again)
+    pub fn release(&mut self, device: InputDevice) {
+        self.remove_device(device);
+    }
+
+    pub fn has_player(&self, player: usize) -> bool {
+        self.players.get(player).is_some_and(|slot| slot.is_some())
+    }
+
+    pub fn get_players(&self) -> Vec<usize> {
+        (0..self.players.len()).filter(|&player| self.has_player(player)).collect()
+    }
+
+    pub fn get_devices(&self, player: usize) -> &[InputDevice] {
+        match self.players.get(player) {
+            Some(Some(devices)) => devices,
+            _ => &[]
+        }
+    }
+
+    pub fn get_owner(&self, device: InputDevice) -> Option<usize> {
+        self.players.iter().position(|slot| slot.as_ref().is_some_and(|devices| devices.contains(&device)))
+    }
+
+    /// The input from the player's devices only
+    pub fn get_player_input(&self, player: usize, kbd: Option<&KeyboardInput>, mouse: Option<&MouseInput>, joystick: Option<&JoystickInput>) -> PlayerInput {
+        let devices = self.get_devices(player);
+        let owns = |device| devices.contains(&device);
+        let mut input = PlayerInput::default();
+        if let Some(kbd) = kbd {
+            input.kbd.devices = kbd.devices.iter().enumerate()
+                .map(|(index, &keys)| if owns(InputDevice::Keyboard(index)) { keys } else { Default::default() })
+                .collect();
+        }
+        if let Some(mouse) = mouse {
+            input.mouse.devices = mouse.devices.iter().enumerate()
+                .map(|(index, &keys)| if owns(InputDevice::Mouse(index)) { keys } else { Default::default() })
+                .collect();
+            // There's one system cursor, it goes with any mouse
+            let has_mouse = devices.iter().any(|device| matches!(device, InputDevice::Mouse(_)));
+            input.mouse.cursor = if has_mouse { mouse.cursor } else { None };
+        }
+        if let Some(joystick) = joystick {
+            input.joystick.devices = joystick.devices.iter().enumerate()
+                .map(|(index, &keys)| if owns(InputDevice::Joystick(index)) { keys } else { Default::default() })
+                .collect();
+        }
+        input.gamepads = devices.iter()
+            .filter_map(|&device| match device {
+                InputDevice::Gamepad(index) => Some(index),
+                _ => None
+            })
+            .collect();
+        input
+    }
+
+    /// The binding's state on the player's devices of its kind (keys on their keyboards...), e.g.
+    /// pressed if it went down on any of them
+    pub fn get_binding_pressed(&self, player: usize, binding: InputBinding, states: &ButtonStates) -> bool {
+        self.get_devices(player).iter()
+            .filter_map(|&device| get_binding_device(device, binding))
+            .any(|index| states.get_device_state(index, binding).pressed)
+    }
+
+    pub fn get_binding_held(&self, player: usize, binding: InputBinding, states: &ButtonStates) -> bool {
+        self.get_devices(player).iter()
+            .filter_map(|&device| get_binding_device(device, binding))
+            .any(|index| states.get_device_state(index, binding).held)
+    }
+
+    fn remove_device(&mut self, device: InputDevice) {
+        for devices in self.players.iter_mut().flatten() {
+            devices.retain(|&claimed| claimed != device);
+        }
+    }
+
+    fn get_join_inputs(&self, kbd: Option<&KeyboardInput>, mouse: Option<&MouseInput>, joystick: Option<&JoystickInput>, gamepads: Option<&GamepadInput>) -> Vec<InputDevice> {
+        let mut joining = Vec::new();
+        if let Some(kbd) = kbd {
+            joining.extend(kbd.devices.iter().enumerate()
+                .filter(|(_, keys)| self.join_keys.iter().any(|&key| keys.get_key(key)))
+                .map(|(index, _)| InputDevice::Keyboard(index)));
+        }
+        if let Some(mouse) = mouse {
+            joining.extend(mouse.devices.iter().enumerate()
+                .filter(|(_, keys)| self.join_mouse_buttons.iter().any(|&button| match button {
+                    MouseButtonCode::Left => keys.left,
+                    MouseButtonCode::Right => keys.right,
+                    MouseButtonCode::Middle => keys.middle,
+                    MouseButtonCode::Button4 => keys.button4,
+                    MouseButtonCode::Button5 => keys.button5
+                }))
+                .map(|(index, _)| InputDevice::Mouse(index)));
+        }
+        if let Some(joystick) = joystick {
+            joining.extend(joystick.devices.iter().enumerate()
+                .filter(|(_, keys)| self.join_joystick_buttons.iter().any(|&button| get_joystick_button(keys, button)))
+                .map(|(index, _)| InputDevice::Joystick(index)));
+        }
+        if let Some(gamepads) = gamepads {
+            joining.extend(gamepads.get_connected().into_iter()
+                .filter(|&index| self.join_gamepad_buttons.iter().any(|&button| gamepads.get_button(index, button)))
+                .map(InputDevice::Gamepad));
+        }
+        joining
+    }
+}
+
+/// The device number a binding reads on the device, None if the binding is for another kind of device
+fn get_binding_device(device: InputDevice, binding: InputBinding) -> Option<usize> {
+    match (device, binding) {
+        (InputDevice::Keyboard(index), InputBinding::Key(_)) => Some(index),
+        (InputDevice::Mouse(index), InputBinding::MouseButton(_)) => Some(index),
+        (InputDevice::Joystick(index), InputBinding::JoystickButton(_))
+        | (InputDevice::Joystick(index), InputBinding::JoystickAxis(_, _))
+        | (InputDevice::Joystick(index), InputBinding::Hat(_)) => Some(index),
+        (InputDevice::Gamepad(index), InputBinding::Gamepad(_)) => Some(index),
+        _ => None
+    }
+}
+
+/// Joins seen in the same frame are taken keyboards first, so the slot order doesn't depend on hashing
+fn get_device_order(device: InputDevice) -> (usize, usize) {
+    match device {
+        InputDevice::Keyboard(index) => (0, index),
+        InputDevice::Mouse(index) => (1, index),
+        InputDevice::Joystick(index) => (2, index),
+        InputDevice::Gamepad(index) => (3, index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::gamepad::{GamepadReport, GamepadState};
+
+    fn gamepad_report(id: &str, start: bool) -> GamepadReport {
+        let mut state = GamepadState::default();
+        state.set_button(GamepadButton::Start, start);
+        GamepadReport { id: id.to_string(), name: id.to_string(), state, supports_rumble: false }
+    }
+
+    #[test]
+    fn pressing_start_joins_once() {
+        let mut slots = PlayerSlots::default();
+        let mut kbd = KeyboardInput::default();
+        let mut gamepads = GamepadInput::default();
+
+        kbd.devices[1].set_key(KeyCode::Return, true);
+        gamepads.update(vec![gamepad_report("pad", true)]);
+        slots.update(Some(&kbd), None, None, Some(&gamepads));
+        assert_eq!(slots.get_players(), vec![0, 1]);
+        assert_eq!(slots.get_devices(0), &[InputDevice::Keyboard(1)]);
+        assert_eq!(slots.get_devices(1), &[InputDevice::Gamepad(0)]);
+
+        // Holding the join input after leaving doesn't join again
+        slots.remove_player(0);
+        slots.update(Some(&kbd), None, None, Some(&gamepads));
+        assert_eq!(slots.get_players(), vec![1]);
+
+        kbd.devices[1].set_key(KeyCode::Return, false);
+        slots.update(Some(&kbd), None, None, Some(&gamepads));
+        kbd.devices[1].set_key(KeyCode::Return, true);
+        slots.update(Some(&kbd), None, None, Some(&gamepads));
+        assert_eq!(slots.get_players(), vec![0, 1]);
+        assert_eq!(slots.get_events(), &[PlayerEvent::Joined(0), PlayerEvent::DeviceClaimed(0, InputDevice::Keyboard(1))]);
+    }
+
+    #[test]
+    fn players_only_see_their_own_devices() {
+        let mut slots = PlayerSlots::default();
+        let mut kbd = KeyboardInput::default();
+        let first = slots.add_player(vec![InputDevice::Keyboard(0)]).unwrap();
+        let second = slots.add_player(vec![InputDevice::Keyboard(1), InputDevice::Mouse(0)]).unwrap();
+
+        kbd.devices[0].set_key(KeyCode::Left, true);
+        assert!(slots.get_player_input(first, Some(&kbd), None, None).kbd.get_left());
+        assert!(!slots.get_player_input(second, Some(&kbd), None, None).kbd.get_left());
+
+        // Claiming a device moves it between players
+        assert!(slots.claim(second, InputDevice::Keyboard(0)));
+        assert!(slots.get_player_input(second, Some(&kbd), None, None).kbd.get_left());
+        assert_eq!(slots.get_devices(first), &[] as &[InputDevice]);
+        assert_eq!(slots.get_owner(InputDevice::Keyboard(0)), Some(second));
+    }
+
+    #[test]
+    fn disconnected_gamepads_are_dropped() {
+        let mut slots = PlayerSlots::default();
+        let mut gamepads = GamepadInput::default();
+        gamepads.update(vec![gamepad_report("pad", false)]);
+        let player = slots.add_player(vec![InputDevice::Gamepad(0)]).unwrap();
+
+        gamepads.update(vec![]);
+        slots.update(None, None, None, Some(&gamepads));
+        assert_eq!(slots.get_events(), &[PlayerEvent::Joined(player), PlayerEvent::DeviceClaimed(player, InputDevice::Gamepad(0)),
+            PlayerEvent::DeviceLost(player, InputDevice::Gamepad(0))]);
+        slots.update(None, None, None, Some(&gamepads));
+        assert!(slots.get_events().is_empty());
+        assert!(slots.has_player(player));
+        assert!(slots.get_devices(player).is_empty());
+    }
+}
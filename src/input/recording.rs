@@ -0,0 +1,718 @@
+use std::io::{Read, Write, BufReader, BufWriter};
+use std::path::Path;
+use std::{error, fmt, fs, io};
+use serde_json::Value;
+use crate::games::GameInput;
+use super::{InputHandler, KeyboardInput, MouseInput, JoystickInput, KeyCode, HatSwitch, CursorPosition, update_button_states};
+use super::{TextInputEvent, GamepadReport, GamepadState, GamepadButton, GamepadAxis};
+use super::mouse::MouseInputKeys;
+use super::joystick::JoystickInputKeys;
+
+/// Where the write_recording console command writes when no path is given
+pub const DEFAULT_RECORDING_PATH: &str = "input_recording.bin";
+
+const RECORDING_MAGIC: &[u8; 4] = b"GGIR";
+/// Version 2 added text and gamepads, and names the keys in the header so reordering KeyCode
+/// doesn't change what old recordings hold. Version 1 keys are read as places in KeyCode::ALL.
+const RECORDING_VERSION: u8 = 2;
+
+const FRAME_HAS_INPUT: u8 = 1;
+const FRAME_KEYBOARD_CHANGED: u8 = 2;
+const FRAME_MOUSE_CHANGED: u8 = 4;
+const FRAME_JOYSTICK_CHANGED: u8 = 8;
+const FRAME_HAS_TEXT: u8 = 16;
+const FRAME_GAMEPADS_CHANGED: u8 = 32;
+
+const TEXT_CHAR: u8 = 0;
+const TEXT_COMPOSITION: u8 = 1;
+const TEXT_COMMIT: u8 = 2;
+
+/// In the order they're written
+const HAT_SWITCHES: [HatSwitch; 9] = [
+    HatSwitch::Up, HatSwitch::UpRight, HatSwitch::Right, HatSwitch::DownRight, HatSwitch::Down,
+    HatSwitch::DownLeft, HatSwitch::Left, HatSwitch::UpLeft, HatSwitch::Center
+];
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(io::Error),
+    InvalidRecording(String)
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::Io(err) => write!(f, "Could not access recording file: {}", err),
+            RecordingError::InvalidRecording(mes) => write!(f, "Could not read recording: {}", mes)
+        }
+    }
+}
+
+impl error::Error for RecordingError {}
+
+impl From<io::Error> for RecordingError {
+    fn from(err: io::Error) -> Self {
+        RecordingError::Io(err)
+    }
+}
+
+/// The input a game held during one frame
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameInput {
+    /// Seconds since the recording started when the input was passed on, for button states
+    pub time: f64,
+    pub kbd: KeyboardInput,
+    pub mouse: MouseInput,
+    pub joystick: JoystickInput,
+    /// This frame's typed text
+    pub text: Vec<TextInputEvent>,
+    /// The connected gamepads in slot order
+    pub gamepads: Vec<GamepadReport>
+}
+
+impl FrameInput {
+    /// Inputs the game doesn't take are left at their defaults
+    pub fn capture(time: f64, input: &mut dyn GameInput) -> Self {
+        FrameInput {
+            time,
+            kbd: input.get_kbd_inp().cloned().unwrap_or_default(),
+            mouse: input.get_mouse_inp().cloned().unwrap_or_default(),
+            joystick: input.get_joystick_inp().cloned().unwrap_or_default(),
+            text: input.get_text_inp().map(|text| text.events.clone()).unwrap_or_default(),
+            gamepads: input.get_gamepad_inp()
+                .map(|gamepads| gamepads.gamepads.iter()
+                    .filter(|gamepad| gamepad.connected)
+                    .map(|gamepad| GamepadReport {
+                        id: gamepad.id.clone(),
+                        name: gamepad.name.clone(),
+                        state: gamepad.state,
+                        supports_rumble: gamepad.supports_rumble
+                    })
+                    .collect())
+                .unwrap_or_default()
+        }
+    }
+
+    /// Hands the input to the game as an input handler would
+    pub fn pass_on(&self, input: &mut dyn GameInput) {
+        if let Some(kbd) = input.get_kbd_inp() {
+            *kbd = self.kbd.clone();
+        }
+        if let Some(mouse) = input.get_mouse_inp() {
+            *mouse = self.mouse.clone();
+        }
+        if let Some(joystick) = input.get_joystick_inp() {
+            *joystick = self.joystick.clone();
+        }
+        if let Some(text) = input.get_text_inp() {
+            text.set_events(self.text.clone());
+        }
+        if let Some(gamepads) = input.get_gamepad_inp() {
+            gamepads.update(self.gamepads.clone());
+        }
+        update_button_states(input, self.time);
+    }
+}
+
+/// One logic update: its time step and, unless the game wasn't given input that frame (e.g. with
+/// the console open), the input it held
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedFrame {
+    pub t_step: f64,
+    pub input: Option<FrameInput>
+}
+
+/// Per frame input for bug reports and replays. Saved compactly: inputs are only written on
+/// frames they change.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputRecording {
+    pub frames: Vec<RecordedFrame>
+}
+
+impl InputRecording {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn push(&mut self, t_step: f64, input: Option<FrameInput>) {
+        self.frames.push(RecordedFrame { t_step, input });
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RecordingError> {
+        Self::read(&mut BufReader::new(fs::File::open(path)?))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), RecordingError> {
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), RecordingError> {
+        writer.write_all(RECORDING_MAGIC)?;
+        write_u8(writer, RECORDING_VERSION)?;
+        write_key_names(writer)?;
+        write_u32(writer, self.frames.len() as u32)?;
+        let mut last_input = FrameInput::default();
+        for frame in &self.frames {
+            write_f64(writer, frame.t_step)?;
+            let input = match &frame.input {
+                Some(input) => input,
+                None => {
+                    write_u8(writer, 0)?;
+                    continue;
+                }
+            };
+            let mut flags = FRAME_HAS_INPUT;
+            if input.kbd != last_input.kbd { flags |= FRAME_KEYBOARD_CHANGED; }
+            if input.mouse != last_input.mouse { flags |= FRAME_MOUSE_CHANGED; }
+            if input.joystick != last_input.joystick { flags |= FRAME_JOYSTICK_CHANGED; }
+            if !input.text.is_empty() { flags |= FRAME_HAS_TEXT; }
+            if input.gamepads != last_input.gamepads { flags |= FRAME_GAMEPADS_CHANGED; }
+            write_u8(writer, flags)?;
+            write_f64(writer, input.time)?;
+            if flags & FRAME_KEYBOARD_CHANGED != 0 {
+                write_keyboard(writer, &input.kbd)?;
+            }
+            if flags & FRAME_MOUSE_CHANGED != 0 {
+                write_mouse(writer, &input.mouse)?;
+            }
+            if flags & FRAME_JOYSTICK_CHANGED != 0 {
+                write_joystick(writer, &input.joystick)?;
+            }
+            if flags & FRAME_HAS_TEXT != 0 {
+                write_text(writer, &input.text)?;
+            }
+            if flags & FRAME_GAMEPADS_CHANGED != 0 {
+                write_gamepads(writer, &input.gamepads)?;
+            }
+            last_input = input.clone();
+        }
+        Ok(())
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, RecordingError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != RECORDING_MAGIC {
+            return Err(RecordingError::InvalidRecording("Not an input recording".to_string()));
+        }
+        let keys = match read_u8(reader)? {
+            1 => KeyCode::ALL.iter().map(|&key| Ok(key)).collect(),
+            RECORDING_VERSION => read_key_names(reader)?,
+            version => return Err(RecordingError::InvalidRecording(format!("Unknown version {}", version)))
+        };
+        let frame_count = read_u32(reader)? as usize;
+        let mut recording = InputRecording::new();
+        let mut last_input = FrameInput::default();
+        for _ in 0..frame_count {
+            let t_step = read_f64(reader)?;
+            let flags = read_u8(reader)?;
+            if flags & FRAME_HAS_INPUT == 0 {
+                recording.push(t_step, None);
+                continue;
+            }
+            last_input.time = read_f64(reader)?;
+            if flags & FRAME_KEYBOARD_CHANGED != 0 {
+                last_input.kbd = read_keyboard(reader, &keys)?;
+            }
+            if flags & FRAME_MOUSE_CHANGED != 0 {
+                last_input.mouse = read_mouse(reader)?;
+            }
+            if flags & FRAME_JOYSTICK_CHANGED != 0 {
+                last_input.joystick = read_joystick(reader)?;
+            }
+            last_input.text = if flags & FRAME_HAS_TEXT != 0 { read_text(reader)? } else { Vec::new() };
+            if flags & FRAME_GAMEPADS_CHANGED != 0 {
+                last_input.gamepads = read_gamepads(reader)?;
+            }
+            recording.push(t_step, Some(last_input.clone()));
+        }
+        Ok(recording)
+    }
+}
+
+/// Plays a recording back frame by frame, with its time steps, in place of the platform's input
+/// handler. Passes nothing on once the recording runs out.
+pub struct ReplayInputHandler {
+    recording: InputRecording,
+    /// Frames started, the current frame is the one before
+    position: usize
+}
+
+impl ReplayInputHandler {
+    pub fn new(recording: InputRecording) -> Self {
+        ReplayInputHandler {
+            recording,
+            position: 0
+        }
+    }
+
+    /// The frame being played, counted from 0 (None before the first)
+    pub fn get_frame(&self) -> Option<usize> {
+        self.position.checked_sub(1)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.recording.len()
+    }
+
+    fn get_current_frame(&self) -> Option<&RecordedFrame> {
+        self.get_frame().and_then(|frame| self.recording.frames.get(frame))
+    }
+}
+
+impl InputHandler for ReplayInputHandler {
+    fn reset(&mut self) {
+        self.position = 0;
+    }
+
+    fn receive_input(&mut self) {
+        if self.position <= self.recording.len() {
+            self.position += 1;
+        }
+    }
+
+    fn pass_on_input(&self, game_input: Option<&mut dyn GameInput>) {
+        if let (Some(input), Some(frame_input)) = (game_input, self.get_current_frame().and_then(|frame| frame.input.as_ref())) {
+            frame_input.pass_on(input);
+        }
+    }
+
+    fn get_cursor(&self) -> Option<CursorPosition> {
+        self.get_current_frame()
+            .and_then(|frame| frame.input.as_ref())
+            .and_then(|input| input.mouse.cursor)
+    }
+
+    fn get_time_step(&self) -> Option<f64> {
+        self.get_current_frame().map(|frame| frame.t_step)
+    }
+}
+
+fn write_u8<W: Write>(writer: &mut W, value: u8) -> io::Result<()> {
+    writer.write_all(&[value])
+}
+
+fn write_u16<W: Write>(writer: &mut W, value: u16) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_u32<W: Write>(writer: &mut W, value: u32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_f64<W: Write>(writer: &mut W, value: f64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut bytes = [0; 1];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> io::Result<u16> {
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_i32<R: Read>(reader: &mut R) -> io::Result<i32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(i32::from_le_bytes(bytes))
+}
+
+fn read_f64<R: Read>(reader: &mut R) -> io::Result<f64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(f64::from_le_bytes(bytes))
+}
+
+fn write_device_count<W: Write>(writer: &mut W, count: usize) -> Result<(), RecordingError> {
+    if count > u16::MAX as usize {
+        return Err(RecordingError::InvalidRecording(format!("Too many devices to record: {}", count)));
+    }
+    write_u16(writer, count as u16)?;
+    Ok(())
+}
+
+fn write_string<W: Write>(writer: &mut W, value: &str) -> Result<(), RecordingError> {
+    if value.len() > u16::MAX as usize {
+        return Err(RecordingError::InvalidRecording(format!("Text too long to record: {} bytes", value.len())));
+    }
+    write_u16(writer, value.len() as u16)?;
+    writer.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+fn read_string<R: Read>(reader: &mut R) -> Result<String, RecordingError> {
+    let mut bytes = vec![0; read_u16(reader)? as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|err| RecordingError::InvalidRecording(err.to_string()))
+}
+
+/// The names of KeyCode::ALL, as saved in action maps
+fn write_key_names<W: Write>(writer: &mut W) -> Result<(), RecordingError> {
+    write_u8(writer, KeyCode::ALL.len() as u8)?;
+    for key in KeyCode::ALL.iter() {
+        match serde_json::to_value(key) {
+            Ok(Value::String(name)) => write_string(writer, &name)?,
+            _ => return Err(RecordingError::InvalidRecording(format!("Key {:?} has no name", key)))
+        }
+    }
+    Ok(())
+}
+
+/// The keys the recording's key indices stand for, names this version doesn't know are kept as errors
+/// for when a keyboard holds them
+fn read_key_names<R: Read>(reader: &mut R) -> Result<Vec<Result<KeyCode, String>>, RecordingError> {
+    let count = read_u8(reader)? as usize;
+    let mut keys = Vec::with_capacity(count);
+    for _ in 0..count {
+        let name = read_string(reader)?;
+        keys.push(serde_json::from_value(Value::String(name.clone())).map_err(|_| name));
+    }
+    Ok(keys)
+}
+
+/// Each keyboard as its held keys, by their place in KeyCode::ALL
+fn write_keyboard<W: Write>(writer: &mut W, kbd: &KeyboardInput) -> Result<(), RecordingError> {
+    write_device_count(writer, kbd.devices.len())?;
+    for keys in &kbd.devices {
+        let held: Vec<u8> = KeyCode::ALL.iter().enumerate()
+            .filter(|&(_, &key)| keys.get_key(key))
+            .map(|(index, _)| index as u8)
+            .collect();
+        write_u8(writer, held.len() as u8)?;
+        writer.write_all(&held)?;
+    }
+    Ok(())
+}
+
+fn read_keyboard<R: Read>(reader: &mut R, keys_by_index: &[Result<KeyCode, String>]) -> Result<KeyboardInput, RecordingError> {
+    let count = read_u16(reader)? as usize;
+    let mut kbd = KeyboardInput { devices: vec![Default::default(); count] };
+    for keys in kbd.devices.iter_mut() {
+        let held_count = read_u8(reader)? as usize;
+        for _ in 0..held_count {
+            let index = read_u8(reader)? as usize;
+            let key = match keys_by_index.get(index) {
+                Some(Ok(key)) => *key,
+                Some(Err(name)) => return Err(RecordingError::InvalidRecording(format!("Unknown key {}", name))),
+                None => return Err(RecordingError::InvalidRecording(format!("Unknown key {}", index)))
+            };
+            keys.set_key(key, true);
+        }
+    }
+    Ok(kbd)
+}
+
+fn write_mouse<W: Write>(writer: &mut W, mouse: &MouseInput) -> Result<(), RecordingError> {
+    write_device_count(writer, mouse.devices.len())?;
+    for keys in &mouse.devices {
+        writer.write_all(&keys.movement.0.to_le_bytes())?;
+        writer.write_all(&keys.movement.1.to_le_bytes())?;
+        write_f64(writer, keys.wheel.0)?;
+        write_f64(writer, keys.wheel.1)?;
+        let buttons = [keys.left, keys.right, keys.middle, keys.button4, keys.button5];
+        write_u8(writer, to_bits(&buttons) as u8)?;
+    }
+    match mouse.cursor {
+        Some(cursor) => {
+            write_u8(writer, 1)?;
+            write_f64(writer, cursor.window_position.0)?;
+            write_f64(writer, cursor.window_position.1)?;
+            write_f64(writer, cursor.window_size.0)?;
+            write_f64(writer, cursor.window_size.1)?;
+        },
+        None => write_u8(writer, 0)?
+    }
+    Ok(())
+}
+
+fn read_mouse<R: Read>(reader: &mut R) -> Result<MouseInput, RecordingError> {
+    let count = read_u16(reader)? as usize;
+    let mut devices = Vec::with_capacity(count);
+    for _ in 0..count {
+        let movement = (read_i32(reader)?, read_i32(reader)?);
+        let wheel = (read_f64(reader)?, read_f64(reader)?);
+        let buttons = read_u8(reader)? as u16;
+        devices.push(MouseInputKeys {
+            movement,
+            wheel,
+            left: get_bit(buttons, 0),
+            right: get_bit(buttons, 1),
+            middle: get_bit(buttons, 2),
+            button4: get_bit(buttons, 3),
+            button5: get_bit(buttons, 4)
+        });
+    }
+    let cursor = if read_u8(reader)? != 0 {
+        Some(CursorPosition {
+            window_position: (read_f64(reader)?, read_f64(reader)?),
+            window_size: (read_f64(reader)?, read_f64(reader)?)
+        })
+    } else {
+        None
+    };
+    Ok(MouseInput { devices, cursor })
+}
+
+fn write_joystick<W: Write>(writer: &mut W, joystick: &JoystickInput) -> Result<(), RecordingError> {
+    write_device_count(writer, joystick.devices.len())?;
+    for keys in &joystick.devices {
+        let buttons = [
+            keys.button_1, keys.button_2, keys.button_3, keys.button_4, keys.button_5,
+            keys.button_6, keys.button_7, keys.button_8, keys.button_9, keys.button_10
+        ];
+        write_u16(writer, to_bits(&buttons))?;
+        for &axis in [keys.x_axis, keys.y_axis, keys.z_axis, keys.rx_axis, keys.ry_axis, keys.rz_axis].iter() {
+            write_f64(writer, axis)?;
+        }
+        let hat = HAT_SWITCHES.iter().position(|&hat| hat == keys.hat_switch).unwrap_or(HAT_SWITCHES.len() - 1);
+        write_u8(writer, hat as u8)?;
+    }
+    Ok(())
+}
+
+fn read_joystick<R: Read>(reader: &mut R) -> Result<JoystickInput, RecordingError> {
+    let count = read_u16(reader)? as usize;
+    let mut devices = Vec::with_capacity(count);
+    for _ in 0..count {
+        let buttons = read_u16(reader)?;
+        let mut axes = [0.0; 6];
+        for axis in axes.iter_mut() {
+            *axis = read_f64(reader)?;
+        }
+        let hat = read_u8(reader)? as usize;
+        let hat_switch = *HAT_SWITCHES.get(hat)
+            .ok_or_else(|| RecordingError::InvalidRecording(format!("Unknown hat switch position {}", hat)))?;
+        devices.push(JoystickInputKeys {
+            button_1: get_bit(buttons, 0),
+            button_2: get_bit(buttons, 1),
+            button_3: get_bit(buttons, 2),
+            button_4: get_bit(buttons, 3),
+            button_5: get_bit(buttons, 4),
+            button_6: get_bit(buttons, 5),
+            button_7: get_bit(buttons, 6),
+            button_8: get_bit(buttons, 7),
+            button_9: get_bit(buttons, 8),
+            button_10: get_bit(buttons, 9),
+            x_axis: axes[0],
+            y_axis: axes[1],
+            z_axis: axes[2],
+            rx_axis: axes[3],
+            ry_axis: axes[4],
+            rz_axis: axes[5],
+            hat_switch
+        });
+    }
+    Ok(JoystickInput { devices })
+}
+
+fn write_text<W: Write>(writer: &mut W, events: &[TextInputEvent]) -> Result<(), RecordingError> {
+    write_device_count(writer, events.len())?;
+    for event in events {
+        match event {
+            TextInputEvent::Char(c) => {
+                write_u8(writer, TEXT_CHAR)?;
+                write_string(writer, &c.to_string())?;
+            },
+            TextInputEvent::Composition(text) => {
+                write_u8(writer, TEXT_COMPOSITION)?;
+                write_string(writer, text)?;
+            },
+            TextInputEvent::Commit(text) => {
+                write_u8(writer, TEXT_COMMIT)?;
+                write_string(writer, text)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_text<R: Read>(reader: &mut R) -> Result<Vec<TextInputEvent>, RecordingError> {
+    let count = read_u16(reader)? as usize;
+    let mut events = Vec::with_capacity(count);
+    for _ in 0..count {
+        let kind = read_u8(reader)?;
+        let text = read_string(reader)?;
+        events.push(match kind {
+            TEXT_CHAR => TextInputEvent::Char(text.chars().next()
+                .ok_or_else(|| RecordingError::InvalidRecording("Empty typed character".to_string()))?),
+            TEXT_COMPOSITION => TextInputEvent::Composition(text),
+            TEXT_COMMIT => TextInputEvent::Commit(text),
+            _ => return Err(RecordingError::InvalidRecording(format!("Unknown text event {}", kind)))
+        });
+    }
+    Ok(events)
+}
+
+fn write_gamepads<W: Write>(writer: &mut W, gamepads: &[GamepadReport]) -> Result<(), RecordingError> {
+    write_device_count(writer, gamepads.len())?;
+    for gamepad in gamepads {
+        write_string(writer, &gamepad.id)?;
+        write_string(writer, &gamepad.name)?;
+        write_u8(writer, gamepad.supports_rumble as u8)?;
+        let buttons: Vec<bool> = GamepadButton::ALL.iter().map(|&button| gamepad.state.get_button(button)).collect();
+        write_u16(writer, to_bits(&buttons))?;
+        for &axis in GamepadAxis::ALL.iter() {
+            write_f64(writer, gamepad.state.get_axis(axis))?;
+        }
+    }
+    Ok(())
+}
+
+fn read_gamepads<R: Read>(reader: &mut R) -> Result<Vec<GamepadReport>, RecordingError> {
+    let count = read_u16(reader)? as usize;
+    let mut gamepads = Vec::with_capacity(count);
+    for _ in 0..count {
+        let id = read_string(reader)?;
+        let name = read_string(reader)?;
+        let supports_rumble = read_u8(reader)? != 0;
+        let buttons = read_u16(reader)?;
+        let mut state = GamepadState::default();
+        for (index, &button) in GamepadButton::ALL.iter().enumerate() {
+            state.set_button(button, get_bit(buttons, index));
+        }
+        for &axis in GamepadAxis::ALL.iter() {
+            state.set_axis(axis, read_f64(reader)?);
+        }
+        gamepads.push(GamepadReport { id, name, state, supports_rumble });
+    }
+    Ok(gamepads)
+}
+
+fn to_bits(flags: &[bool]) -> u16 {
+    flags.iter().enumerate().fold(0, |bits, (index, &flag)| if flag { bits | 1 << index } else { bits })
+}
+
+fn get_bit(bits: u16, index: usize) -> bool {
+    bits & (1 << index) != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::Handler;
+    use crate::handler_basic::HandlerBasic;
+    use crate::games::Game;
+    use crate::rendering::{RendererStub, StandardPrimitive};
+    use crate::window::WindowHandlerStub;
+
+    fn frame_input(time: f64, right: bool, movement: (i32, i32)) -> FrameInput {
+        let mut input = FrameInput { time, ..Default::default() };
+        input.kbd.devices[0].set_key(KeyCode::Right, right);
+        input.mouse.devices[1].movement = movement;
+        input.joystick.devices[0].hat_switch = HatSwitch::DownLeft;
+        input
+    }
+
+    fn gamepad(south: bool) -> GamepadReport {
+        let mut state = GamepadState::default();
+        state.set_button(GamepadButton::South, south);
+        state.set_axis(GamepadAxis::LeftX, -0.5);
+        GamepadReport { id: "xinput:0".to_string(), name: "Pad".to_string(), state, supports_rumble: true }
+    }
+
+    #[test]
+    fn recordings_survive_saving() {
+        let mut recording = InputRecording::new();
+        recording.push(0.016, Some(frame_input(0.0, true, (3, -2))));
+        recording.push(0.017, None);
+        recording.push(0.015, Some(frame_input(0.03, true, (3, -2))));
+        recording.push(0.016, Some(frame_input(0.05, false, (0, 0))));
+        let mut typing = FrameInput {
+            text: vec![TextInputEvent::Char('é'), TextInputEvent::Composition("か".to_string())],
+            gamepads: vec![gamepad(true)],
+            ..frame_input(0.07, false, (0, 0))
+        };
+        recording.push(0.016, Some(typing.clone()));
+        typing.text.clear();
+        typing.gamepads[0] = gamepad(false);
+        recording.push(0.016, Some(typing));
+
+        let mut bytes = Vec::new();
+        recording.write(&mut bytes).unwrap();
+        assert_eq!(InputRecording::read(&mut bytes.as_slice()).unwrap(), recording);
+
+        // Version 1: no key names, keys by their place in KeyCode::ALL
+        let mut version_1 = RECORDING_MAGIC.to_vec();
+        version_1.push(1);
+        version_1.extend_from_slice(&1u32.to_le_bytes());
+        version_1.extend_from_slice(&0.5f64.to_le_bytes());
+        version_1.push(FRAME_HAS_INPUT | FRAME_KEYBOARD_CHANGED);
+        version_1.extend_from_slice(&0.0f64.to_le_bytes());
+        version_1.extend_from_slice(&1u16.to_le_bytes());
+        version_1.extend_from_slice(&[1, KeyCode::Right as u8]);
+        let old = InputRecording::read(&mut version_1.as_slice()).unwrap();
+        assert!(old.frames[0].input.as_ref().unwrap().kbd.get_right());
+
+        assert!(InputRecording::read(&mut &b"nope"[..]).is_err());
+    }
+
+    /// Moves right at one unit a second while right is held
+    struct WalkingGame {
+        kbd: KeyboardInput,
+        position: Rc<RefCell<f64>>
+    }
+
+    impl GameInput for WalkingGame {
+        fn get_kbd_inp(&mut self) -> Option<&mut KeyboardInput> { Some(&mut self.kbd) }
+    }
+
+    impl Game for WalkingGame {
+        type Primitive = StandardPrimitive;
+        fn get_input(&mut self) -> Option<&mut dyn GameInput> { Some(self) }
+        fn update_logic(&mut self, t_step: f64) {
+            if self.kbd.get_right() {
+                *self.position.borrow_mut() += t_step;
+            }
+        }
+    }
+
+    #[test]
+    fn replays_run_headless_through_the_handler() {
+        let mut recording = InputRecording::new();
+        recording.push(0.5, Some(frame_input(0.0, true, (0, 0))));
+        recording.push(0.25, Some(frame_input(0.5, true, (0, 0))));
+        recording.push(1.0, Some(frame_input(0.75, false, (0, 0))));
+
+        let position = Rc::new(RefCell::new(0.0));
+        let game = WalkingGame { kbd: KeyboardInput::default(), position: position.clone() };
+        let mut handler = HandlerBasic::new(
+            Box::new(RendererStub::new()),
+            Box::new(ReplayInputHandler::new(recording)),
+            Box::new(WindowHandlerStub),
+            Box::new(game));
+        handler.init();
+
+        let mut positions = Vec::new();
+        for _ in 0..3 {
+            handler.update_input();
+            handler.update_logic();
+            handler.update_rendering();
+            positions.push(*position.borrow());
+        }
+        assert_eq!(positions, vec![0.5, 0.75, 0.75]);
+    }
+}
@@ -0,0 +1,113 @@
+use super::keyboard::{KeyCode, get_key_char};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TextInputEvent {
+    /// A typed character, following the keyboard layout and key repeat
+    Char(char),
+    /// Text an input method is composing, replacing the previous composition (empty when cancelled)
+    Composition(String),
+    /// The finished composition, to insert as typed text
+    Commit(String)
+}
+
+/// Text typed since the last frame, kept apart from the held keys
+#[derive(Clone, Debug, Default)]
+pub struct TextInput {
+    /// This frame's events, oldest first
+    pub events: Vec<TextInputEvent>,
+    composition: String
+}
+
+impl TextInput {
+    /// Replaces the events with the next frame's, keeping track of the composition
+    pub fn set_events(&mut self, events: Vec<TextInputEvent>) {
+        for event in events.iter() {
+            match event {
+                TextInputEvent::Composition(text) => self.composition = text.clone(),
+                TextInputEvent::Commit(_) => self.composition.clear(),
+                TextInputEvent::Char(_) => ()
+            }
+        }
+        self.events = events;
+    }
+
+    /// Text being composed but not yet committed, to show at the caret
+    pub fn get_composition(&self) -> &str {
+        &self.composition
+    }
+
+    /// Characters typed and compositions committed this frame
+    pub fn get_text(&self) -> String {
+        let mut text = String::new();
+        for event in self.events.iter() {
+            match event {
+                TextInputEvent::Char(c) => text.push(*c),
+                TextInputEvent::Commit(committed) => text.push_str(committed),
+                TextInputEvent::Composition(_) => ()
+            }
+        }
+        text
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.composition.clear();
+    }
+}
+
+/// Turns key presses into typed characters for input handlers that only see keys (US layout)
+#[derive(Clone, Debug, Default)]
+pub struct KeyTextTranslator {
+    left_shift: bool,
+    right_shift: bool,
+    caps_lock: bool
+}
+
+impl KeyTextTranslator {
+    /// The character a key event types, repeated presses while held type again
+    pub fn translate(&mut self, key: KeyCode, pressed: bool) -> Option<char> {
+        match key {
+            KeyCode::LeftShift => self.left_shift = pressed,
+            KeyCode::RightShift => self.right_shift = pressed,
+            KeyCode::CapsLock if pressed => self.caps_lock = !self.caps_lock,
+            _ if pressed => {
+                let shift = self.left_shift || self.right_shift;
+                return get_key_char(key, shift, shift ^ self.caps_lock);
+            },
+            _ => ()
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compositions_end_when_committed() {
+        let mut text_input = TextInput::default();
+        text_input.set_events(vec![TextInputEvent::Char('a'), TextInputEvent::Composition("ni".to_string())]);
+        assert_eq!(text_input.get_composition(), "ni");
+        assert_eq!(text_input.get_text(), "a");
+
+        text_input.set_events(vec![TextInputEvent::Commit("你".to_string()), TextInputEvent::Char('!')]);
+        assert_eq!(text_input.get_composition(), "");
+        assert_eq!(text_input.get_text(), "你!");
+    }
+
+    #[test]
+    fn translator_follows_shift_and_caps_lock() {
+        let mut translator = KeyTextTranslator::default();
+        assert_eq!(translator.translate(KeyCode::A, true), Some('a'));
+        assert_eq!(translator.translate(KeyCode::A, false), None);
+        translator.translate(KeyCode::RightShift, true);
+        assert_eq!(translator.translate(KeyCode::One, true), Some('!'));
+        translator.translate(KeyCode::RightShift, false);
+        translator.translate(KeyCode::CapsLock, true);
+        translator.translate(KeyCode::CapsLock, false);
+        assert_eq!(translator.translate(KeyCode::B, true), Some('B'));
+        assert_eq!(translator.translate(KeyCode::One, true), Some('1'));
+        assert_eq!(translator.translate(KeyCode::F1, true), None);
+    }
+}
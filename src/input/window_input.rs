@@ -0,0 +1,568 @@
+use glium::glutin::{Event, WindowEvent, DeviceEvent, ElementState, MouseButton, MouseScrollDelta, VirtualKeyCode};
+use std::collections::{HashMap, HashSet};
+use crate::games::GameInput;
+use crate::window::WindowEventQueue;
+use crate::rendering::WindowSpec;
+use super::{InputHandler, bool_switch, update_button_states};
+use super::cursor::{CursorPosition, CursorTracker};
+use super::keyboard::KeyCode;
+use super::text_input::TextInputEvent;
+
+/// Touchpads scroll by pixels rather than notches, this many make up a notch
+pub const WHEEL_PIXELS_PER_NOTCH: f64 = 20.0;
+
+/// Input from the window's own events, for platforms without MultiInput (Linux, macOS).
+/// The window reports a single keyboard and mouse, filled in as device 0, and typed text as it
+/// arrives (winit has no input method composition events yet). It reports no joysticks,
+/// so joystick input is left untouched.
+pub struct WindowInput {
+    pub raw_states: WindowRawStates,
+    event_queue: WindowEventQueue,
+    cursor_tracker: CursorTracker,
+    /// From the focus events, input queued while unfocused is dropped
+    focused: bool,
+    escape_key_switch: bool_switch::BoolSwitch,
+    backtick_key_switch: bool_switch::BoolSwitch,
+    f7_key_switch: bool_switch::BoolSwitch,
+    f8_key_switch: bool_switch::BoolSwitch,
+    f9_key_switch: bool_switch::BoolSwitch
+}
+
+/// Like multihandler::RawStates the per frame maps only hold what changed since the last flush
+#[derive(Default)]
+pub struct WindowRawStates {
+    pub key_states: HashMap<VirtualKeyCode, bool>,
+    pub mouse_button_states: HashMap<MouseButton, bool>,
+    pub mouse_movement: (f64, f64),
+    pub mouse_wheel: (f64, f64),
+    pub text_events: Vec<TextInputEvent>,
+    held_keys: HashSet<VirtualKeyCode>,
+    held_mouse_buttons: HashSet<MouseButton>
+}
+
+impl WindowRawStates {
+    pub fn flush(&mut self) {
+        self.key_states.clear();
+        self.mouse_button_states.clear();
+        self.mouse_movement = (0.0, 0.0);
+        self.mouse_wheel = (0.0, 0.0);
+        self.text_events.clear();
+    }
+
+    fn update_key(&mut self, key: VirtualKeyCode, pressed: bool) {
+        self.key_states.insert(key, pressed);
+        if pressed {
+            self.held_keys.insert(key);
+        } else {
+            self.held_keys.remove(&key);
+        }
+    }
+
+    fn update_mouse_button(&mut self, button: MouseButton, pressed: bool) {
+        self.mouse_button_states.insert(button, pressed);
+        if pressed {
+            self.held_mouse_buttons.insert(button);
+        } else {
+            self.held_mouse_buttons.remove(&button);
+        }
+    }
+
+    /// Releases go to another window once focus is lost, so everything held is released
+    fn release_all(&mut self) {
+        for key in self.held_keys.drain() {
+            self.key_states.insert(key, false);
+        }
+        for button in self.held_mouse_buttons.drain() {
+            self.mouse_button_states.insert(button, false);
+        }
+    }
+}
+
+impl WindowInput {
+    /// event_queue should be the one given to GlutinInput::with_event_queue
+    pub fn new(event_queue: WindowEventQueue) -> Self {
+        WindowInput {
+            raw_states: WindowRawStates::default(),
+            event_queue,
+            cursor_tracker: CursorTracker::default(),
+            focused: true,
+            escape_key_switch: bool_switch::BoolSwitch::new(),
+            backtick_key_switch: bool_switch::BoolSwitch::new(),
+            f7_key_switch: bool_switch::BoolSwitch::new(),
+            f8_key_switch: bool_switch::BoolSwitch::new(),
+            f9_key_switch: bool_switch::BoolSwitch::new()
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        self.cursor_tracker.handle_event(event);
+        if let Event::WindowEvent { event: WindowEvent::Focused(focused), .. } = event {
+            self.focused = *focused;
+        }
+        if !self.focused {
+            // Movement and presses made for other windows would all land at once on refocusing
+            if let Event::WindowEvent { event: WindowEvent::Focused(false), .. } = event {
+                self.raw_states.release_all();
+            }
+            return;
+        }
+        match event {
+            Event::WindowEvent { event: WindowEvent::KeyboardInput { input, .. }, .. } => {
+                if let Some(key) = input.virtual_keycode {
+                    self.raw_states.update_key(key, input.state == ElementState::Pressed);
+                }
+            },
+            Event::WindowEvent { event: WindowEvent::MouseInput { state, button, .. }, .. }
+                => self.raw_states.update_mouse_button(*button, *state == ElementState::Pressed),
+            // Editing keys (backspace, return...) arrive as control characters and macOS sends
+            // arrow and function keys as private use characters, those are read as keys instead
+            Event::WindowEvent { event: WindowEvent::ReceivedCharacter(c), .. }
+                if !c.is_control() && !('\u{f700}'..='\u{f8ff}').contains(c)
+                => self.raw_states.text_events.push(TextInputEvent::Char(*c)),
+            Event::WindowEvent { event: WindowEvent::MouseWheel { delta, .. }, .. } => {
+                let (x, y) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (*x as f64, *y as f64),
+                    MouseScrollDelta::PixelDelta(position)
+                        => (position.x / WHEEL_PIXELS_PER_NOTCH, position.y / WHEEL_PIXELS_PER_NOTCH)
+                };
+                self.raw_states.mouse_wheel.0 += x;
+                self.raw_states.mouse_wheel.1 += y;
+            },
+            // Unaccelerated motion, as MultiInput reports it
+            Event::DeviceEvent { event: DeviceEvent::MouseMotion { delta }, .. } => {
+                self.raw_states.mouse_movement.0 += delta.0;
+                self.raw_states.mouse_movement.1 += delta.1;
+            },
+            _ => ()
+        }
+    }
+
+    fn update_switches(&mut self) {
+        let raw_states = &self.raw_states;
+        let mut switches = [
+            (VirtualKeyCode::Escape, &mut self.escape_key_switch),
+            (VirtualKeyCode::Grave, &mut self.backtick_key_switch),
+            (VirtualKeyCode::F7, &mut self.f7_key_switch),
+            (VirtualKeyCode::F8, &mut self.f8_key_switch),
+            (VirtualKeyCode::F9, &mut self.f9_key_switch)
+        ];
+        for (key, switch) in switches.iter_mut() {
+            if let Some(&state) = raw_states.key_states.get(&*key) {
+                switch.update_state(state);
+            } else {
+                switch.clear_switch();
+            }
+        }
+    }
+}
+
+impl InputHandler for WindowInput {
+    fn reset(&mut self) {
+        self.raw_states = WindowRawStates::default();
+        self.event_queue.take();
+    }
+
+    fn receive_input(&mut self) {
+        for event in self.event_queue.take() {
+            self.handle_event(&event);
+        }
+        self.update_switches();
+    }
+
+    fn pass_on_input<'a>(&self, game_input: Option<&'a mut dyn GameInput>) {
+        if let Some(input) = game_input {
+            if let Some(kbds) = input.get_kbd_inp() {
+                if kbds.devices.is_empty() {
+                    kbds.devices.push(Default::default());
+                }
+                for (&key, &val) in self.raw_states.key_states.iter() {
+                    if let Some(key_code) = get_key_code(key) {
+                        kbds.devices[0].set_key(key_code, val);
+                    }
+                }
+            }
+
+            if let Some(text) = input.get_text_inp() {
+                text.set_events(self.raw_states.text_events.clone());
+            }
+
+            if let Some(mice) = input.get_mouse_inp() {
+                for mouse in &mut mice.devices {
+                    mouse.movement = (0, 0);
+                    mouse.wheel = (0.0, 0.0);
+                }
+                if mice.devices.is_empty() {
+                    mice.devices.push(Default::default());
+                }
+                let mouse = &mut mice.devices[0];
+                for (&button, &val) in self.raw_states.mouse_button_states.iter() {
+                    match button {
+                        MouseButton::Left => mouse.left = val,
+                        MouseButton::Right => mouse.right = val,
+                        MouseButton::Middle => mouse.middle = val,
+                        // The back and forward buttons, as X11 numbers them
+                        MouseButton::Other(8) => mouse.button4 = val,
+                        MouseButton::Other(9) => mouse.button5 = val,
+                        MouseButton::Other(_) => ()
+                    }
+                }
+                let (x, y) = self.raw_states.mouse_movement;
+                mouse.movement = (x.round() as i32, y.round() as i32);
+                mouse.wheel = self.raw_states.mouse_wheel;
+                mice.cursor = self.cursor_tracker.get_cursor();
+            }
+
+            update_button_states(input, time::precise_time_s());
+        }
+    }
+
+    fn flush_input(&mut self) {
+        self.raw_states.flush();
+    }
+
+    fn set_window_spec(&mut self, window_spec: WindowSpec) {
+        self.cursor_tracker.set_window_size(window_spec.size);
+    }
+
+    fn get_cursor(&self) -> Option<CursorPosition> {
+        self.cursor_tracker.get_cursor()
+    }
+
+    fn escape_key_pressed(&self) -> bool {
+        self.escape_key_switch.pressed()
+    }
+
+    fn f7_key_pressed(&self) -> bool {
+        self.f7_key_switch.pressed()
+    }
+
+    fn f8_key_pressed(&self) -> bool {
+        self.f8_key_switch.pressed()
+    }
+
+    fn f9_key_pressed(&self) -> bool {
+        self.f9_key_switch.pressed()
+    }
+
+    fn backtick_key_pressed(&self) -> bool {
+        self.backtick_key_switch.pressed()
+    }
+}
+
+/// Keys with no KeyCode (e.g. media keys) give None
+fn get_key_code(key: VirtualKeyCode) -> Option<KeyCode> {
+    use glium::glutin::VirtualKeyCode::*;
+    let key_code = match key {
+        Escape => KeyCode::Escape,
+        Return => KeyCode::Return,
+        NumpadEnter => KeyCode::NumpadEnter,
+        Back => KeyCode::Backspace,
+        Left => KeyCode::Left,
+        Right => KeyCode::Right,
+        Up => KeyCode::Up,
+        Down => KeyCode::Down,
+        Space => KeyCode::Space,
+        A => KeyCode::A,
+        B => KeyCode::B,
+        C => KeyCode::C,
+        D => KeyCode::D,
+        E => KeyCode::E,
+        F => KeyCode::F,
+        G => KeyCode::G,
+        H => KeyCode::H,
+        I => KeyCode::I,
+        J => KeyCode::J,
+        K => KeyCode::K,
+        L => KeyCode::L,
+        M => KeyCode::M,
+        N => KeyCode::N,
+        O => KeyCode::O,
+        P => KeyCode::P,
+        Q => KeyCode::Q,
+        R => KeyCode::R,
+        S => KeyCode::S,
+        T => KeyCode::T,
+        U => KeyCode::U,
+        V => KeyCode::V,
+        W => KeyCode::W,
+        X => KeyCode::X,
+        Y => KeyCode::Y,
+        Z => KeyCode::Z,
+        Key0 => KeyCode::Zero,
+        Key1 => KeyCode::One,
+        Key2 => KeyCode::Two,
+        Key3 => KeyCode::Three,
+        Key4 => KeyCode::Four,
+        Key5 => KeyCode::Five,
+        Key6 => KeyCode::Six,
+        Key7 => KeyCode::Seven,
+        Key8 => KeyCode::Eight,
+        Key9 => KeyCode::Nine,
+        LShift => KeyCode::LeftShift,
+        RShift => KeyCode::RightShift,
+        LWin => KeyCode::LeftSuper,
+        RWin => KeyCode::RightSuper,
+        Apps => KeyCode::Menu,
+        LControl => KeyCode::LeftCtrl,
+        RControl => KeyCode::RightCtrl,
+        LAlt => KeyCode::LeftAlt,
+        RAlt => KeyCode::RightAlt,
+        Capital => KeyCode::CapsLock,
+        Pause => KeyCode::Pause,
+        PageUp => KeyCode::PageUp,
+        PageDown => KeyCode::PageDown,
+        Snapshot => KeyCode::PrintScreen,
+        Scroll => KeyCode::ScrollLock,
+        Insert => KeyCode::Insert,
+        End => KeyCode::End,
+        Home => KeyCode::Home,
+        Delete => KeyCode::Delete,
+        Add => KeyCode::Add,
+        Subtract => KeyCode::Subtract,
+        Multiply => KeyCode::Multiply,
+        NumpadComma => KeyCode::Separator,
+        Decimal => KeyCode::Decimal,
+        Divide => KeyCode::Divide,
+        Backslash => KeyCode::Backslash,
+        Slash => KeyCode::ForwardSlash,
+        // The =/+ key, which raw input calls plus
+        Equals => KeyCode::Plus,
+        Minus => KeyCode::Minus,
+        Period => KeyCode::FullStop,
+        Comma => KeyCode::Comma,
+        Tab => KeyCode::Tab,
+        Numlock => KeyCode::NumLock,
+        LBracket => KeyCode::LeftSquareBracket,
+        RBracket => KeyCode::RightSquareBracket,
+        Semicolon => KeyCode::Semicolon,
+        Apostrophe => KeyCode::Apostrophe,
+        Numpad0 => KeyCode::Numpad0,
+        Numpad1 => KeyCode::Numpad1,
+        Numpad2 => KeyCode::Numpad2,
+        Numpad3 => KeyCode::Numpad3,
+        Numpad4 => KeyCode::Numpad4,
+        Numpad5 => KeyCode::Numpad5,
+        Numpad6 => KeyCode::Numpad6,
+        Numpad7 => KeyCode::Numpad7,
+        Numpad8 => KeyCode::Numpad8,
+        Numpad9 => KeyCode::Numpad9,
+        NumpadEquals => KeyCode::NumpadEquals,
+        Grave => KeyCode::Backtick,
+        F1 => KeyCode::F1,
+        F2 => KeyCode::F2,
+        F3 => KeyCode::F3,
+        F4 => KeyCode::F4,
+        F5 => KeyCode::F5,
+        F6 => KeyCode::F6,
+        F7 => KeyCode::F7,
+        F8 => KeyCode::F8,
+        F9 => KeyCode::F9,
+        F10 => KeyCode::F10,
+        F11 => KeyCode::F11,
+        F12 => KeyCode::F12,
+        F13 => KeyCode::F13,
+        F14 => KeyCode::F14,
+        F15 => KeyCode::F15,
+        F16 => KeyCode::F16,
+        F17 => KeyCode::F17,
+        F18 => KeyCode::F18,
+        F19 => KeyCode::F19,
+        F20 => KeyCode::F20,
+        F21 => KeyCode::F21,
+        F22 => KeyCode::F22,
+        F23 => KeyCode::F23,
+        F24 => KeyCode::F24,
+        _ => return None
+    };
+    Some(key_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glium::glutin::{DeviceId, WindowId, KeyboardInput as KeyEvent, ModifiersState, TouchPhase};
+    use crate::input::{KeyboardInput, MouseInput, TextInput, ButtonStates, InputBinding, MouseButtonCode};
+
+    #[derive(Default)]
+    struct TestInput {
+        kbd: KeyboardInput,
+        mouse: MouseInput,
+        text: TextInput,
+        buttons: ButtonStates
+    }
+
+    impl GameInput for TestInput {
+        fn get_kbd_inp<'a>(&'a mut self) -> Option<&'a mut KeyboardInput> { Some(&mut self.kbd) }
+        fn get_mouse_inp<'a>(&'a mut self) -> Option<&'a mut MouseInput> { Some(&mut self.mouse) }
+        fn get_text_inp<'a>(&'a mut self) -> Option<&'a mut TextInput> { Some(&mut self.text) }
+        fn get_button_states<'a>(&'a mut self) -> Option<&'a mut ButtonStates> { Some(&mut self.buttons) }
+    }
+
+    fn window_event(event: WindowEvent) -> Event {
+        Event::WindowEvent {
+            window_id: unsafe { WindowId::dummy() },
+            event
+        }
+    }
+
+    fn key_event(key: VirtualKeyCode, state: ElementState) -> Event {
+        window_event(WindowEvent::KeyboardInput {
+            device_id: unsafe { DeviceId::dummy() },
+            input: KeyEvent {
+                scancode: 0,
+                state,
+                virtual_keycode: Some(key),
+                modifiers: ModifiersState::default()
+            }
+        })
+    }
+
+    fn button_event(button: MouseButton, state: ElementState) -> Event {
+        window_event(WindowEvent::MouseInput {
+            device_id: unsafe { DeviceId::dummy() },
+            state,
+            button,
+            modifiers: ModifiersState::default()
+        })
+    }
+
+    fn motion_event(x: f64, y: f64) -> Event {
+        Event::DeviceEvent {
+            device_id: unsafe { DeviceId::dummy() },
+            event: DeviceEvent::MouseMotion { delta: (x, y) }
+        }
+    }
+
+    fn wheel_event(delta: MouseScrollDelta) -> Event {
+        window_event(WindowEvent::MouseWheel {
+            device_id: unsafe { DeviceId::dummy() },
+            delta,
+            phase: TouchPhase::Moved,
+            modifiers: ModifiersState::default()
+        })
+    }
+
+    fn run_frame(input_handler: &mut WindowInput, events: Vec<Event>, game_input: &mut TestInput) {
+        for event in events {
+            input_handler.event_queue.push(event);
+        }
+        input_handler.receive_input();
+        input_handler.pass_on_input(Some(game_input));
+        input_handler.flush_input();
+    }
+
+    #[test]
+    fn keys_stay_held_until_released() {
+        let mut input_handler = WindowInput::new(WindowEventQueue::new());
+        let mut game_input = TestInput::default();
+
+        run_frame(&mut input_handler, vec![
+            key_event(VirtualKeyCode::W, ElementState::Pressed),
+            key_event(VirtualKeyCode::LShift, ElementState::Pressed),
+            key_event(VirtualKeyCode::RShift, ElementState::Pressed),
+            key_event(VirtualKeyCode::RShift, ElementState::Released)
+        ], &mut game_input);
+        assert!(game_input.kbd.get_w());
+        assert!(game_input.kbd.get_shift());
+
+        run_frame(&mut input_handler, vec![], &mut game_input);
+        assert!(game_input.kbd.get_w());
+
+        run_frame(&mut input_handler, vec![key_event(VirtualKeyCode::W, ElementState::Released)], &mut game_input);
+        assert!(!game_input.kbd.get_w());
+        assert!(game_input.kbd.get_shift());
+    }
+
+    #[test]
+    fn losing_focus_releases_everything() {
+        let mut input_handler = WindowInput::new(WindowEventQueue::new());
+        let mut game_input = TestInput::default();
+
+        run_frame(&mut input_handler, vec![
+            key_event(VirtualKeyCode::Space, ElementState::Pressed),
+            button_event(MouseButton::Left, ElementState::Pressed)
+        ], &mut game_input);
+        assert!(game_input.kbd.get_space());
+        assert!(game_input.mouse.devices[0].left);
+        assert!(game_input.buttons.get_pressed(InputBinding::Key(KeyCode::Space)));
+
+        run_frame(&mut input_handler, vec![window_event(WindowEvent::Focused(false))], &mut game_input);
+        assert!(!game_input.kbd.get_space());
+        assert!(!game_input.mouse.devices[0].left);
+        assert!(game_input.buttons.get_released(InputBinding::MouseButton(MouseButtonCode::Left)));
+    }
+
+    #[test]
+    fn input_queued_while_unfocused_is_dropped() {
+        let mut input_handler = WindowInput::new(WindowEventQueue::new());
+        let mut game_input = TestInput::default();
+
+        run_frame(&mut input_handler, vec![
+            window_event(WindowEvent::Focused(false)),
+            motion_event(300.0, -120.0),
+            key_event(VirtualKeyCode::Space, ElementState::Pressed),
+            window_event(WindowEvent::ReceivedCharacter('a')),
+            window_event(WindowEvent::Focused(true))
+        ], &mut game_input);
+        assert_eq!(game_input.mouse.devices[0].movement, (0, 0));
+        assert!(!game_input.kbd.get_space());
+        assert!(game_input.text.events.is_empty());
+
+        run_frame(&mut input_handler, vec![motion_event(1.0, 2.0)], &mut game_input);
+        assert_eq!(game_input.mouse.devices[0].movement, (1, 2));
+    }
+
+    #[test]
+    fn mouse_movement_is_summed_per_frame() {
+        let mut input_handler = WindowInput::new(WindowEventQueue::new());
+        let mut game_input = TestInput::default();
+
+        run_frame(&mut input_handler, vec![motion_event(1.5, -2.0), motion_event(2.0, -1.0)], &mut game_input);
+        assert_eq!(game_input.mouse.devices[0].movement, (4, -3));
+
+        run_frame(&mut input_handler, vec![], &mut game_input);
+        assert_eq!(game_input.mouse.devices[0].movement, (0, 0));
+    }
+
+    #[test]
+    fn wheel_turns_are_summed_in_notches() {
+        let mut input_handler = WindowInput::new(WindowEventQueue::new());
+        let mut game_input = TestInput::default();
+
+        run_frame(&mut input_handler, vec![
+            wheel_event(MouseScrollDelta::LineDelta(0.0, 1.0)),
+            wheel_event(MouseScrollDelta::PixelDelta((WHEEL_PIXELS_PER_NOTCH, -WHEEL_PIXELS_PER_NOTCH / 2.0).into()))
+        ], &mut game_input);
+        assert_eq!(game_input.mouse.get_wheel(), (1.0, 0.5));
+
+        run_frame(&mut input_handler, vec![], &mut game_input);
+        assert_eq!(game_input.mouse.get_wheel(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn switch_keys_report_presses_once() {
+        let mut input_handler = WindowInput::new(WindowEventQueue::new());
+        let mut game_input = TestInput::default();
+
+        run_frame(&mut input_handler, vec![key_event(VirtualKeyCode::Grave, ElementState::Pressed)], &mut game_input);
+        assert!(input_handler.backtick_key_pressed());
+        run_frame(&mut input_handler, vec![], &mut game_input);
+        assert!(!input_handler.backtick_key_pressed());
+    }
+
+    #[test]
+    fn typed_characters_become_text_events() {
+        let mut input_handler = WindowInput::new(WindowEventQueue::new());
+        let mut game_input = TestInput::default();
+
+        run_frame(&mut input_handler, vec![
+            window_event(WindowEvent::ReceivedCharacter('é')),
+            window_event(WindowEvent::ReceivedCharacter('\u{8}')),
+            window_event(WindowEvent::ReceivedCharacter('!'))
+        ], &mut game_input);
+        assert_eq!(game_input.text.get_text(), "é!");
+
+        run_frame(&mut input_handler, vec![], &mut game_input);
+        assert!(game_input.text.events.is_empty());
+    }
+}
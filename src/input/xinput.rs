@@ -0,0 +1,113 @@
+use libloading::{Library, Symbol};
+use winapi::shared::minwindef::DWORD;
+use winapi::shared::winerror::ERROR_SUCCESS;
+use winapi::um::xinput::*;
+use std::mem;
+use super::gamepad::{GamepadButton, GamepadAxis, GamepadState, GamepadReport};
+
+/// XInput numbers its controllers by their player light
+pub const XINPUT_USER_COUNT: usize = 4;
+
+/// Newest first, older ones for Windows 7 and before
+const XINPUT_LIBRARIES: [&str; 3] = ["xinput1_4.dll", "xinput1_3.dll", "xinput9_1_0.dll"];
+const XINPUT_ID_PREFIX: &str = "xinput:";
+/// Asking after a missing controller stalls, so empty slots are only checked this often (seconds)
+const DISCONNECTED_POLL_INTERVAL: f64 = 1.0;
+
+type XInputGetState<'a> = Symbol<'a, unsafe extern "system" fn(user_index: DWORD, state: *mut XINPUT_STATE) -> DWORD>;
+type XInputSetState<'a> = Symbol<'a, unsafe extern "system" fn(user_index: DWORD, vibration: *mut XINPUT_VIBRATION) -> DWORD>;
+
+/// Xbox style controllers, polled so they can be plugged in at any time, with rumble.
+/// Loaded at runtime like Shcore.dll in handler_basic, so a missing XInput only loses gamepads.
+pub struct XInput {
+    library: Library,
+    next_polls: [f64; XINPUT_USER_COUNT]
+}
+
+impl XInput {
+    pub fn load() -> Option<XInput> {
+        XINPUT_LIBRARIES.iter()
+            .filter_map(|name| Library::new(name).ok())
+            .next()
+            .map(|library| XInput { library, next_polls: [0.0; XINPUT_USER_COUNT] })
+    }
+
+    /// The connected controllers, with ids like "xinput:0". time is in seconds.
+    pub fn poll(&mut self, time: f64) -> Vec<GamepadReport> {
+        let get_state = match unsafe { self.library.get::<XInputGetState<'_>>(b"XInputGetState") } {
+            Ok(get_state) => get_state,
+            Err(_) => return Vec::new()
+        };
+        let next_polls = &mut self.next_polls;
+        (0..XINPUT_USER_COUNT)
+            .filter_map(|user| {
+                if time < next_polls[user] {
+                    return None;
+                }
+                let mut state: XINPUT_STATE = unsafe { mem::zeroed() };
+                if unsafe { get_state(user as DWORD, &mut state) } != ERROR_SUCCESS {
+                    next_polls[user] = time + DISCONNECTED_POLL_INTERVAL;
+                    return None;
+                }
+                Some(GamepadReport {
+                    id: format!("{}{}", XINPUT_ID_PREFIX, user),
+                    name: format!("XInput Controller {}", user + 1),
+                    state: get_gamepad_state(&state.Gamepad),
+                    supports_rumble: true
+                })
+            })
+            .collect()
+    }
+
+    /// Motor speeds from 0 to 1 for the controller with the id, other ids are ignored
+    pub fn set_rumble(&self, id: &str, strong: f64, weak: f64) {
+        let user = match id.strip_prefix(XINPUT_ID_PREFIX).and_then(|user| user.parse::<DWORD>().ok()) {
+            Some(user) => user,
+            None => return
+        };
+        if let Ok(set_state) = unsafe { self.library.get::<XInputSetState<'_>>(b"XInputSetState") } {
+            let mut vibration = XINPUT_VIBRATION {
+                wLeftMotorSpeed: (strong * 65535.0) as u16,
+                wRightMotorSpeed: (weak * 65535.0) as u16
+            };
+            unsafe { set_state(user, &mut vibration); }
+        }
+    }
+}
+
+/// Whether a raw input device path is an XInput controller (XInput reports those itself)
+pub fn is_xinput_device(device_path: &str) -> bool {
+    device_path.to_uppercase().contains("&IG_")
+}
+
+fn get_gamepad_state(gamepad: &XINPUT_GAMEPAD) -> GamepadState {
+    let mut state = GamepadState::default();
+    let buttons = [
+        (XINPUT_GAMEPAD_A, GamepadButton::South),
+        (XINPUT_GAMEPAD_B, GamepadButton::East),
+        (XINPUT_GAMEPAD_X, GamepadButton::West),
+        (XINPUT_GAMEPAD_Y, GamepadButton::North),
+        (XINPUT_GAMEPAD_LEFT_SHOULDER, GamepadButton::LeftShoulder),
+        (XINPUT_GAMEPAD_RIGHT_SHOULDER, GamepadButton::RightShoulder),
+        (XINPUT_GAMEPAD_BACK, GamepadButton::Back),
+        (XINPUT_GAMEPAD_START, GamepadButton::Start),
+        (XINPUT_GAMEPAD_LEFT_THUMB, GamepadButton::LeftStick),
+        (XINPUT_GAMEPAD_RIGHT_THUMB, GamepadButton::RightStick),
+        (XINPUT_GAMEPAD_DPAD_UP, GamepadButton::DPadUp),
+        (XINPUT_GAMEPAD_DPAD_DOWN, GamepadButton::DPadDown),
+        (XINPUT_GAMEPAD_DPAD_LEFT, GamepadButton::DPadLeft),
+        (XINPUT_GAMEPAD_DPAD_RIGHT, GamepadButton::DPadRight)
+    ];
+    for &(flag, button) in buttons.iter() {
+        state.set_button(button, gamepad.wButtons & flag != 0);
+    }
+    // XInput already has y up
+    let stick = |value: i16| (value as f64 / 32767.0).max(-1.0);
+    state.set_axis(GamepadAxis::LeftX, stick(gamepad.sThumbLX));
+    state.set_axis(GamepadAxis::LeftY, stick(gamepad.sThumbLY));
+    state.set_axis(GamepadAxis::RightX, stick(gamepad.sThumbRX));
+    state.set_axis(GamepadAxis::RightY, stick(gamepad.sThumbRY));
+    state.set_axis(GamepadAxis::LeftTrigger, gamepad.bLeftTrigger as f64 / 255.0);
+    state.set_axis(GamepadAxis::RightTrigger, gamepad.bRightTrigger as f64 / 255.0);
+    state
+}
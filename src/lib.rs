@@ -11,7 +11,10 @@ use unicode_normalization;
 use rusttype;
 #[macro_use]
 extern crate lazy_static;
+#[macro_use]
+extern crate serde_derive;
 
+#[cfg(windows)]
 use winapi;
 use image;
 
@@ -26,6 +29,9 @@ pub mod debug;
 pub mod window;
 pub mod animation;
 pub mod sound;
+pub mod particles;
+pub mod ui;
+pub mod console;
 
 use crate::debug::*;
 use std::env;
@@ -0,0 +1,74 @@
+use std::f64::consts;
+use rand::Rng;
+use crate::geometry::*;
+
+/// Region new particles are spawned in
+#[derive(Clone, Debug)]
+pub enum EmitterShape {
+    Point(Point),
+    Line(Line),
+    Circle(Point, f64), // Center and radius, spawns anywhere inside
+    Area(ConPoly)
+}
+
+impl EmitterShape {
+    pub fn sample_position<R: Rng>(&self, rng: &mut R) -> Point {
+        match *self {
+            EmitterShape::Point(point) => point,
+            EmitterShape::Line(line) => line.get_point(rng.gen::<f64>()),
+            EmitterShape::Circle(center, radius) => {
+                // sqrt keeps the density uniform over the disc
+                let radial = radius * rng.gen::<f64>().sqrt();
+                let angle = 2.0 * consts::PI * rng.gen::<f64>();
+                center + radial * Point::from_angle(angle)
+            },
+            EmitterShape::Area(ref con_poly) => sample_con_poly(con_poly, rng)
+        }
+    }
+
+    pub fn shift_by(&mut self, shift: Point) {
+        match self {
+            EmitterShape::Point(point) => *point += shift,
+            EmitterShape::Line(line) => *line = line.shifted_by(shift),
+            EmitterShape::Circle(center, _) => *center += shift,
+            EmitterShape::Area(con_poly) => con_poly.shift_by(shift)
+        }
+    }
+}
+
+/// Picks a triangle of the fan from corner 0 (weighted by area) and then a point inside it
+fn sample_con_poly<R: Rng>(con_poly: &ConPoly, rng: &mut R) -> Point {
+    let corners = &con_poly.corners;
+    if corners.len() < 3 {
+        return con_poly.center();
+    }
+
+    let triangle_areas: Vec<f64> = (1..corners.len() - 1)
+        .map(|index| triangle_area(corners[0], corners[index], corners[index + 1]))
+        .collect();
+    let total_area: f64 = triangle_areas.iter().sum();
+
+    let mut target = rng.gen::<f64>() * total_area;
+    let mut triangle_index = triangle_areas.len() - 1;
+    for (index, area) in triangle_areas.iter().enumerate() {
+        if target < *area {
+            triangle_index = index;
+            break;
+        }
+        target -= area;
+    }
+
+    let (mut u, mut v) = (rng.gen::<f64>(), rng.gen::<f64>());
+    if u + v > 1.0 {
+        u = 1.0 - u;
+        v = 1.0 - v;
+    }
+    let origin = corners[0];
+    origin + u * (corners[triangle_index + 1] - origin) + v * (corners[triangle_index + 2] - origin)
+}
+
+fn triangle_area(a: Point, b: Point, c: Point) -> f64 {
+    let ab = b - a;
+    let ac = c - a;
+    (ab.x * ac.y - ab.y * ac.x).abs() / 2.0
+}
@@ -0,0 +1,11 @@
+pub mod particle;
+pub mod particle_curve;
+pub mod emitter_shape;
+pub mod particle_emitter;
+pub mod particle_system;
+
+pub use self::particle::Particle;
+pub use self::particle_curve::ParticleCurve;
+pub use self::emitter_shape::EmitterShape;
+pub use self::particle_emitter::{ParticleEmitter, ParticleShape, ParticleCollisionResponse};
+pub use self::particle_system::{ParticleSystem, ParticleBatch};
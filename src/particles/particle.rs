@@ -0,0 +1,34 @@
+use crate::geometry::*;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Particle {
+    pub pos: Point,
+    pub velocity: Point,
+    pub age: f64,
+    pub lifetime: f64,
+    pub emitter_index: usize
+}
+
+impl Particle {
+    pub fn new(pos: Point, velocity: Point, lifetime: f64, emitter_index: usize) -> Self {
+        Self {
+            pos,
+            velocity,
+            age: 0.0,
+            lifetime,
+            emitter_index
+        }
+    }
+
+    /// Fraction of the lifetime used up, in [0,1]
+    pub fn get_life_fraction(&self) -> f64 {
+        if self.lifetime <= 0.0 {
+            return 1.0;
+        }
+        (self.age / self.lifetime).min(1.0)
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.age >= self.lifetime
+    }
+}
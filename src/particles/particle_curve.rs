@@ -0,0 +1,31 @@
+use std::ops::{Add, Mul};
+use crate::animation::AnimationFunctionEnum;
+use crate::geometry::interpolate;
+
+/// Value over a particle's lifetime, eased from start (t=0) to end (t=1)
+#[derive(Copy, Clone, Debug)]
+pub struct ParticleCurve<T: Copy> {
+    pub start: T,
+    pub end: T,
+    pub easing: AnimationFunctionEnum
+}
+
+impl<T> ParticleCurve<T>
+    where T: Copy + Add<T, Output=T> + Mul<f64, Output=T>
+{
+    pub fn new(start: T, end: T, easing: AnimationFunctionEnum) -> Self {
+        Self {
+            start,
+            end,
+            easing
+        }
+    }
+
+    pub fn constant(value: T) -> Self {
+        Self::new(value, value, AnimationFunctionEnum::Linear)
+    }
+
+    pub fn get_value(&self, t: f64) -> T {
+        interpolate(self.start, self.end, self.easing.apply(t))
+    }
+}
@@ -0,0 +1,78 @@
+use crate::animation::AnimationFunctionEnum;
+use crate::rendering::*;
+use crate::geometry::*;
+use super::{EmitterShape, ParticleCurve};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ParticleShape {
+    Circle,
+    Square
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ParticleCollisionResponse {
+    Ignore,
+    Kill,
+    Bounce(f64) // Restitution, 1.0 keeps all the speed
+}
+
+/// Spawning rules and lifetime curves shared by every particle an emitter creates
+/// Ranges are (min, max) and sampled uniformly per particle
+#[derive(Clone, Debug)]
+pub struct ParticleEmitter {
+    pub shape: EmitterShape,
+    pub rate: f64, // Particles per second
+    pub lifetime: (f64, f64),
+    pub speed: (f64, f64),
+    pub direction: (f64, f64), // Angle in radians
+    pub acceleration: Point,
+    pub speed_curve: ParticleCurve<f64>, // Scales the velocity
+    pub size_curve: ParticleCurve<f64>,
+    pub color_curve: ParticleCurve<Color>,
+    pub particle_shape: ParticleShape,
+    pub collision_response: ParticleCollisionResponse,
+    pub depth: f64,
+    pub fixed: bool,
+    pub active: bool,
+    pub(crate) spawn_accumulator: f64
+}
+
+impl ParticleEmitter {
+    pub fn new(shape: EmitterShape, rate: f64, size: f64, color: Color) -> Self {
+        Self {
+            shape,
+            rate,
+            lifetime: (1.0, 1.0),
+            speed: (0.0, 0.0),
+            direction: (0.0, 2.0 * std::f64::consts::PI),
+            acceleration: Point::zero(),
+            speed_curve: ParticleCurve::constant(1.0),
+            size_curve: ParticleCurve::constant(size),
+            color_curve: ParticleCurve::constant(color),
+            particle_shape: ParticleShape::Circle,
+            collision_response: ParticleCollisionResponse::Ignore,
+            depth: 0.0,
+            fixed: false,
+            active: true,
+            spawn_accumulator: 0.0
+        }
+    }
+
+    /// Fades the alpha channel to zero over the lifetime
+    pub fn fade_out(&mut self, easing: AnimationFunctionEnum) {
+        let start = self.color_curve.start;
+        let end = Color::new(start.r, start.g, start.b, 0.0);
+        self.color_curve = ParticleCurve::new(start, end, easing);
+    }
+
+    /// Number of particles due this frame (fractional spawns carry over)
+    pub(crate) fn take_spawn_count(&mut self, t_step: f64) -> usize {
+        if !self.active {
+            return 0;
+        }
+        self.spawn_accumulator += self.rate * t_step;
+        let count = self.spawn_accumulator.floor();
+        self.spawn_accumulator -= count;
+        count as usize
+    }
+}
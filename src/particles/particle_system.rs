@@ -0,0 +1,263 @@
+use std::mem;
+use rand::{Rng, FromEntropy};
+use rand::rngs::SmallRng;
+use crate::collision::CollisionObject;
+use crate::collision::CollisionObjectDetails;
+use crate::collision::collision_logic::{circ_point_coll, poly_point_coll};
+use crate::rendering::*;
+use crate::geometry::*;
+use super::{Particle, ParticleEmitter, ParticleShape, ParticleCollisionResponse, EmitterShape};
+
+/// Owns a pool of particles fed by any number of emitters
+/// Particles are drawn through the standard circle/rectangle buffers in one batch
+pub struct ParticleSystem {
+    emitters: Vec<ParticleEmitter>,
+    particles: Vec<Particle>,
+    obstacles: Vec<CollisionObject>,
+    max_particles: usize,
+    rng: SmallRng
+}
+
+impl ParticleSystem {
+    pub fn new(max_particles: usize) -> Self {
+        Self {
+            emitters: Vec::new(),
+            particles: Vec::with_capacity(max_particles),
+            obstacles: Vec::new(),
+            max_particles,
+            rng: SmallRng::from_entropy()
+        }
+    }
+
+    /// Returns the index used to refer to the emitter later
+    pub fn add_emitter(&mut self, emitter: ParticleEmitter) -> usize {
+        self.emitters.push(emitter);
+        self.emitters.len() - 1
+    }
+
+    pub fn get_emitter_mut(&mut self, index: usize) -> Option<&mut ParticleEmitter> {
+        self.emitters.get_mut(index)
+    }
+
+    /// Static objects particles collide with (only used by emitters that don't ignore collisions)
+    pub fn set_obstacles(&mut self, obstacles: Vec<CollisionObject>) {
+        self.obstacles = obstacles;
+    }
+
+    pub fn get_particles(&self) -> &Vec<Particle> {
+        &self.particles
+    }
+
+    pub fn get_particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    /// Spawns a number of particles at once (e.g. explosions), as many as fit under max_particles
+    pub fn burst(&mut self, emitter_index: usize, count: usize) {
+        let count = count.min(self.max_particles.saturating_sub(self.particles.len()));
+        for _ in 0..count {
+            self.spawn(emitter_index);
+        }
+    }
+
+    fn spawn(&mut self, emitter_index: usize) {
+        let emitter = match self.emitters.get(emitter_index) {
+            Some(emitter) => emitter,
+            None => return
+        };
+        let rng = &mut self.rng;
+
+        let pos = emitter.shape.sample_position(rng);
+        let angle = sample_range(rng, emitter.direction);
+        let speed = sample_range(rng, emitter.speed);
+        let lifetime = sample_range(rng, emitter.lifetime);
+
+        self.particles.push(Particle::new(pos, speed * Point::from_angle(angle), lifetime, emitter_index));
+    }
+
+    pub fn update(&mut self, t_step: f64) {
+        for emitter_index in 0..self.emitters.len() {
+            let count = self.emitters[emitter_index].take_spawn_count(t_step);
+            self.burst(emitter_index, count);
+        }
+
+        let emitters = &self.emitters;
+        let obstacles = &self.obstacles;
+
+        let mut index = 0;
+        while index < self.particles.len() {
+            let particle = &mut self.particles[index];
+            particle.age += t_step;
+
+            let alive = !particle.is_dead() && Self::move_particle(particle, &emitters[particle.emitter_index], obstacles, t_step);
+
+            if alive {
+                index += 1;
+            }
+            else {
+                self.particles.swap_remove(index);
+            }
+        }
+    }
+
+    /// Returns false if the particle was killed by a collision
+    fn move_particle(particle: &mut Particle, emitter: &ParticleEmitter, obstacles: &[CollisionObject], t_step: f64) -> bool {
+        particle.velocity += t_step * emitter.acceleration;
+        let speed_scale = emitter.speed_curve.get_value(particle.get_life_fraction());
+        let next_pos = particle.pos + (speed_scale * t_step) * particle.velocity;
+
+        if emitter.collision_response == ParticleCollisionResponse::Ignore {
+            particle.pos = next_pos;
+            return true;
+        }
+
+        match earliest_collision(obstacles, particle.pos, next_pos) {
+            None => {
+                particle.pos = next_pos;
+                true
+            },
+            Some((time, normal)) => match emitter.collision_response {
+                ParticleCollisionResponse::Bounce(restitution) => {
+                    // Stop just short of the surface so the particle doesn't tunnel through next frame
+                    particle.pos = Line::new(particle.pos, next_pos).get_point(time) + COLLISION_OFFSET * normal;
+                    let normal_speed = particle.velocity.dot(&normal);
+                    particle.velocity = restitution * (particle.velocity - (2.0 * normal_speed) * normal);
+                    true
+                },
+                _ => false
+            }
+        }
+    }
+
+    pub fn build_batch(&self) -> ParticleBatch {
+        let mut primitives = Vec::with_capacity(self.particles.len());
+
+        for particle in self.particles.iter() {
+            let emitter = &self.emitters[particle.emitter_index];
+            let life_fraction = particle.get_life_fraction();
+            let size = emitter.size_curve.get_value(life_fraction);
+            let color = emitter.color_curve.get_value(life_fraction);
+            let pos = Point3::new(particle.pos.x, particle.pos.y, emitter.depth);
+
+            let primitive = match emitter.particle_shape {
+                ParticleShape::Circle => StandardPrimitive::Circ(CirclePart {
+                    radial_dim: Point::new(0.0, size / 2.0),
+                    angular_dim: Point::new(0.0, 1.0),
+                    pos,
+                    color,
+                    fixed: emitter.fixed
+                }),
+                ParticleShape::Square => StandardPrimitive::Rect(RectanglePrimitive::new_regular(size, size, pos, color, emitter.fixed))
+            };
+            primitives.push(primitive);
+        }
+
+        ParticleBatch {
+            primitives
+        }
+    }
+}
+
+const COLLISION_OFFSET: f64 = 0.0001;
+
+fn sample_range<R: Rng>(rng: &mut R, range: (f64, f64)) -> f64 {
+    range.0 + (range.1 - range.0) * rng.gen::<f64>()
+}
+
+/// Time along the path (in [0,1]) and outward surface normal of the first obstacle hit
+fn earliest_collision(obstacles: &[CollisionObject], prev: Point, next: Point) -> Option<(f64, Point)> {
+    let mut earliest: Option<(f64, Point)> = None;
+
+    for obstacle in obstacles {
+        let results = match obstacle {
+            CollisionObject::Circ(circle) => circ_point_coll(circle, circle, next, prev),
+            CollisionObject::ConPoly(con_poly) => poly_point_coll(con_poly, con_poly, next, prev),
+            CollisionObject::Line(line) => poly_point_coll(line, line, next, prev),
+            _ => None
+        };
+
+        if let Some((_, point_results)) = results {
+            if let CollisionObjectDetails::Point(dir) = point_results.details {
+                let is_earlier = match earliest {
+                    Some((time, _)) => point_results.time < time,
+                    None => true
+                };
+                if is_earlier {
+                    earliest = Some((point_results.time, -dir));
+                }
+            }
+        }
+    }
+
+    earliest
+}
+
+/// Snapshot of a particle system's primitives, handed to the renderer each frame
+pub struct ParticleBatch {
+    primitives: Vec<StandardPrimitive>
+}
+
+impl Renderable<StandardPrimitive> for ParticleBatch {
+    fn get_primitives(&mut self) -> Vec<StandardPrimitive> {
+        mem::take(&mut self.primitives)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_system(response: ParticleCollisionResponse) -> ParticleSystem {
+        let mut system = ParticleSystem::new(100);
+        let mut emitter = ParticleEmitter::new(EmitterShape::Point(Point::zero()), 0.0, 0.1, Color::white());
+        emitter.speed = (1.0, 1.0);
+        emitter.direction = (0.0, 0.0);
+        emitter.lifetime = (10.0, 10.0);
+        emitter.collision_response = response;
+        system.add_emitter(emitter);
+        system.set_obstacles(vec![CollisionObject::Line(Line::new(Point::new(0.5, -1.0), Point::new(0.5, 1.0)))]);
+        system
+    }
+
+    #[test]
+    fn rate_spawns_whole_particles() {
+        let mut system = build_system(ParticleCollisionResponse::Ignore);
+        system.get_emitter_mut(0).unwrap().rate = 10.0;
+        system.update(0.25);
+        assert_eq!(system.get_particle_count(), 2);
+        system.update(0.25);
+        assert_eq!(system.get_particle_count(), 5);
+    }
+
+    #[test]
+    fn bursts_stop_at_the_limit() {
+        let mut system = build_system(ParticleCollisionResponse::Ignore);
+        system.burst(0, 1000);
+        assert_eq!(system.get_particle_count(), 100);
+        system.clear();
+        system.burst(1, 10);
+        assert_eq!(system.get_particle_count(), 0);
+    }
+
+    #[test]
+    fn particles_bounce_off_lines() {
+        let mut system = build_system(ParticleCollisionResponse::Bounce(1.0));
+        system.burst(0, 1);
+        system.update(1.0);
+        let particle = system.get_particles()[0];
+        assert!(particle.pos.x < 0.5);
+        assert!((particle.velocity.x + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn particles_die_on_collision() {
+        let mut system = build_system(ParticleCollisionResponse::Kill);
+        system.burst(0, 1);
+        system.update(1.0);
+        assert_eq!(system.get_particle_count(), 0);
+    }
+}
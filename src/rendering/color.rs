@@ -1,4 +1,6 @@
-#[derive(Copy, Clone, Debug)]
+use std::ops::{Add, Mul};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Color {
     pub r: f64,
     pub g: f64,
@@ -107,4 +109,20 @@ impl From<[f64; 4]> for Color {
             a: arr[3]
         }
     }
+}
+
+impl Add for Color {
+    type Output = Color;
+
+    fn add(self, other: Color) -> Color {
+        Color::add(self, other)
+    }
+}
+
+impl Mul<f64> for Color {
+    type Output = Color;
+
+    fn mul(self, t: f64) -> Color {
+        self.multiply(t)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,46 @@
+use crate::rendering::TextureRect;
+use crate::geometry::*;
+
+/// In front of the console and other overlays
+pub const CURSOR_SPRITE_DEPTH: f64 = -0.9;
+
+/// A texture drawn in place of the system cursor
+#[derive(Copy, Clone, Debug)]
+pub struct CursorSprite {
+    pub texture_corner: Point3,
+    pub texture_dim: Point,
+    pub length: f64,
+    pub height: f64,
+    /// The point of the sprite at the cursor, relative to its centre
+    pub hotspot: Point
+}
+
+impl CursorSprite {
+    /// The sprite for a cursor at screen_position, see CursorPosition::get_screen_position
+    pub fn get_texture_rect(&self, screen_position: Point) -> TextureRect {
+        let centre = screen_position - self.hotspot;
+        TextureRect::new_regular(
+            self.length,
+            self.height,
+            Point3::new(centre.x, centre.y, CURSOR_SPRITE_DEPTH),
+            self.texture_corner,
+            self.texture_dim,
+            true)
+    }
+}
+
+/// How the game wants the cursor shown, asked for every frame
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CursorSettings {
+    /// Show the system cursor, hidden while a sprite is drawn instead
+    pub visible: bool,
+    /// Keep the cursor inside the window
+    pub grabbed: bool,
+    pub sprite: Option<CursorSprite>
+}
+
+impl CursorSettings {
+    pub fn shows_system_cursor(&self) -> bool {
+        self.visible && self.sprite.is_none()
+    }
+}
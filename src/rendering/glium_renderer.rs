@@ -2,7 +2,6 @@ use super::Renderer;
 use super::shaders::make_program_from_shaders;
 use crate::rendering::*;
 use crate::geometry::*;
-use crate::rendering::text_buffer::OPEN_SANS;
 use glium;
 use glium::Frame;
 use glium::{Display, Surface, DrawParameters, Depth, DepthTest, Program};
@@ -25,10 +24,13 @@ pub struct GliumRenderer<'a> {
     circ_buffer: BasicBuffer<CirclePart>,
     polygon_buffer: BasicBuffer<Polygon>,
     text_processor: TextBuffer<'a, PlainText>,
+    lighting_buffer: LightingBuffer,
     view_details: view_details::ViewDetails,
     display_settings: DisplaySettings,
     texture_array: texture::texture2d_array::Texture2dArray,
-    hidden_cursor: bool,
+    cursor_settings: CursorSettings,
+    /// Whether the system cursor is shown and grabbed, None until first applied
+    applied_cursor: Option<(bool, bool)>,
 }
 
 impl<'a> GliumRenderer<'a> {
@@ -51,11 +53,13 @@ impl<'a> GliumRenderer<'a> {
             texture_rect_buffer: BasicBuffer::<TextureRect>::new(&display),
             circ_buffer: BasicBuffer::<CirclePart>::new(&display),
             polygon_buffer: BasicBuffer::<Polygon>::new(&display),
-            text_processor: TextBuffer::new(&display, settings, OPEN_SANS),
+            text_processor: TextBuffer::new(&display, settings),
+            lighting_buffer: LightingBuffer::new(&display),
             view_details: view_details::ViewDetails::TwoDim(view_details::ViewDetails2D::default()),
             display_settings: settings,
             texture_array: texture::texture2d_array::Texture2dArray::empty(&display, 1024, 1024, 1).unwrap(),
-            hidden_cursor: false,
+            cursor_settings: CursorSettings::default(),
+            applied_cursor: None,
         };
 
         output
@@ -67,6 +71,7 @@ impl<'a> GliumRenderer<'a> {
         let context = Self::build_context(settings);
 
         self.display.rebuild(window, context, &self.events_loop).unwrap();
+        self.applied_cursor = None;
     }
 
     fn reset_buffers(&mut self) {
@@ -75,7 +80,8 @@ impl<'a> GliumRenderer<'a> {
         self.texture_rect_buffer = BasicBuffer::<TextureRect>::new(display);
         self.circ_buffer = BasicBuffer::<CirclePart>::new(display);
         self.polygon_buffer = BasicBuffer::<Polygon>::new(display);
-        self.text_processor = TextBuffer::new(display, self.display_settings, OPEN_SANS);
+        self.text_processor = TextBuffer::new(display, self.display_settings);
+        self.lighting_buffer = LightingBuffer::new(display);
     }
 
     fn build_display_and_events_loop(settings: DisplaySettings) -> (Display, EventsLoop) {
@@ -104,12 +110,27 @@ impl<'a> GliumRenderer<'a> {
         glium::glutin::ContextBuilder::new().with_multisampling(settings.multisample_level)
     }
 
+    /// The window only changes the cursor when the settings do
+    fn apply_cursor_settings(&mut self) {
+        let cursor = (self.cursor_settings.shows_system_cursor(), self.cursor_settings.grabbed);
+        if self.applied_cursor == Some(cursor) {
+            return;
+        }
+        let window = self.display.gl_window();
+        window.hide_cursor(!cursor.0);
+        if let Err(error) = window.grab_cursor(cursor.1) {
+            log_warn(LogTarget::Rendering, &format!("Could not grab the cursor: {}", error));
+        }
+        self.applied_cursor = Some(cursor);
+    }
+
     fn flush_buffers(&mut self) {
         self.rect_buffer.flush_buffer();
         self.texture_rect_buffer.flush_buffer();
         self.circ_buffer.flush_buffer();
         self.polygon_buffer.flush_buffer();
         self.text_processor.flush_buffer();
+        self.lighting_buffer.flush_buffer();
     }
     
     pub fn create_worldview_mat(view_details: view_details::ViewDetails, aspect_ratio: f64) ->  [[f32; 4]; 4] {
@@ -161,7 +182,9 @@ impl<'a> Renderer for GliumRenderer<'a> {
                         StandardPrimitive::TextureRect(rect) => self.texture_rect_buffer.load_renderable(rect),
                         StandardPrimitive::Circ(circle) => self.circ_buffer.load_renderable(circle),
                         StandardPrimitive::Text(text) => self.text_processor.load_renderable(text),
-                        StandardPrimitive::Poly(polygon) => self.polygon_buffer.load_renderable(polygon)
+                        StandardPrimitive::Poly(polygon) => self.polygon_buffer.load_renderable(polygon),
+                        StandardPrimitive::Light(light) => self.lighting_buffer.load_renderable(light),
+                        StandardPrimitive::AmbientLight(color) => self.lighting_buffer.set_ambient(color)
                 }
             }
         }
@@ -176,13 +199,14 @@ impl<'a> Renderer for GliumRenderer<'a> {
 
         let (width, height) = target.get_dimensions();
         let aspect_ratio = width as f64 / height as f64;
+        let world_view = GliumRenderer::create_worldview_mat(self.view_details, aspect_ratio);
         
         {
             let uniforms = uniform! {
                 screen_width: width,
                 screen_height: height,
                 aspect_ratio: aspect_ratio as f32,
-                world_view: GliumRenderer::create_worldview_mat(self.view_details, aspect_ratio),
+                world_view: world_view,
                 tex: &self.texture_array
             };
             
@@ -190,6 +214,8 @@ impl<'a> Renderer for GliumRenderer<'a> {
             self.texture_rect_buffer.draw_at_target(&mut target, &self.display, self.view_details, &self.draw_params, &uniforms);
             self.circ_buffer.draw_at_target(&mut target, &self.display, self.view_details, &self.draw_params, &uniforms);
             self.polygon_buffer.draw_at_target(&mut target, &self.display, self.view_details, &self.draw_params, &uniforms);        
+            // Text is drawn after lighting so it stays readable in the dark
+            self.lighting_buffer.draw_at_target(&mut target, &self.display, world_view);
             self.text_processor.draw_at_target(&mut target, &self.display, self.view_details, &self.draw_params, &uniforms);
             
             target.finish().unwrap();
@@ -197,10 +223,7 @@ impl<'a> Renderer for GliumRenderer<'a> {
         
         self.flush_buffers();
 
-        if !self.hidden_cursor {
-            self.display.gl_window().hide_cursor(true);
-            self.hidden_cursor = true;
-        }
+        self.apply_cursor_settings();
 
         debug_clock_stop("Render::glium_render");
     }
@@ -217,7 +240,8 @@ impl<'a> Renderer for GliumRenderer<'a> {
         let inner_size = self.display.gl_window().get_inner_size().unwrap();
 
         super::WindowSpec {
-            aspect_ratio: inner_size.width as f64 / inner_size.height as f64
+            aspect_ratio: inner_size.width as f64 / inner_size.height as f64,
+            size: (inner_size.width, inner_size.height)
         }
     }
 
@@ -227,5 +251,11 @@ impl<'a> Renderer for GliumRenderer<'a> {
         let context = Self::build_context(settings);
 
         self.display.rebuild(window, context, &self.events_loop).unwrap();
+        // The new window starts with the system cursor shown and free
+        self.applied_cursor = None;
+    }
+
+    fn set_cursor_settings(&mut self, cursor_settings: CursorSettings) {
+        self.cursor_settings = cursor_settings;
     }
 }
@@ -0,0 +1,116 @@
+use super::shaders::make_program_from_shaders;
+use crate::rendering::*;
+use crate::rendering::primitives::light_polygon::{LightVertex, LightCompositeVertex, get_composite_shaders};
+use glium;
+use glium::{Display, Surface, DrawParameters, Blend, BlendingFunction, LinearBlendingFactor, Program, Frame};
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::texture::Texture2d;
+
+/// Accumulates lights into an off-screen light map which then multiplies the scene
+/// Does nothing on frames where no lights (ambient or otherwise) were loaded
+pub struct LightingBuffer {
+    vertices: Vec<LightVertex>,
+    ambient: Option<Color>,
+    light_program: Program,
+    composite_program: Program,
+    light_map: Option<Texture2d>,
+}
+
+impl LightingBuffer {
+    pub fn new(display: &Display) -> Self {
+        Self {
+            vertices: Vec::new(),
+            ambient: None,
+            light_program: make_program_from_shaders(LightPolygon::get_shaders(), display),
+            composite_program: make_program_from_shaders(get_composite_shaders(), display),
+            light_map: None,
+        }
+    }
+
+    pub fn load_renderable(&mut self, light: LightPolygon) {
+        self.vertices.append(&mut light.get_vertex());
+    }
+
+    pub fn set_ambient(&mut self, color: Color) {
+        self.ambient = Some(color);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.ambient.is_some() || !self.vertices.is_empty()
+    }
+
+    pub fn draw_at_target(&mut self, target: &mut Frame, display: &Display, world_view: [[f32; 4]; 4]) {
+        if !self.is_active() {
+            return;
+        }
+
+        let dimensions = target.get_dimensions();
+        let light_map_outdated = match self.light_map {
+            Some(ref light_map) => light_map.dimensions() != dimensions,
+            None => true
+        };
+        if light_map_outdated {
+            self.light_map = Some(Texture2d::empty(display, dimensions.0, dimensions.1).unwrap());
+        }
+        let light_map = self.light_map.as_ref().unwrap();
+
+        {
+            let mut light_surface = SimpleFrameBuffer::new(display, light_map).unwrap();
+            let ambient = self.ambient.unwrap_or(Color::new(0.0, 0.0, 0.0, 1.0));
+            light_surface.clear_color(ambient.r as f32, ambient.g as f32, ambient.b as f32, 1.0);
+
+            if !self.vertices.is_empty() {
+                let additive = DrawParameters {
+                    blend: Blend {
+                        color: BlendingFunction::Addition {
+                            source: LinearBlendingFactor::One,
+                            destination: LinearBlendingFactor::One
+                        },
+                        alpha: BlendingFunction::AlwaysReplace,
+                        constant_value: (0.0, 0.0, 0.0, 0.0)
+                    },
+                    ..Default::default()
+                };
+                let vertex_buffer = glium::VertexBuffer::new(display, &self.vertices).unwrap();
+                light_surface.draw(&vertex_buffer,
+                            glium::index::NoIndices(LightPolygon::get_primitive_type()),
+                            &self.light_program,
+                            &uniform! { world_view: world_view },
+                            &additive).unwrap();
+            }
+        }
+
+        // Scene colour is multiplied by the light map: dst = dst * light
+        let multiply = DrawParameters {
+            blend: Blend {
+                color: BlendingFunction::Addition {
+                    source: LinearBlendingFactor::DestinationColor,
+                    destination: LinearBlendingFactor::Zero
+                },
+                alpha: BlendingFunction::Addition {
+                    source: LinearBlendingFactor::Zero,
+                    destination: LinearBlendingFactor::One
+                },
+                constant_value: (0.0, 0.0, 0.0, 0.0)
+            },
+            ..Default::default()
+        };
+        let quad = vec![
+            LightCompositeVertex { screen_pos: [-1.0, -1.0] },
+            LightCompositeVertex { screen_pos: [1.0, -1.0] },
+            LightCompositeVertex { screen_pos: [-1.0, 1.0] },
+            LightCompositeVertex { screen_pos: [1.0, 1.0] },
+        ];
+        let quad_buffer = glium::VertexBuffer::new(display, &quad).unwrap();
+        target.draw(&quad_buffer,
+                    glium::index::NoIndices(glium::index::PrimitiveType::TriangleStrip),
+                    &self.composite_program,
+                    &uniform! { light_map: light_map },
+                    &multiply).unwrap();
+    }
+
+    pub fn flush_buffer(&mut self) {
+        self.vertices = Vec::new();
+        self.ambient = None;
+    }
+}
@@ -6,6 +6,8 @@ pub mod render_by_shaders;
 pub mod glium_buffer;
 pub mod display_settings;
 pub mod color;
+pub mod lighting_buffer;
+pub mod cursor_settings;
 
 use glium::Display;
 
@@ -17,10 +19,13 @@ pub use self::color::Color;
 pub use self::shaders::*;
 pub use self::render_by_shaders::*;
 pub use self::glium_buffer::*;
+pub use self::lighting_buffer::LightingBuffer;
+pub use self::cursor_settings::{CursorSettings, CursorSprite, CURSOR_SPRITE_DEPTH};
 pub use self::renderables::text::*;
 
 use crate::games::view_details;
 use glium::glutin::EventsLoop;
+use std::marker::PhantomData;
 
 pub trait Renderer {
     type Primitive;
@@ -31,9 +36,34 @@ pub trait Renderer {
     fn get_events_loop(&mut self) -> Option<&mut EventsLoop> { None }
     fn get_window_spec(&self) -> WindowSpec { WindowSpec::default() }
     fn reset(&mut self, _display_settings: DisplaySettings) {}
+    fn set_cursor_settings(&mut self, _: CursorSettings) {}
+}
+
+/// Draws nothing, for running games headless (e.g. replaying input in tests)
+#[allow(dead_code)]
+pub struct RendererStub<Prim> {
+    primitive: PhantomData<Prim>
+}
+
+impl<Prim> RendererStub<Prim> {
+    pub fn new() -> Self {
+        RendererStub { primitive: PhantomData }
+    }
+}
+
+impl<Prim> Default for RendererStub<Prim> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Prim> Renderer for RendererStub<Prim> {
+    type Primitive = Prim;
 }
 
 #[derive(Copy, Clone, Debug, Default)]
 pub struct WindowSpec {
-    pub aspect_ratio: f64
+    pub aspect_ratio: f64,
+    /// The inner size in logical pixels
+    pub size: (f64, f64)
 }
\ No newline at end of file
@@ -0,0 +1,24 @@
+#version 410 core
+
+in vec2 world_pos_vs;
+in vec2 center_vs;
+in float radius_vs;
+in vec4 color_vs;
+in vec2 direction_vs;
+in vec2 cone_vs;
+
+out vec4 value;
+
+void main()
+{
+  vec2 offset = world_pos_vs - center_vs;
+  float dist = length(offset);
+  float attenuation = clamp(1.0 - dist / radius_vs, 0.0, 1.0);
+  attenuation = attenuation * attenuation;
+
+  // Point lights use a cone wider than a full turn so this is always 1
+  float cos_angle = dist > 0.0 ? dot(offset / dist, direction_vs) : 1.0;
+  float spot = smoothstep(cone_vs.x, cone_vs.y, cos_angle);
+
+  value = vec4(color_vs.rgb * color_vs.a * attenuation * spot, 1.0);
+}
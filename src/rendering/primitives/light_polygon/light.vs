@@ -0,0 +1,27 @@
+#version 410 core
+uniform mat4 world_view;
+
+in vec2 pos;
+in vec2 center;
+in float radius;
+in vec4 color;
+in vec2 direction;
+in vec2 cone;
+
+out vec2 world_pos_vs;
+out vec2 center_vs;
+out float radius_vs;
+out vec4 color_vs;
+out vec2 direction_vs;
+out vec2 cone_vs;
+
+void main()
+{
+  gl_Position = world_view * vec4(pos, 0.0, 1.0);
+  world_pos_vs = pos;
+  center_vs = center;
+  radius_vs = radius;
+  color_vs = color;
+  direction_vs = direction;
+  cone_vs = cone;
+}
@@ -0,0 +1,12 @@
+#version 410 core
+
+uniform sampler2D light_map;
+
+in vec2 tex_coords;
+
+out vec4 value;
+
+void main()
+{
+  value = vec4(texture(light_map, tex_coords).rgb, 1.0);
+}
@@ -0,0 +1,11 @@
+#version 410 core
+
+in vec2 screen_pos;
+
+out vec2 tex_coords;
+
+void main()
+{
+  gl_Position = vec4(screen_pos, 0.0, 1.0);
+  tex_coords = (screen_pos + vec2(1.0)) / 2.0;
+}
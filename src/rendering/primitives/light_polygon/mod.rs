@@ -0,0 +1,78 @@
+use crate::rendering::*;
+use crate::geometry::*;
+use glium;
+
+/// The area lit by one light, as a fan around the point rays were cast from
+/// Falloff and the spot cone are measured from center, which may differ from
+/// the fan origin when soft shadows jitter the casting point
+#[derive(Clone, Debug)]
+pub struct LightPolygon {
+    pub fan_origin: Point,
+    pub polygon: Vec<Point>,
+    pub center: Point,
+    pub radius: f64,
+    pub color: Color,
+    pub direction: Point,
+    pub cone: Point, // cosines of the outer and inner cone angles
+}
+
+impl GliumStandardPrimitive for LightPolygon {
+    type Vertex = LightVertex;
+
+    fn get_shaders() -> Shaders {
+        Shaders::VertexFragment(
+            include_str!("light.vs"),
+            include_str!("light.fs"))
+    }
+
+    fn get_primitive_type() -> glium::index::PrimitiveType {
+        glium::index::PrimitiveType::TrianglesList
+    }
+
+    fn get_vertex(self) -> Vec<Self::Vertex> {
+        let corner_count = self.polygon.len();
+        let mut vertices = Vec::with_capacity(3 * corner_count);
+
+        for index in 0..corner_count {
+            let next_index = (index + 1) % corner_count;
+            for &point in [self.fan_origin, self.polygon[index], self.polygon[next_index]].iter() {
+                vertices.push(LightVertex {
+                    pos: point.into(),
+                    center: self.center.into(),
+                    radius: self.radius as f32,
+                    color: self.color.get_array_f32(),
+                    direction: self.direction.into(),
+                    cone: self.cone.into()
+                });
+            }
+        }
+
+        vertices
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct LightVertex {
+    pub pos: [f32; 2],
+    pub center: [f32; 2],
+    pub radius: f32,
+    pub color: [f32; 4],
+    pub direction: [f32; 2],
+    pub cone: [f32; 2]
+}
+
+implement_vertex!(LightVertex, pos, center, radius, color, direction, cone);
+
+/// Vertices for the full screen quad the light map is multiplied onto the scene with
+#[derive(Copy, Clone, Debug)]
+pub struct LightCompositeVertex {
+    pub screen_pos: [f32; 2]
+}
+
+implement_vertex!(LightCompositeVertex, screen_pos);
+
+pub fn get_composite_shaders() -> Shaders {
+    Shaders::VertexFragment(
+        include_str!("light_composite.vs"),
+        include_str!("light_composite.fs"))
+}
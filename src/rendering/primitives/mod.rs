@@ -3,11 +3,14 @@ pub mod polygon;
 pub mod text;
 pub mod rectangle;
 pub mod texture_rect;
+pub mod light_polygon;
 pub use self::circle_part::CirclePart;
 pub use self::polygon::Polygon;
 pub use self::text::*;
 pub use self::rectangle::*;
 pub use self::texture_rect::TextureRect;
+pub use self::light_polygon::LightPolygon;
+use super::color::Color;
 use super::renderables::Renderable;
 
 #[derive(Clone)]
@@ -17,4 +20,6 @@ pub enum StandardPrimitive {
     Circ(CirclePart),
     Poly(Polygon),
     Text(PlainText),
+    Light(LightPolygon),
+    AmbientLight(Color),
 }
\ No newline at end of file
@@ -0,0 +1,113 @@
+use rusttype::{Font, FontCollection};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use super::text_buffer::OPEN_SANS;
+
+pub type FontId = usize;
+
+/// Always available, Open Sans unless another font is registered as "OpenSans"
+pub const DEFAULT_FONT: FontId = 0;
+
+lazy_static! {
+    pub static ref FONT_REGISTRY: Mutex<FontRegistry> = Mutex::new(FontRegistry::new());
+}
+
+#[derive(Debug)]
+pub enum FontError {
+    Io(io::Error),
+    InvalidFont(String)
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontError::Io(err) => write!(f, "Could not read font file: {}", err),
+            FontError::InvalidFont(mes) => write!(f, "Could not parse font: {}", mes)
+        }
+    }
+}
+
+impl From<io::Error> for FontError {
+    fn from(err: io::Error) -> Self {
+        FontError::Io(err)
+    }
+}
+
+/// Fonts games can refer to by id (or look up by name) in text renderables
+pub struct FontRegistry {
+    fonts: Vec<Font<'static>>,
+    names: HashMap<String, FontId>,
+    /// Goes up whenever a font is replaced, so glyphs cached from the old one can be dropped
+    revision: usize
+}
+
+impl FontRegistry {
+    pub fn new() -> Self {
+        let mut registry = Self {
+            fonts: Vec::new(),
+            names: HashMap::new(),
+            revision: 0
+        };
+        registry.add_font_bytes("OpenSans", OPEN_SANS.to_vec()).unwrap();
+        registry
+    }
+
+    /// Re-registering a name replaces the font but keeps its id
+    pub fn add_font_bytes(&mut self, name: &str, bytes: Vec<u8>) -> Result<FontId, FontError> {
+        let font = FontCollection::from_bytes(bytes)
+            .and_then(|collection| collection.into_font())
+            .map_err(|err| FontError::InvalidFont(err.to_string()))?;
+
+        if let Some(&id) = self.names.get(name) {
+            self.fonts[id] = font;
+            self.revision += 1;
+            return Ok(id);
+        }
+
+        self.fonts.push(font);
+        let id = self.fonts.len() - 1;
+        self.names.insert(name.to_string(), id);
+        Ok(id)
+    }
+
+    /// Loads a TTF/OTF file from disk
+    pub fn load_font_file<P: AsRef<Path>>(&mut self, name: &str, path: P) -> Result<FontId, FontError> {
+        let bytes = fs::read(path)?;
+        self.add_font_bytes(name, bytes)
+    }
+
+    pub fn get_revision(&self) -> usize {
+        self.revision
+    }
+
+    pub fn get_font_id(&self, name: &str) -> Option<FontId> {
+        self.names.get(name).cloned()
+    }
+
+    /// Unknown ids fall back to the default font
+    pub fn get_font(&self, id: FontId) -> &Font<'static> {
+        self.fonts.get(id).unwrap_or(&self.fonts[DEFAULT_FONT])
+    }
+}
+
+impl Default for FontRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn register_font(name: &str, bytes: Vec<u8>) -> Result<FontId, FontError> {
+    FONT_REGISTRY.lock().unwrap().add_font_bytes(name, bytes)
+}
+
+pub fn load_font_file<P: AsRef<Path>>(name: &str, path: P) -> Result<FontId, FontError> {
+    FONT_REGISTRY.lock().unwrap().load_font_file(name, path)
+}
+
+pub fn get_font_id(name: &str) -> Option<FontId> {
+    FONT_REGISTRY.lock().unwrap().get_font_id(name)
+}
@@ -1,7 +1,13 @@
 pub mod text_buffer;
+pub mod font_registry;
+pub mod text_layout;
+pub mod text_metrics;
 mod plain_text;
 pub use self::text_buffer::TextBuffer;
 pub use self::plain_text::{PlainText, TextAlign};
+pub use self::font_registry::{FontId, FontRegistry, FontError, DEFAULT_FONT, register_font, load_font_file, get_font_id};
+pub use self::text_layout::{TextStyle, StyledSpan, LayoutRun, LayoutLine, layout_spans, layout_dimensions, measure_text_width};
+pub use self::text_metrics::{GlyphLayout, LineLayout, TextLayout};
 
 use unicode_normalization;
 use rusttype::{FontCollection, Font, Scale, point, vector, PositionedGlyph};
@@ -25,6 +31,8 @@ pub trait RenderText {
     ) -> Vec<Self::TextVert>;
 
     fn get_content(&self) -> &String;
+
+    fn get_font(&self) -> FontId { DEFAULT_FONT }
 }
 
 impl<T: RenderText> GliumStandardPrimitive for T {
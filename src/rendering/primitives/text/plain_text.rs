@@ -8,6 +8,8 @@ use glium::Surface;
 use std::borrow::Cow;
 use crate::games::view_details;
 use super::RenderText;
+use super::font_registry::{FontId, DEFAULT_FONT};
+use super::text_metrics::TextLayout;
 use crate::rendering::*;
 use crate::geometry::*;
 
@@ -19,7 +21,8 @@ pub struct PlainText {
     pub transform: [[f64; 2]; 2], //Applied Second
     pub color: Color,
     pub fixed: bool,
-    pub align: TextAlign
+    pub align: TextAlign,
+    pub font: FontId
 }
 
 impl Default for PlainText {
@@ -31,7 +34,8 @@ impl Default for PlainText {
             transform: [[1.0, 0.0], [0.0, 1.0]], //Applied Second
             color: Color::white(),
             fixed: true,
-            align: TextAlign::Centered
+            align: TextAlign::Centered,
+            font: DEFAULT_FONT
         }
     }
 }
@@ -44,6 +48,9 @@ impl RenderText for PlainText {
         glyph_pos_data: Vec<(Rect<f32>, Rect<i32>)>
     ) -> Vec<Self::TextVert>
     {
+        if glyph_pos_data.is_empty() {
+            return Vec::new();
+        }
         let color = [self.color.r as f32,
                      self.color.g as f32,
                      self.color.b as f32,
@@ -73,6 +80,7 @@ impl RenderText for PlainText {
                                       screen_rect_pos[1]],
                 TextAlign::BaseLine => [screen_rect_pos[0] - average_glyph_pos[0],
                                       screen_rect_pos[1]],
+                TextAlign::Origin => screen_rect_pos,
             };
             
             TextVertex {
@@ -100,6 +108,8 @@ impl RenderText for PlainText {
     }
 
     fn get_content(&self) -> &String {&self.content}
+
+    fn get_font(&self) -> FontId {self.font}
 }
 
 impl PlainText {
@@ -114,10 +124,16 @@ impl PlainText {
             transform: [[1.0, 0.0], [0.0, 1.0]],
             color,
             fixed: true,
-            align
+            align,
+            font: DEFAULT_FONT
         }
     }
 
+    /// Glyph positions and bounds relative to position, measured without a renderer
+    pub fn get_layout(&self) -> TextLayout {
+        TextLayout::from_plain_text(self)
+    }
+
     pub fn get_number_of_lines(&self) -> usize {
         self.content.chars().fold(1, |mut acc, char| {if char == '\r' {acc += 1; } acc })
     }
@@ -131,7 +147,9 @@ impl PlainText {
 pub enum TextAlign {
     Centered,
     BaseLine,
-    LeftBaseLine
+    LeftBaseLine,
+    /// Position is where the caret starts on the baseline, used by laid out text
+    Origin
 }
 
 #[derive(Copy, Clone, Debug)]
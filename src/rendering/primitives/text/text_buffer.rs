@@ -1,5 +1,5 @@
 use unicode_normalization;
-use rusttype::{FontCollection, Font, Scale, point, vector, PositionedGlyph};
+use rusttype::{Scale, point, vector, PositionedGlyph};
 use rusttype::gpu_cache::Cache;
 use rusttype;
 use rusttype::Rect;
@@ -7,6 +7,8 @@ use glium;
 use glium::{Surface, Display, Frame, DrawParameters, Depth, DepthTest};
 use std::borrow::Cow;
 use super::{RenderText};
+use super::font_registry::{DEFAULT_FONT, FONT_REGISTRY};
+use super::text_layout::layout_paragraph;
 use crate::rendering::*;
 use crate::games::view_details;
 use std::sync::Mutex;
@@ -19,12 +21,14 @@ pub struct TextBuffer<'a, T: RenderText> {
     text_cache: rusttype::gpu_cache::Cache<'a>,
     program: glium::Program,
     cache_tex: glium::texture::Texture2d,
-    font: Font<'a>,
+    /// The FONT_REGISTRY revision the cached glyphs were drawn from
+    font_revision: usize,
     glyph_scale: f32
 }
 
 impl<'a, T: RenderText> TextBuffer<'a, T> {
-    pub fn new(display: &Display, settings: DisplaySettings, font_bytes: &'a [u8]) -> Self {
+    /// Text is drawn with the fonts in FONT_REGISTRY
+    pub fn new(display: &Display, settings: DisplaySettings) -> Self {
         let dpi_factor = display.gl_window().get_hidpi_factor();
 
         let (cache_width, cache_height) = (10000 * dpi_factor as u32, 10000 * dpi_factor as u32);
@@ -47,7 +51,7 @@ impl<'a, T: RenderText> TextBuffer<'a, T> {
             text_cache: cache,
             cache_tex: cache_tex,
             program: shaders::make_program_from_shaders(T::get_shaders(), &display),
-            font: FontCollection::from_bytes(font_bytes).unwrap().into_font().unwrap(),
+            font_revision: FONT_REGISTRY.lock().unwrap().get_revision(),
             glyph_scale: settings.text_glyph_detail * dpi_factor as f32
         }
     }
@@ -95,7 +99,7 @@ impl<'a, T: RenderText> GliumBuffer<T> for TextBuffer<'a, T> {
             let aspect_ratio = width as f64 / height as f64;
 
             let cache_tex = &self.cache_tex;
-            let v_metrics = self.font.v_metrics(Scale::uniform(self.glyph_scale));
+            let v_metrics = FONT_REGISTRY.lock().unwrap().get_font(DEFAULT_FONT).v_metrics(Scale::uniform(self.glyph_scale));
             let uniforms = uniform! {
                 tex: cache_tex
                     .sampled()
@@ -118,12 +122,21 @@ impl<'a, T: RenderText> GliumBuffer<T> for TextBuffer<'a, T> {
         let glyph_scale = Scale::uniform(self.glyph_scale);
 
         debug_clock_start("Render::glium_load::text::layout_paragraph");
-        let glyphs = layout_paragraph(&self.font, glyph_scale, &text.get_content());
+        let font_id = text.get_font();
+        let font = {
+            let registry = FONT_REGISTRY.lock().unwrap();
+            if registry.get_revision() != self.font_revision {
+                self.text_cache.clear();
+                self.font_revision = registry.get_revision();
+            }
+            registry.get_font(font_id).clone()
+        };
+        let glyphs = layout_paragraph(&font, glyph_scale, text.get_content());
         debug_clock_stop("Render::glium_load::text::layout_paragraph");
 
         debug_clock_start("Render::glium_load::text::queue_glyph");
         for glyph in &glyphs {
-            self.text_cache.queue_glyph(0, glyph.clone());
+            self.text_cache.queue_glyph(font_id, glyph.clone());
         }
         debug_clock_stop("Render::glium_load::text::queue_glyph");
 
@@ -151,7 +164,7 @@ impl<'a, T: RenderText> GliumBuffer<T> for TextBuffer<'a, T> {
         let glyph_pos_data: Vec<(Rect<f32>, Rect<i32>)> = glyphs
             .iter()
             .filter_map(|g| {
-                if let Ok(Some(pos_data)) = text_cache.rect_for(0, g) {
+                if let Ok(Some(pos_data)) = text_cache.rect_for(font_id, g) {
                     Some(pos_data)
                 } else {
                     None
@@ -174,35 +187,3 @@ impl<'a, T: RenderText> GliumBuffer<T> for TextBuffer<'a, T> {
         self.vertices = Vec::new();
     }
 }
-
-fn layout_paragraph<'a>(font: &Font<'a>,
-                        scale: Scale,
-                        text: &str) -> Vec<PositionedGlyph<'a>> {
-    use unicode_normalization::UnicodeNormalization;
-    let mut result = Vec::new();
-    // let v_metrics = font.v_metrics(scale);
-    // let advance_height = v_metrics.ascent - v_metrics.descent;
-    let mut caret = point(0.0, 0.0);
-    let mut last_glyph_id = None;
-    for c in text.nfc() {
-        if c.is_control() {
-            // match c {
-            //     '\r' => {
-            //         caret = point(0.0, caret.y + advance_height);
-            //     }
-            //     '\n' => {},
-            //     _ => {}
-            // }
-            continue;
-        }
-        let base_glyph =  font.glyph(c);
-        if let Some(id) = last_glyph_id.take() {
-            caret.x += font.pair_kerning(scale, id, base_glyph.id());
-        }
-        last_glyph_id = Some(base_glyph.id());
-        let glyph = base_glyph.scaled(scale).positioned(caret);
-        caret.x += glyph.unpositioned().h_metrics().advance_width;
-        result.push(glyph);
-    }
-    result
-}
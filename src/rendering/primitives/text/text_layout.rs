@@ -0,0 +1,357 @@
+use rusttype::{Font, Scale, point, PositionedGlyph};
+use super::font_registry::*;
+use crate::rendering::Color;
+use crate::geometry::*;
+
+/// Glyph scale measurements are taken at, only ratios of it ever leave the text module
+pub(super) const MEASURE_SCALE: f32 = 100.0;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextStyle {
+    pub font: FontId,
+    pub size: f64,
+    pub color: Color
+}
+
+impl TextStyle {
+    pub fn new(font: FontId, size: f64, color: Color) -> Self {
+        Self {
+            font,
+            size,
+            color
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StyledSpan {
+    pub text: String,
+    pub style: TextStyle
+}
+
+impl StyledSpan {
+    pub fn new(text: String, style: TextStyle) -> Self {
+        Self {
+            text,
+            style
+        }
+    }
+}
+
+/// Piece of a line drawn with one style, offset is from the start of the line
+/// first_char indexes the chars of all the spans' text put together
+#[derive(Clone, Debug)]
+pub struct LayoutRun {
+    pub text: String,
+    pub style: TextStyle,
+    pub first_char: usize,
+    pub offset: f64,
+    pub width: f64
+}
+
+/// Lines are measured downwards from the top of the text block
+/// (baseline is the distance from that top to this line's baseline)
+#[derive(Clone, Debug)]
+pub struct LayoutLine {
+    pub runs: Vec<LayoutRun>,
+    pub first_char: usize,
+    pub width: f64,
+    pub ascent: f64,
+    pub descent: f64,
+    pub baseline: f64
+}
+
+/// Same layout the TextBuffer uses when drawing: nfc normalised, kerned, control chars skipped
+pub(crate) fn layout_paragraph<'a>(font: &Font<'a>,
+                                   scale: Scale,
+                                   text: &str) -> Vec<PositionedGlyph<'a>> {
+    use unicode_normalization::UnicodeNormalization;
+    let mut result = Vec::new();
+    let mut caret = point(0.0, 0.0);
+    let mut last_glyph_id = None;
+    for c in text.nfc() {
+        if c.is_control() {
+            continue;
+        }
+        let base_glyph = font.glyph(c);
+        if let Some(id) = last_glyph_id.take() {
+            caret.x += font.pair_kerning(scale, id, base_glyph.id());
+        }
+        last_glyph_id = Some(base_glyph.id());
+        let glyph = base_glyph.scaled(scale).positioned(caret);
+        caret.x += glyph.unpositioned().h_metrics().advance_width;
+        result.push(glyph);
+    }
+    result
+}
+
+/// Pixels the caret moves over when laying out text
+fn paragraph_advance(font: &Font<'_>, scale: Scale, text: &str) -> f32 {
+    match layout_paragraph(font, scale, text).last() {
+        Some(glyph) => glyph.position().x + glyph.unpositioned().h_metrics().advance_width,
+        None => 0.0
+    }
+}
+
+/// Text is drawn so that a size of 1.0 is one default font line high, this converts pixels to that
+pub(super) fn world_per_pixel(registry: &FontRegistry, size: f64) -> f64 {
+    let v_metrics = registry.get_font(DEFAULT_FONT).v_metrics(Scale::uniform(MEASURE_SCALE));
+    size / (v_metrics.ascent - v_metrics.descent) as f64
+}
+
+/// Width (in world units) of a single line of text
+pub fn measure_text_width(text: &str, style: &TextStyle) -> f64 {
+    let registry = FONT_REGISTRY.lock().unwrap();
+    measure_width(&registry, text, style)
+}
+
+fn measure_width(registry: &FontRegistry, text: &str, style: &TextStyle) -> f64 {
+    let font = registry.get_font(style.font);
+    paragraph_advance(font, Scale::uniform(MEASURE_SCALE), text) as f64 * world_per_pixel(registry, style.size)
+}
+
+/// Ascent and (positive) descent of a style in world units
+pub(super) fn measure_v_metrics(registry: &FontRegistry, style: &TextStyle) -> (f64, f64) {
+    let v_metrics = registry.get_font(style.font).v_metrics(Scale::uniform(MEASURE_SCALE));
+    let world_per_pixel = world_per_pixel(registry, style.size);
+    (v_metrics.ascent as f64 * world_per_pixel, -v_metrics.descent as f64 * world_per_pixel)
+}
+
+struct LayoutPiece {
+    span: StyledSpan,
+    first_char: usize
+}
+
+enum LayoutUnit {
+    Word(Vec<LayoutPiece>),
+    Space(Vec<LayoutPiece>),
+    Newline(TextStyle, usize)
+}
+
+/// Splits spans into words, whitespace and line breaks ('\n' and '\r' both break)
+/// A word can cross span boundaries so restyling part of a word doesn't let it wrap
+fn split_units(spans: &[StyledSpan]) -> Vec<LayoutUnit> {
+    let mut units = Vec::new();
+    let mut current: Option<LayoutUnit> = None;
+    let mut char_index = 0;
+
+    for span in spans {
+        for c in span.text.chars() {
+            char_index += 1;
+            if c == '\n' || c == '\r' {
+                units.extend(current.take());
+                units.push(LayoutUnit::Newline(span.style, char_index));
+                continue;
+            }
+
+            let is_space = c.is_whitespace();
+            let matches_current = match current {
+                Some(LayoutUnit::Word(_)) => !is_space,
+                Some(LayoutUnit::Space(_)) => is_space,
+                _ => false
+            };
+            if !matches_current {
+                units.extend(current.take());
+                current = Some(if is_space { LayoutUnit::Space(Vec::new()) } else { LayoutUnit::Word(Vec::new()) });
+            }
+
+            if let Some(LayoutUnit::Word(ref mut pieces)) | Some(LayoutUnit::Space(ref mut pieces)) = current {
+                match pieces.last_mut() {
+                    Some(ref mut piece) if piece.span.style == span.style => piece.span.text.push(c),
+                    _ => pieces.push(LayoutPiece {
+                        span: StyledSpan::new(c.to_string(), span.style),
+                        first_char: char_index - 1
+                    })
+                }
+            }
+        }
+    }
+    units.extend(current.take());
+
+    units
+}
+
+struct LineBuilder {
+    runs: Vec<LayoutRun>,
+    first_char: usize,
+    empty_style: TextStyle
+}
+
+impl LineBuilder {
+    fn new(first_char: usize, empty_style: TextStyle) -> Self {
+        Self {
+            runs: Vec::new(),
+            first_char,
+            empty_style
+        }
+    }
+
+    fn width(&self) -> f64 {
+        match self.runs.last() {
+            Some(run) => run.offset + run.width,
+            None => 0.0
+        }
+    }
+
+    fn push(&mut self, registry: &FontRegistry, piece: &LayoutPiece) {
+        let offset = self.width();
+        match self.runs.last_mut() {
+            Some(ref mut run) if run.style == piece.span.style => {
+                run.text.push_str(&piece.span.text);
+                run.width = measure_width(registry, &run.text, &run.style);
+            },
+            _ => self.runs.push(LayoutRun {
+                text: piece.span.text.clone(),
+                style: piece.span.style,
+                first_char: piece.first_char,
+                offset,
+                width: measure_width(registry, &piece.span.text, &piece.span.style)
+            })
+        }
+    }
+
+    fn finish(self, registry: &FontRegistry) -> LayoutLine {
+        let width = self.width();
+        let empty_style = self.empty_style;
+        let (ascent, descent) = self.runs.iter()
+            .map(|run| measure_v_metrics(registry, &run.style))
+            .fold(None, |acc: Option<(f64, f64)>, (ascent, descent)| match acc {
+                Some((max_ascent, max_descent)) => Some((max_ascent.max(ascent), max_descent.max(descent))),
+                None => Some((ascent, descent))
+            })
+            .unwrap_or_else(|| measure_v_metrics(registry, &empty_style));
+
+        LayoutLine {
+            runs: self.runs,
+            first_char: self.first_char,
+            width,
+            ascent,
+            descent,
+            baseline: 0.0
+        }
+    }
+}
+
+/// Lays the spans out into lines, greedily wrapping between words once a line would pass max_width
+/// Words wider than max_width get a line to themselves and overflow it
+/// line_spacing multiplies the distance between baselines
+pub fn layout_spans(spans: &[StyledSpan], max_width: Option<f64>, line_spacing: f64) -> Vec<LayoutLine> {
+    let registry = FONT_REGISTRY.lock().unwrap();
+    let default_style = match spans.first() {
+        Some(span) => span.style,
+        None => return Vec::new()
+    };
+
+    let mut lines = Vec::new();
+    let mut line = LineBuilder::new(0, default_style);
+    let mut pending_space: Vec<LayoutPiece> = Vec::new();
+
+    for unit in split_units(spans) {
+        match unit {
+            LayoutUnit::Newline(style, next_char) => {
+                lines.push(line.finish(&registry));
+                line = LineBuilder::new(next_char, style);
+                pending_space.clear();
+            },
+            LayoutUnit::Space(pieces) => pending_space.extend(pieces),
+            LayoutUnit::Word(pieces) => {
+                let space_width: f64 = pending_space.iter().map(|piece| measure_width(&registry, &piece.span.text, &piece.span.style)).sum();
+                let word_width: f64 = pieces.iter().map(|piece| measure_width(&registry, &piece.span.text, &piece.span.style)).sum();
+                let overflows = match max_width {
+                    Some(max_width) => !line.runs.is_empty() && line.width() + space_width + word_width > max_width,
+                    None => false
+                };
+
+                if overflows {
+                    let empty_style = pieces[0].span.style;
+                    lines.push(line.finish(&registry));
+                    line = LineBuilder::new(pieces[0].first_char, empty_style);
+                } else {
+                    for piece in pending_space.iter() {
+                        line.push(&registry, piece);
+                    }
+                }
+                pending_space.clear();
+
+                for piece in pieces.iter() {
+                    line.push(&registry, piece);
+                }
+            }
+        }
+    }
+    lines.push(line.finish(&registry));
+
+    let mut baseline = 0.0;
+    let mut previous_descent: Option<f64> = None;
+    for line in lines.iter_mut() {
+        baseline += match previous_descent {
+            Some(descent) => (descent + line.ascent) * line_spacing,
+            None => line.ascent
+        };
+        line.baseline = baseline;
+        previous_descent = Some(line.descent);
+    }
+
+    lines
+}
+
+/// Width and height of laid out lines
+pub fn layout_dimensions(lines: &[LayoutLine]) -> Point {
+    let width = lines.iter().map(|line| line.width).fold(0.0, f64::max);
+    let height = match lines.last() {
+        Some(line) => line.baseline + line.descent,
+        None => 0.0
+    };
+    Point::new(width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn style(size: f64) -> TextStyle {
+        TextStyle::new(DEFAULT_FONT, size, Color::white())
+    }
+
+    #[test]
+    fn width_scales_with_size() {
+        let small = measure_text_width("Hello", &style(1.0));
+        let large = measure_text_width("Hello", &style(2.0));
+        assert!(small > 0.0);
+        assert!((large - 2.0 * small).abs() < 1e-6);
+    }
+
+    #[test]
+    fn wraps_between_words() {
+        let text = "one two three four";
+        let full_width = measure_text_width(text, &style(1.0));
+        let spans = vec![StyledSpan::new(text.to_string(), style(1.0))];
+
+        let lines = layout_spans(&spans, Some(full_width * 0.6), 1.0);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].runs[0].text, "one two");
+        assert_eq!(lines[1].runs[0].text, "three four");
+        assert!(lines[1].baseline > lines[0].baseline);
+
+        let unwrapped = layout_spans(&spans, None, 1.0);
+        assert_eq!(unwrapped.len(), 1);
+        assert!((unwrapped[0].width - full_width).abs() < 1e-6);
+    }
+
+    #[test]
+    fn styled_word_stays_together() {
+        let red = TextStyle::new(DEFAULT_FONT, 1.0, Color::new(1.0, 0.0, 0.0, 1.0));
+        let spans = vec![
+            StyledSpan::new("aa bb".to_string(), style(1.0)),
+            StyledSpan::new("cc".to_string(), red)
+        ];
+        let narrow = measure_text_width("aa bb", &style(1.0)) * 0.9;
+
+        let lines = layout_spans(&spans, Some(narrow), 1.0);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].runs.len(), 2);
+        assert_eq!(lines[1].runs[0].text, "bb");
+        assert_eq!(lines[1].runs[1].text, "cc");
+        assert!((lines[1].runs[1].offset - lines[1].runs[0].width).abs() < 1e-9);
+    }
+}
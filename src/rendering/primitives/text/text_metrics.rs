@@ -0,0 +1,307 @@
+use std::cmp::Ordering;
+use rusttype::Scale;
+use super::font_registry::*;
+use super::text_layout::*;
+use super::plain_text::{PlainText, TextAlign};
+use crate::geometry::*;
+
+/// Where one character ends up once laid out, in world units relative to the text's position (y up)
+/// caret is the glyph's origin on the baseline, bounds are the drawn pixels (None for whitespace)
+#[derive(Copy, Clone, Debug)]
+pub struct GlyphLayout {
+    pub character: char,
+    pub char_index: usize,
+    pub line: usize,
+    pub caret: Point,
+    pub advance: f64,
+    pub bounds: Option<Rectangle>
+}
+
+/// Characters [first_char, end_char) are drawn on this line, starting at left
+#[derive(Copy, Clone, Debug)]
+pub struct LineLayout {
+    pub first_char: usize,
+    pub end_char: usize,
+    pub left: f64,
+    pub width: f64,
+    pub baseline: f64,
+    pub ascent: f64,
+    pub descent: f64
+}
+
+/// Measured text, usable for placing text, hit testing and carets without a renderer
+/// Character indices count chars of the displayed text (markup tags aren't counted)
+#[derive(Clone, Debug, Default)]
+pub struct TextLayout {
+    pub glyphs: Vec<GlyphLayout>,
+    pub lines: Vec<LineLayout>
+}
+
+impl TextLayout {
+    /// Single line of text in one style, caret starting at the origin
+    pub fn from_text(text: &str, style: &TextStyle) -> Self {
+        let registry = FONT_REGISTRY.lock().unwrap();
+        let glyphs = layout_run_glyphs(&registry, text, style, 0, 0, Point::zero());
+        let (ascent, descent) = measure_v_metrics(&registry, style);
+
+        let mut layout = Self {
+            glyphs,
+            lines: Vec::new()
+        };
+        let line = layout.build_line(0, 0, 0.0, 0.0, ascent, descent);
+        layout.lines.push(line);
+        layout
+    }
+
+    /// Laid out lines, shifting each line right by line_shift (e.g. for alignment)
+    /// The top of the first line is at y = 0
+    pub fn from_lines<F: Fn(&LayoutLine) -> f64>(lines: &[LayoutLine], line_shift: F) -> Self {
+        let registry = FONT_REGISTRY.lock().unwrap();
+        let mut layout = Self::default();
+
+        for (line_index, line) in lines.iter().enumerate() {
+            let left = line_shift(line);
+            for run in line.runs.iter() {
+                let run_origin = Point::new(left + run.offset, -line.baseline);
+                layout.glyphs.extend(layout_run_glyphs(&registry, &run.text, &run.style, run.first_char, line_index, run_origin));
+            }
+            let line_layout = layout.build_line(line_index, line.first_char, left, -line.baseline, line.ascent, line.descent);
+            layout.lines.push(line_layout);
+        }
+
+        layout
+    }
+
+    /// Matches what the TextBuffer draws for the text before its transform is applied
+    /// (control characters are skipped, so the text is always one line)
+    pub fn from_plain_text(text: &PlainText) -> Self {
+        let style = TextStyle::new(text.font, 1.0, text.color);
+        let mut layout = Self::from_text(&text.content, &style);
+
+        let visible: Vec<Rectangle> = layout.glyphs.iter().filter_map(|glyph| glyph.bounds).collect();
+        let shift = match (visible.first(), visible.last()) {
+            (Some(first), Some(last)) => {
+                let average_center = 0.5 * (first.pos + last.pos);
+                match text.align {
+                    TextAlign::Centered => -average_center,
+                    TextAlign::BaseLine => Point::new(-average_center.x, 0.0),
+                    TextAlign::LeftBaseLine => Point::new(-(first.pos.x - first.length / 2.0), 0.0),
+                    TextAlign::Origin => Point::zero()
+                }
+            },
+            _ => Point::zero()
+        };
+
+        let scale = text.scale;
+        let scale_point = |pos: Point| Point::new((pos.x + shift.x) * scale.x, (pos.y + shift.y) * scale.y);
+        for glyph in layout.glyphs.iter_mut() {
+            glyph.caret = scale_point(glyph.caret);
+            glyph.advance *= scale.x;
+            glyph.bounds = glyph.bounds.map(|bounds| Rectangle::new_regular(
+                bounds.length * scale.x.abs(),
+                bounds.height * scale.y.abs(),
+                scale_point(bounds.pos)));
+        }
+        for line in layout.lines.iter_mut() {
+            let origin = scale_point(Point::new(line.left, line.baseline));
+            line.left = origin.x;
+            line.baseline = origin.y;
+            line.width *= scale.x;
+            line.ascent *= scale.y;
+            line.descent *= scale.y;
+        }
+
+        layout
+    }
+
+    fn build_line(&self, line_index: usize, first_char: usize, left: f64, baseline: f64, ascent: f64, descent: f64) -> LineLayout {
+        let line_glyphs: Vec<&GlyphLayout> = self.glyphs.iter().filter(|glyph| glyph.line == line_index).collect();
+        let (end_char, width) = match line_glyphs.last() {
+            Some(glyph) => (glyph.char_index + 1, glyph.caret.x + glyph.advance - left),
+            None => (first_char, 0.0)
+        };
+
+        LineLayout {
+            first_char,
+            end_char,
+            left,
+            width,
+            baseline,
+            ascent,
+            descent
+        }
+    }
+
+    /// Box around the drawn pixels of every glyph
+    pub fn get_ink_bounds(&self) -> Option<Rectangle> {
+        let mut bounds = self.glyphs.iter().filter_map(|glyph| glyph.bounds);
+        let first = bounds.next()?;
+        let (min, max) = bounds.fold(
+            (first.pos - 0.5 * Point::new(first.length, first.height), first.pos + 0.5 * Point::new(first.length, first.height)),
+            |(min, max), rect| {
+                let half_dim = 0.5 * Point::new(rect.length, rect.height);
+                (Point::new(min.x.min(rect.pos.x - half_dim.x), min.y.min(rect.pos.y - half_dim.y)),
+                 Point::new(max.x.max(rect.pos.x + half_dim.x), max.y.max(rect.pos.y + half_dim.y)))
+            });
+        Some(Rectangle::new_corner(max.x - min.x, max.y - min.y, min))
+    }
+
+    /// Box from the top of the first line to the bottom of the last, as wide as the widest line
+    /// This is the box to use when fitting text into panels
+    pub fn get_bounds(&self) -> Rectangle {
+        if self.lines.is_empty() {
+            return Rectangle::new_regular(0.0, 0.0, Point::zero());
+        }
+        let left = self.lines.iter().map(|line| line.left).fold(f64::INFINITY, f64::min);
+        let right = self.lines.iter().map(|line| line.left + line.width).fold(f64::NEG_INFINITY, f64::max);
+        let top = self.lines.iter().map(|line| line.baseline + line.ascent).fold(f64::NEG_INFINITY, f64::max);
+        let bottom = self.lines.iter().map(|line| line.baseline - line.descent).fold(f64::INFINITY, f64::min);
+
+        Rectangle::new_corner(right - left, top - bottom, Point::new(left, bottom))
+    }
+
+    pub fn get_char_count(&self) -> usize {
+        self.lines.iter().map(|line| line.end_char).max().unwrap_or(0)
+    }
+
+    /// Char indices where a new line starts
+    pub fn get_line_breaks(&self) -> Vec<usize> {
+        self.lines.iter().skip(1).map(|line| line.first_char).collect()
+    }
+
+    /// Line a char index is drawn on (indices past the end belong to the last line)
+    pub fn get_line_of_char(&self, char_index: usize) -> usize {
+        self.lines.iter()
+            .rposition(|line| line.first_char <= char_index)
+            .unwrap_or(0)
+    }
+
+    /// Point on the baseline a text cursor placed before char_index would sit at
+    pub fn get_caret_position(&self, char_index: usize) -> Point {
+        let line_index = self.get_line_of_char(char_index);
+        let line = match self.lines.get(line_index) {
+            Some(line) => line,
+            None => return Point::zero()
+        };
+
+        self.glyphs.iter()
+            .find(|glyph| glyph.line == line_index && glyph.char_index >= char_index)
+            .map(|glyph| glyph.caret)
+            .unwrap_or_else(|| Point::new(line.left + line.width, line.baseline))
+    }
+
+    /// Char index a caret should be placed at for a click at pos
+    pub fn get_char_index_at(&self, pos: Point) -> usize {
+        let line_distance = |line: &LineLayout| {
+            if pos.y > line.baseline + line.ascent {
+                pos.y - line.baseline - line.ascent
+            } else if pos.y < line.baseline - line.descent {
+                line.baseline - line.descent - pos.y
+            } else {
+                0.0
+            }
+        };
+        let line_index = match self.lines.iter()
+            .enumerate()
+            .min_by(|(_, line1), (_, line2)| line_distance(line1).partial_cmp(&line_distance(line2)).unwrap_or(Ordering::Equal)) {
+            Some((line_index, _)) => line_index,
+            None => return 0
+        };
+
+        self.glyphs.iter()
+            .filter(|glyph| glyph.line == line_index)
+            .find(|glyph| pos.x < glyph.caret.x + glyph.advance / 2.0)
+            .map(|glyph| glyph.char_index)
+            .unwrap_or(self.lines[line_index].end_char)
+    }
+}
+
+/// Per-char positions for a run of text with its caret starting at origin
+/// Follows the drawing layout (kerned, control chars skipped) but without nfc normalisation
+fn layout_run_glyphs(registry: &FontRegistry, text: &str, style: &TextStyle, first_char: usize, line: usize, origin: Point) -> Vec<GlyphLayout> {
+    let font = registry.get_font(style.font);
+    let scale = Scale::uniform(MEASURE_SCALE);
+    let world_per_pixel = world_per_pixel(registry, style.size);
+
+    let mut glyphs = Vec::new();
+    let mut caret = 0.0;
+    let mut last_glyph_id = None;
+    for (index, c) in text.chars().enumerate() {
+        if c.is_control() {
+            continue;
+        }
+        let base_glyph = font.glyph(c);
+        if let Some(id) = last_glyph_id.take() {
+            caret += font.pair_kerning(scale, id, base_glyph.id());
+        }
+        last_glyph_id = Some(base_glyph.id());
+        let glyph = base_glyph.scaled(scale);
+        let advance = glyph.h_metrics().advance_width;
+
+        // Pixel coordinates point down from the baseline
+        let bounds = glyph.exact_bounding_box().map(|rect| Rectangle::new_corner(
+            rect.width() as f64 * world_per_pixel,
+            rect.height() as f64 * world_per_pixel,
+            origin + world_per_pixel * Point::new((caret + rect.min.x) as f64, -rect.max.y as f64)));
+
+        glyphs.push(GlyphLayout {
+            character: c,
+            char_index: first_char + index,
+            line,
+            caret: origin + Point::new(caret as f64 * world_per_pixel, 0.0),
+            advance: advance as f64 * world_per_pixel,
+            bounds
+        });
+        caret += advance;
+    }
+
+    glyphs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::Color;
+
+    fn style() -> TextStyle {
+        TextStyle::new(DEFAULT_FONT, 1.0, Color::white())
+    }
+
+    #[test]
+    fn caret_walks_along_line() {
+        let layout = TextLayout::from_text("Hi there", &style());
+        assert_eq!(layout.get_char_count(), 8);
+        assert!(layout.get_caret_position(0).x.abs() < 1e-9);
+
+        let end = layout.get_caret_position(8);
+        assert!((end.x - measure_text_width("Hi there", &style())).abs() < 1e-6);
+        assert!(layout.get_caret_position(3).x > layout.get_caret_position(2).x);
+        assert_eq!(layout.get_char_index_at(layout.get_caret_position(5) + Point::new(0.01, 0.0)), 5);
+        assert_eq!(layout.get_char_index_at(Point::new(100.0, 0.0)), 8);
+        assert_eq!(layout.get_char_index_at(Point::new(0.0, f64::NAN)), 0);
+    }
+
+    #[test]
+    fn wrapped_lines_report_breaks() {
+        let spans = vec![StyledSpan::new("one two\nthree".to_string(), style())];
+        let lines = layout_spans(&spans, None, 1.0);
+        let layout = TextLayout::from_lines(&lines, |_| 0.0);
+
+        assert_eq!(layout.get_line_breaks(), vec![8]);
+        assert_eq!(layout.get_line_of_char(9), 1);
+        assert!(layout.get_caret_position(8).y < layout.get_caret_position(0).y);
+        assert!(layout.get_caret_position(8).x.abs() < 1e-9);
+
+        let bounds = layout.get_bounds();
+        assert!((bounds.pos.y + bounds.height / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn centered_plain_text_is_centered() {
+        let text = PlainText::new_simple_white("Menu".to_string(), 0.5, Point3::zero(), TextAlign::Centered);
+        let bounds = TextLayout::from_plain_text(&text).get_ink_bounds().unwrap();
+        assert!(bounds.pos.x.abs() < 0.05);
+        assert!(bounds.pos.y.abs() < 0.05);
+        assert!(bounds.height < 0.5);
+    }
+}
@@ -9,6 +9,8 @@ pub struct TextureRect {
     pub pos: Point3,
     pub texture_corner: Point3,
     pub texture_dim: Point,
+    /// Multiplied with the texture (white leaves it untouched)
+    pub color: Color,
     pub fixed: bool
 }
 
@@ -28,6 +30,7 @@ impl TextureRect {
             pos,
             texture_corner,
             texture_dim,
+            color: Color::white(),
             fixed
         }
     }
@@ -48,6 +51,7 @@ impl TextureRect {
             pos,
             texture_corner,
             texture_dim,
+            color: Color::white(),
             fixed
         }
     }
@@ -74,10 +78,11 @@ pub struct TextureRectVertex {
     pub pos: [f32; 3],
     pub texture_corner: [f32; 3],
     pub texture_dim: [f32; 2],
+    pub color: [f32; 4],
     pub fixed_pos: u32
 }
 
-implement_vertex!(TextureRectVertex, length, height, rot, pos, texture_corner, texture_dim, fixed_pos);
+implement_vertex!(TextureRectVertex, length, height, rot, pos, texture_corner, texture_dim, color, fixed_pos);
 
 impl From<TextureRect> for TextureRectVertex {
     fn from(rect: TextureRect) -> Self {
@@ -88,6 +93,7 @@ impl From<TextureRect> for TextureRectVertex {
             pos: rect.pos.into(),
             texture_corner: rect.texture_corner.into(),
             texture_dim: rect.texture_dim.into(),
+            color: rect.color.get_array_f32(),
             fixed_pos: rect.fixed as u32
         }
     }
@@ -3,12 +3,13 @@
 uniform sampler2DArray  tex;
 
 in vec3 texture_corner_ges;
+in vec4 color_ges;
 
 out vec4 value;
 
 void main()
 {
-  vec4 texture_value = texture(tex, texture_corner_ges);
+  vec4 texture_value = texture(tex, texture_corner_ges) * color_ges;
   
   if (texture_value.a < 0.5) { discard; }
 
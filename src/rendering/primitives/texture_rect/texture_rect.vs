@@ -6,6 +6,7 @@ in mat2 rot;
 in vec3 pos;
 in vec3 texture_corner;
 in vec2 texture_dim;
+in vec4 color;
 in uint fixed_pos;
 
 out float length_vs;
@@ -14,6 +15,7 @@ out mat2 rot_vs;
 out vec3 pos_vs;
 out vec3 texture_corner_vs;
 out vec2 texture_dim_vs;
+out vec4 color_vs;
 out uint fixed_vs;
 
 void main()
@@ -24,5 +26,6 @@ void main()
   pos_vs = pos;
   texture_corner_vs = texture_corner;
   texture_dim_vs = texture_dim;
+  color_vs = color;
   fixed_vs = fixed_pos;
 }
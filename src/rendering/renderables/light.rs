@@ -0,0 +1,115 @@
+use std::f64::consts;
+use crate::rendering::*;
+use crate::geometry::*;
+
+#[derive(Copy, Clone, Debug)]
+pub enum LightKind {
+    Point,
+    Spot(f64, f64), // Direction angle and half-width of the cone (radians)
+}
+
+/// A point or spot light whose shadows are cast by the given occluders
+/// A non-zero source_radius gives soft shadows by casting from several points on the source
+#[derive(Clone, Debug)]
+pub struct Light {
+    pub pos: Point,
+    pub radius: f64,
+    pub color: Color,
+    pub intensity: f64,
+    pub kind: LightKind,
+    pub source_radius: f64,
+    pub soft_samples: usize,
+    pub occluders: Vec<Line>
+}
+
+impl Light {
+    pub fn new_point(pos: Point, radius: f64, color: Color, occluders: Vec<Line>) -> Self {
+        Self {
+            pos,
+            radius,
+            color,
+            intensity: 1.0,
+            kind: LightKind::Point,
+            source_radius: 0.0,
+            soft_samples: 1,
+            occluders
+        }
+    }
+
+    pub fn new_spot(pos: Point, radius: f64, direction: f64, half_angle: f64, color: Color, occluders: Vec<Line>) -> Self {
+        Self {
+            kind: LightKind::Spot(direction, half_angle),
+            ..Self::new_point(pos, radius, color, occluders)
+        }
+    }
+
+    pub fn new_soft_point(pos: Point, radius: f64, source_radius: f64, soft_samples: usize, color: Color, occluders: Vec<Line>) -> Self {
+        Self {
+            source_radius,
+            soft_samples,
+            ..Self::new_point(pos, radius, color, occluders)
+        }
+    }
+
+    fn get_sample_origins(&self) -> Vec<Point> {
+        if self.source_radius <= 0.0 || self.soft_samples <= 1 {
+            return vec![self.pos];
+        }
+        (0..self.soft_samples)
+            .map(|index| 2.0 * consts::PI * index as f64 / self.soft_samples as f64)
+            .map(|angle| self.pos + self.source_radius * Point::from_angle(angle))
+            .collect()
+    }
+
+    /// Direction and cone cosines; the edge of the cone fades over a tenth of its width
+    fn get_cone(&self) -> (Point, Point) {
+        match self.kind {
+            LightKind::Point => (Point::x(), Point::new(-2.0, -1.5)),
+            LightKind::Spot(direction, half_angle) => {
+                let inner_angle = 0.9 * half_angle;
+                (Point::from_angle(direction), Point::new(half_angle.cos(), inner_angle.cos()))
+            }
+        }
+    }
+}
+
+impl Renderable<StandardPrimitive> for Light {
+    fn get_primitives(&mut self) -> Vec<StandardPrimitive> {
+        let origins = self.get_sample_origins();
+        let sample_weight = self.intensity / origins.len() as f64;
+        let color = Color::new(self.color.r, self.color.g, self.color.b, self.color.a * sample_weight);
+        let (direction, cone) = self.get_cone();
+
+        origins.into_iter()
+            .map(|origin| {
+                StandardPrimitive::Light(LightPolygon {
+                    fan_origin: origin,
+                    polygon: visibility_polygon(origin, &self.occluders, self.radius),
+                    center: self.pos,
+                    radius: self.radius,
+                    color,
+                    direction,
+                    cone
+                })
+            })
+            .collect()
+    }
+}
+
+/// Light level of unlit areas, enables the lighting pass for the frame
+#[derive(Copy, Clone, Debug)]
+pub struct AmbientLight {
+    pub color: Color
+}
+
+impl AmbientLight {
+    pub fn new(color: Color) -> Self {
+        Self {
+            color
+        }
+    }
+}
+
+impl Renderable<StandardPrimitive> for AmbientLight {
+    fn get_primitives(&mut self) -> Vec<StandardPrimitive> { vec![StandardPrimitive::AmbientLight(self.color)] }
+}
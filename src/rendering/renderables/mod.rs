@@ -8,6 +8,11 @@ pub mod annulus;
 pub mod annular_segment;
 pub mod box_border;
 pub mod texture_rect;
+pub mod sprite_sheet;
+pub mod sprite_animation;
+pub mod light;
+pub mod rich_text;
+pub mod skeleton;
 use super::primitives::StandardPrimitive;
 
 pub use self::line::*;
@@ -16,6 +21,10 @@ pub use self::circle::*;
 pub use self::annulus::Annulus;
 pub use self::annular_segment::AnnularSegment;
 pub use self::box_border::*;
+pub use self::sprite_sheet::*;
+pub use self::sprite_animation::*;
+pub use self::light::*;
+pub use self::rich_text::*;
 
 pub trait Renderable<Prim> {
     fn get_primitives(&mut self) -> Vec<Prim>;
@@ -0,0 +1,259 @@
+use crate::rendering::*;
+use crate::geometry::*;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RichTextAlign {
+    Left,
+    Centered,
+    Right
+}
+
+/// Multi-line text with inline styling, laid out below and to the right of position
+/// (centred/right aligned lines line up against max_width, or the widest line without one)
+/// Markup:
+///     [color=#RRGGBB] or [color=#RRGGBBAA] ... [/color]
+///     [size=1.5] ... [/size] scales the current size
+///     [font=Name] ... [/font] uses a font added to the font registry
+///     [/] closes the last tag and [[ is a literal [
+#[derive(Clone, Debug)]
+pub struct RichText {
+    pub markup: String,
+    pub position: Point3,
+    pub size: f64,
+    pub color: Color,
+    pub font: FontId,
+    pub max_width: Option<f64>,
+    pub line_spacing: f64,
+    pub align: RichTextAlign,
+    pub fixed: bool
+}
+
+impl RichText {
+    pub fn new(markup: String, position: Point3, size: f64, max_width: Option<f64>, fixed: bool) -> Self {
+        Self {
+            markup,
+            position,
+            size,
+            color: Color::white(),
+            font: DEFAULT_FONT,
+            max_width,
+            line_spacing: 1.0,
+            align: RichTextAlign::Left,
+            fixed
+        }
+    }
+
+    pub fn get_base_style(&self) -> TextStyle {
+        TextStyle::new(self.font, self.size, self.color)
+    }
+
+    pub fn get_spans(&self) -> Vec<StyledSpan> {
+        parse_markup(&self.markup, self.get_base_style())
+    }
+
+    pub fn get_lines(&self) -> Vec<LayoutLine> {
+        layout_spans(&self.get_spans(), self.max_width, self.line_spacing)
+    }
+
+    /// Glyph and line positions relative to position
+    pub fn get_layout(&self) -> TextLayout {
+        let lines = self.get_lines();
+        let block_width = self.get_block_width(&lines);
+        TextLayout::from_lines(&lines, |line| self.get_line_shift(line, block_width))
+    }
+
+    fn get_block_width(&self, lines: &[LayoutLine]) -> f64 {
+        self.max_width.unwrap_or_else(|| layout_dimensions(lines).x)
+    }
+
+    fn get_line_shift(&self, line: &LayoutLine, block_width: f64) -> f64 {
+        match self.align {
+            RichTextAlign::Left => 0.0,
+            RichTextAlign::Centered => (block_width - line.width) / 2.0,
+            RichTextAlign::Right => block_width - line.width
+        }
+    }
+
+    /// Width and height of the laid out block
+    pub fn get_dimensions(&self) -> Point {
+        let dimensions = layout_dimensions(&self.get_lines());
+        match self.max_width {
+            Some(max_width) if self.align != RichTextAlign::Left => Point::new(max_width.max(dimensions.x), dimensions.y),
+            _ => dimensions
+        }
+    }
+}
+
+impl Renderable<StandardPrimitive> for RichText {
+    fn get_primitives(&mut self) -> Vec<StandardPrimitive> {
+        let lines = self.get_lines();
+        let block_width = self.get_block_width(&lines);
+
+        lines.iter()
+            .flat_map(|line| {
+                let line_shift = self.get_line_shift(line, block_width);
+                line.runs.iter()
+                    .filter(|run| !run.text.trim().is_empty())
+                    .map(move |run| (line_shift + run.offset, line.baseline, run))
+            })
+            .map(|(x, baseline, run)| StandardPrimitive::Text(PlainText {
+                content: run.text.clone(),
+                position: self.position + Point3::new(x, -baseline, 0.0),
+                scale: Point::new(run.style.size, run.style.size),
+                transform: [[1.0, 0.0], [0.0, 1.0]],
+                color: run.style.color,
+                fixed: self.fixed,
+                align: TextAlign::Origin,
+                font: run.style.font
+            }))
+            .collect()
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum MarkupTagKind {
+    Color,
+    Size,
+    Font
+}
+
+#[derive(Copy, Clone, Debug)]
+enum StyleChange {
+    Color(Color),
+    Size(f64),
+    Font(FontId)
+}
+
+impl StyleChange {
+    fn kind(&self) -> MarkupTagKind {
+        match self {
+            StyleChange::Color(_) => MarkupTagKind::Color,
+            StyleChange::Size(_) => MarkupTagKind::Size,
+            StyleChange::Font(_) => MarkupTagKind::Font
+        }
+    }
+
+    fn apply(self, style: TextStyle) -> TextStyle {
+        match self {
+            StyleChange::Color(color) => TextStyle { color, ..style },
+            StyleChange::Size(factor) => TextStyle { size: style.size * factor, ..style },
+            StyleChange::Font(font) => TextStyle { font, ..style }
+        }
+    }
+}
+
+enum MarkupTag {
+    Open(StyleChange),
+    Close(Option<MarkupTagKind>)
+}
+
+fn parse_tag(tag: &str) -> Option<MarkupTag> {
+    if tag == "/" {
+        return Some(MarkupTag::Close(None));
+    }
+    if let Some(closed) = tag.strip_prefix('/') {
+        return match closed {
+            "color" => Some(MarkupTag::Close(Some(MarkupTagKind::Color))),
+            "size" => Some(MarkupTag::Close(Some(MarkupTagKind::Size))),
+            "font" => Some(MarkupTag::Close(Some(MarkupTagKind::Font))),
+            _ => None
+        };
+    }
+
+    let mut parts = tag.splitn(2, '=');
+    let name = parts.next()?.trim();
+    let value = parts.next()?.trim();
+    let change = match name {
+        "color" => StyleChange::Color(parse_hex_color(value)?),
+        "size" => StyleChange::Size(value.parse().ok()?),
+        "font" => StyleChange::Font(get_font_id(value)?),
+        _ => return None
+    };
+
+    Some(MarkupTag::Open(change))
+}
+
+fn parse_hex_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    let rgba = u32::from_str_radix(hex, 16).ok()?;
+    match hex.len() {
+        6 => Some(Color::new_rgb(rgba)),
+        8 => Some(Color::new_rgba(rgba)),
+        _ => None
+    }
+}
+
+/// Splits markup into spans of uniformly styled text
+/// Tags that don't parse (or fonts that aren't registered) are left in the text as written
+pub fn parse_markup(markup: &str, base_style: TextStyle) -> Vec<StyledSpan> {
+    let mut spans: Vec<StyledSpan> = Vec::new();
+    let mut changes: Vec<StyleChange> = Vec::new();
+    let mut text = String::new();
+    let mut style = base_style;
+    let mut rest = markup;
+
+    while let Some(tag_start) = rest.find('[') {
+        text.push_str(&rest[..tag_start]);
+        rest = &rest[tag_start..];
+
+        if rest.starts_with("[[") {
+            text.push('[');
+            rest = &rest[2..];
+            continue;
+        }
+
+        let tag = rest.find(']').and_then(|tag_end| parse_tag(&rest[1..tag_end]).map(|tag| (tag, tag_end)));
+        match tag {
+            Some((tag, tag_end)) => {
+                match tag {
+                    MarkupTag::Open(change) => changes.push(change),
+                    MarkupTag::Close(kind) => {
+                        let position = changes.iter().rposition(|change| match kind {
+                            Some(kind) => change.kind() == kind,
+                            None => true
+                        });
+                        if let Some(position) = position {
+                            changes.remove(position);
+                        }
+                    }
+                }
+                let new_style = changes.iter().fold(base_style, |style, change| change.apply(style));
+                if new_style != style && !text.is_empty() {
+                    spans.push(StyledSpan::new(text.split_off(0), style));
+                }
+                style = new_style;
+                rest = &rest[tag_end + 1..];
+            },
+            None => {
+                text.push('[');
+                rest = &rest[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+
+    if !text.is_empty() {
+        spans.push(StyledSpan::new(text, style));
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markup_splits_styled_spans() {
+        let base = TextStyle::new(DEFAULT_FONT, 2.0, Color::white());
+        let spans = parse_markup("a [color=#FF0000]red [size=0.5]small[/color] text[/] [[x] [bold]", base);
+
+        let texts: Vec<&str> = spans.iter().map(|span| span.text.as_str()).collect();
+        assert_eq!(texts, vec!["a ", "red ", "small", " text", " [x] [bold]"]);
+        assert_eq!(spans[1].style.color, Color::new(1.0, 0.0, 0.0, 1.0));
+        assert_eq!(spans[2].style.size, 1.0);
+        assert_eq!(spans[3].style.color, Color::white());
+        assert_eq!(spans[3].style.size, 1.0);
+        assert_eq!(spans[4].style, base);
+    }
+}
@@ -0,0 +1,11 @@
+use crate::rendering::{Renderable, StandardPrimitive};
+use crate::animation::skeleton::{Skeleton, PartShape};
+
+impl Renderable<StandardPrimitive> for Skeleton {
+    fn get_primitives(&mut self) -> Vec<StandardPrimitive> {
+        self.get_posed_parts().into_iter().map(|part| match part {
+            PartShape::Texture(rect) => StandardPrimitive::TextureRect(rect),
+            PartShape::Polygon(polygon) => StandardPrimitive::Poly(polygon)
+        }).collect()
+    }
+}
@@ -0,0 +1,240 @@
+use crate::rendering::*;
+use crate::geometry::*;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SpriteAnimationMode {
+    Once,
+    Loop,
+    PingPong
+}
+
+/// Steps through sprite sheet frames and draws the current one as a TextureRect
+#[derive(Clone, Debug)]
+pub struct SpriteAnimation {
+    frames: Vec<SpriteFrame>,
+    mode: SpriteAnimationMode,
+    time: f64,
+    pub length: f64,
+    pub height: f64,
+    pub pos: Point3,
+    pub rot: Rotation,
+    pub tint: Color,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub fixed: bool
+}
+
+impl SpriteAnimation {
+    pub fn new(frames: Vec<SpriteFrame>, mode: SpriteAnimationMode, length: f64, height: f64, pos: Point3, fixed: bool) -> Self {
+        Self {
+            frames,
+            mode,
+            time: 0.0,
+            length,
+            height,
+            pos,
+            rot: Rotation::new(0.0),
+            tint: Color::white(),
+            flip_x: false,
+            flip_y: false,
+            fixed
+        }
+    }
+
+    /// Uses the tag's frames; ping-pong tags override the given mode
+    pub fn from_tag(sheet: &SpriteSheet, tag_name: &str, mode: SpriteAnimationMode, length: f64, height: f64, pos: Point3, fixed: bool) -> Option<Self> {
+        let frames = sheet.get_tag_frames(tag_name)?;
+        let mode = match sheet.get_tag(tag_name) {
+            Some(tag) if tag.direction == SpriteTagDirection::PingPong => SpriteAnimationMode::PingPong,
+            _ => mode
+        };
+
+        Some(Self::new(frames, mode, length, height, pos, fixed))
+    }
+
+    pub fn update(&mut self, t_step: f64) {
+        self.time += t_step;
+
+        let cycle_length = self.get_cycle_length();
+        if self.mode != SpriteAnimationMode::Once && cycle_length > 0.0 {
+            self.time %= cycle_length;
+        }
+    }
+
+    pub fn restart(&mut self) {
+        self.time = 0.0;
+    }
+
+    pub fn set_frames(&mut self, frames: Vec<SpriteFrame>) {
+        self.frames = frames;
+        self.time = 0.0;
+    }
+
+    pub fn get_mode(&self) -> SpriteAnimationMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: SpriteAnimationMode) {
+        self.mode = mode;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.mode == SpriteAnimationMode::Once && self.time >= self.get_cycle_length()
+    }
+
+    /// Frame indices visited in one pass, ping-pong doesn't repeat the end frames
+    fn get_cycle(&self) -> Vec<usize> {
+        let frame_count = self.frames.len();
+        let mut cycle: Vec<usize> = (0..frame_count).collect();
+
+        if self.mode == SpriteAnimationMode::PingPong && frame_count > 2 {
+            cycle.extend((1..frame_count - 1).rev());
+        }
+
+        cycle
+    }
+
+    fn get_cycle_length(&self) -> f64 {
+        self.get_cycle().into_iter().map(|index| self.frames[index].duration).sum()
+    }
+
+    pub fn get_current_frame_index(&self) -> usize {
+        let cycle = self.get_cycle();
+        let mut elapsed = 0.0;
+
+        for &index in cycle.iter() {
+            elapsed += self.frames[index].duration;
+            if self.time < elapsed {
+                return index;
+            }
+        }
+
+        cycle.last().cloned().unwrap_or(0)
+    }
+
+    /// None for an animation without frames
+    pub fn get_texture_rect(&self) -> Option<TextureRect> {
+        let frame = *self.frames.get(self.get_current_frame_index())?;
+        let mut texture_corner = frame.texture_corner;
+        let mut texture_dim = frame.texture_dim;
+
+        // Flipping walks the texture from the opposite edge
+        if self.flip_x {
+            texture_corner.x += texture_dim.x;
+            texture_dim.x = -texture_dim.x;
+        }
+        if self.flip_y {
+            texture_corner.y += texture_dim.y;
+            texture_dim.y = -texture_dim.y;
+        }
+
+        let mut rect = TextureRect::new_with_rotation(
+            self.length,
+            self.height,
+            self.pos,
+            self.rot,
+            texture_corner,
+            texture_dim,
+            self.fixed
+        );
+        rect.color = self.tint;
+
+        Some(rect)
+    }
+}
+
+impl Renderable<StandardPrimitive> for SpriteAnimation {
+    fn get_primitives(&mut self) -> Vec<StandardPrimitive> {
+        self.get_texture_rect().into_iter().map(StandardPrimitive::TextureRect).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_animation(mode: SpriteAnimationMode) -> SpriteAnimation {
+        let sheet = SpriteSheet::from_grid(0, 2, 2, vec![0.1, 0.1, 0.1, 0.1]);
+        SpriteAnimation::new(sheet.frames, mode, 1.0, 1.0, Point3::zero(), false)
+    }
+
+    #[test]
+    fn loop_wraps_around() {
+        let mut anim = build_animation(SpriteAnimationMode::Loop);
+        anim.update(0.25);
+        assert_eq!(anim.get_current_frame_index(), 2);
+        anim.update(0.2);
+        assert_eq!(anim.get_current_frame_index(), 0);
+        assert!(!anim.is_finished());
+    }
+
+    #[test]
+    fn ping_pong_reverses() {
+        let mut anim = build_animation(SpriteAnimationMode::PingPong);
+        anim.update(0.45);
+        assert_eq!(anim.get_current_frame_index(), 2);
+        anim.update(0.1);
+        assert_eq!(anim.get_current_frame_index(), 1);
+        anim.update(0.1);
+        assert_eq!(anim.get_current_frame_index(), 0);
+    }
+
+    #[test]
+    fn once_holds_last_frame() {
+        let mut anim = build_animation(SpriteAnimationMode::Once);
+        anim.update(10.0);
+        assert_eq!(anim.get_current_frame_index(), 3);
+        assert!(anim.is_finished());
+    }
+
+    #[test]
+    fn flip_x_reverses_texture_coords() {
+        let mut anim = build_animation(SpriteAnimationMode::Loop);
+        anim.flip_x = true;
+        let rect = anim.get_texture_rect().unwrap();
+        assert!((rect.texture_corner.x - 0.5).abs() < 1e-9);
+        assert!((rect.texture_dim.x + 0.5).abs() < 1e-9);
+        assert!((rect.texture_corner.y - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn aseprite_hash_export_keeps_frame_order() {
+        let json = r#"{
+            "frames": {
+                "walk 0.aseprite": { "frame": { "x": 0, "y": 0, "w": 16, "h": 16 }, "duration": 100 },
+                "walk 1.aseprite": { "frame": { "x": 16, "y": 0, "w": 16, "h": 16 }, "duration": 200 }
+            },
+            "meta": {
+                "size": { "w": 32, "h": 32 },
+                "frameTags": [ { "name": "walk", "from": 0, "to": 1, "direction": "pingpong" } ]
+            }
+        }"#;
+        let sheet = SpriteSheet::from_aseprite_json(json, 1).unwrap();
+        assert_eq!(sheet.frames.len(), 2);
+        assert!((sheet.frames[1].texture_corner.x - 0.5).abs() < 1e-9);
+        assert!((sheet.frames[1].texture_corner.y - 0.5).abs() < 1e-9);
+        assert!((sheet.frames[1].duration - 0.2).abs() < 1e-9);
+
+        let anim = SpriteAnimation::from_tag(&sheet, "walk", SpriteAnimationMode::Loop, 1.0, 1.0, Point3::zero(), false).unwrap();
+        assert_eq!(anim.get_mode(), SpriteAnimationMode::PingPong);
+    }
+
+    #[test]
+    fn broken_tags_and_empty_animations_do_not_panic() {
+        let json = r#"{
+            "frames": [ { "frame": { "x": 0, "y": 0, "w": 16, "h": 16 }, "duration": 100 } ],
+            "meta": {
+                "size": { "w": 16, "h": 16 },
+                "frameTags": [ { "name": "backwards", "from": 1, "to": 0, "direction": "forward" } ]
+            }
+        }"#;
+        assert!(SpriteSheet::from_aseprite_json(json, 0).is_err());
+        let empty_sheet = r#"{ "frames": [], "meta": { "size": { "w": 0, "h": 16 } } }"#;
+        assert!(SpriteSheet::from_aseprite_json(empty_sheet, 0).is_err());
+
+        let mut anim = SpriteAnimation::new(vec![], SpriteAnimationMode::Loop, 1.0, 1.0, Point3::zero(), false);
+        anim.update(0.1);
+        assert!(anim.get_texture_rect().is_none());
+        assert!(anim.get_primitives().is_empty());
+    }
+}
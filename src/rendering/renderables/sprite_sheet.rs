@@ -0,0 +1,220 @@
+use crate::rendering::*;
+use crate::geometry::*;
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json;
+use std::fmt;
+
+/// A single cell of a sprite sheet, in normalised texture coordinates
+/// (texture_corner is the bottom left corner, z picks the texture array layer)
+#[derive(Copy, Clone, Debug)]
+pub struct SpriteFrame {
+    pub texture_corner: Point3,
+    pub texture_dim: Point,
+    pub duration: f64
+}
+
+impl SpriteFrame {
+    pub fn new(texture_corner: Point3, texture_dim: Point, duration: f64) -> Self {
+        Self {
+            texture_corner,
+            texture_dim,
+            duration
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SpriteTagDirection {
+    Forward,
+    Reverse,
+    PingPong
+}
+
+/// Named range of frames (inclusive at both ends)
+#[derive(Clone, Debug)]
+pub struct SpriteTag {
+    pub name: String,
+    pub from: usize,
+    pub to: usize,
+    pub direction: SpriteTagDirection
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SpriteSheet {
+    pub frames: Vec<SpriteFrame>,
+    pub tags: Vec<SpriteTag>
+}
+
+impl SpriteSheet {
+    /// Frames are read row by row starting from the top left of the layer
+    /// There is one frame per entry in frame_durations (so the last row can be partially filled)
+    /// Panics if the grid has no columns or rows
+    pub fn from_grid(layer: usize, columns: usize, rows: usize, frame_durations: Vec<f64>) -> Self {
+        assert!(columns > 0 && rows > 0, "Sprite sheet grid of {}x{} has no cells", columns, rows);
+        let cell_dim = Point::new(1.0 / columns as f64, 1.0 / rows as f64);
+
+        let frames = frame_durations.into_iter()
+            .enumerate()
+            .map(|(index, duration)| {
+                let column = index % columns;
+                let row = index / columns;
+                // Textures are loaded upside down so rows are counted from the top of the image
+                let corner = Point3::new(
+                    column as f64 * cell_dim.x,
+                    1.0 - (row + 1) as f64 * cell_dim.y,
+                    layer as f64);
+                SpriteFrame::new(corner, cell_dim, duration)
+            })
+            .collect();
+
+        Self {
+            frames,
+            tags: Vec::new()
+        }
+    }
+
+    /// Reads the JSON data file Aseprite exports alongside a sheet (both the hash and array layouts)
+    /// The sheet image is assumed to fill the whole texture array layer
+    pub fn from_aseprite_json(json: &str, layer: usize) -> serde_json::Result<Self> {
+        let export: AsepriteExport = serde_json::from_str(json)?;
+        let sheet_dim = Point::new(export.meta.size.w, export.meta.size.h);
+        if sheet_dim.x <= 0.0 || sheet_dim.y <= 0.0 {
+            return Err(serde::de::Error::custom(format!("sheet size {}x{} is empty", sheet_dim.x, sheet_dim.y)));
+        }
+
+        let frames = export.frames.0.into_iter()
+            .map(|frame| {
+                let rect = frame.frame;
+                let corner = Point3::new(
+                    rect.x / sheet_dim.x,
+                    1.0 - (rect.y + rect.h) / sheet_dim.y,
+                    layer as f64);
+                let dim = Point::new(rect.w / sheet_dim.x, rect.h / sheet_dim.y);
+                SpriteFrame::new(corner, dim, frame.duration / 1000.0)
+            })
+            .collect();
+
+        if let Some(tag) = export.meta.frame_tags.iter().find(|tag| tag.from > tag.to) {
+            return Err(serde::de::Error::custom(format!("tag {} ends before it starts", tag.name)));
+        }
+
+        let tags = export.meta.frame_tags.into_iter()
+            .map(|tag| {
+                let direction = match tag.direction.as_str() {
+                    "reverse" => SpriteTagDirection::Reverse,
+                    "pingpong" => SpriteTagDirection::PingPong,
+                    _ => SpriteTagDirection::Forward
+                };
+                SpriteTag {
+                    name: tag.name,
+                    from: tag.from,
+                    to: tag.to,
+                    direction
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            frames,
+            tags
+        })
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<&SpriteTag> {
+        self.tags.iter().find(|tag| tag.name == name)
+    }
+
+    /// Frames of a tag in playback order (reversed tags come back reversed)
+    pub fn get_tag_frames(&self, name: &str) -> Option<Vec<SpriteFrame>> {
+        let tag = self.get_tag(name)?;
+        let mut frames: Vec<SpriteFrame> = self.frames.iter()
+            .skip(tag.from)
+            .take((tag.to + 1).saturating_sub(tag.from))
+            .cloned()
+            .collect();
+
+        if tag.direction == SpriteTagDirection::Reverse {
+            frames.reverse();
+        }
+
+        Some(frames)
+    }
+}
+
+#[derive(Deserialize)]
+struct AsepriteExport {
+    frames: AsepriteFrames,
+    meta: AsepriteMeta
+}
+
+#[derive(Deserialize)]
+struct AsepriteFrame {
+    frame: AsepriteRect,
+    duration: f64
+}
+
+#[derive(Deserialize)]
+struct AsepriteRect {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64
+}
+
+#[derive(Deserialize)]
+struct AsepriteSize {
+    w: f64,
+    h: f64
+}
+
+#[derive(Deserialize)]
+struct AsepriteMeta {
+    size: AsepriteSize,
+    #[serde(rename = "frameTags", default)]
+    frame_tags: Vec<AsepriteTag>
+}
+
+#[derive(Deserialize)]
+struct AsepriteTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: String
+}
+
+/// Aseprite writes frames either as an array or as a map keyed by file name
+/// Map entries are kept in file order, which is the frame order
+struct AsepriteFrames(Vec<AsepriteFrame>);
+
+impl<'de> Deserialize<'de> for AsepriteFrames {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(AsepriteFramesVisitor)
+    }
+}
+
+struct AsepriteFramesVisitor;
+
+impl<'de> Visitor<'de> for AsepriteFramesVisitor {
+    type Value = AsepriteFrames;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("an array or map of frames")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut frames = Vec::new();
+        while let Some(frame) = seq.next_element()? {
+            frames.push(frame);
+        }
+        Ok(AsepriteFrames(frames))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut frames = Vec::new();
+        while let Some((_, frame)) = map.next_entry::<String, AsepriteFrame>()? {
+            frames.push(frame);
+        }
+        Ok(AsepriteFrames(frames))
+    }
+}
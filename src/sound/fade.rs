@@ -0,0 +1,34 @@
+/// A volume moving linearly from one level to another, over seconds
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Fade {
+    pub from: f32,
+    pub to: f32,
+    pub elapsed: f64,
+    pub duration: f64
+}
+
+impl Fade {
+    pub fn new(from: f32, to: f32, duration: f64) -> Self {
+        Fade { from, to, elapsed: 0.0, duration }
+    }
+
+    /// Already at the level
+    pub fn constant(level: f32) -> Self {
+        Fade::new(level, level, 0.0)
+    }
+
+    pub fn update(&mut self, t_step: f64) {
+        self.elapsed += t_step;
+    }
+
+    pub fn get_level(&self) -> f32 {
+        if self.is_done() {
+            return self.to;
+        }
+        self.from + (self.to - self.from) * (self.elapsed / self.duration) as f32
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
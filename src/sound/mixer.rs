@@ -0,0 +1,120 @@
+use std::hash::Hash;
+use crate::sound::*;
+
+/// Where a sound's volume is set, under the master volume
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Bus {
+    Music,
+    Effects,
+    Ui
+}
+
+/// The volumes of each bus, from 0 to 1, e.g. for an options menu to save and load
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BusLevels {
+    pub master: f32,
+    pub music: f32,
+    pub effects: f32,
+    pub ui: f32
+}
+
+impl Default for BusLevels {
+    fn default() -> Self {
+        BusLevels {
+            master: 1.0,
+            music: 1.0,
+            effects: 1.0,
+            ui: 1.0
+        }
+    }
+}
+
+impl BusLevels {
+    /// The bus's volume with the master volume applied
+    pub fn get_level(&self, bus: Bus) -> f32 {
+        let level = match bus {
+            Bus::Music => self.music,
+            Bus::Effects => self.effects,
+            Bus::Ui => self.ui
+        };
+        self.master * level
+    }
+}
+
+/// How to play one sound
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PlayParams {
+    /// Relative to the sound's own volume
+    pub volume: f32,
+    /// 2 plays an octave up and twice as fast
+    pub pitch: f32,
+    /// From -1 (left) to 1 (right)
+    pub pan: f32,
+    /// Sounds only take the voices of sounds with the same or lower priority
+    pub priority: i32,
+    pub bus: Bus
+}
+
+impl Default for PlayParams {
+    fn default() -> Self {
+        PlayParams {
+            volume: 1.0,
+            pitch: 1.0,
+            pan: 0.0,
+            priority: 0,
+            bus: Bus::Effects
+        }
+    }
+}
+
+/// Sound effects and music under one set of bus volumes. Call update every frame.
+pub struct Mixer<S: Eq + Hash> {
+    pub levels: BusLevels,
+    pub sounds: SoundPlayer<S>,
+    pub music: MusicPlayer
+}
+
+impl<S: Eq + Hash + Copy> Mixer<S> {
+    pub fn new(sounds: SoundPlayer<S>, music: MusicPlayer) -> Self {
+        Mixer {
+            levels: BusLevels::default(),
+            sounds,
+            music
+        }
+    }
+
+    /// Plays through the default audio device, staying silent (logging why) without one
+    pub fn new_or_silent(sound_list: Vec<SoundData<S>>) -> Self {
+        Self::new(SoundPlayer::new_or_silent(sound_list), MusicPlayer::new_or_silent())
+    }
+
+    /// Applies the bus levels, then moves the sounds' and music's fades along, t_step in seconds
+    pub fn update(&mut self, t_step: f64) {
+        self.sounds.set_bus_levels(self.levels);
+        self.music.set_bus_level(self.levels.get_level(Bus::Music));
+        self.sounds.update(t_step);
+        self.music.update(t_step);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sound::sound_player::tests::TENTH_SECOND_WAV;
+
+    #[test]
+    fn bus_levels_scale_playing_sounds() {
+        let output = RecordingOutput::new();
+        let sounds = SoundPlayer::with_output(vec![SoundData::new(0, TENTH_SECOND_WAV.as_slice(), 0.5)], Box::new(output.clone()));
+        let mut mixer = Mixer::new(sounds, MusicPlayer::with_output(Box::new(NullOutput)));
+
+        mixer.sounds.play_with(0, PlayParams { volume: 0.5, ..Default::default() }).unwrap();
+        mixer.sounds.play_with(0, PlayParams { bus: Bus::Ui, ..Default::default() }).unwrap();
+        mixer.levels.master = 0.5;
+        mixer.levels.effects = 0.5;
+        mixer.update(0.1);
+
+        let volumes: Vec<f32> = output.get_played().iter().map(|sound| sound.volume).collect();
+        assert_eq!(volumes, vec![0.0625, 0.25]);
+    }
+}
@@ -1,7 +1,19 @@
 pub mod sound_player;
 pub mod sound_bank;
 pub mod sound_data;
+pub mod sound_output;
+pub mod music;
+pub mod fade;
+pub mod panning;
+pub mod mixer;
+pub mod positional;
 
 pub use self::sound_player::*;
 pub use self::sound_bank::*;
-pub use self::sound_data::*;
\ No newline at end of file
+pub use self::sound_data::*;
+pub use self::sound_output::*;
+pub use self::music::*;
+pub use self::fade::*;
+pub use self::panning::*;
+pub use self::mixer::*;
+pub use self::positional::*;
@@ -0,0 +1,629 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use rodio::{Decoder, Source};
+use rand::Rng;
+use crate::sound::*;
+use crate::debug::{log_warn, LogTarget};
+
+/// Seconds the next track in a playlist fades in over while the last fades out
+pub const DEFAULT_CROSSFADE: f64 = 2.0;
+
+/// The part of a track repeated once it's played through, in seconds from the start of the file
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LoopPoints {
+    pub start: f64,
+    /// None loops at the end of the file
+    pub end: Option<f64>
+}
+
+impl LoopPoints {
+    /// The whole file
+    pub fn whole() -> Self {
+        LoopPoints { start: 0.0, end: None }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.end.is_none_or(|end| end > self.start)
+    }
+}
+
+/// A WAV, OGG, FLAC or MP3 file streamed from disk as it plays
+#[derive(Clone, Debug, PartialEq)]
+pub struct MusicTrack {
+    pub path: PathBuf,
+    pub volume: f32,
+    /// None plays the track once
+    pub looping: Option<LoopPoints>
+}
+
+impl MusicTrack {
+    pub fn new<P: AsRef<Path>>(path: P, volume: f32, looping: Option<LoopPoints>) -> Self {
+        MusicTrack {
+            path: path.as_ref().to_path_buf(),
+            volume,
+            looping
+        }
+    }
+
+    /// Fails if the file can't be opened or isn't a sound file, or the loop ends before it starts.
+    /// Reopened files are only read once looping.
+    pub fn open(&self) -> Result<SoundSource, SoundError> {
+        if self.looping.is_some_and(|loop_points| !loop_points.is_valid()) {
+            return Err(SoundError::InvalidLoopPoints);
+        }
+        let decoder = open_sound_file(&self.path)?;
+        Ok(match self.looping {
+            Some(loop_points) => {
+                let path = self.path.clone();
+                Box::new(LoopingSource::new(decoder, loop_points, move || open_sound_file(&path).ok()))
+            },
+            None => Box::new(decoder)
+        })
+    }
+}
+
+pub fn open_sound_file<P: AsRef<Path>>(path: P) -> Result<Decoder<BufReader<File>>, SoundError> {
+    let mut file = File::open(path)?;
+    let mut header = Vec::new();
+    file.by_ref().take(12).read_to_end(&mut header)?;
+    if !has_sound_header(&header) {
+        return Err(SoundError::Decode("Unrecognized format".to_string()));
+    }
+    file.seek(SeekFrom::Start(0))?;
+    Ok(Decoder::new(BufReader::new(file))?)
+}
+
+/// Plays a source through then repeats its loop forever, reopening the source to get back to the
+/// loop start. Sources can't seek, so the reopened one is read up to the loop start a sample per
+/// sample played, and caught up all at once if the loop comes round before it's there.
+pub struct LoopingSource<D, F> {
+    source: D,
+    open: F,
+    /// The reopened source and how far it's been read
+    next_loop: Option<(D, u64)>,
+    /// Reopening isn't retried every sample, the track ends at the loop instead
+    reopen_failed: bool,
+    channels: u16,
+    sample_rate: u32,
+    /// In samples (one per channel) like position
+    loop_start: u64,
+    loop_end: Option<u64>,
+    position: u64
+}
+
+impl<D, F> LoopingSource<D, F> where D: Source<Item=i16>, F: FnMut() -> Option<D> {
+    /// A loop ending before it starts loops at the end of the source instead
+    pub fn new(source: D, mut loop_points: LoopPoints, open: F) -> Self {
+        if !loop_points.is_valid() {
+            log_warn(LogTarget::Sound, &format!("Ignoring loop end {:?} before loop start {}", loop_points.end, loop_points.start));
+            loop_points.end = None;
+        }
+        let channels = source.channels();
+        let sample_rate = source.sample_rate();
+        let to_samples = |seconds: f64| (seconds.max(0.0) * sample_rate as f64) as u64 * channels as u64;
+        LoopingSource {
+            source,
+            open,
+            next_loop: None,
+            reopen_failed: false,
+            channels,
+            sample_rate,
+            loop_start: to_samples(loop_points.start),
+            loop_end: loop_points.end.map(to_samples),
+            position: 0
+        }
+    }
+
+    /// Reads the reopened source a little further towards the loop start, all the way if catching up
+    fn prepare_next_loop(&mut self, catch_up: bool) -> Option<()> {
+        if self.next_loop.is_none() {
+            match (self.open)() {
+                Some(next_source) => self.next_loop = Some((next_source, 0)),
+                None => {
+                    self.reopen_failed = true;
+                    return None;
+                }
+            }
+        }
+        let loop_start = self.loop_start;
+        let (next_source, read) = self.next_loop.as_mut()?;
+        while *read < loop_start {
+            next_source.next()?;
+            *read += 1;
+            if !catch_up {
+                break;
+            }
+        }
+        Some(())
+    }
+
+    fn restart(&mut self) -> Option<()> {
+        if self.reopen_failed || self.prepare_next_loop(true).is_none() {
+            // Couldn't reopen, or the loop starts past the end
+            self.next_loop = None;
+            return None;
+        }
+        let (next_source, _) = self.next_loop.take()?;
+        self.source = next_source;
+        self.position = self.loop_start;
+        Some(())
+    }
+}
+
+impl<D, F> Iterator for LoopingSource<D, F> where D: Source<Item=i16>, F: FnMut() -> Option<D> {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if self.loop_end.is_some_and(|loop_end| self.position >= loop_end) {
+            self.restart()?;
+        }
+        let sample = match self.source.next() {
+            Some(sample) => sample,
+            None => {
+                self.restart()?;
+                self.source.next()?
+            }
+        };
+        self.position += 1;
+        let next_loop_ready = self.next_loop.as_ref().is_some_and(|&(_, read)| read >= self.loop_start);
+        if !next_loop_ready && !self.reopen_failed {
+            self.prepare_next_loop(false);
+        }
+        Some(sample)
+    }
+}
+
+impl<D, F> Source for LoopingSource<D, F> where D: Source<Item=i16>, F: FnMut() -> Option<D> {
+    /// Every loop is the same file, so the format never changes
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PlaylistMode {
+    /// Stops after the last track
+    Once,
+    Repeat,
+    /// Repeats, in a new order each time round
+    Shuffle
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Playlist {
+    pub tracks: Vec<MusicTrack>,
+    pub mode: PlaylistMode,
+    /// Seconds each track fades into the next over
+    pub crossfade: f64
+}
+
+impl Playlist {
+    pub fn new(tracks: Vec<MusicTrack>, mode: PlaylistMode) -> Self {
+        Playlist {
+            tracks,
+            mode,
+            crossfade: DEFAULT_CROSSFADE
+        }
+    }
+}
+
+struct PlayingTrack {
+    track: MusicTrack,
+    channel: Box<dyn AudioChannel>,
+    /// Seconds played, for starting the crossfade into the next track
+    elapsed: f64,
+    /// None while looping, or when the file doesn't say
+    duration: Option<f64>,
+    fade: Fade
+}
+
+/// Music on channels of its own, apart from SoundPlayer's sound effects. Call update every frame
+/// to move fades and playlists along.
+pub struct MusicPlayer {
+    pub volume: f32,
+    /// The music bus's level, set by Mixer
+    bus_level: f32,
+    output: Box<dyn ChannelOutput>,
+    current: Option<PlayingTrack>,
+    fading_out: Vec<PlayingTrack>,
+    playlist: Option<Playlist>,
+    /// The playlist's track order and the place in it of the current track
+    playlist_order: Vec<usize>,
+    playlist_position: usize,
+    paused: bool
+}
+
+impl MusicPlayer {
+    /// Plays through the default audio device, failing without one
+    pub fn new() -> Result<Self, SoundError> {
+        Ok(Self::with_output(Box::new(RodioOutput::new()?)))
+    }
+
+    /// Like new, but stays silent (logging why) rather than fail without an audio device
+    pub fn new_or_silent() -> Self {
+        match RodioOutput::new() {
+            Ok(output) => Self::with_output(Box::new(output)),
+            Err(err) => {
+                log_warn(LogTarget::Sound, &format!("Music disabled: {}", err));
+                Self::with_output(Box::new(NullOutput))
+            }
+        }
+    }
+
+    pub fn with_output(output: Box<dyn ChannelOutput>) -> Self {
+        MusicPlayer {
+            volume: 1.0,
+            bus_level: 1.0,
+            output,
+            current: None,
+            fading_out: Vec::new(),
+            playlist: None,
+            playlist_order: Vec::new(),
+            playlist_position: 0,
+            paused: false
+        }
+    }
+
+    /// Fades from the current track, if any, into this one over crossfade seconds, ending any playlist
+    pub fn play(&mut self, track: MusicTrack, crossfade: f64) -> Result<(), SoundError> {
+        self.playlist = None;
+        self.start_track(track, crossfade)
+    }
+
+    /// Starts the playlist from its first track (or a random one when shuffled)
+    pub fn play_playlist(&mut self, playlist: Playlist) -> Result<(), SoundError> {
+        let crossfade = playlist.crossfade;
+        self.playlist_order = get_playlist_order(&playlist);
+        self.playlist_position = 0;
+        let first = self.playlist_order.first().map(|&index| playlist.tracks[index].clone());
+        self.playlist = Some(playlist);
+        match first {
+            Some(track) => self.start_track(track, crossfade),
+            None => {
+                self.stop(crossfade);
+                Ok(())
+            }
+        }
+    }
+
+    /// Moves on to the playlist's next track, skipping any that can't be played
+    pub fn skip(&mut self) {
+        let (crossfade, track_count) = match &self.playlist {
+            Some(playlist) => (playlist.crossfade, playlist.tracks.len()),
+            None => return
+        };
+        for _ in 0..track_count {
+            let track = match self.next_playlist_track() {
+                Some(track) => track,
+                None => {
+                    self.stop(crossfade);
+                    return;
+                }
+            };
+            match self.start_track(track.clone(), crossfade) {
+                Ok(()) => return,
+                Err(err) => log_warn(LogTarget::Sound, &format!("Could not play {}: {}", track.path.display(), err))
+            }
+        }
+        self.stop(crossfade);
+    }
+
+    /// Fades the music out over fade seconds, ending any playlist
+    pub fn stop(&mut self, fade: f64) {
+        self.playlist = None;
+        if let Some(playing) = self.current.take() {
+            self.fade_out(playing, fade);
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.set_paused(true);
+    }
+
+    pub fn resume(&mut self) {
+        self.set_paused(false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Whether a track is playing (or paused), not counting ones fading out
+    pub fn is_playing(&self) -> bool {
+        self.current.is_some()
+    }
+
+    pub fn get_current_track(&self) -> Option<&MusicTrack> {
+        self.current.as_ref().map(|playing| &playing.track)
+    }
+
+    pub fn get_playlist(&self) -> Option<&Playlist> {
+        self.playlist.as_ref()
+    }
+
+    /// Fades, ends finished tracks and moves playlists on, t_step in seconds. Nothing moves while paused.
+    pub fn update(&mut self, t_step: f64) {
+        if self.paused {
+            return;
+        }
+        for playing in self.current.iter_mut().chain(self.fading_out.iter_mut()) {
+            playing.elapsed += t_step;
+            playing.fade.update(t_step);
+        }
+        for playing in self.fading_out.iter_mut().filter(|playing| playing.fade.is_done()) {
+            playing.channel.stop();
+        }
+        self.fading_out.retain(|playing| playing.channel.is_playing());
+
+        let finished = self.current.as_ref().map(|playing| !playing.channel.is_playing());
+        if finished == Some(true) {
+            self.current = None;
+        }
+        if let Some(playlist) = &self.playlist {
+            let crossfade = playlist.crossfade;
+            // The last track of a playlist played once has nothing to fade into, so plays to its end
+            let has_next = playlist.mode != PlaylistMode::Once || self.playlist_position + 1 < self.playlist_order.len();
+            let ending = self.current.as_ref().is_none_or(|playing| {
+                // Tracks shorter than the crossfade play at least half way through
+                has_next && playing.duration.is_some_and(|duration| playing.elapsed >= duration - crossfade.min(duration / 2.0))
+            });
+            if ending {
+                self.skip();
+            }
+        }
+        self.apply_volumes();
+    }
+
+    fn start_track(&mut self, track: MusicTrack, crossfade: f64) -> Result<(), SoundError> {
+        let source = track.open()?;
+        let duration = match track.looping {
+            Some(_) => None,
+            None => source.total_duration().map(|duration| duration.as_secs_f64())
+        };
+        if let Some(playing) = self.current.take() {
+            self.fade_out(playing, crossfade);
+        }
+        let mut channel = self.output.open_channel();
+        channel.set_paused(self.paused);
+        let fade = if crossfade > 0.0 { Fade::new(0.0, 1.0, crossfade) } else { Fade::constant(1.0) };
+        channel.set_volume(self.volume * self.bus_level * track.volume * fade.get_level());
+        channel.play(source);
+        self.current = Some(PlayingTrack { track, channel, elapsed: 0.0, duration, fade });
+        Ok(())
+    }
+
+    fn fade_out(&mut self, mut playing: PlayingTrack, fade: f64) {
+        if fade > 0.0 {
+            playing.fade = Fade::new(playing.fade.get_level(), 0.0, fade);
+            self.fading_out.push(playing);
+        } else {
+            playing.channel.stop();
+        }
+    }
+
+    fn next_playlist_track(&mut self) -> Option<MusicTrack> {
+        let playlist = self.playlist.as_ref()?;
+        self.playlist_position += 1;
+        if self.playlist_position >= self.playlist_order.len() {
+            if playlist.mode == PlaylistMode::Once {
+                return None;
+            }
+            self.playlist_order = get_playlist_order(playlist);
+            self.playlist_position = 0;
+        }
+        let index = *self.playlist_order.get(self.playlist_position)?;
+        playlist.tracks.get(index).cloned()
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        for playing in self.current.iter_mut().chain(self.fading_out.iter_mut()) {
+            playing.channel.set_paused(paused);
+        }
+    }
+
+    pub fn set_bus_level(&mut self, level: f32) {
+        self.bus_level = level;
+        self.apply_volumes();
+    }
+
+    fn apply_volumes(&mut self) {
+        let volume = self.volume * self.bus_level;
+        for playing in self.current.iter_mut().chain(self.fading_out.iter_mut()) {
+            playing.channel.set_volume(volume * playing.track.volume * playing.fade.get_level());
+        }
+    }
+}
+
+fn get_playlist_order(playlist: &Playlist) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..playlist.tracks.len()).collect();
+    if playlist.mode == PlaylistMode::Shuffle {
+        rand::thread_rng().shuffle(&mut order);
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::fs;
+    use rodio::buffer::SamplesBuffer;
+    use crate::sound::sound_player::tests::wav_bytes;
+
+    #[test]
+    fn loops_repeat_between_loop_points() {
+        let open = || Some(SamplesBuffer::new(1, 10, (0..10).collect::<Vec<i16>>()));
+        let looping = LoopingSource::new(open().unwrap(), LoopPoints { start: 0.2, end: Some(0.8) }, open);
+        let samples: Vec<i16> = looping.take(16).collect();
+        assert_eq!(samples, vec![0, 1, 2, 3, 4, 5, 6, 7, 2, 3, 4, 5, 6, 7, 2, 3]);
+
+        let whole = LoopingSource::new(open().unwrap(), LoopPoints::whole(), open);
+        assert_eq!(whole.skip(8).take(4).collect::<Vec<i16>>(), vec![8, 9, 0, 1]);
+    }
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    struct ChannelState {
+        volume: f32,
+        paused: bool,
+        playing: bool
+    }
+
+    /// Channels that never finish by themselves, with their states shared for checking
+    #[derive(Default)]
+    struct TestOutput {
+        channels: Rc<RefCell<Vec<ChannelState>>>
+    }
+
+    struct TestChannel {
+        index: usize,
+        channels: Rc<RefCell<Vec<ChannelState>>>
+    }
+
+    impl ChannelOutput for TestOutput {
+        fn open_channel(&mut self) -> Box<dyn AudioChannel> {
+            self.channels.borrow_mut().push(ChannelState::default());
+            Box::new(TestChannel { index: self.channels.borrow().len() - 1, channels: self.channels.clone() })
+        }
+    }
+
+    impl AudioChannel for TestChannel {
+        fn play(&mut self, _source: SoundSource) {
+            self.channels.borrow_mut()[self.index].playing = true;
+        }
+        fn set_volume(&mut self, volume: f32) {
+            self.channels.borrow_mut()[self.index].volume = volume;
+        }
+        fn set_paused(&mut self, paused: bool) {
+            self.channels.borrow_mut()[self.index].paused = paused;
+        }
+        fn stop(&mut self) {
+            self.channels.borrow_mut()[self.index].playing = false;
+        }
+        fn is_playing(&self) -> bool {
+            self.channels.borrow()[self.index].playing
+        }
+    }
+
+    /// Tracks written to the temp directory, deleted when dropped
+    struct TempTracks(Vec<MusicTrack>);
+
+    impl std::ops::Deref for TempTracks {
+        type Target = Vec<MusicTrack>;
+
+        fn deref(&self) -> &Vec<MusicTrack> {
+            &self.0
+        }
+    }
+
+    impl Drop for TempTracks {
+        fn drop(&mut self) {
+            for track in &self.0 {
+                let _ = fs::remove_file(&track.path);
+            }
+        }
+    }
+
+    /// One second WAV files, streamed from disk like real music
+    fn write_tracks(names: &[&str]) -> TempTracks {
+        TempTracks(names.iter()
+            .map(|name| {
+                let path = std::env::temp_dir().join(format!("generic_game_music_{}_{}.wav", std::process::id(), name));
+                fs::write(&path, wav_bytes(1000, 1000)).unwrap();
+                MusicTrack::new(path, 1.0, None)
+            })
+            .collect())
+    }
+
+    #[test]
+    fn tracks_crossfade() {
+        let output = TestOutput::default();
+        let channels = output.channels.clone();
+        let mut player = MusicPlayer::with_output(Box::new(output));
+        let tracks = write_tracks(&["crossfade_a", "crossfade_b"]);
+
+        player.play(tracks[0].clone(), 0.0).unwrap();
+        player.play(tracks[1].clone(), 1.0).unwrap();
+        player.update(0.25);
+        assert_eq!((channels.borrow()[0].volume, channels.borrow()[1].volume), (0.75, 0.25));
+
+        player.pause();
+        player.update(10.0);
+        assert!(channels.borrow().iter().all(|channel| channel.paused && channel.playing));
+        player.resume();
+
+        player.update(0.75);
+        assert!(!channels.borrow()[0].playing);
+        assert_eq!(channels.borrow()[1].volume, 1.0);
+        assert_eq!(player.get_current_track(), Some(&tracks[1]));
+
+        assert!(matches!(player.play(MusicTrack::new("missing.ogg", 1.0, None), 1.0), Err(SoundError::Io(_))));
+    }
+
+    #[test]
+    fn playlists_fade_into_the_next_track() {
+        let output = TestOutput::default();
+        let channels = output.channels.clone();
+        let mut player = MusicPlayer::with_output(Box::new(output));
+        let tracks = write_tracks(&["playlist_a", "playlist_b"]);
+        let mut playlist = Playlist::new(tracks.clone(), PlaylistMode::Repeat);
+        playlist.crossfade = 0.5;
+
+        player.play_playlist(playlist).unwrap();
+        player.update(0.25);
+        assert_eq!(player.get_current_track(), Some(&tracks[0]));
+        player.update(0.25);
+        assert_eq!(player.get_current_track(), Some(&tracks[1]));
+        assert_eq!(channels.borrow().len(), 2);
+
+        // Repeats from the first track
+        player.update(0.5);
+        assert_eq!(player.get_current_track(), Some(&tracks[0]));
+        assert!(!channels.borrow()[0].playing);
+
+        player.stop(0.0);
+        assert!(!player.is_playing());
+        assert!(player.get_playlist().is_none());
+    }
+
+    #[test]
+    fn last_tracks_play_to_their_end() {
+        let output = TestOutput::default();
+        let channels = output.channels.clone();
+        let mut player = MusicPlayer::with_output(Box::new(output));
+        let tracks = write_tracks(&["once_a", "once_b"]);
+        let mut playlist = Playlist::new(tracks.clone(), PlaylistMode::Once);
+        playlist.crossfade = 4.0;
+
+        player.play_playlist(playlist).unwrap();
+        player.update(0.25);
+        assert_eq!(player.get_current_track(), Some(&tracks[0]));
+        player.update(0.25);
+        assert_eq!(player.get_current_track(), Some(&tracks[1]));
+
+        player.update(5.0);
+        assert_eq!(player.get_current_track(), Some(&tracks[1]));
+        channels.borrow_mut()[1].playing = false;
+        player.update(0.1);
+        assert!(!player.is_playing() && player.get_playlist().is_none());
+
+        let backwards = LoopPoints { start: 0.5, end: Some(0.25) };
+        let track = MusicTrack::new(&tracks[0].path, 1.0, Some(backwards));
+        assert!(matches!(player.play(track, 0.0), Err(SoundError::InvalidLoopPoints)));
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use rodio::Source;
+
+/// A pan shared with the PanSource playing it, from -1 (left) to 1 (right). Kept as the f32's bits
+/// so the audio thread never waits on a lock.
+#[derive(Clone, Debug, Default)]
+pub struct PanControl {
+    pan: Arc<AtomicU32>
+}
+
+impl PanControl {
+    pub fn new(pan: f32) -> Self {
+        let control = PanControl::default();
+        control.set(pan);
+        control
+    }
+
+    pub fn set(&self, pan: f32) {
+        self.pan.store(pan.clamp(-1.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.pan.load(Ordering::Relaxed))
+    }
+}
+
+/// Left and right gains for a pan, both full in the centre so panning never makes a sound louder
+pub fn get_pan_gains(pan: f32) -> (f32, f32) {
+    ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0))
+}
+
+/// Plays a source in stereo, panned between the speakers. Mono sources go to both speakers,
+/// sources with more than two channels are left as they are.
+pub struct PanSource<I> {
+    input: I,
+    pan: PanControl,
+    /// A mono sample's right channel, played after its left
+    right_sample: Option<i16>,
+    /// The stereo channel the next sample is for
+    right_next: bool
+}
+
+impl<I: Source<Item=i16>> PanSource<I> {
+    pub fn new(input: I, pan: PanControl) -> Self {
+        PanSource {
+            input,
+            pan,
+            right_sample: None,
+            right_next: false
+        }
+    }
+}
+
+fn scale(sample: i16, gain: f32) -> i16 {
+    (sample as f32 * gain) as i16
+}
+
+impl<I: Source<Item=i16>> Iterator for PanSource<I> {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if let Some(sample) = self.right_sample.take() {
+            return Some(sample);
+        }
+        let (left, right) = get_pan_gains(self.pan.get());
+        match self.input.channels() {
+            1 => {
+                let sample = self.input.next()?;
+                self.right_sample = Some(scale(sample, right));
+                Some(scale(sample, left))
+            },
+            2 => {
+                let sample = self.input.next()?;
+                let gain = if self.right_next { right } else { left };
+                self.right_next = !self.right_next;
+                Some(scale(sample, gain))
+            },
+            _ => self.input.next()
+        }
+    }
+}
+
+impl<I: Source<Item=i16>> Source for PanSource<I> {
+    fn current_frame_len(&self) -> Option<usize> {
+        let mono = self.input.channels() == 1;
+        self.input.current_frame_len().map(|len| if mono { len * 2 } else { len })
+    }
+
+    fn channels(&self) -> u16 {
+        match self.input.channels() {
+            1 => 2,
+            channels => channels
+        }
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    #[test]
+    fn mono_sounds_are_panned_in_stereo() {
+        let pan = PanControl::new(0.5);
+        let source = PanSource::new(SamplesBuffer::new(1, 100, vec![1000i16, -1000]), pan.clone());
+        assert_eq!(source.channels(), 2);
+        assert_eq!(source.collect::<Vec<i16>>(), vec![500, 1000, -500, -1000]);
+
+        pan.set(-3.0);
+        assert_eq!(pan.get(), -1.0);
+        let source = PanSource::new(SamplesBuffer::new(2, 100, vec![1000i16, 1000]), pan);
+        assert_eq!(source.collect::<Vec<i16>>(), vec![1000, 0]);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::geometry::{Point, Rotation};
+use crate::games::view_details::{ViewDetails2D, PolarViewDetails};
+
+/// How quickly a sound quietens between its reference and max distances
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Rolloff {
+    /// Straight down to silence at the max distance
+    Linear,
+    /// Halving at twice the reference distance, like sound in the open, then cut at the max distance
+    Inverse
+}
+
+/// How a positioned sound's volume and pan follow its place relative to the listener
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Attenuation {
+    /// Full volume up to this distance
+    pub reference_distance: f64,
+    /// Silent from this distance
+    pub max_distance: f64,
+    pub rolloff: Rolloff,
+    /// How far a sound to the side is panned, 0 for not at all and 1 for entirely into one speaker
+    pub pan_amount: f32
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Attenuation {
+            reference_distance: 1.0,
+            max_distance: 10.0,
+            rolloff: Rolloff::Linear,
+            pan_amount: 0.8
+        }
+    }
+}
+
+impl Attenuation {
+    pub fn get_volume(&self, distance: f64) -> f32 {
+        if distance >= self.max_distance {
+            return 0.0;
+        }
+        if distance <= self.reference_distance {
+            return 1.0;
+        }
+        let beyond = distance - self.reference_distance;
+        let volume = match self.rolloff {
+            Rolloff::Linear => 1.0 - beyond / (self.max_distance - self.reference_distance),
+            Rolloff::Inverse => self.reference_distance / (self.reference_distance + beyond)
+        };
+        volume as f32
+    }
+}
+
+/// Where sounds are heard from, usually the camera
+#[derive(Copy, Clone, Debug)]
+pub struct Listener2D {
+    pub position: Point,
+    /// The direction of the top of the screen, with sounds to its right panned right
+    pub up_vector: Point
+}
+
+impl Default for Listener2D {
+    fn default() -> Self {
+        Listener2D {
+            position: Point::zero(),
+            up_vector: Point::y()
+        }
+    }
+}
+
+impl Listener2D {
+    /// The worldview turns the world by the view's rotation angle, so the top of the screen is the
+    /// world turned back the other way rather than the view's up vector
+    pub fn from_view(view: &ViewDetails2D) -> Self {
+        Listener2D {
+            position: view.camera_pos,
+            up_vector: Rotation::new(-view.get_rotation_angle()) * Point::y()
+        }
+    }
+
+    /// At the centre of the polar world, facing the top of the screen. Sound positions are given to
+    /// it through PolarViewDetails::to_cartesian.
+    pub fn from_polar() -> Self {
+        Listener2D::default()
+    }
+
+    /// The volume and pan of a sound at a position
+    pub fn get_volume_and_pan(&self, position: Point, attenuation: &Attenuation) -> (f32, f32) {
+        let relative = position - self.position;
+        let distance = relative.norm();
+        // A zero up vector has no direction, so it's taken as the top of the screen
+        let up = if self.up_vector.norm_squared() > 0.0 { self.up_vector.normalized() } else { Point::y() };
+        let right = Point::new(up.y, -up.x);
+        // Sounds close to the listener come from both speakers rather than flicking between them
+        let divisor = distance.max(attenuation.reference_distance);
+        let side = if divisor > 0.0 { relative.dot(&right) / divisor } else { 0.0 };
+
+        (attenuation.get_volume(distance), (side as f32 * attenuation.pan_amount).clamp(-1.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn sounds_follow_the_camera() {
+        let attenuation = Attenuation { pan_amount: 1.0, ..Default::default() };
+        let mut view = ViewDetails2D { camera_pos: Point::new(5.0, 0.0), ..Default::default() };
+
+        let listener = Listener2D::from_view(&view);
+        assert_eq!(listener.get_volume_and_pan(Point::new(5.5, 0.0), &attenuation), (1.0, 0.5));
+        assert_eq!(listener.get_volume_and_pan(Point::new(10.5, 0.0), &attenuation), (0.5, 1.0));
+        assert_eq!(listener.get_volume_and_pan(Point::new(-5.0, 0.0), &attenuation).0, 0.0);
+
+        // The worldview turns the world a quarter anticlockwise, so what's above the camera is on the left
+        view.set_rotation_angle(PI / 2.0);
+        let (volume, pan) = Listener2D::from_view(&view).get_volume_and_pan(Point::new(5.0, 2.0), &attenuation);
+        assert!((volume - (1.0 - 1.0 / 9.0)).abs() < 1e-6);
+        assert!((pan + 1.0).abs() < 1e-6);
+
+        let inverse = Attenuation { rolloff: Rolloff::Inverse, ..Default::default() };
+        assert_eq!(inverse.get_volume(2.0), 0.5);
+    }
+
+    #[test]
+    fn polar_sounds_are_heard_from_the_centre() {
+        let view = PolarViewDetails { length_circle: 4.0, ..Default::default() };
+        let attenuation = Attenuation { pan_amount: 1.0, ..Default::default() };
+        let position = view.to_cartesian(Point::new(1.0, 2.0));
+        let (volume, pan) = Listener2D::from_polar().get_volume_and_pan(position, &attenuation);
+
+        assert!((volume - (1.0 - 1.0 / 9.0)).abs() < 1e-6);
+        assert!((pan - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn degenerate_listeners_centre_sounds() {
+        let attenuation = Attenuation { reference_distance: 0.0, ..Default::default() };
+        let listener = Listener2D { up_vector: Point::zero(), ..Default::default() };
+
+        assert_eq!(listener.get_volume_and_pan(Point::zero(), &attenuation), (1.0, 0.0));
+        let (_, pan) = listener.get_volume_and_pan(Point::new(1.0, 0.0), &attenuation);
+        assert!((pan - attenuation.pan_amount).abs() < 1e-6);
+    }
+}
@@ -1,5 +1,5 @@
 use std::hash::Hash;
-use rodio::{Decoder, Sink};
+use rodio::{Decoder, Source};
 use std::io::BufReader;
 use std::io::Cursor;
 use crate::sound::*;
@@ -15,13 +15,45 @@ impl<S: Eq + Hash> SoundBank<S> {
         }
     }
 
-    pub fn play(&mut self, sink: &mut Sink) {
-        let decoder = Decoder::new(BufReader::new(Cursor::new(self.data.file))).unwrap();
+    /// Fails for data that isn't a WAV, OGG, FLAC or MP3 file
+    pub fn decode(&self) -> Result<SoundSource, SoundError> {
+        if !has_sound_header(self.data.file) {
+            return Err(SoundError::Decode("Unrecognized format".to_string()));
+        }
+        let decoder = Decoder::new(BufReader::new(Cursor::new(self.data.file)))?;
+
+        Ok(Box::new(decoder))
+    }
+}
+
+impl<S: Eq + Hash + Copy> SoundBank<S> {
+    pub fn get_volume(&self) -> f32 {
+        self.data.volume
+    }
 
-        sink.append(decoder);
+    pub fn get_max_voices(&self) -> Option<usize> {
+        self.data.get_max_voices()
+    }
+
+    /// Plays a source from decode. Volume is relative to the sound's own, pitch 2 plays an octave
+    /// up and twice as fast.
+    pub fn play(&self, output: &mut dyn SoundOutput<S>, mut source: SoundSource, volume: f32, pitch: f32, pan: PanControl) -> Box<dyn AudioChannel> {
+        if pitch != 1.0 {
+            source = Box::new(source.speed(pitch));
+        }
+        let source = Box::new(PanSource::new(source, pan));
 
-        sink.set_volume(self.data.volume);
-        
-        sink.play();
+        output.play(self.data.id, source, self.data.volume * volume)
     }
-}
\ No newline at end of file
+}
+
+/// rodio tries anything it doesn't recognise as MP3, and its MP3 decoder can crash on data that
+/// isn't, so only files starting like a sound file are handed over
+pub fn has_sound_header(file: &[u8]) -> bool {
+    let mpeg_frame = file.len() >= 2 && file[0] == 0xFF && file[1] & 0xE0 == 0xE0;
+    (file.starts_with(b"RIFF") && file.get(8..12) == Some(b"WAVE"))
+        || file.starts_with(b"OggS")
+        || file.starts_with(b"fLaC")
+        || file.starts_with(b"ID3")
+        || mpeg_frame
+}
@@ -3,7 +3,9 @@ use std::hash::Hash;
 pub struct SoundData<S: Eq + Hash> {
     pub id: S,
     pub file: &'static[u8],
-    pub volume: f32
+    pub volume: f32,
+    /// How many of the sound can play at once, None for no limit but SoundPlayer's own
+    max_voices: Option<usize>
 }
 
 impl<S: Eq + Hash> SoundData<S> {
@@ -11,7 +13,17 @@ impl<S: Eq + Hash> SoundData<S> {
         Self {
             id,
             file, 
-            volume
+            volume,
+            max_voices: None
         }
     }
-}
\ No newline at end of file
+
+    pub fn with_max_voices(mut self, max_voices: usize) -> Self {
+        self.max_voices = Some(max_voices);
+        self
+    }
+
+    pub fn get_max_voices(&self) -> Option<usize> {
+        self.max_voices
+    }
+}
@@ -0,0 +1,250 @@
+use rodio::{Sink, Device, Source};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::sound::*;
+
+/// A decoded sound, ready to be played
+pub type SoundSource = Box<dyn Source<Item=i16> + Send>;
+
+/// Where SoundPlayer sends its sounds: the audio device, or nowhere for headless runs and tests.
+/// Each sound gets a channel of its own, for changing it while it plays.
+pub trait SoundOutput<S> {
+    fn play(&mut self, id: S, source: SoundSource, volume: f32) -> Box<dyn AudioChannel>;
+}
+
+/// The default audio device
+pub struct RodioOutput {
+    device: Device
+}
+
+impl RodioOutput {
+    pub fn new() -> Result<Self, SoundError> {
+        let device = rodio::default_output_device().ok_or(SoundError::NoOutputDevice)?;
+
+        Ok(Self {
+            device
+        })
+    }
+}
+
+impl ChannelOutput for RodioOutput {
+    fn open_channel(&mut self) -> Box<dyn AudioChannel> {
+        Box::new(RodioChannel {
+            device: self.device.clone(),
+            sink: None,
+            volume: 1.0,
+            paused: false
+        })
+    }
+}
+
+impl<S> SoundOutput<S> for RodioOutput {
+    fn play(&mut self, _id: S, source: SoundSource, volume: f32) -> Box<dyn AudioChannel> {
+        let mut channel = self.open_channel();
+        channel.set_volume(volume);
+        channel.play(source);
+        channel
+    }
+}
+
+/// Plays nothing, for machines without an audio device
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NullOutput;
+
+impl<S> SoundOutput<S> for NullOutput {
+    fn play(&mut self, _id: S, _source: SoundSource, _volume: f32) -> Box<dyn AudioChannel> {
+        Box::new(NullChannel { playing: true })
+    }
+}
+
+impl ChannelOutput for NullOutput {
+    fn open_channel(&mut self) -> Box<dyn AudioChannel> {
+        Box::new(NullChannel::default())
+    }
+}
+
+/// One sound at a time, kept apart from other sounds so it can be faded and paused as it plays
+pub trait AudioChannel {
+    /// Replaces whatever the channel was playing
+    fn play(&mut self, source: SoundSource);
+    fn set_volume(&mut self, volume: f32);
+    fn set_paused(&mut self, paused: bool);
+    fn stop(&mut self);
+    /// False once the sound has ended or been stopped
+    fn is_playing(&self) -> bool;
+}
+
+/// Outputs that can give a player (e.g. MusicPlayer) channels of its own
+pub trait ChannelOutput {
+    fn open_channel(&mut self) -> Box<dyn AudioChannel>;
+}
+
+/// A sink of its own on the audio device, replaced for each sound since a stopped sink stays stopped
+pub struct RodioChannel {
+    device: Device,
+    sink: Option<Sink>,
+    volume: f32,
+    paused: bool
+}
+
+impl AudioChannel for RodioChannel {
+    fn play(&mut self, source: SoundSource) {
+        let sink = Sink::new(&self.device);
+        sink.set_volume(self.volume);
+        if self.paused {
+            sink.pause();
+        }
+        sink.append(source);
+        self.sink = Some(sink);
+    }
+
+    fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+        if let Some(sink) = &self.sink {
+            sink.set_volume(volume);
+        }
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if let Some(sink) = &self.sink {
+            if paused { sink.pause() } else { sink.play() }
+        }
+    }
+
+    fn stop(&mut self) {
+        // Dropping the sink stops it
+        self.sink = None;
+    }
+
+    fn is_playing(&self) -> bool {
+        self.sink.as_ref().is_some_and(|sink| !sink.empty())
+    }
+}
+
+/// Plays nothing, but counts as playing until stopped so players without audio carry on as usual
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NullChannel {
+    playing: bool
+}
+
+impl AudioChannel for NullChannel {
+    fn play(&mut self, _source: SoundSource) {
+        self.playing = true;
+    }
+    fn set_volume(&mut self, _volume: f32) {}
+    fn set_paused(&mut self, _paused: bool) {}
+    fn stop(&mut self) {
+        self.playing = false;
+    }
+    fn is_playing(&self) -> bool {
+        self.playing
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayedSound<S> {
+    pub id: S,
+    /// Kept up to date while the sound plays
+    pub volume: f32,
+    pub paused: bool,
+    pub playing: bool,
+    pub channels: u16,
+    pub sample_rate: u32,
+    /// None if the decoder can't tell
+    pub duration: Option<Duration>
+}
+
+/// Plays nothing but keeps what would have played, as it changes. Sounds play until stopped or
+/// finish_all is called. Clones share the list, so keep one to check the sounds played by a
+/// SoundPlayer given the other. Meant for tests: every sound played stays in the list, so it
+/// grows for as long as the output is used.
+#[derive(Clone, Debug)]
+pub struct RecordingOutput<S> {
+    played: Arc<Mutex<Vec<PlayedSound<S>>>>,
+    /// How many of the played sounds have been taken
+    taken: Arc<Mutex<usize>>
+}
+
+impl<S> Default for RecordingOutput<S> {
+    fn default() -> Self {
+        Self {
+            played: Arc::new(Mutex::new(Vec::new())),
+            taken: Arc::new(Mutex::new(0))
+        }
+    }
+}
+
+impl<S: Clone> RecordingOutput<S> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn get_played(&self) -> Vec<PlayedSound<S>> {
+        self.played.lock().unwrap().clone()
+    }
+
+    /// The sounds played since the last take
+    pub fn take_played(&self) -> Vec<PlayedSound<S>> {
+        let played = self.played.lock().unwrap();
+        let mut taken = self.taken.lock().unwrap();
+        let new_sounds = played[*taken..].to_vec();
+        *taken = played.len();
+        new_sounds
+    }
+
+    /// As though every sound had played to its end
+    pub fn finish_all(&self) {
+        for sound in self.played.lock().unwrap().iter_mut() {
+            sound.playing = false;
+        }
+    }
+}
+
+impl<S: 'static> SoundOutput<S> for RecordingOutput<S> {
+    fn play(&mut self, id: S, source: SoundSource, volume: f32) -> Box<dyn AudioChannel> {
+        let mut played = self.played.lock().unwrap();
+        played.push(PlayedSound {
+            id,
+            volume,
+            paused: false,
+            playing: true,
+            channels: source.channels(),
+            sample_rate: source.sample_rate(),
+            duration: source.total_duration()
+        });
+        Box::new(RecordingChannel { played: self.played.clone(), index: played.len() - 1 })
+    }
+}
+
+/// A RecordingOutput sound's channel, writing changes back to it
+pub struct RecordingChannel<S> {
+    played: Arc<Mutex<Vec<PlayedSound<S>>>>,
+    index: usize
+}
+
+impl<S> RecordingChannel<S> {
+    fn update<F: FnOnce(&mut PlayedSound<S>)>(&self, change: F) {
+        if let Some(sound) = self.played.lock().unwrap().get_mut(self.index) {
+            change(sound);
+        }
+    }
+}
+
+impl<S> AudioChannel for RecordingChannel<S> {
+    fn play(&mut self, _source: SoundSource) {
+        self.update(|sound| sound.playing = true);
+    }
+    fn set_volume(&mut self, volume: f32) {
+        self.update(|sound| sound.volume = volume);
+    }
+    fn set_paused(&mut self, paused: bool) {
+        self.update(|sound| sound.paused = paused);
+    }
+    fn stop(&mut self) {
+        self.update(|sound| sound.playing = false);
+    }
+    fn is_playing(&self) -> bool {
+        self.played.lock().unwrap().get(self.index).is_some_and(|sound| sound.playing)
+    }
+}
@@ -1,24 +1,119 @@
 use std::hash::Hash;
-use rodio::{Sink, Device};
 use std::collections::HashMap;
+use std::{error, fmt, io};
+use rodio::decoder::DecoderError;
 use crate::sound::*;
+use crate::geometry::Point;
+use crate::debug::{log, log_warn, LogLevel, LogTarget};
+
+#[derive(Debug)]
+pub enum SoundError {
+    Io(io::Error),
+    NoOutputDevice,
+    UnknownSound,
+    Decode(String),
+    /// Every voice is taken by a sound of higher priority
+    NoFreeVoice,
+    /// A loop ending at or before its start
+    InvalidLoopPoints
+}
+
+impl fmt::Display for SoundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SoundError::Io(err) => write!(f, "Could not access sound file: {}", err),
+            SoundError::NoOutputDevice => write!(f, "No audio output device"),
+            SoundError::UnknownSound => write!(f, "No sound loaded with that id"),
+            SoundError::Decode(mes) => write!(f, "Could not decode sound: {}", mes),
+            SoundError::NoFreeVoice => write!(f, "Too many sounds playing"),
+            SoundError::InvalidLoopPoints => write!(f, "Loop ends before it starts")
+        }
+    }
+}
+
+impl error::Error for SoundError {}
+
+impl From<io::Error> for SoundError {
+    fn from(err: io::Error) -> Self {
+        SoundError::Io(err)
+    }
+}
+
+impl From<DecoderError> for SoundError {
+    fn from(err: DecoderError) -> Self {
+        SoundError::Decode(err.to_string())
+    }
+}
+
+/// How many sounds SoundPlayer plays at once unless told otherwise
+pub const DEFAULT_MAX_VOICES: usize = 32;
+
+/// A sound SoundPlayer has played, for changing it while it plays. Handles to sounds that have
+/// ended are ignored.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SoundHandle(u64);
+
+struct Voice<S> {
+    handle: SoundHandle,
+    sound: S,
+    bus: Bus,
+    priority: i32,
+    /// The sound's own volume from its SoundData
+    own_volume: f32,
+    /// Relative to own_volume
+    volume: f32,
+    /// The pan it was played or set at, before any from its position
+    pan: f32,
+    pan_control: PanControl,
+    /// Where in the world it plays from, None for sounds heard the same everywhere
+    position: Option<Point>,
+    fade: Option<Fade>,
+    channel: Box<dyn AudioChannel>
+}
 
 pub struct SoundPlayer<S: Eq + Hash> {
-    sinks: Vec<Sink>,
-    device: Device,
+    output: Box<dyn SoundOutput<S>>,
     sound_effect_decoders: HashMap<S, SoundBank<S>>,
-    _music_decoder: Option<SoundBank<S>>,
+    /// Oldest first
+    voices: Vec<Voice<S>>,
+    next_handle: u64,
+    bus_levels: BusLevels,
+    listener: Listener2D,
+    /// How positioned sounds quieten with distance from the listener
+    pub attenuation: Attenuation,
+    /// How many sounds can play at once, the quietest priority oldest giving way to new ones
+    pub max_voices: usize
 }
 
-impl<'a, S: Eq + Hash + Copy> SoundPlayer<S> {
-    pub fn new(sound_list: Vec<SoundData<S>>) -> Self {
-        let device = rodio::default_output_device().unwrap();
+impl<S: Eq + Hash + Copy> SoundPlayer<S> {
+    /// Plays through the default audio device, failing without one
+    pub fn new(sound_list: Vec<SoundData<S>>) -> Result<Self, SoundError> {
+        Ok(Self::with_output(sound_list, Box::new(RodioOutput::new()?)))
+    }
+
+    /// Like new, but stays silent (logging why) rather than fail without an audio device
+    pub fn new_or_silent(sound_list: Vec<SoundData<S>>) -> Self {
+        let output: Box<dyn SoundOutput<S>> = match RodioOutput::new() {
+            Ok(output) => Box::new(output),
+            Err(err) => {
+                log_warn(LogTarget::Sound, &format!("Sound disabled: {}", err));
+                Box::new(NullOutput)
+            }
+        };
+        Self::with_output(sound_list, output)
+    }
 
+    /// For headless runs and tests, see NullOutput and RecordingOutput
+    pub fn with_output(sound_list: Vec<SoundData<S>>, output: Box<dyn SoundOutput<S>>) -> Self {
         Self {
-            sinks: vec![Sink::new(&device)],
-            device,
+            output,
             sound_effect_decoders: Self::build_sound_decoders(sound_list),
-            _music_decoder: None,
+            voices: Vec::new(),
+            next_handle: 0,
+            bus_levels: BusLevels::default(),
+            listener: Listener2D::default(),
+            attenuation: Attenuation::default(),
+            max_voices: DEFAULT_MAX_VOICES
         }
     }
 
@@ -32,26 +127,322 @@ impl<'a, S: Eq + Hash + Copy> SoundPlayer<S> {
         output
     }
 
+    /// Sounds that fail to play are logged and skipped
     pub fn process_sounds(&mut self, sounds: Vec<S>) {
         for sound in sounds {
-            self.process_sound(sound)
+            match self.play(sound) {
+                Ok(_) => (),
+                // Expected when lots of sounds play at once
+                Err(SoundError::NoFreeVoice) => log(LogLevel::Debug, LogTarget::Sound, "Sound dropped, no free voice"),
+                Err(err) => log_warn(LogTarget::Sound, &format!("Could not play sound: {}", err))
+            }
         }
     }
 
-    fn process_sound(&mut self, sound: S) {
-        let next_available_sink = Self::get_next_available_sink(&self.device, &mut self.sinks);
-        self.sound_effect_decoders.get_mut(&sound).unwrap().play(next_available_sink);
+    pub fn play(&mut self, sound: S) -> Result<SoundHandle, SoundError> {
+        self.play_with(sound, PlayParams::default())
     }
 
-    fn get_next_available_sink(device: &Device, sinks: &'a mut Vec<Sink>) -> &'a mut Sink {
-        let free_sink_pos = sinks.iter_mut().position(|sink| {sink.len() == 0});
+    /// Takes the voice of the lowest priority, oldest sound when too many are playing, failing if
+    /// they all have a higher priority than this one
+    pub fn play_with(&mut self, sound: S, params: PlayParams) -> Result<SoundHandle, SoundError> {
+        self.play_from(sound, params, None)
+    }
+
+    /// Plays from a place in the world, quietened and panned by where it is from the listener
+    pub fn play_at(&mut self, sound: S, position: Point, params: PlayParams) -> Result<SoundHandle, SoundError> {
+        self.play_from(sound, params, Some(position))
+    }
 
-        if free_sink_pos.is_none() {
-            sinks.push(Sink::new(device));
+    fn play_from(&mut self, sound: S, params: PlayParams, position: Option<Point>) -> Result<SoundHandle, SoundError> {
+        self.remove_finished();
+        let bank = self.sound_effect_decoders.get(&sound).ok_or(SoundError::UnknownSound)?;
 
-            return sinks.last_mut().unwrap();
-        } else {
-            return sinks.iter_mut().nth(free_sink_pos.unwrap()).unwrap();
+        // Voices are only stopped once the sound is known to play, so a failure silences nothing
+        let sound_voices = self.voices.iter().filter(|voice| voice.sound == sound).count();
+        let mut stolen = Vec::new();
+        if bank.get_max_voices().is_some_and(|max| sound_voices >= max) {
+            stolen.push(self.find_voice_to_steal(Some(sound), params.priority, &stolen)?);
+        }
+        if self.voices.len() - stolen.len() >= self.max_voices {
+            stolen.push(self.find_voice_to_steal(None, params.priority, &stolen)?);
+        }
+        let source = bank.decode()?;
+        stolen.sort_unstable_by(|a, b| b.cmp(a));
+        for index in stolen {
+            self.voices.remove(index).channel.stop();
+        }
+
+        let (positional_volume, positional_pan) = self.get_positional(position);
+        let pan_control = PanControl::new(params.pan + positional_pan);
+        let level = self.bus_levels.get_level(params.bus) * positional_volume;
+        let channel = bank.play(&mut *self.output, source, params.volume * level, params.pitch, pan_control.clone());
+        let handle = SoundHandle(self.next_handle);
+        self.next_handle += 1;
+        self.voices.push(Voice {
+            handle,
+            sound,
+            bus: params.bus,
+            priority: params.priority,
+            own_volume: bank.get_volume(),
+            volume: params.volume,
+            pan: params.pan,
+            pan_control,
+            position,
+            fade: None,
+            channel
+        });
+
+        Ok(handle)
+    }
+
+    fn get_bank(&self, sound: S) -> Result<&SoundBank<S>, SoundError> {
+        self.sound_effect_decoders.get(&sound).ok_or(SoundError::UnknownSound)
+    }
+
+    /// The index of the lowest priority, oldest voice (of only the sound, if given) that isn't
+    /// above priority or already taken
+    fn find_voice_to_steal(&self, sound: Option<S>, priority: i32, taken: &[usize]) -> Result<usize, SoundError> {
+        self.voices.iter().enumerate()
+            .filter(|(index, voice)| sound.is_none_or(|sound| voice.sound == sound) && !taken.contains(index))
+            .min_by_key(|(_, voice)| voice.priority)
+            .filter(|(_, voice)| voice.priority <= priority)
+            .map(|(index, _)| index)
+            .ok_or(SoundError::NoFreeVoice)
+    }
+
+    fn get_voice(&mut self, handle: SoundHandle) -> Option<&mut Voice<S>> {
+        self.voices.iter_mut().find(|voice| voice.handle == handle)
+    }
+
+    pub fn stop(&mut self, handle: SoundHandle) {
+        if let Some(index) = self.voices.iter().position(|voice| voice.handle == handle) {
+            self.voices.remove(index).channel.stop();
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        for mut voice in self.voices.drain(..) {
+            voice.channel.stop();
         }
     }
-}
\ No newline at end of file
+
+    /// Fades to silence over seconds, then stops
+    pub fn fade_out(&mut self, handle: SoundHandle, seconds: f64) {
+        if let Some(voice) = self.get_voice(handle) {
+            let level = voice.fade.map_or(1.0, |fade| fade.get_level());
+            voice.fade = Some(Fade::new(level, 0.0, seconds));
+        }
+    }
+
+    /// Relative to the sound's own volume, as in PlayParams
+    pub fn set_volume(&mut self, handle: SoundHandle, volume: f32) {
+        if let Some(voice) = self.get_voice(handle) {
+            voice.volume = volume;
+        }
+        self.apply_volumes();
+    }
+
+    pub fn set_pan(&mut self, handle: SoundHandle, pan: f32) {
+        if let Some(voice) = self.get_voice(handle) {
+            voice.pan = pan;
+        }
+        self.apply_volumes();
+    }
+
+    /// The pan the sound is playing at, including any from its position. None once the sound has ended.
+    pub fn get_pan(&self, handle: SoundHandle) -> Option<f32> {
+        self.voices.iter().find(|voice| voice.handle == handle).map(|voice| voice.pan_control.get())
+    }
+
+    /// Moves a sound played with play_at, e.g. to follow whatever is making it
+    pub fn set_position(&mut self, handle: SoundHandle, position: Point) {
+        if let Some(voice) = self.get_voice(handle) {
+            voice.position = Some(position);
+        }
+        self.apply_volumes();
+    }
+
+    /// Usually Listener2D::from_view with the game's view, every frame
+    pub fn set_listener(&mut self, listener: Listener2D) {
+        self.listener = listener;
+        self.apply_volumes();
+    }
+
+    pub fn get_listener(&self) -> Listener2D {
+        self.listener
+    }
+
+    fn get_positional(&self, position: Option<Point>) -> (f32, f32) {
+        position.map_or((1.0, 0.0), |position| self.listener.get_volume_and_pan(position, &self.attenuation))
+    }
+
+    pub fn is_playing(&self, handle: SoundHandle) -> bool {
+        self.voices.iter().any(|voice| voice.handle == handle && voice.channel.is_playing())
+    }
+
+    /// How many sounds are playing
+    pub fn get_voice_count(&self) -> usize {
+        self.voices.iter().filter(|voice| voice.channel.is_playing()).count()
+    }
+
+    pub fn set_bus_levels(&mut self, levels: BusLevels) {
+        self.bus_levels = levels;
+        self.apply_volumes();
+    }
+
+    /// Moves fades along and lets go of finished sounds, t_step in seconds
+    pub fn update(&mut self, t_step: f64) {
+        for voice in self.voices.iter_mut() {
+            if let Some(fade) = &mut voice.fade {
+                fade.update(t_step);
+                if fade.is_done() && fade.get_level() <= 0.0 {
+                    voice.channel.stop();
+                }
+            }
+        }
+        self.remove_finished();
+        self.apply_volumes();
+    }
+
+    fn remove_finished(&mut self) {
+        self.voices.retain(|voice| voice.channel.is_playing());
+    }
+
+    fn apply_volumes(&mut self) {
+        let levels = self.bus_levels;
+        let listener = self.listener;
+        let attenuation = self.attenuation;
+        for voice in self.voices.iter_mut() {
+            let fade = voice.fade.map_or(1.0, |fade| fade.get_level());
+            let (positional_volume, positional_pan) = voice.position
+                .map_or((1.0, 0.0), |position| listener.get_volume_and_pan(position, &attenuation));
+            voice.channel.set_volume(voice.own_volume * voice.volume * levels.get_level(voice.bus) * fade * positional_volume);
+            voice.pan_control.set(voice.pan + positional_pan);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A mono 16 bit WAV file of silence
+    pub(crate) fn wav_bytes(samples: u32, sample_rate: u32) -> Vec<u8> {
+        let data_size = samples * 2;
+        let mut file = Vec::new();
+        file.extend_from_slice(b"RIFF");
+        file.extend_from_slice(&(36 + data_size).to_le_bytes());
+        file.extend_from_slice(b"WAVEfmt ");
+        file.extend_from_slice(&16u32.to_le_bytes());
+        file.extend_from_slice(&1u16.to_le_bytes());
+        file.extend_from_slice(&1u16.to_le_bytes());
+        file.extend_from_slice(&sample_rate.to_le_bytes());
+        file.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        file.extend_from_slice(&2u16.to_le_bytes());
+        file.extend_from_slice(&16u16.to_le_bytes());
+        file.extend_from_slice(b"data");
+        file.extend_from_slice(&data_size.to_le_bytes());
+        file.resize(file.len() + data_size as usize, 0);
+        file
+    }
+
+    lazy_static! {
+        /// A tenth of a second at 44100 Hz
+        pub(crate) static ref TENTH_SECOND_WAV: Vec<u8> = wav_bytes(4410, 44100);
+        /// 100 samples at 8000 Hz
+        pub(crate) static ref SHORT_WAV: Vec<u8> = wav_bytes(100, 8000);
+    }
+
+    #[test]
+    fn failed_sounds_are_errors_not_crashes() {
+        let output = RecordingOutput::new();
+        let mut player = SoundPlayer::with_output(vec![
+            SoundData::new(1, TENTH_SECOND_WAV.as_slice(), 0.5),
+            SoundData::new(2, b"not a sound file", 1.0)
+        ], Box::new(output.clone()));
+
+        assert!(player.play(1).is_ok());
+        assert!(matches!(player.play(2), Err(SoundError::Decode(_))));
+        assert!(matches!(player.play(3), Err(SoundError::UnknownSound)));
+
+        let played = output.take_played();
+        assert_eq!(played.len(), 1);
+        assert_eq!((played[0].id, played[0].volume, played[0].channels, played[0].sample_rate), (1, 0.5, 2, 44100));
+    }
+
+    #[test]
+    fn processing_skips_failed_sounds() {
+        let output = RecordingOutput::new();
+        let mut player = SoundPlayer::with_output(vec![SoundData::new("beep", SHORT_WAV.as_slice(), 1.0)], Box::new(output.clone()));
+
+        player.process_sounds(vec!["missing", "beep", "missing", "beep"]);
+        let played: Vec<&str> = output.take_played().iter().map(|sound| sound.id).collect();
+        assert_eq!(played, vec!["beep", "beep"]);
+    }
+
+    #[test]
+    fn voices_are_limited_and_stolen_by_priority() {
+        let output = RecordingOutput::new();
+        let mut player = SoundPlayer::with_output(vec![
+            SoundData::new("explosion", SHORT_WAV.as_slice(), 1.0).with_max_voices(2),
+            SoundData::new("alarm", SHORT_WAV.as_slice(), 1.0)
+        ], Box::new(output.clone()));
+        player.max_voices = 3;
+
+        let first = player.play("explosion").unwrap();
+        let second = player.play("explosion").unwrap();
+        let third = player.play("explosion").unwrap();
+        assert!(!player.is_playing(first));
+        assert!(player.is_playing(second) && player.is_playing(third));
+
+        let alarm = player.play_with("alarm", PlayParams { priority: 1, ..Default::default() }).unwrap();
+        assert!(player.play_with("alarm", PlayParams { priority: 2, ..Default::default() }).is_ok());
+        assert!(!player.is_playing(second));
+        assert!(matches!(player.play_with("alarm", PlayParams { priority: -1, ..Default::default() }), Err(SoundError::NoFreeVoice)));
+        assert_eq!(player.get_voice_count(), 3);
+
+        player.fade_out(alarm, 1.0);
+        player.update(0.5);
+        assert_eq!(output.get_played()[3].volume, 0.5);
+        player.update(0.5);
+        assert!(!player.is_playing(alarm));
+
+        output.finish_all();
+        player.update(0.1);
+        assert_eq!(player.get_voice_count(), 0);
+        let played: Vec<&str> = output.take_played().iter().map(|sound| sound.id).collect();
+        assert_eq!(played, vec!["explosion", "explosion", "explosion", "alarm", "alarm"]);
+    }
+
+    #[test]
+    fn failed_sounds_steal_nothing() {
+        let output = RecordingOutput::new();
+        let mut player = SoundPlayer::with_output(vec![
+            SoundData::new("engine", SHORT_WAV.as_slice(), 1.0),
+            SoundData::new("broken", &b"not a sound"[..], 1.0)
+        ], Box::new(output.clone()));
+        player.max_voices = 1;
+
+        let engine = player.play("engine").unwrap();
+        assert!(matches!(player.play("broken"), Err(SoundError::Decode(_))));
+        assert!(player.is_playing(engine));
+    }
+
+    #[test]
+    fn positioned_sounds_follow_the_listener() {
+        let output = RecordingOutput::new();
+        let mut player = SoundPlayer::with_output(vec![SoundData::new(0, SHORT_WAV.as_slice(), 1.0)], Box::new(output.clone()));
+        player.attenuation = Attenuation { pan_amount: 1.0, ..Default::default() };
+
+        let engine = player.play_at(0, Point::new(5.5, 0.0), PlayParams::default()).unwrap();
+        assert_eq!((output.get_played()[0].volume, player.get_pan(engine)), (0.5, Some(1.0)));
+
+        player.set_listener(Listener2D { position: Point::new(5.0, 0.0), ..Default::default() });
+        assert_eq!((output.get_played()[0].volume, player.get_pan(engine)), (1.0, Some(0.5)));
+
+        player.set_position(engine, Point::new(-5.0, 0.0));
+        player.set_pan(engine, 0.25);
+        assert_eq!((output.get_played()[0].volume, player.get_pan(engine)), (0.0, Some(-0.75)));
+    }
+}
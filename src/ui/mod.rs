@@ -0,0 +1,13 @@
+pub mod ui_input;
+pub mod ui_theme;
+pub mod ui_layout;
+pub mod ui_context;
+pub mod widgets;
+
+pub use self::ui_input::UiInput;
+pub use self::ui_theme::UiTheme;
+pub use self::ui_layout::{UiRect, Anchor, LayoutDirection};
+pub use self::ui_context::{Ui, Interaction, get_display_label};
+pub(crate) use self::ui_layout::{Container, ScrollArea};
+
+pub type WidgetId = u64;
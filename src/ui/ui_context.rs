@@ -0,0 +1,386 @@
+use crate::rendering::*;
+use crate::geometry::*;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use super::*;
+
+/// Immediate mode UI: call begin, then the widget functions (which report interactions straight away),
+/// then end to collect the renderables for the frame
+/// The Ui itself only remembers focus, drags, carets and scroll positions between frames
+pub struct Ui {
+    pub theme: UiTheme,
+    pub(crate) input: UiInput,
+    pub(crate) previous_input: UiInput,
+    aspect_ratio: f64,
+    cursor: Option<Point>,
+    virtual_cursor: Option<Point>,
+    containers: Vec<Container>,
+    id_stack: Vec<WidgetId>,
+    focus: Option<WidgetId>,
+    focus_order: Vec<WidgetId>,
+    focus_navigated: bool,
+    pub(crate) focus_takes_horizontal: bool,
+    active: Option<WidgetId>,
+    pointer_captured: bool,
+    pub(crate) text_carets: HashMap<WidgetId, usize>,
+    pub(crate) scroll_offsets: HashMap<WidgetId, f64>,
+    renderables: Vec<Box<StandardRenderable>>
+}
+
+/// What happened to a widget this frame
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Interaction {
+    pub visible: bool,
+    pub hovered: bool,
+    pub pressed: bool,
+    pub focused: bool,
+    pub clicked: bool
+}
+
+impl Ui {
+    pub fn new(theme: UiTheme) -> Self {
+        Self {
+            theme,
+            input: UiInput::default(),
+            previous_input: UiInput::default(),
+            aspect_ratio: 1.0,
+            cursor: None,
+            virtual_cursor: None,
+            containers: Vec::new(),
+            id_stack: Vec::new(),
+            focus: None,
+            focus_order: Vec::new(),
+            focus_navigated: false,
+            focus_takes_horizontal: false,
+            active: None,
+            pointer_captured: false,
+            text_carets: HashMap::new(),
+            scroll_offsets: HashMap::new(),
+            renderables: Vec::new()
+        }
+    }
+
+    pub fn begin(&mut self, input: UiInput, window_spec: WindowSpec) {
+        self.previous_input = mem::replace(&mut self.input, input);
+        self.aspect_ratio = if window_spec.aspect_ratio > 0.0 { window_spec.aspect_ratio } else { 1.0 };
+        self.update_cursor();
+        self.update_focus_navigation();
+
+        let screen = UiRect::new(Point::new(-self.aspect_ratio, -1.0), Point::new(self.aspect_ratio, 1.0));
+        self.containers = vec![Container::new(LayoutDirection::Column, screen.shrink(self.theme.padding), None)];
+        self.id_stack.clear();
+        self.pointer_captured = false;
+    }
+
+    pub fn end(&mut self) -> Vec<Box<StandardRenderable>> {
+        // Clicking away from every widget drops focus
+        if self.pointer_pressed() && !self.pointer_captured {
+            self.focus = None;
+        }
+        if !self.input.pointer_down {
+            self.active = None;
+        }
+
+        if let Some(cursor) = self.virtual_cursor {
+            let dim = Point::new(self.theme.cursor_size, self.theme.cursor_size);
+            self.draw_rect(UiRect::new(cursor - Point::new(0.0, dim.y), cursor + Point::new(dim.x, 0.0)), self.theme.focus_color);
+        }
+
+        mem::take(&mut self.renderables)
+    }
+
+    fn update_cursor(&mut self) {
+        match self.input.cursor {
+            Some(cursor) => {
+                self.cursor = Some(cursor);
+                self.virtual_cursor = None;
+            },
+            None => {
+                let movement = self.input.cursor_movement;
+                if self.virtual_cursor.is_some() || movement.norm_squared() > 0.0 {
+                    let moved = self.virtual_cursor.unwrap_or_else(Point::zero) + movement;
+                    let clamped = Point::new(moved.x.clamp(-self.aspect_ratio, self.aspect_ratio), moved.y.clamp(-1.0, 1.0));
+                    self.virtual_cursor = Some(clamped);
+                }
+                self.cursor = self.virtual_cursor;
+            }
+        }
+    }
+
+    /// Up/down (and left/right unless the focused widget uses them) step through widgets in the order they were added
+    fn update_focus_navigation(&mut self) {
+        let horizontal = !self.focus_takes_horizontal;
+        let previous = self.pressed(|input| input.up) || (horizontal && self.pressed(|input| input.left));
+        let next = self.pressed(|input| input.down) || (horizontal && self.pressed(|input| input.right));
+        let order = mem::take(&mut self.focus_order);
+        self.focus_takes_horizontal = false;
+        self.focus_navigated = false;
+
+        if previous == next || order.is_empty() {
+            return;
+        }
+
+        let count = order.len();
+        let position = self.focus.and_then(|focus| order.iter().position(|&id| id == focus));
+        let index = match (position, next) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(position), true) => (position + 1) % count,
+            (Some(position), false) => (position + count - 1) % count
+        };
+        self.focus = Some(order[index]);
+        self.focus_navigated = true;
+    }
+
+    pub(crate) fn pressed<F: Fn(&UiInput) -> bool>(&self, button: F) -> bool {
+        button(&self.input) && !button(&self.previous_input)
+    }
+
+    pub fn pointer_pressed(&self) -> bool {
+        self.pressed(|input| input.pointer_down)
+    }
+
+    pub fn pointer_released(&self) -> bool {
+        !self.input.pointer_down && self.previous_input.pointer_down
+    }
+
+    pub fn back_pressed(&self) -> bool {
+        self.pressed(|input| input.back)
+    }
+
+    pub fn get_cursor(&self) -> Option<Point> {
+        self.cursor
+    }
+
+    pub fn get_aspect_ratio(&self) -> f64 {
+        self.aspect_ratio
+    }
+
+    pub fn get_focus(&self) -> Option<WidgetId> {
+        self.focus
+    }
+
+    pub fn set_focus(&mut self, focus: Option<WidgetId>) {
+        self.focus = focus;
+    }
+
+    /// Labels can carry a hidden suffix after "##" so identical labels get different ids
+    pub fn get_id(&self, label: &str) -> WidgetId {
+        let mut hasher = DefaultHasher::new();
+        self.id_stack.last().hash(&mut hasher);
+        label.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Scopes ids of the widgets added until the matching pop_id (e.g. for each item of a list)
+    pub fn push_id(&mut self, label: &str) {
+        let id = self.get_id(label);
+        self.id_stack.push(id);
+    }
+
+    pub fn pop_id(&mut self) {
+        self.id_stack.pop();
+    }
+
+    pub fn begin_panel(&mut self, anchor: Anchor, offset: Point, dim: Point) {
+        let rect = anchor.place(self.aspect_ratio, offset, dim);
+        self.draw_rect(rect, self.theme.panel_color);
+        self.draw_border(rect, self.theme.widget_color);
+
+        let clip = self.get_clip();
+        self.containers.push(Container::new(LayoutDirection::Column, rect.shrink(self.theme.padding), clip));
+    }
+
+    pub fn end_panel(&mut self) {
+        self.pop_container();
+    }
+
+    /// Widgets until end_row are placed left to right
+    pub fn begin_row(&mut self) {
+        self.begin_layout(LayoutDirection::Row, None);
+    }
+
+    pub fn end_row(&mut self) {
+        self.end_layout();
+    }
+
+    /// Widgets until end_column are stacked top to bottom, width fills the parent when not given
+    pub fn begin_column(&mut self, width: Option<f64>) {
+        self.begin_layout(LayoutDirection::Column, width);
+    }
+
+    pub fn end_column(&mut self) {
+        self.end_layout();
+    }
+
+    fn begin_layout(&mut self, direction: LayoutDirection, width: Option<f64>) {
+        let parent = self.get_container();
+        let right = match width {
+            Some(width) => parent.cursor.x + width,
+            None => parent.rect.max.x
+        };
+        let rect = UiRect::new(Point::new(parent.cursor.x, parent.rect.min.y), Point::new(right, parent.cursor.y));
+        let clip = parent.clip;
+        self.containers.push(Container::new(direction, rect, clip));
+    }
+
+    fn end_layout(&mut self) {
+        if let Some(layout) = self.pop_container() {
+            let dim = Point::new(layout.extent.x - layout.rect.min.x, layout.rect.max.y - layout.extent.y);
+            self.allocate(dim);
+        }
+    }
+
+    pub(crate) fn pop_container(&mut self) -> Option<Container> {
+        // The screen container always stays
+        if self.containers.len() > 1 {
+            self.containers.pop()
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn push_container(&mut self, container: Container) {
+        self.containers.push(container);
+    }
+
+    pub(crate) fn get_container(&self) -> &Container {
+        self.containers.last().unwrap()
+    }
+
+    pub(crate) fn get_clip(&self) -> Option<UiRect> {
+        self.get_container().clip
+    }
+
+    /// Next free rect in the current layout
+    pub fn allocate(&mut self, dim: Point) -> UiRect {
+        let spacing = self.theme.spacing;
+        self.containers.last_mut().unwrap().allocate(dim, spacing)
+    }
+
+    pub fn get_remaining_space(&self) -> Point {
+        self.get_container().get_remaining()
+    }
+
+    pub fn is_visible(&self, rect: &UiRect) -> bool {
+        match self.get_clip() {
+            Some(clip) => clip.contains_rect(rect),
+            None => true
+        }
+    }
+
+    /// Registers a focusable widget occupying rect and works out how it's being used
+    pub fn interact(&mut self, id: WidgetId, rect: UiRect) -> Interaction {
+        self.focus_order.push(id);
+        let visible = self.is_visible(&rect);
+
+        if self.focus == Some(id) && self.focus_navigated && !visible {
+            self.scroll_into_view(rect);
+        }
+
+        let hovered = visible && match self.cursor {
+            Some(cursor) => rect.contains(cursor),
+            None => false
+        };
+        if hovered && self.pointer_pressed() {
+            self.active = Some(id);
+            self.focus = Some(id);
+            self.pointer_captured = true;
+        }
+
+        let focused = self.focus == Some(id);
+        let is_active = self.active == Some(id);
+        Interaction {
+            visible,
+            hovered,
+            pressed: is_active && self.input.pointer_down,
+            focused,
+            clicked: (is_active && hovered && self.pointer_released()) || (focused && self.pressed(|input| input.activate))
+        }
+    }
+
+    fn scroll_into_view(&mut self, rect: UiRect) {
+        let container = match self.containers.iter().rev().find(|container| container.scroll.is_some()) {
+            Some(container) => container,
+            None => return
+        };
+        let area = container.scroll.unwrap();
+        let viewport = container.rect;
+        let offset = self.scroll_offsets.entry(area.id).or_insert(0.0);
+
+        if rect.max.y > viewport.max.y {
+            *offset -= rect.max.y - viewport.max.y;
+        } else if rect.min.y < viewport.min.y {
+            *offset += viewport.min.y - rect.min.y;
+        }
+    }
+
+    pub(crate) fn get_text_style(&self, color: Color) -> TextStyle {
+        TextStyle::new(DEFAULT_FONT, self.theme.text_size, color)
+    }
+
+    pub fn get_text_width(&self, text: &str) -> f64 {
+        measure_text_width(text, &self.get_text_style(self.theme.text_color))
+    }
+
+    /// Baseline that centres a line of text vertically in rect
+    pub(crate) fn get_text_baseline(&self, rect: UiRect) -> f64 {
+        let layout = TextLayout::from_text("", &self.get_text_style(self.theme.text_color));
+        let line = layout.lines[0];
+        rect.get_center().y - (line.ascent - line.descent) / 2.0
+    }
+
+    pub fn draw(&mut self, renderable: Box<StandardRenderable>) {
+        self.renderables.push(renderable);
+    }
+
+    pub fn draw_rect(&mut self, rect: UiRect, color: Color) {
+        let center = rect.get_center();
+        let position = Point3::new(center.x, center.y, self.theme.depth);
+        self.draw(Box::new(RectanglePrimitive::new_regular(rect.get_width(), rect.get_height(), position, color, true)));
+    }
+
+    pub fn draw_border(&mut self, rect: UiRect, color: Color) {
+        let center = rect.get_center();
+        let position = Point3::new(center.x, center.y, self.theme.depth);
+        self.draw(Box::new(BoxBorder::new(self.theme.border_thickness, position, rect.get_height(), rect.get_width(), color, true)));
+    }
+
+    /// Text starting at left, centred vertically in rect
+    pub fn draw_text(&mut self, text: &str, left: f64, rect: UiRect, color: Color) {
+        if text.trim().is_empty() {
+            return;
+        }
+        let baseline = self.get_text_baseline(rect);
+        let size = self.theme.text_size;
+        self.draw(Box::new(PlainText {
+            content: text.to_string(),
+            position: Point3::new(left, baseline, self.theme.depth),
+            scale: Point::new(size, size),
+            color,
+            align: TextAlign::Origin,
+            ..Default::default()
+        }));
+    }
+
+    pub fn draw_text_centered(&mut self, text: &str, rect: UiRect, color: Color) {
+        let left = rect.get_center().x - self.get_text_width(text) / 2.0;
+        self.draw_text(text, left, rect, color);
+    }
+}
+
+impl Default for Ui {
+    fn default() -> Self {
+        Self::new(UiTheme::default())
+    }
+}
+
+/// Part of a label before any "##" id suffix
+pub fn get_display_label(label: &str) -> &str {
+    match label.find("##") {
+        Some(index) => &label[..index],
+        None => label
+    }
+}
@@ -0,0 +1,57 @@
+use crate::input::{KeyboardInput, MouseInput, JoystickInput, HatSwitch};
+use crate::geometry::*;
+
+/// Screen distance the virtual cursor moves per unit of raw mouse movement
+pub const MOUSE_SENSITIVITY: f64 = 0.002;
+pub const JOYSTICK_NAVIGATION_DEADZONE: f64 = 0.5;
+
+/// Everything the UI reads in a frame (held state, presses are found by comparing frames)
+/// Positions are fixed screen coordinates: y runs from -1 at the bottom to 1 at the top
+/// and x from -aspect_ratio to aspect_ratio
+#[derive(Clone, Debug, Default)]
+pub struct UiInput {
+    /// Absolute cursor position, when None the UI moves a virtual cursor by cursor_movement
+    pub cursor: Option<Point>,
+    pub cursor_movement: Point,
+    pub pointer_down: bool,
+    pub scroll: f64,
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub activate: bool,
+    pub back: bool,
+    pub backspace: bool,
+    pub delete: bool,
+    pub held_chars: Vec<char>
+}
+
+impl UiInput {
+    /// Arrows/hat/left stick navigate, return/space/button 1 activate and escape/button 2 go back
+    pub fn from_devices(kbd: &KeyboardInput, mouse: &MouseInput, joystick: &JoystickInput) -> Self {
+        let hat = joystick.get_hat_switch();
+        let movement = mouse.devices.iter()
+            .fold(Point::zero(), |acc, device| acc + Point::new(device.movement.0 as f64, device.movement.1 as f64));
+
+        Self {
+            cursor: None,
+            // Raw mouse movement counts y downwards
+            cursor_movement: MOUSE_SENSITIVITY * Point::new(movement.x, -movement.y),
+            pointer_down: mouse.get_left_button(),
+            scroll: 0.0,
+            up: kbd.get_up() || hat == HatSwitch::Up || hat == HatSwitch::UpLeft || hat == HatSwitch::UpRight
+                || joystick.get_y_axis_left_flag(JOYSTICK_NAVIGATION_DEADZONE),
+            down: kbd.get_down() || hat == HatSwitch::Down || hat == HatSwitch::DownLeft || hat == HatSwitch::DownRight
+                || joystick.get_y_axis_right_flag(JOYSTICK_NAVIGATION_DEADZONE),
+            left: kbd.get_left() || hat == HatSwitch::Left || hat == HatSwitch::UpLeft || hat == HatSwitch::DownLeft
+                || joystick.get_x_axis_left_flag(JOYSTICK_NAVIGATION_DEADZONE),
+            right: kbd.get_right() || hat == HatSwitch::Right || hat == HatSwitch::UpRight || hat == HatSwitch::DownRight
+                || joystick.get_x_axis_right_flag(JOYSTICK_NAVIGATION_DEADZONE),
+            activate: kbd.get_ret() || kbd.get_space() || joystick.get_button_1(),
+            back: kbd.get_escape() || joystick.get_button_2(),
+            backspace: kbd.get_backspace(),
+            delete: kbd.get_delete(),
+            held_chars: kbd.get_held_chars()
+        }
+    }
+}
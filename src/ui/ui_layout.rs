@@ -0,0 +1,183 @@
+use crate::geometry::*;
+use super::WidgetId;
+
+/// Axis aligned box in fixed screen coordinates
+#[derive(Copy, Clone, Debug)]
+pub struct UiRect {
+    pub min: Point,
+    pub max: Point
+}
+
+impl UiRect {
+    pub fn new(min: Point, max: Point) -> Self {
+        Self {
+            min,
+            max
+        }
+    }
+
+    pub fn from_top_left(top_left: Point, dim: Point) -> Self {
+        Self::new(Point::new(top_left.x, top_left.y - dim.y), Point::new(top_left.x + dim.x, top_left.y))
+    }
+
+    pub fn get_width(&self) -> f64 {
+        self.max.x - self.min.x
+    }
+
+    pub fn get_height(&self) -> f64 {
+        self.max.y - self.min.y
+    }
+
+    pub fn get_center(&self) -> Point {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn get_top_left(&self) -> Point {
+        Point::new(self.min.x, self.max.y)
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+
+    pub fn contains_rect(&self, other: &UiRect) -> bool {
+        self.contains(other.min) && self.contains(other.max)
+    }
+
+    pub fn intersect(&self, other: &UiRect) -> UiRect {
+        let min = Point::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y));
+        let max = Point::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y));
+        UiRect::new(min, Point::new(max.x.max(min.x), max.y.max(min.y)))
+    }
+
+    pub fn shrink(&self, amount: f64) -> UiRect {
+        UiRect::new(self.min + Point::new(amount, amount), self.max - Point::new(amount, amount))
+    }
+}
+
+/// Point of the screen (or of a panel) that a panel is pinned to
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight
+}
+
+impl Anchor {
+    /// -1, 0 or 1 along each axis
+    pub fn get_factors(self) -> Point {
+        match self {
+            Anchor::TopLeft => Point::new(-1.0, 1.0),
+            Anchor::Top => Point::new(0.0, 1.0),
+            Anchor::TopRight => Point::new(1.0, 1.0),
+            Anchor::Left => Point::new(-1.0, 0.0),
+            Anchor::Center => Point::new(0.0, 0.0),
+            Anchor::Right => Point::new(1.0, 0.0),
+            Anchor::BottomLeft => Point::new(-1.0, -1.0),
+            Anchor::Bottom => Point::new(0.0, -1.0),
+            Anchor::BottomRight => Point::new(1.0, -1.0)
+        }
+    }
+
+    /// Screen spans -aspect_ratio to aspect_ratio horizontally and -1 to 1 vertically
+    /// The same point of the panel sits on the anchor, so a TopRight panel hugs the top right corner
+    pub fn place(self, aspect_ratio: f64, offset: Point, dim: Point) -> UiRect {
+        let factors = self.get_factors();
+        let anchor_point = Point::new(factors.x * aspect_ratio, factors.y);
+        let center = anchor_point + offset - 0.5 * Point::new(factors.x * dim.x, factors.y * dim.y);
+        UiRect::new(center - 0.5 * dim, center + 0.5 * dim)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LayoutDirection {
+    Row,
+    Column
+}
+
+/// Area widgets are currently being placed into
+#[derive(Clone, Debug)]
+pub(crate) struct Container {
+    pub direction: LayoutDirection,
+    pub rect: UiRect,
+    pub cursor: Point,
+    pub extent: Point,
+    pub clip: Option<UiRect>,
+    pub scroll: Option<ScrollArea>
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct ScrollArea {
+    pub id: WidgetId,
+    pub content_top: f64
+}
+
+impl Container {
+    pub fn new(direction: LayoutDirection, rect: UiRect, clip: Option<UiRect>) -> Self {
+        Self {
+            direction,
+            rect,
+            cursor: rect.get_top_left(),
+            extent: rect.get_top_left(),
+            clip,
+            scroll: None
+        }
+    }
+
+    /// Columns stretch widgets to their width, rows use the widgets' own width
+    pub fn allocate(&mut self, dim: Point, spacing: f64) -> UiRect {
+        let dim = match self.direction {
+            LayoutDirection::Column => Point::new(self.rect.get_width(), dim.y),
+            LayoutDirection::Row => dim
+        };
+        let rect = UiRect::from_top_left(self.cursor, dim);
+
+        self.extent = Point::new(self.extent.x.max(rect.max.x), self.extent.y.min(rect.min.y));
+        match self.direction {
+            LayoutDirection::Column => self.cursor.y -= dim.y + spacing,
+            LayoutDirection::Row => self.cursor.x += dim.x + spacing
+        }
+
+        rect
+    }
+
+    /// Space left before the far edge along the layout direction
+    pub fn get_remaining(&self) -> Point {
+        Point::new(self.rect.max.x - self.cursor.x, self.cursor.y - self.rect.min.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anchored_panels_hug_their_corner() {
+        let dim = Point::new(0.4, 0.2);
+        let top_right = Anchor::TopRight.place(1.5, Point::zero(), dim);
+        assert!((top_right.max.x - 1.5).abs() < 1e-9);
+        assert!((top_right.max.y - 1.0).abs() < 1e-9);
+
+        let center = Anchor::Center.place(1.5, Point::new(0.1, 0.0), dim);
+        assert!((center.get_center().x - 0.1).abs() < 1e-9);
+        assert!((center.get_width() - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn columns_stack_downwards() {
+        let mut column = Container::new(LayoutDirection::Column, UiRect::new(Point::new(0.0, 0.0), Point::new(1.0, 1.0)), None);
+        let first = column.allocate(Point::new(0.2, 0.1), 0.05);
+        let second = column.allocate(Point::new(0.2, 0.1), 0.05);
+
+        assert!((first.get_width() - 1.0).abs() < 1e-9);
+        assert!((first.max.y - 1.0).abs() < 1e-9);
+        assert!((second.max.y - 0.85).abs() < 1e-9);
+        assert!((column.extent.y - 0.75).abs() < 1e-9);
+    }
+}
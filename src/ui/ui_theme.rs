@@ -0,0 +1,44 @@
+use crate::rendering::Color;
+
+/// Colours and sizes every widget is drawn with (sizes are in fixed screen units)
+#[derive(Copy, Clone, Debug)]
+pub struct UiTheme {
+    pub text_color: Color,
+    pub text_size: f64,
+    pub panel_color: Color,
+    pub widget_color: Color,
+    pub hovered_color: Color,
+    pub pressed_color: Color,
+    pub accent_color: Color,
+    pub focus_color: Color,
+    pub border_thickness: f64,
+    pub padding: f64,
+    pub spacing: f64,
+    pub widget_height: f64,
+    pub scrollbar_width: f64,
+    pub cursor_size: f64,
+    /// UI is drawn at this z, in front of the world
+    pub depth: f64
+}
+
+impl Default for UiTheme {
+    fn default() -> Self {
+        Self {
+            text_color: Color::white(),
+            text_size: 0.06,
+            panel_color: Color::new(0.08, 0.08, 0.1, 0.9),
+            widget_color: Color::new(0.2, 0.2, 0.25, 1.0),
+            hovered_color: Color::new(0.3, 0.3, 0.38, 1.0),
+            pressed_color: Color::new(0.14, 0.14, 0.18, 1.0),
+            accent_color: Color::new(0.3, 0.6, 0.9, 1.0),
+            focus_color: Color::new(0.95, 0.75, 0.2, 1.0),
+            border_thickness: 0.006,
+            padding: 0.025,
+            spacing: 0.015,
+            widget_height: 0.09,
+            scrollbar_width: 0.02,
+            cursor_size: 0.015,
+            depth: -0.5
+        }
+    }
+}
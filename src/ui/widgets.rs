@@ -0,0 +1,343 @@
+use crate::rendering::*;
+use crate::geometry::*;
+use super::*;
+
+/// Fraction of a slider's range one left/right press moves it
+const SLIDER_KEY_STEP: f64 = 0.05;
+
+impl Ui {
+    fn get_widget_color(&self, interaction: &Interaction) -> Color {
+        if interaction.pressed {
+            self.theme.pressed_color
+        } else if interaction.hovered {
+            self.theme.hovered_color
+        } else {
+            self.theme.widget_color
+        }
+    }
+
+    fn draw_focus(&mut self, rect: UiRect, interaction: &Interaction) {
+        if interaction.focused {
+            self.draw_border(rect, self.theme.focus_color);
+        }
+    }
+
+    pub fn label(&mut self, text: &str) {
+        let dim = Point::new(self.get_text_width(text), self.theme.widget_height);
+        let rect = self.allocate(dim);
+        if self.is_visible(&rect) {
+            self.draw_text(text, rect.min.x, rect, self.theme.text_color);
+        }
+    }
+
+    /// True on the frame the button is clicked (or activated while focused)
+    pub fn button(&mut self, label: &str) -> bool {
+        let id = self.get_id(label);
+        let text = get_display_label(label);
+        let dim = Point::new(self.get_text_width(text) + 2.0 * self.theme.padding, self.theme.widget_height);
+        let rect = self.allocate(dim);
+        let interaction = self.interact(id, rect);
+
+        if interaction.visible {
+            let color = self.get_widget_color(&interaction);
+            self.draw_rect(rect, color);
+            self.draw_text_centered(text, rect, self.theme.text_color);
+            self.draw_focus(rect, &interaction);
+        }
+
+        interaction.clicked
+    }
+
+    /// Full width button for list entries, highlighted while selected
+    pub fn selectable(&mut self, label: &str, selected: bool) -> bool {
+        let id = self.get_id(label);
+        let text = get_display_label(label);
+        let dim = Point::new(self.get_text_width(text) + 2.0 * self.theme.padding, self.theme.widget_height);
+        let rect = self.allocate(dim);
+        let interaction = self.interact(id, rect);
+
+        if interaction.visible {
+            let color = if selected { self.theme.accent_color } else { self.get_widget_color(&interaction) };
+            self.draw_rect(rect, color);
+            self.draw_text(text, rect.min.x + self.theme.padding, rect, self.theme.text_color);
+            self.draw_focus(rect, &interaction);
+        }
+
+        interaction.clicked
+    }
+
+    /// Returns true when the value was toggled
+    pub fn checkbox(&mut self, label: &str, checked: &mut bool) -> bool {
+        let id = self.get_id(label);
+        let text = get_display_label(label);
+        let box_size = 0.6 * self.theme.widget_height;
+        let dim = Point::new(box_size + self.theme.padding + self.get_text_width(text), self.theme.widget_height);
+        let rect = self.allocate(dim);
+        let interaction = self.interact(id, rect);
+
+        if interaction.clicked {
+            *checked = !*checked;
+        }
+
+        if interaction.visible {
+            let box_top_left = Point::new(rect.min.x, rect.get_center().y + box_size / 2.0);
+            let box_rect = UiRect::from_top_left(box_top_left, Point::new(box_size, box_size));
+            let color = self.get_widget_color(&interaction);
+            self.draw_rect(box_rect, color);
+            if *checked {
+                self.draw_rect(box_rect.shrink(box_size / 5.0), self.theme.accent_color);
+            }
+            self.draw_text(text, box_rect.max.x + self.theme.padding, rect, self.theme.text_color);
+            self.draw_focus(rect, &interaction);
+        }
+
+        interaction.clicked
+    }
+
+    /// Drag the knob, or press left/right while focused; returns true when the value changed
+    pub fn slider(&mut self, label: &str, value: &mut f64, min: f64, max: f64) -> bool {
+        let id = self.get_id(label);
+        let text = format!("{} {:.2}", get_display_label(label), *value);
+        let dim = Point::new(self.get_text_width(&text) + 2.0 * self.theme.padding + 0.4, self.theme.widget_height);
+        let rect = self.allocate(dim);
+        let interaction = self.interact(id, rect);
+        let old_value = *value;
+
+        let track_left = rect.min.x + self.get_text_width(&text) + 2.0 * self.theme.padding;
+        let track = UiRect::new(
+            Point::new(track_left, rect.get_center().y - self.theme.border_thickness),
+            Point::new(rect.max.x - self.theme.padding, rect.get_center().y + self.theme.border_thickness));
+
+        if interaction.focused {
+            self.focus_takes_horizontal = true;
+            let step = SLIDER_KEY_STEP * (max - min);
+            if self.pressed(|input| input.left) {
+                *value -= step;
+            }
+            if self.pressed(|input| input.right) {
+                *value += step;
+            }
+        }
+        if interaction.pressed && track.get_width() > 0.0 {
+            if let Some(cursor) = self.get_cursor() {
+                *value = min + (cursor.x - track.min.x) / track.get_width() * (max - min);
+            }
+        }
+        *value = value.max(min.min(max)).min(max.max(min));
+
+        if interaction.visible {
+            let color = self.get_widget_color(&interaction);
+            self.draw_rect(rect, color);
+            self.draw_text(&text, rect.min.x + self.theme.padding, rect, self.theme.text_color);
+            self.draw_rect(track, self.theme.panel_color);
+
+            let fraction = if max != min { (*value - min) / (max - min) } else { 0.0 };
+            let knob_center = Point::new(track.min.x + fraction * track.get_width(), track.get_center().y);
+            let knob_half_dim = Point::new(self.theme.border_thickness * 2.0, self.theme.widget_height / 4.0);
+            self.draw_rect(UiRect::new(knob_center - knob_half_dim, knob_center + knob_half_dim), self.theme.accent_color);
+            self.draw_focus(rect, &interaction);
+        }
+
+        (*value - old_value).abs() > 0.0
+    }
+
+    /// Single line text entry, the label is shown while it's empty; returns true when the text changed
+    pub fn text_field(&mut self, label: &str, text: &mut String) -> bool {
+        let id = self.get_id(label);
+        let dim = Point::new(self.get_text_width(get_display_label(label)) + 2.0 * self.theme.padding, self.theme.widget_height);
+        let rect = self.allocate(dim);
+        let interaction = self.interact(id, rect);
+        let text_left = rect.min.x + self.theme.padding;
+        let baseline = self.get_text_baseline(rect);
+        let style = self.get_text_style(self.theme.text_color);
+        let old_text = text.clone();
+
+        let char_count = text.chars().count();
+        let mut caret = self.text_carets.get(&id).cloned().unwrap_or(char_count).min(char_count);
+
+        if interaction.hovered && self.pointer_pressed() {
+            let layout = TextLayout::from_text(text, &style);
+            if let Some(cursor) = self.get_cursor() {
+                caret = layout.get_char_index_at(cursor - Point::new(text_left, baseline));
+            }
+        }
+
+        if interaction.focused {
+            self.focus_takes_horizontal = true;
+            let typed: Vec<char> = self.input.held_chars.iter()
+                .filter(|c| !self.previous_input.held_chars.contains(c))
+                .cloned()
+                .collect();
+            for c in typed {
+                text.insert(get_byte_index(text, caret), c);
+                caret += 1;
+            }
+            if self.pressed(|input| input.backspace) && caret > 0 {
+                caret -= 1;
+                text.remove(get_byte_index(text, caret));
+            }
+            if self.pressed(|input| input.delete) && caret < text.chars().count() {
+                text.remove(get_byte_index(text, caret));
+            }
+            if self.pressed(|input| input.left) && caret > 0 {
+                caret -= 1;
+            }
+            if self.pressed(|input| input.right) && caret < text.chars().count() {
+                caret += 1;
+            }
+        }
+        self.text_carets.insert(id, caret);
+
+        if interaction.visible {
+            let color = self.get_widget_color(&interaction);
+            self.draw_rect(rect, color);
+            if text.is_empty() && !interaction.focused {
+                let mut placeholder_color = self.theme.text_color;
+                placeholder_color.a *= 0.5;
+                self.draw_text(get_display_label(label), text_left, rect, placeholder_color);
+            } else {
+                self.draw_text(text, text_left, rect, self.theme.text_color);
+            }
+
+            if interaction.focused {
+                let caret_x = TextLayout::from_text(text, &style).get_caret_position(caret).x + text_left;
+                let caret_half_dim = Point::new(self.theme.border_thickness / 2.0, self.theme.text_size / 2.0);
+                let caret_center = Point::new(caret_x, rect.get_center().y);
+                self.draw_rect(UiRect::new(caret_center - caret_half_dim, caret_center + caret_half_dim), self.theme.text_color);
+            }
+            self.draw_focus(rect, &interaction);
+        }
+
+        *text != old_text
+    }
+
+    /// Widgets until end_scroll_list go in a list of the given height that scrolls with the wheel
+    /// or follows focus; only widgets entirely inside the list are drawn
+    pub fn begin_scroll_list(&mut self, label: &str, height: f64) {
+        let id = self.get_id(label);
+        let width = self.get_remaining_space().x;
+        let rect = self.allocate(Point::new(width, height));
+        self.draw_rect(rect, self.theme.pressed_color);
+
+        let viewport = UiRect::new(rect.min, Point::new(rect.max.x - self.theme.scrollbar_width, rect.max.y));
+        let clip = match self.get_clip() {
+            Some(clip) => clip.intersect(&viewport),
+            None => viewport
+        };
+        let offset = self.scroll_offsets.get(&id).cloned().unwrap_or(0.0);
+
+        let mut container = Container::new(LayoutDirection::Column, viewport, Some(clip));
+        let content_top = viewport.max.y + offset;
+        container.cursor.y = content_top;
+        container.extent.y = content_top;
+        container.scroll = Some(ScrollArea {
+            id,
+            content_top
+        });
+        self.push_container(container);
+        self.push_id(label);
+    }
+
+    pub fn end_scroll_list(&mut self) {
+        self.pop_id();
+        let container = match self.get_container().scroll {
+            Some(_) => self.get_container().clone(),
+            None => return
+        };
+        self.pop_container();
+
+        let area = container.scroll.unwrap();
+        let viewport = container.rect;
+        let content_height = area.content_top - container.extent.y;
+        let max_offset = (content_height - viewport.get_height()).max(0.0);
+
+        let mut offset = self.scroll_offsets.get(&area.id).cloned().unwrap_or(0.0);
+        let hovered = match self.get_cursor() {
+            Some(cursor) => viewport.contains(cursor),
+            None => false
+        };
+        if hovered {
+            offset -= self.input.scroll * self.theme.widget_height;
+        }
+        offset = offset.max(0.0).min(max_offset);
+        self.scroll_offsets.insert(area.id, offset);
+
+        if max_offset > 0.0 {
+            let track = UiRect::new(Point::new(viewport.max.x, viewport.min.y), Point::new(viewport.max.x + self.theme.scrollbar_width, viewport.max.y));
+            let thumb_height = track.get_height() * viewport.get_height() / content_height;
+            let thumb_top = track.max.y - offset / max_offset * (track.get_height() - thumb_height);
+            self.draw_rect(track, self.theme.widget_color);
+            self.draw_rect(UiRect::new(Point::new(track.min.x, thumb_top - thumb_height), Point::new(track.max.x, thumb_top)), self.theme.accent_color);
+        }
+    }
+}
+
+fn get_byte_index(text: &str, char_index: usize) -> usize {
+    text.char_indices()
+        .nth(char_index)
+        .map(|(index, _)| index)
+        .unwrap_or_else(|| text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_frame<F: FnMut(&mut Ui)>(ui: &mut Ui, input: UiInput, mut widgets: F) {
+        ui.begin(input, WindowSpec { aspect_ratio: 1.5, ..Default::default() });
+        widgets(ui);
+        ui.end();
+    }
+
+    #[test]
+    fn keyboard_navigates_and_activates() {
+        let mut ui = Ui::default();
+        let mut clicked = Vec::new();
+        let frames = vec![
+            UiInput::default(),
+            UiInput { down: true, ..Default::default() },
+            UiInput::default(),
+            UiInput { down: true, ..Default::default() },
+            UiInput { activate: true, ..Default::default() }
+        ];
+
+        for input in frames {
+            run_frame(&mut ui, input, |ui| {
+                if ui.button("Play") {
+                    clicked.push("Play");
+                }
+                if ui.button("Quit") {
+                    clicked.push("Quit");
+                }
+            });
+        }
+
+        assert_eq!(clicked, vec!["Quit"]);
+        assert_eq!(ui.get_focus(), Some(ui.get_id("Quit")));
+    }
+
+    #[test]
+    fn text_field_types_after_click() {
+        let mut ui = Ui::default();
+        let mut name = String::new();
+        // Default layout puts the first widget in the top left corner
+        let field_point = Point::new(-1.4, 0.9);
+        let frames = vec![
+            UiInput { cursor: Some(field_point), ..Default::default() },
+            UiInput { cursor: Some(field_point), pointer_down: true, ..Default::default() },
+            UiInput { held_chars: vec!['h'], ..Default::default() },
+            UiInput { held_chars: vec!['h', 'i'], ..Default::default() },
+            UiInput { backspace: true, ..Default::default() }
+        ];
+
+        let mut history = Vec::new();
+        for input in frames {
+            run_frame(&mut ui, input, |ui| {
+                ui.text_field("Name", &mut name);
+            });
+            history.push(name.clone());
+        }
+
+        assert_eq!(history, vec!["", "", "h", "hi", "h"]);
+    }
+}
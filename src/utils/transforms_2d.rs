@@ -38,14 +38,8 @@ pub fn build_worldview_mat(
     use_aspect: bool
 ) -> Matrix4 {
     let trans_mat = translation_mat(-position);
-    let scaling = match use_aspect {
-        true => Point::new(1.0 / (aspect_ratio * view_length), 1.0 / view_height),
-        false => Point::new(1.0 / view_length, 1.0 / view_height)
-    };
-    let scale_mat = scaling_mat(scaling);
-
-    let rotation_angle = (up_vector.y).atan2(up_vector.x) - PI / 2.0;
-    let rot_mat = rotation_mat(rotation_angle);
+    let scale_mat = scaling_mat(view_scaling(view_height, view_length, aspect_ratio, use_aspect));
+    let rot_mat = rotation_mat(view_rotation_angle(up_vector));
 
     let three_mat = scale_mat * rot_mat * trans_mat;
 
@@ -56,3 +50,29 @@ pub fn build_worldview_mat(
         three_mat.zx, three_mat.zy, 0.0, three_mat.zz,
     )
 }
+
+/// Inverse of build_worldview_mat, from view space (-1 to 1 on both axes) back to the world
+pub fn view_to_world(
+    view_point: Point,
+    position: Point,
+    view_height: f64,
+    view_length: f64,
+    aspect_ratio: f64,
+    up_vector: Point,
+    use_aspect: bool
+) -> Point {
+    let scaling = view_scaling(view_height, view_length, aspect_ratio, use_aspect);
+    let unrotated = Point::new(view_point.x / scaling.x, view_point.y / scaling.y);
+    position + Rotation::new(-view_rotation_angle(up_vector)) * unrotated
+}
+
+fn view_scaling(view_height: f64, view_length: f64, aspect_ratio: f64, use_aspect: bool) -> Point {
+    match use_aspect {
+        true => Point::new(1.0 / (aspect_ratio * view_length), 1.0 / view_height),
+        false => Point::new(1.0 / view_length, 1.0 / view_height)
+    }
+}
+
+fn view_rotation_angle(up_vector: Point) -> f64 {
+    (up_vector.y).atan2(up_vector.x) - PI / 2.0
+}
@@ -1,12 +1,13 @@
 use glium::glutin::Event;
 use glium::Display;
-use super::WindowHandler;
+use super::{WindowHandler, WindowEventQueue};
 use glium::glutin::EventsLoop;
 use glium::glutin::WindowEvent;
 
 pub struct GlutinInput {
     focused_flag: bool,
-    requested_close: bool
+    requested_close: bool,
+    event_queue: Option<WindowEventQueue>
 }
 
 impl GlutinInput {
@@ -14,28 +15,45 @@ impl GlutinInput {
         GlutinInput {
             focused_flag: true,
             requested_close: false,
+            event_queue: None
+        }
+    }
+
+    /// Also pushes every polled event onto event_queue, for an input handler built on window events
+    pub fn with_event_queue(event_queue: WindowEventQueue) -> GlutinInput {
+        GlutinInput {
+            event_queue: Some(event_queue),
+            ..GlutinInput::new()
         }
     }
 }
 
 impl WindowHandler for GlutinInput {
     fn receive_input(&mut self, events_loop: &mut EventsLoop) {
+        let event_queue = &self.event_queue;
+        let focused_flag = &mut self.focused_flag;
+        let requested_close = &mut self.requested_close;
         events_loop.poll_events( |event| {
             match event
             {
                 Event::WindowEvent {
                     window_id: _,
                     event: WindowEvent::Focused(b),
-                } => self.focused_flag = b,
+                } => *focused_flag = b,
                 Event::WindowEvent {
                     window_id: _,
                     event: WindowEvent::CloseRequested,
-                } => self.requested_close = true,
+                } => *requested_close = true,
                 _ => (),
             }
+            // Device events (e.g. raw mouse motion) keep coming while another window has focus
+            let for_other_window = !*focused_flag && matches!(event, Event::DeviceEvent { .. });
+            if let (Some(queue), false) = (event_queue, for_other_window) {
+                queue.push(event);
+            }
         })
     }
-    
+
     fn is_focused(&self) -> bool { self.focused_flag }
 
     fn request_close(&self) -> bool { self.requested_close }
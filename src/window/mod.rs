@@ -1,9 +1,11 @@
 mod glutin_handler;
+mod window_events;
 use crate::games::GameInput;
 use glium::Display;
 use glium::glutin::EventsLoop;
 
 pub use self::glutin_handler::GlutinInput;
+pub use self::window_events::WindowEventQueue;
 
 pub trait WindowHandler {
     fn init(&mut self) {}
@@ -12,3 +14,9 @@ pub trait WindowHandler {
     fn is_focused(&self) -> bool { true }
     fn request_close(&self) -> bool { false }
 }
+
+/// No window, always focused
+#[allow(dead_code)]
+pub struct WindowHandlerStub;
+
+impl WindowHandler for WindowHandlerStub {}
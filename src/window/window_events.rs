@@ -0,0 +1,23 @@
+use glium::glutin::Event;
+use std::sync::{Arc, Mutex};
+
+/// Events polled by GlutinInput, shared with an input handler that reads them (e.g. WindowInput)
+#[derive(Clone, Default)]
+pub struct WindowEventQueue {
+    events: Arc<Mutex<Vec<Event>>>
+}
+
+impl WindowEventQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, event: Event) {
+        self.events.lock().unwrap().push(event);
+    }
+
+    /// Events pushed since the last call, oldest first
+    pub fn take(&self) -> Vec<Event> {
+        std::mem::take(&mut *self.events.lock().unwrap())
+    }
+}